@@ -0,0 +1,8 @@
+-- Optional expiry time for ephemeral memory entries (e.g. "remember my OTP
+-- for 10 minutes"). NULL means the entry never expires. Expired entries are
+-- excluded from recall and purged on agent startup.
+
+ALTER TABLE memory_entries ADD COLUMN expires_at DATETIME;
+
+CREATE INDEX IF NOT EXISTS idx_memory_expires_at
+    ON memory_entries(expires_at);
@@ -0,0 +1,16 @@
+-- History of scheduled task executions. `scheduled_tasks.last_run` and
+-- `last_result` only hold the latest fire; each run is also recorded here
+-- so users can audit what a recurring task has been doing.
+
+CREATE TABLE IF NOT EXISTS scheduled_task_runs (
+    id TEXT PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    timestamp DATETIME NOT NULL,
+    success INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    FOREIGN KEY (task_id) REFERENCES scheduled_tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_task_runs_task_id
+    ON scheduled_task_runs(task_id, timestamp);
@@ -0,0 +1,4 @@
+-- Failed scheduled task runs send an OS notification even when `notify` is
+-- off, unless the task opts out.
+
+ALTER TABLE scheduled_tasks ADD COLUMN notify_on_failure INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,9 @@
+-- Active TODO list of the planning tools, so an in-progress multi-step task
+-- survives app restarts. One list per instance; items are stored as JSON.
+
+CREATE TABLE IF NOT EXISTS todos (
+    instance_id TEXT PRIMARY KEY,
+    context TEXT NOT NULL,
+    items TEXT NOT NULL,
+    updated_at DATETIME NOT NULL
+);
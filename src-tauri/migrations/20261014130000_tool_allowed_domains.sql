@@ -0,0 +1,4 @@
+-- Optional per-tool HTTP host allowlist (JSON array of domains). NULL means
+-- the tool may reach any HTTPS host.
+
+ALTER TABLE tools ADD COLUMN allowed_domains TEXT;
@@ -0,0 +1,6 @@
+-- Optional category for grouping dynamic tools in the UI (e.g. "web",
+-- "data", "files"). NULL means uncategorized.
+
+ALTER TABLE tools ADD COLUMN category TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_tools_category ON tools(category);
@@ -0,0 +1,12 @@
+-- Progress of running sub-agent tasks, so a run cut short by a timeout,
+-- error or app restart can be resumed. One checkpoint per task name and
+-- instance; it is removed when the task completes.
+
+CREATE TABLE IF NOT EXISTS subagent_checkpoints (
+    instance_id TEXT NOT NULL,
+    task_name TEXT NOT NULL,
+    turn_count INTEGER NOT NULL,
+    last_message TEXT NOT NULL,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (instance_id, task_name)
+);
@@ -0,0 +1,11 @@
+-- Events emitted to the frontend (program updates, program opens, ...),
+-- kept so the UI can show an activity history. The payload is stored as JSON.
+
+CREATE TABLE IF NOT EXISTS activity_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_log_timestamp ON activity_log(timestamp);
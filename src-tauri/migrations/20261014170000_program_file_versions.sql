@@ -0,0 +1,12 @@
+-- Prior contents of Canvas program files, captured before each write or
+-- edit so a file can be restored. `version` counts up per file starting
+-- at 1; only the most recent snapshots per file are kept.
+
+CREATE TABLE IF NOT EXISTS program_file_versions (
+    program_name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    content BLOB NOT NULL,
+    created_at DATETIME NOT NULL,
+    PRIMARY KEY (program_name, path, version)
+);
@@ -0,0 +1,10 @@
+-- Saved prompts the user can reuse. The body may contain `{{name}}`
+-- placeholders that are filled in when the template is sent.
+
+CREATE TABLE IF NOT EXISTS prompt_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    body TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Pinned memory entries are kept at full weight: they are not down-ranked by
+-- recency decay, never removed by deduplication, and never purged on expiry.
+
+ALTER TABLE memory_entries ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT 0;
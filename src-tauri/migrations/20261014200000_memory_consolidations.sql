@@ -0,0 +1,9 @@
+-- Runs of memory consolidation (merging clusters of similar long-term memory
+-- entries). The latest timestamp decides when a scheduled run is due.
+
+CREATE TABLE IF NOT EXISTS memory_consolidations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME NOT NULL,
+    clusters_merged INTEGER NOT NULL,
+    entries_removed INTEGER NOT NULL
+);
@@ -0,0 +1,191 @@
+//! Per-turn budget for expensive tool calls.
+//!
+//! Some tools make network requests or spawn sub-agents. A `ToolBudget`
+//! caps how often each of them may run within one `chat`/`stream_chat` turn;
+//! once a tool's budget is spent, further calls return an error result telling
+//! the model to stop instead of executing.
+
+use rig::completion::ToolDefinition;
+use rig::tool::{ToolDyn, ToolError};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use crate::ai_instances::AIInstance;
+
+/// Default per-turn limits for tools that perform HTTP calls or spawn
+/// sub-agents. Instances can override them via `AIInstance::tool_budgets`.
+pub const DEFAULT_TOOL_BUDGETS: &[(&str, usize)] = &[
+    ("execute_dynamic_tool", 20),
+    ("ingest_document", 10),
+    ("delegate_task", 5),
+];
+
+/// Per-turn call limits by tool name and the calls made so far.
+#[derive(Debug, Default)]
+pub struct ToolBudget {
+    limits: HashMap<String, usize>,
+    used: Mutex<HashMap<String, usize>>,
+}
+
+/// Budget shared between the agent and its wrapped tools.
+pub type SharedToolBudget = Arc<ToolBudget>;
+
+impl ToolBudget {
+    pub fn new(limits: HashMap<String, usize>) -> Self {
+        Self {
+            limits,
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Budget for an instance: the defaults, overridden per tool by the
+    /// instance's `tool_budgets`.
+    pub fn for_instance(instance: &AIInstance) -> Self {
+        let mut limits: HashMap<String, usize> = DEFAULT_TOOL_BUDGETS
+            .iter()
+            .map(|(name, limit)| (name.to_string(), *limit))
+            .collect();
+        limits.extend(instance.tool_budgets.clone());
+        Self::new(limits)
+    }
+
+    /// Whether calls to `tool_name` are counted.
+    pub fn limits(&self, tool_name: &str) -> bool {
+        self.limits.contains_key(tool_name)
+    }
+
+    /// Count one call of `tool_name`. Returns an error message for the model
+    /// if the tool's budget for this turn is already spent.
+    pub fn try_consume(&self, tool_name: &str) -> Result<(), String> {
+        let Some(&limit) = self.limits.get(tool_name) else {
+            return Ok(());
+        };
+        let mut used = self.lock();
+        let count = used.entry(tool_name.to_string()).or_insert(0);
+        if *count >= limit {
+            return Err(format!(
+                "Tool budget exceeded: '{}' may be called at most {} times per turn. \
+                 Do not call it again; answer with the results you already have.",
+                tool_name, limit
+            ));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Start a new turn with all budgets unspent.
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, usize>> {
+        self.used.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct BudgetExceeded(String);
+
+/// A tool whose calls are counted against a `ToolBudget`.
+struct BudgetedTool {
+    inner: Box<dyn ToolDyn>,
+    budget: SharedToolBudget,
+}
+
+impl ToolDyn for BudgetedTool {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn definition<'a>(
+        &'a self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + 'a>> {
+        self.inner.definition(prompt)
+    }
+
+    fn call<'a>(
+        &'a self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.budget
+                .try_consume(&self.inner.name())
+                .map_err(|e| ToolError::ToolCallError(Box::new(BudgetExceeded(e))))?;
+            self.inner.call(args).await
+        })
+    }
+}
+
+/// Wrap every tool that has a limit in `budget`; others are returned as is.
+pub(super) fn apply_budget(
+    tools: Vec<Box<dyn ToolDyn>>,
+    budget: &SharedToolBudget,
+) -> Vec<Box<dyn ToolDyn>> {
+    tools
+        .into_iter()
+        .map(|tool| -> Box<dyn ToolDyn> {
+            if budget.limits(&tool.name()) {
+                Box::new(BudgetedTool {
+                    inner: tool,
+                    budget: budget.clone(),
+                })
+            } else {
+                tool
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(limits: &[(&str, usize)]) -> ToolBudget {
+        ToolBudget::new(
+            limits
+                .iter()
+                .map(|(name, limit)| (name.to_string(), *limit))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_budget_enforced_per_tool_until_reset() {
+        let budget = budget(&[("execute_dynamic_tool", 2), ("delegate_task", 1)]);
+
+        assert!(budget.try_consume("execute_dynamic_tool").is_ok());
+        assert!(budget.try_consume("execute_dynamic_tool").is_ok());
+        let err = budget.try_consume("execute_dynamic_tool").unwrap_err();
+        assert!(err.contains("at most 2 times per turn"));
+
+        // Budgets are independent per tool; unlisted tools are unlimited
+        assert!(budget.try_consume("delegate_task").is_ok());
+        assert!(budget.try_consume("delegate_task").is_err());
+        for _ in 0..100 {
+            assert!(budget.try_consume("read_file").is_ok());
+        }
+
+        budget.reset();
+        assert!(budget.try_consume("execute_dynamic_tool").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_tool_returns_error_result() {
+        let budget: SharedToolBudget = Arc::new(budget(&[("read_todos", 1)]));
+        let todo_list = crate::tools::planning::create_shared_todo_list();
+        let tools = apply_budget(
+            vec![Box::new(crate::tools::planning::ReadTodosTool::new(
+                todo_list,
+            ))],
+            &budget,
+        );
+
+        assert!(tools[0].call("{}".to_string()).await.is_ok());
+        let err = tools[0].call("{}".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("Tool budget exceeded"));
+    }
+}
@@ -1,14 +1,25 @@
 use anyhow::Result;
 use chrono::Utc;
+use rig::agent::PromptResponse;
 use rig::completion::Prompt;
+use rig::message::Message as RigMessage;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::memory::working_memory::Message;
 
+use super::fallback::fallback_note;
 use super::providers::AgentProvider;
+use super::retry::{is_retryable_error, retry_delay, MAX_LLM_RETRIES};
+use super::usage::{ChatResult, TokenUsage};
 use super::OwnAIAgent;
-use super::MAX_TOOL_TURNS;
+
+/// A failed `prompt_with_retry` turn.
+pub(super) struct PromptFailure {
+    pub(super) error: anyhow::Error,
+    /// Whether any tool call ran before the failure
+    pub(super) tools_ran: bool,
+}
 
 impl OwnAIAgent {
     /// Main chat method (non-streaming) - combines Memory + Tools + LLM.
@@ -16,18 +27,19 @@ impl OwnAIAgent {
     /// Creates an instrumented tracing span with Langfuse context attributes
     /// so that all LLM calls within this chat turn are associated with the
     /// correct session, tags, and metadata in Langfuse.
-    pub async fn chat(&mut self, user_message: &str) -> Result<String> {
+    pub async fn chat(&mut self, user_message: &str) -> Result<ChatResult> {
         let chat_span = tracing::info_span!(
             "ownai.chat",
             instance_id = %self.instance_id,
             instance_name = %self.instance_name,
         );
         self.attach_langfuse_context(&chat_span);
+        self.tool_budget.reset();
         self.chat_inner(user_message).instrument(chat_span).await
     }
 
     /// Inner implementation of `chat()`, executed within an instrumented span.
-    async fn chat_inner(&mut self, user_message: &str) -> Result<String> {
+    async fn chat_inner(&mut self, user_message: &str) -> Result<ChatResult> {
         // Set GenAI semantic convention attributes on the parent span so that
         // Langfuse can display Input/Output and render the flow diagram.
         let current_span = tracing::Span::current();
@@ -53,12 +65,15 @@ impl OwnAIAgent {
         // 2. Add to working memory (may trigger eviction -> summarization)
         self.add_to_working_memory(user_msg).await;
 
-        // 3. Build context from all memory layers
-        let context = self.context_builder.build_context(user_message).await?;
+        // 3. Build chat history from working memory (with time gap markers)
+        let mut base_history = self.build_history_with_time_markers();
 
-        // 4. Build chat history from working memory (with time gap markers)
-        let mut history = self.build_history_with_time_markers();
-        let history_len_before = history.len();
+        // 4. Build context from all memory layers, trimmed together with the
+        //    history to fit the model's window
+        let context = self
+            .build_prompt_context(&mut base_history, user_message)
+            .await?;
+        let history_len_before = base_history.len();
 
         // 5. Prepare prompt with memory context
         let prompt = if !context.is_empty() {
@@ -67,30 +82,21 @@ impl OwnAIAgent {
             user_message.to_string()
         };
 
-        // 6. Call LLM with multi-turn tool support
-        let response = match &self.agent {
-            AgentProvider::Anthropic(agent) => {
-                agent
-                    .prompt(&prompt)
-                    .with_history(&mut history)
-                    .max_turns(MAX_TOOL_TURNS)
-                    .await?
-            }
-            AgentProvider::OpenAI(agent) => {
-                agent
-                    .prompt(&prompt)
-                    .with_history(&mut history)
-                    .max_turns(MAX_TOOL_TURNS)
-                    .await?
-            }
-            AgentProvider::Ollama(agent) => {
-                agent
-                    .prompt(&prompt)
-                    .with_history(&mut history)
-                    .max_turns(MAX_TOOL_TURNS)
-                    .await?
-            }
+        // 6. Call LLM with multi-turn tool support (retrying transient errors,
+        //    then falling back to the configured fallback providers)
+        let (prompt_response, history, answered_by) =
+            self.prompt_with_fallback(&prompt, &base_history).await?;
+
+        let response = match answered_by {
+            Some(label) => format!(
+                "{}{}",
+                fallback_note(&self.provider_label(), &label),
+                prompt_response.output
+            ),
+            None => prompt_response.output,
         };
+        let usage =
+            TokenUsage::from_provider(Some(prompt_response.total_usage), &prompt, &response);
 
         // 7. Extract intermediate tool messages from rig's modified history.
         //    rig appends: [prompt, assistant+tool_calls, user+tool_results, ..., final_assistant]
@@ -134,6 +140,72 @@ impl OwnAIAgent {
         current_span.set_attribute("gen_ai.completion.0.role", "assistant");
         current_span.set_attribute("gen_ai.completion.0.content", response.clone());
 
-        Ok(response)
+        Ok(ChatResult {
+            text: response,
+            usage,
+        })
+    }
+
+    /// Run one multi-turn prompt on `agent` against `base_history`.
+    /// Transient provider errors (rate limits, timeouts) are retried with
+    /// exponential backoff while no tool has been called yet, so a retry never
+    /// runs a tool twice; each attempt starts from a fresh copy of the history.
+    /// Returns the response and the history extended by rig.
+    pub(super) async fn prompt_with_retry(
+        &self,
+        agent: &AgentProvider,
+        prompt: &str,
+        base_history: &[RigMessage],
+    ) -> std::result::Result<(PromptResponse, Vec<RigMessage>), PromptFailure> {
+        let mut attempt = 0;
+        loop {
+            let mut history = base_history.to_vec();
+            let result = match agent {
+                AgentProvider::Anthropic(agent) => {
+                    agent
+                        .prompt(prompt)
+                        .with_history(&mut history)
+                        .max_turns(self.max_tool_turns)
+                        .extended_details()
+                        .await
+                }
+                AgentProvider::OpenAI(agent) => {
+                    agent
+                        .prompt(prompt)
+                        .with_history(&mut history)
+                        .max_turns(self.max_tool_turns)
+                        .extended_details()
+                        .await
+                }
+                AgentProvider::Ollama(agent) => {
+                    agent
+                        .prompt(prompt)
+                        .with_history(&mut history)
+                        .max_turns(self.max_tool_turns)
+                        .extended_details()
+                        .await
+                }
+            };
+
+            // rig extends the history as the turn progresses: beyond the
+            // prompt, it only holds assistant turns whose tool calls ran
+            let tools_ran = history.len() > base_history.len() + 1;
+            match result.map_err(anyhow::Error::from) {
+                Ok(response) => return Ok((response, history)),
+                Err(e) if !tools_ran && attempt < MAX_LLM_RETRIES && is_retryable_error(&e) => {
+                    let delay = retry_delay(attempt);
+                    attempt += 1;
+                    tracing::warn!(
+                        "Transient LLM error (attempt {}/{}), retrying in {:?}: {}",
+                        attempt,
+                        MAX_LLM_RETRIES,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(error) => return Err(PromptFailure { error, tools_ran }),
+            }
+        }
     }
 }
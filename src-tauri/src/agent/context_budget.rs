@@ -0,0 +1,331 @@
+//! Context-window budgeting for prompt requests.
+//!
+//! Working memory has its own token budget, but the system prompt, injected
+//! memory context and user message come on top of it. Before each request the
+//! estimated total is compared against the model's context limit; the oldest
+//! history messages are dropped first, then the memory context is shortened.
+//! Recalled memories are deduplicated against the history that remains.
+
+use anyhow::Result;
+use rig::message::{AssistantContent, Message as RigMessage, ToolResultContent, UserContent};
+
+use crate::ai_instances::{AIInstance, LLMProvider};
+
+use super::OwnAIAgent;
+
+/// Tokens kept free for tool definitions and the model's response, at most
+/// (see `response_reserve`).
+pub(crate) const RESPONSE_RESERVE_TOKENS: usize = 16_384;
+
+/// Smallest context limit accepted for an instance override.
+pub const MIN_CONTEXT_LIMIT_TOKENS: usize = 4_096;
+
+/// Note appended to a shortened memory context.
+const CONTEXT_TRUNCATED_NOTE: &str = "\n[Memory context truncated to fit the context window]";
+
+/// Context window of an instance's model: the configured override, or a
+/// default by provider and model name.
+pub(crate) fn context_limit(instance: &AIInstance) -> usize {
+    if let Some(limit) = instance.context_limit_tokens {
+        return limit.max(MIN_CONTEXT_LIMIT_TOKENS);
+    }
+    let model = instance.model.to_lowercase();
+    match instance.provider {
+        LLMProvider::Anthropic => 200_000,
+        LLMProvider::OpenAI if model.starts_with("gpt-4.1") => 1_000_000,
+        LLMProvider::OpenAI if model.starts_with("gpt-5") => 400_000,
+        LLMProvider::OpenAI => 128_000,
+        // Local and third-party models vary widely; stay conservative
+        LLMProvider::Ollama | LLMProvider::OpenAICompatible => 32_768,
+    }
+}
+
+/// Tokens kept free within `limit`: `RESPONSE_RESERVE_TOKENS`, but no more
+/// than a quarter of the limit so that small context windows still leave room
+/// for history and memory context.
+pub(crate) fn response_reserve(limit: usize) -> usize {
+    RESPONSE_RESERVE_TOKENS.min(limit / 4)
+}
+
+/// Estimate tokens of a text (rough approximation: ~4 chars = 1 token).
+pub(crate) fn estimate_text_tokens(text: &str) -> usize {
+    text.len() / 4
+}
+
+/// Estimate tokens of a history message, including tool calls and results.
+fn estimate_message_tokens(msg: &RigMessage) -> usize {
+    let serialized = serde_json::to_string(msg).unwrap_or_default();
+    estimate_text_tokens(&serialized) + 5
+}
+
+/// Whether a history may start with `msg`: a user message that is not a
+/// tool result (tool results must follow the assistant's tool call).
+fn is_turn_start(msg: &RigMessage) -> bool {
+    match msg {
+        RigMessage::User { content } => !content
+            .iter()
+            .any(|c| matches!(c, UserContent::ToolResult(_))),
+        _ => false,
+    }
+}
+
+/// Text of a history message as the model reads it: text parts and tool
+/// results.
+fn message_text(msg: &RigMessage) -> String {
+    let parts: Vec<&str> = match msg {
+        RigMessage::User { content } => content
+            .iter()
+            .flat_map(|c| match c {
+                UserContent::Text(text) => vec![text.text.as_str()],
+                UserContent::ToolResult(result) => result
+                    .content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ToolResultContent::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect(),
+        RigMessage::Assistant { content, .. } => content
+            .iter()
+            .filter_map(|c| match c {
+                AssistantContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect(),
+    };
+    parts.join("\n")
+}
+
+/// What `fit_to_context_window` removed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct BudgetOutcome {
+    pub(crate) dropped_messages: usize,
+    pub(crate) context_truncated: bool,
+}
+
+/// Trim `history` (oldest first) and then `context` so that they plus
+/// `fixed_tokens` (system prompt, user message) and the response reserve fit
+/// into `limit` tokens. The trimmed history always starts at a user turn.
+pub(crate) fn fit_to_context_window(
+    history: &mut Vec<RigMessage>,
+    context: &mut String,
+    fixed_tokens: usize,
+    limit: usize,
+) -> BudgetOutcome {
+    let mut outcome = BudgetOutcome::default();
+    let available = limit.saturating_sub(fixed_tokens + response_reserve(limit));
+
+    let mut history_tokens: usize = history.iter().map(estimate_message_tokens).sum();
+    let context_tokens = estimate_text_tokens(context);
+
+    // Drop the oldest messages until everything fits
+    let mut drop_count = 0;
+    while drop_count < history.len() && history_tokens + context_tokens > available {
+        history_tokens -= estimate_message_tokens(&history[drop_count]);
+        drop_count += 1;
+    }
+    if drop_count > 0 {
+        // Never start mid-turn (e.g. with a tool result whose call was dropped)
+        while drop_count < history.len() && !is_turn_start(&history[drop_count]) {
+            history_tokens -= estimate_message_tokens(&history[drop_count]);
+            drop_count += 1;
+        }
+        history.drain(..drop_count);
+        outcome.dropped_messages = drop_count;
+    }
+
+    // Shorten the memory context if it alone is still too large
+    let context_budget = available.saturating_sub(history_tokens);
+    if context_tokens > context_budget {
+        let mut cut = (context_budget * 4).saturating_sub(CONTEXT_TRUNCATED_NOTE.len());
+        while !context.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        context.truncate(cut);
+        if !context.is_empty() {
+            context.push_str(CONTEXT_TRUNCATED_NOTE);
+        }
+        outcome.context_truncated = true;
+    }
+
+    outcome
+}
+
+impl OwnAIAgent {
+    /// Build the memory context for the next request and fit it together with
+    /// `history` into the model's context window. Recalled memories are
+    /// deduplicated against the history that is actually sent, so when old
+    /// messages are trimmed the context is rebuilt without them.
+    pub(super) async fn build_prompt_context(
+        &self,
+        history: &mut Vec<RigMessage>,
+        user_message: &str,
+    ) -> Result<String> {
+        loop {
+            let mut window: Vec<String> = history.iter().map(message_text).collect();
+            window.push(user_message.to_string());
+            let mut context = self
+                .context_builder
+                .build_context(user_message, &window)
+                .await?;
+            // Every retry follows dropped messages, so the loop ends
+            let outcome = self.fit_prompt_to_context(history, &mut context, user_message);
+            if outcome.dropped_messages == 0 {
+                return Ok(context);
+            }
+        }
+    }
+
+    /// Fit history and memory context of the next request into the model's
+    /// context window (see `fit_to_context_window`).
+    fn fit_prompt_to_context(
+        &self,
+        history: &mut Vec<RigMessage>,
+        context: &mut String,
+        user_message: &str,
+    ) -> BudgetOutcome {
+        let fixed_tokens =
+            estimate_text_tokens(&self.system_prompt) + estimate_text_tokens(user_message);
+        let outcome = fit_to_context_window(history, context, fixed_tokens, self.context_limit);
+        if outcome != BudgetOutcome::default() {
+            tracing::info!(
+                "Trimmed prompt to fit {} token context window: dropped {} history messages{}",
+                self.context_limit,
+                outcome.dropped_messages,
+                if outcome.context_truncated {
+                    ", truncated memory context"
+                } else {
+                    ""
+                }
+            );
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(text: &str) -> RigMessage {
+        RigMessage::user(text)
+    }
+
+    fn assistant(text: &str) -> RigMessage {
+        RigMessage::assistant(text)
+    }
+
+    fn long_history(turns: usize) -> Vec<RigMessage> {
+        (0..turns)
+            .flat_map(|i| {
+                [
+                    user(&format!("question {} {}", i, "x".repeat(4_000))),
+                    assistant(&format!("answer {} {}", i, "y".repeat(4_000))),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_over_budget_history_drops_oldest() {
+        let mut history = long_history(20); // ~40 messages, ~40k tokens
+        let mut context = "User likes tea.".to_string();
+        let limit = 40_000;
+
+        let outcome = fit_to_context_window(&mut history, &mut context, 500, limit);
+
+        assert!(outcome.dropped_messages > 0);
+        assert!(!outcome.context_truncated);
+        assert_eq!(context, "User likes tea.");
+        // The newest turn is kept and the history starts with a user message
+        assert!(serde_json::to_string(history.last().unwrap())
+            .unwrap()
+            .contains("answer 19"));
+        assert!(is_turn_start(&history[0]));
+        let total: usize = history.iter().map(estimate_message_tokens).sum::<usize>()
+            + estimate_text_tokens(&context)
+            + 500;
+        assert!(total <= limit - response_reserve(limit));
+    }
+
+    #[test]
+    fn test_smallest_context_limit_keeps_recent_history() {
+        let mut history = vec![
+            user("old question"),
+            assistant(&"old answer ".repeat(1_500)),
+            user("What did I ask?"),
+            assistant("You asked about tea."),
+        ];
+        let mut context = "User likes tea.".to_string();
+
+        let outcome =
+            fit_to_context_window(&mut history, &mut context, 200, MIN_CONTEXT_LIMIT_TOKENS);
+
+        assert_eq!(outcome.dropped_messages, 2);
+        assert!(!outcome.context_truncated);
+        assert_eq!(history.len(), 2);
+        assert_eq!(context, "User likes tea.");
+        assert_eq!(response_reserve(MIN_CONTEXT_LIMIT_TOKENS), 1_024);
+        assert_eq!(response_reserve(200_000), RESPONSE_RESERVE_TOKENS);
+    }
+
+    #[test]
+    fn test_within_budget_keeps_everything() {
+        let mut history = long_history(2);
+        let mut context = "Some memory".to_string();
+
+        let outcome = fit_to_context_window(&mut history, &mut context, 500, 200_000);
+
+        assert_eq!(outcome, BudgetOutcome::default());
+        assert_eq!(history.len(), 4);
+    }
+
+    #[test]
+    fn test_oversized_context_is_truncated() {
+        let mut history = Vec::new();
+        let mut context = "memory ".repeat(60_000); // ~105k tokens
+        let limit = RESPONSE_RESERVE_TOKENS * 4 + 1_000;
+
+        let outcome = fit_to_context_window(&mut history, &mut context, 0, limit);
+
+        assert!(outcome.context_truncated);
+        assert!(context.ends_with(CONTEXT_TRUNCATED_NOTE));
+        assert!(estimate_text_tokens(&context) <= limit - RESPONSE_RESERVE_TOKENS);
+    }
+
+    #[test]
+    fn test_message_text_includes_tool_results() {
+        let tool_result = RigMessage::User {
+            content: rig::OneOrMany::one(UserContent::tool_result(
+                "call-1",
+                rig::OneOrMany::one(ToolResultContent::text("User's cat is called Miso")),
+            )),
+        };
+
+        assert_eq!(message_text(&tool_result), "User's cat is called Miso");
+        assert_eq!(message_text(&assistant("Noted.")), "Noted.");
+    }
+
+    #[test]
+    fn test_context_limit_defaults_and_override() {
+        let mut instance: AIInstance = serde_json::from_value(serde_json::json!({
+            "id": "inst-1",
+            "name": "Test",
+            "provider": "anthropic",
+            "model": "claude-sonnet-4-5",
+            "created_at": "2026-01-01T00:00:00Z",
+            "last_active": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        assert_eq!(context_limit(&instance), 200_000);
+
+        instance.provider = LLMProvider::Ollama;
+        assert_eq!(context_limit(&instance), 32_768);
+
+        instance.context_limit_tokens = Some(8_192);
+        assert_eq!(context_limit(&instance), 8_192);
+    }
+}
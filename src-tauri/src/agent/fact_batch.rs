@@ -0,0 +1,94 @@
+//! Batching of conversation turns for fact extraction.
+//!
+//! With `FactExtractionMode::Batched`, turns are collected here and the fact
+//! extractor runs once per batch instead of after every turn. Pending turns
+//! live only in memory: if the agent is dropped before a batch is full, those
+//! turns are not extracted.
+
+/// A finished conversation turn awaiting fact extraction.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PendingTurn {
+    pub(crate) user_message: String,
+    pub(crate) agent_response: String,
+    pub(crate) user_msg_id: String,
+    pub(crate) agent_msg_id: String,
+}
+
+/// Collects turns until `batch_size` of them are pending.
+#[derive(Debug, Default)]
+pub(crate) struct FactBatch {
+    pending: Vec<PendingTurn>,
+}
+
+impl FactBatch {
+    /// Add a turn. Returns the full batch (and starts a new one) once
+    /// `batch_size` turns are pending.
+    pub(crate) fn push(
+        &mut self,
+        turn: PendingTurn,
+        batch_size: usize,
+    ) -> Option<Vec<PendingTurn>> {
+        self.pending.push(turn);
+        if self.pending.len() >= batch_size.max(1) {
+            Some(std::mem::take(&mut self.pending))
+        } else {
+            None
+        }
+    }
+
+    /// Number of turns waiting for the next extraction.
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Conversation text handed to the fact extractor for a batch of turns.
+pub(crate) fn batch_transcript(turns: &[PendingTurn]) -> String {
+    turns
+        .iter()
+        .map(|t| format!("User: {}\nAgent: {}", t.user_message, t.agent_response))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(n: usize) -> PendingTurn {
+        PendingTurn {
+            user_message: format!("question {}", n),
+            agent_response: format!("answer {}", n),
+            user_msg_id: format!("user-{}", n),
+            agent_msg_id: format!("agent-{}", n),
+        }
+    }
+
+    #[test]
+    fn test_batch_triggers_on_kth_turn() {
+        let mut batch = FactBatch::default();
+
+        assert!(batch.push(turn(1), 3).is_none());
+        assert!(batch.push(turn(2), 3).is_none());
+        assert_eq!(batch.len(), 2);
+
+        let full = batch
+            .push(turn(3), 3)
+            .expect("third turn completes the batch");
+        assert_eq!(full.len(), 3);
+        assert_eq!(full[2].agent_msg_id, "agent-3");
+        assert_eq!(batch.len(), 0);
+
+        // The next batch starts from scratch
+        assert!(batch.push(turn(4), 3).is_none());
+    }
+
+    #[test]
+    fn test_batch_transcript_joins_turns() {
+        let transcript = batch_transcript(&[turn(1), turn(2)]);
+        assert_eq!(
+            transcript,
+            "User: question 1\nAgent: answer 1\n\nUser: question 2\nAgent: answer 2"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Provider fallback chain.
+//!
+//! When a turn fails on the instance's own provider (after retries), it is
+//! run again against the instance's `fallback_providers` in order. Responses
+//! from a fallback start with a note naming the provider that answered.
+
+use anyhow::Result;
+use rig::agent::{PromptResponse, StreamingError};
+use rig::completion::{CompletionError, PromptError};
+use rig::message::Message as RigMessage;
+
+use super::chat::PromptFailure;
+use super::providers::AgentProvider;
+use super::OwnAIAgent;
+
+/// A chat agent built for one entry of `AIInstance::fallback_providers`.
+pub(crate) struct FallbackAgent {
+    /// `provider/model`, used in logs and the response note
+    pub(crate) label: String,
+    pub(crate) agent: AgentProvider,
+}
+
+/// Whether `error` is a provider failure that a fallback provider might not
+/// have. Only completion (request/response) errors qualify; failures caused
+/// by the turn itself (tool errors, turn limit, cancellation) would happen
+/// the same way on another provider. The outermost rig error decides.
+pub(super) fn is_provider_failure(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<PromptError>() {
+                return Some(matches!(e, PromptError::CompletionError(_)));
+            }
+            if let Some(e) = cause.downcast_ref::<StreamingError>() {
+                return Some(match e {
+                    StreamingError::Completion(_) => true,
+                    StreamingError::Prompt(e) => matches!(**e, PromptError::CompletionError(_)),
+                    StreamingError::Tool(_) => false,
+                });
+            }
+            cause.downcast_ref::<CompletionError>().map(|_| true)
+        })
+        .unwrap_or(false)
+}
+
+/// Index (1-based into the fallback list) of the provider to try after
+/// `error` occurred on provider `current` (0 = the instance's own provider),
+/// or `None` if the error is final.
+pub(super) fn next_fallback(
+    error: &anyhow::Error,
+    current: usize,
+    fallback_count: usize,
+) -> Option<usize> {
+    (current < fallback_count && is_provider_failure(error)).then_some(current + 1)
+}
+
+/// Note put in front of a response produced by a fallback provider.
+pub(super) fn fallback_note(primary: &str, answered_by: &str) -> String {
+    format!(
+        "_[{} was unavailable; answered by {}]_\n\n",
+        primary, answered_by
+    )
+}
+
+impl OwnAIAgent {
+    /// `provider/model` of the instance's own provider
+    pub(super) fn provider_label(&self) -> String {
+        format!("{}/{}", self.provider_name, self.model)
+    }
+
+    /// `provider/model` for chain position `index` (0 = the instance's own provider).
+    pub(super) fn agent_label(&self, index: usize) -> String {
+        match index {
+            0 => self.provider_label(),
+            i => self.fallbacks[i - 1].label.clone(),
+        }
+    }
+
+    /// The agent for chain position `index` (0 = the instance's own provider).
+    pub(super) fn agent_at(&self, index: usize) -> &AgentProvider {
+        match index {
+            0 => &self.agent,
+            i => &self.fallbacks[i - 1].agent,
+        }
+    }
+
+    /// `prompt_with_retry` against the instance's provider, then against each
+    /// fallback while the failure is a provider failure that happened before
+    /// any tool ran (a fallback would run those tools again). Returns the
+    /// label of the fallback that answered, if any.
+    pub(super) async fn prompt_with_fallback(
+        &self,
+        prompt: &str,
+        base_history: &[RigMessage],
+    ) -> Result<(PromptResponse, Vec<RigMessage>, Option<String>)> {
+        let mut current = 0;
+        loop {
+            let error = match self
+                .prompt_with_retry(self.agent_at(current), prompt, base_history)
+                .await
+            {
+                Ok((response, history)) => {
+                    let answered_by =
+                        (current > 0).then(|| self.fallbacks[current - 1].label.clone());
+                    return Ok((response, history, answered_by));
+                }
+                Err(PromptFailure { error, tools_ran }) if tools_ran => return Err(error),
+                Err(PromptFailure { error, .. }) => error,
+            };
+
+            let Some(next) = next_fallback(&error, current, self.fallbacks.len()) else {
+                return Err(error);
+            };
+            tracing::warn!(
+                "LLM request failed, falling back to {}: {}",
+                self.fallbacks[next - 1].label,
+                error
+            );
+            current = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::tool::ToolSetError;
+
+    fn provider_error(body: &str) -> CompletionError {
+        CompletionError::ProviderError(body.to_string())
+    }
+
+    #[test]
+    fn test_provider_failures_move_to_next_fallback() {
+        let outage: anyhow::Error =
+            PromptError::CompletionError(provider_error(r#"{"type":"overloaded_error"}"#)).into();
+        assert_eq!(next_fallback(&outage, 0, 2), Some(1));
+        assert_eq!(next_fallback(&outage, 1, 2), Some(2));
+        // The chain is exhausted after the last fallback
+        assert_eq!(next_fallback(&outage, 2, 2), None);
+        // Without fallbacks, the primary error is final
+        assert_eq!(next_fallback(&outage, 0, 0), None);
+
+        let auth: anyhow::Error =
+            PromptError::CompletionError(provider_error("invalid x-api-key")).into();
+        assert_eq!(next_fallback(&auth, 0, 1), Some(1));
+        let streamed = anyhow::Error::from(StreamingError::Completion(provider_error(
+            "SSE Error: Invalid status code: 503",
+        )))
+        .context("Streaming error");
+        assert_eq!(next_fallback(&streamed, 0, 1), Some(1));
+    }
+
+    #[test]
+    fn test_turn_errors_do_not_fall_back() {
+        let errors: Vec<anyhow::Error> = vec![
+            PromptError::MaxTurnsError {
+                max_turns: 50,
+                chat_history: Box::default(),
+                prompt: Box::new(RigMessage::user("hi")),
+            }
+            .into(),
+            PromptError::ToolError(ToolSetError::ToolNotFoundError("grep".to_string())).into(),
+            PromptError::PromptCancelled {
+                chat_history: Box::default(),
+                reason: "user stopped".to_string(),
+            }
+            .into(),
+            anyhow::Error::from(StreamingError::Tool(ToolSetError::ToolNotFoundError(
+                "grep".to_string(),
+            )))
+            .context("Streaming error"),
+            // Error text mentioning a completion error is not enough
+            anyhow::anyhow!("CompletionError: ProviderError: 529 overloaded"),
+        ];
+        for error in errors {
+            assert_eq!(next_fallback(&error, 0, 2), None, "{:#}", error);
+        }
+    }
+
+    #[test]
+    fn test_fallback_note_names_providers() {
+        let note = fallback_note("anthropic/claude-sonnet-4-5", "ollama/llama3");
+        assert!(note.contains("anthropic/claude-sonnet-4-5 was unavailable"));
+        assert!(note.contains("answered by ollama/llama3"));
+        assert!(note.ends_with("\n\n"));
+    }
+}
@@ -1,14 +1,20 @@
+mod budget;
 mod chat;
+mod context_budget;
+mod fact_batch;
+mod fallback;
 mod history;
+mod output_limit;
 mod persistence;
+mod plan_mode;
 mod providers;
+mod retry;
 mod streaming;
 mod system_prompt;
 mod tools;
+mod usage;
 
 use anyhow::Result;
-use rig::client::{CompletionClient, Nothing};
-use rig::providers::{anthropic, ollama, openai};
 use sqlx::{Pool, Sqlite};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -16,24 +22,39 @@ use tauri::AppHandle;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-use crate::ai_instances::{AIInstance, APIKeyStorage, LLMProvider};
+use crate::ai_instances::{AIInstance, FactExtractionMode};
 use crate::memory::{
-    fact_extraction, working_memory::Message, ContextBuilder, FactExtractionResponse,
-    LongTermMemory, SharedLongTermMemory, SummarizationAgent, SummaryResponse, WorkingMemory,
+    fact_extraction, working_memory::Message, ContextBuilder, FactExtractor, LongTermMemory,
+    SharedLongTermMemory, SummarizationAgent, WorkingMemory,
 };
 use crate::tools::planning::{self, SharedTodoList};
+use crate::tools::read_only::READ_ONLY_PROMPT_NOTE;
 use crate::tools::registry::RhaiToolRegistry;
 use crate::tools::rhai_bridge_tool::SharedRegistry;
 use crate::tools::subagents::ClientProvider;
 use crate::utils::paths;
 
-use providers::{AgentProvider, FactExtractorProvider, SummaryExtractorProvider};
+use budget::{apply_budget, SharedToolBudget, ToolBudget};
+pub use context_budget::MIN_CONTEXT_LIMIT_TOKENS;
+use fact_batch::{batch_transcript, FactBatch, PendingTurn};
+use fallback::FallbackAgent;
+pub(crate) use output_limit::{apply_output_limit, OutputLimit};
+pub use output_limit::{DEFAULT_MAX_TOOL_OUTPUT_CHARS, MIN_TOOL_OUTPUT_CHARS};
+use plan_mode::{apply_plan_mode, SharedToolPlan, ToolPlan};
+pub use plan_mode::{PlanResult, PlannedToolCall};
+pub(crate) use providers::openai_client;
+pub(crate) use providers::JsonExtractorProvider;
+use providers::{build_client, AgentProvider, FactExtractorProvider, SummaryExtractorProvider};
+pub use streaming::StreamEvent;
 use tools::create_tools;
+pub use usage::{ChatResult, TokenUsage};
 
 /// ownAI Agent with Memory, Tools, and LLM integration
 pub struct OwnAIAgent {
     pub(crate) agent: AgentProvider,
     pub(crate) fact_extractor: Arc<FactExtractorProvider>,
+    /// LLM client of the instance, for one-off extractors (see `json_extractor`)
+    pub(crate) client: ClientProvider,
     pub(crate) context_builder: ContextBuilder,
     pub(crate) db: Pool<Sqlite>,
     #[allow(dead_code)]
@@ -44,10 +65,55 @@ pub struct OwnAIAgent {
     pub(crate) provider_name: String,
     pub(crate) model: String,
     pub(crate) system_prompt: String,
+    /// Instance policy: abort streamed turns on repeated tool failures
+    pub(crate) stop_on_repeated_tool_error: bool,
+    /// Instance setting: forward reasoning content while streaming
+    pub(crate) stream_reasoning: bool,
+    /// Maximum number of multi-turn iterations for tool calling
+    pub(crate) max_tool_turns: usize,
+    /// Per-turn limits for expensive tools, reset at the start of each turn
+    pub(crate) tool_budget: SharedToolBudget,
+    /// Intercepts tool calls during plan-only turns
+    pub(crate) tool_plan: SharedToolPlan,
+    /// Agents tried in order when the primary provider fails
+    pub(crate) fallbacks: Vec<FallbackAgent>,
+    /// Instance policy: when facts are extracted from finished turns
+    pub(crate) fact_extraction: FactExtractionMode,
+    /// Turns waiting for the next batched fact extraction
+    pub(crate) fact_batch: FactBatch,
+    /// Estimated context window of the model, in tokens
+    pub(crate) context_limit: usize,
 }
 
-/// Maximum number of multi-turn iterations for tool calling
-const MAX_TOOL_TURNS: usize = 50;
+/// Default maximum number of multi-turn iterations for tool calling
+pub const DEFAULT_MAX_TOOL_TURNS: usize = 50;
+
+/// Upper bound for a configured tool-calling turn limit
+pub const MAX_TOOL_TURNS_LIMIT: usize = 200;
+
+/// Tool-calling turn limit for an instance (configured or default, clamped
+/// to 1..=MAX_TOOL_TURNS_LIMIT).
+pub(crate) fn max_tool_turns(instance: &AIInstance) -> usize {
+    instance
+        .max_tool_turns
+        .unwrap_or(DEFAULT_MAX_TOOL_TURNS)
+        .clamp(1, MAX_TOOL_TURNS_LIMIT)
+}
+
+/// Default number of recent messages reloaded into working memory on startup
+pub const DEFAULT_HISTORY_WINDOW: i32 = 100;
+
+/// Upper bound for a configured history window
+pub const MAX_HISTORY_WINDOW: i32 = 2_000;
+
+/// Number of messages to reload for an instance (configured or default,
+/// clamped to 1..=MAX_HISTORY_WINDOW).
+pub(crate) fn history_window(instance: &AIInstance) -> i32 {
+    instance
+        .history_window
+        .unwrap_or(DEFAULT_HISTORY_WINDOW)
+        .clamp(1, MAX_HISTORY_WINDOW)
+}
 
 impl OwnAIAgent {
     /// Create a new ownAI Agent with tools.
@@ -61,12 +127,19 @@ impl OwnAIAgent {
         // Initialize Memory System components
         let mut working_memory = WorkingMemory::new(max_tokens.unwrap_or(50_000));
         let long_term_memory = LongTermMemory::new(db.clone()).await?;
+        // Ephemeral entries (add_memory with ttl_minutes) are swept on startup
+        if let Err(e) = crate::memory::long_term::purge_expired(&db, chrono::Utc::now()).await {
+            tracing::warn!("Failed to purge expired memory entries: {}", e);
+        }
         let shared_long_term_memory: SharedLongTermMemory =
             std::sync::Arc::new(tokio::sync::Mutex::new(long_term_memory));
         let summarization_agent = SummarizationAgent::new(db.clone());
 
-        // Load recent messages from database into working memory
-        let recent_messages = Self::load_recent_messages_from_db(&db, 100).await?;
+        // Load recent messages from database into working memory.
+        // The token budget still applies: if the window holds more than fits,
+        // only the newest messages are kept.
+        let recent_messages =
+            Self::load_recent_messages_from_db(&db, history_window(instance)).await?;
         if !recent_messages.is_empty() {
             working_memory.load_from_messages(recent_messages);
         }
@@ -77,193 +150,117 @@ impl OwnAIAgent {
             summarization_agent,
         );
 
-        // Create shared TODO list state and register with context builder
-        let todo_list = planning::create_shared_todo_list();
+        // Restore the persisted TODO list and register it with the context builder
+        let todo_list = planning::load_shared_todo_list(&db, &instance.id).await;
         context_builder.set_todo_list(todo_list.clone());
 
-        // Load API key from keychain if needed
-        let api_key = if instance.provider.needs_api_key() {
-            APIKeyStorage::load(&instance.provider)?.ok_or_else(|| {
-                anyhow::anyhow!("API key not found for provider: {}", instance.provider)
-            })?
-        } else {
-            String::new()
-        };
-
-        let system_prompt = Self::system_prompt(&instance.name);
-        let summary_preamble = "Extract a structured summary from the conversation below. \
-            Identify the key facts discussed, any tools that were used or mentioned, \
-            and the main topics covered. Be concise but thorough.";
-        let fact_preamble = "Extract important, long-term relevant facts from this conversation turn. \
-            Focus on: user preferences, skills they mention, factual information about the user, \
-            important context for future conversations, and successful tool usage patterns. \
-            Ignore temporary context or trivial details. Each fact should be concise and self-contained.";
+        let mut system_prompt =
+            Self::system_prompt(&instance.name, instance.custom_instructions.as_deref());
+        if instance.read_only {
+            system_prompt = format!("{}\n\n{}", system_prompt, READ_ONLY_PROMPT_NOTE);
+        }
+        let summary_preamble = Self::summary_preamble(instance.language.as_deref());
+        let fact_preamble = Self::fact_preamble(instance.language.as_deref());
 
         // Initialize Rhai Tool Registry for dynamic tools
-        let workspace =
-            paths::get_instance_workspace_path(&instance.id).unwrap_or_else(|_| PathBuf::from("."));
+        let workspace = paths::get_instance_workspace_path(
+            &instance.id,
+            instance.workspace_override.as_deref(),
+        )
+        .unwrap_or_else(|_| PathBuf::from("."));
         let rhai_registry = RhaiToolRegistry::new(
             db.clone(),
-            workspace,
+            workspace.clone(),
             app_handle.clone(),
             Some(instance.name.clone()),
-        );
+        )
+        .with_instance_id(&instance.id)
+        .with_embedder(LongTermMemory::sandbox_embedder(
+            shared_long_term_memory.clone(),
+        ));
         let available_dynamic_tools = rhai_registry.tool_summary().await.unwrap_or_default();
         let tool_registry: SharedRegistry =
             std::sync::Arc::new(tokio::sync::RwLock::new(rhai_registry));
 
+        let tool_budget: SharedToolBudget = Arc::new(ToolBudget::for_instance(instance));
+        let tool_plan: SharedToolPlan = Arc::new(ToolPlan::default());
+        let output_limit = OutputLimit::for_instance(instance, &workspace);
+
         // Resolve programs root for canvas tools
         let programs_root = paths::get_instance_programs_path(&instance.id)
             .unwrap_or_else(|_| PathBuf::from("./programs"));
 
-        // Create provider-specific agent with tools, summary extractor, and fact extractor
-        let (agent, summary_extractor, fact_extractor) = match instance.provider {
-            LLMProvider::Anthropic => {
-                let client = anthropic::Client::builder().api_key(&api_key).build()?;
-                let client_provider = ClientProvider::Anthropic(client.clone());
-
-                let tools = create_tools(
-                    &instance.id,
-                    &instance.name,
-                    todo_list.clone(),
-                    tool_registry.clone(),
-                    available_dynamic_tools.clone(),
-                    db.clone(),
-                    programs_root.clone(),
-                    shared_long_term_memory.clone(),
-                    client_provider,
-                    instance.model.clone(),
-                    app_handle.clone(),
-                );
-
-                let agent = client
-                    .agent(&instance.model)
-                    .preamble(&system_prompt)
-                    .max_tokens(32768)
-                    .temperature(0.7)
-                    .name(&instance.name)
-                    .tools(tools)
-                    .build();
-
-                let summary_extractor = client
-                    .extractor::<SummaryResponse>(&instance.model)
-                    .preamble(summary_preamble)
-                    .max_tokens(8192)
-                    .build();
-
-                let fact_extractor = client
-                    .extractor::<FactExtractionResponse>(&instance.model)
-                    .preamble(fact_preamble)
-                    .max_tokens(4096)
-                    .build();
-
-                (
-                    AgentProvider::Anthropic(agent),
-                    SummaryExtractorProvider::Anthropic(summary_extractor),
-                    FactExtractorProvider::Anthropic(fact_extractor),
-                )
-            }
-
-            LLMProvider::OpenAI => {
-                let openai_client = openai::Client::builder().api_key(&api_key).build()?;
-                let client_provider = ClientProvider::OpenAI(openai_client.clone());
-
-                let tools = create_tools(
-                    &instance.id,
-                    &instance.name,
-                    todo_list.clone(),
-                    tool_registry.clone(),
-                    available_dynamic_tools.clone(),
-                    db.clone(),
-                    programs_root.clone(),
-                    shared_long_term_memory.clone(),
-                    client_provider,
-                    instance.model.clone(),
-                    app_handle.clone(),
-                );
-
-                let agent = openai_client
-                    .clone()
-                    .completions_api()
-                    .agent(&instance.model)
-                    .preamble(&system_prompt)
-                    .temperature(0.7)
-                    .name(&instance.name)
-                    .tools(tools)
-                    .build();
-
-                let summary_extractor = openai_client
-                    .clone()
-                    .completions_api()
-                    .extractor::<SummaryResponse>(&instance.model)
-                    .preamble(summary_preamble)
-                    .build();
-
-                let fact_extractor = openai_client
-                    .completions_api()
-                    .extractor::<FactExtractionResponse>(&instance.model)
-                    .preamble(fact_preamble)
-                    .build();
-
-                (
-                    AgentProvider::OpenAI(agent),
-                    SummaryExtractorProvider::OpenAI(summary_extractor),
-                    FactExtractorProvider::OpenAI(fact_extractor),
-                )
-            }
-
-            LLMProvider::Ollama => {
-                let ollama_client = if let Some(url) = &instance.api_base_url {
-                    ollama::Client::builder()
-                        .api_key(Nothing)
-                        .base_url(url)
-                        .build()?
-                } else {
-                    ollama::Client::new(Nothing)?
-                };
-                let client_provider = ClientProvider::Ollama(ollama_client.clone());
-
-                let tools = create_tools(
-                    &instance.id,
-                    &instance.name,
-                    todo_list.clone(),
-                    tool_registry.clone(),
-                    available_dynamic_tools.clone(),
-                    db.clone(),
-                    programs_root.clone(),
-                    shared_long_term_memory.clone(),
-                    client_provider,
-                    instance.model.clone(),
-                    app_handle,
-                );
-
-                let agent = ollama_client
-                    .clone()
-                    .agent(&instance.model)
-                    .preamble(&system_prompt)
-                    .name(&instance.name)
-                    .tools(tools)
-                    .build();
-
-                let summary_extractor = ollama_client
-                    .clone()
-                    .extractor::<SummaryResponse>(&instance.model)
-                    .preamble(summary_preamble)
-                    .build();
-
-                let fact_extractor = ollama_client
-                    .extractor::<FactExtractionResponse>(&instance.model)
-                    .preamble(fact_preamble)
-                    .build();
-
-                (
-                    AgentProvider::Ollama(agent),
-                    SummaryExtractorProvider::Ollama(summary_extractor),
-                    FactExtractorProvider::Ollama(fact_extractor),
-                )
-            }
+        // Tools are built per provider client, since sub-agents (delegate_task)
+        // run on the same provider as the agent that spawns them
+        let build_tools = |client: &ClientProvider, model: &str| {
+            apply_plan_mode(
+                apply_budget(
+                    apply_output_limit(
+                        create_tools(
+                            &instance.id,
+                            &instance.name,
+                            todo_list.clone(),
+                            tool_registry.clone(),
+                            available_dynamic_tools.clone(),
+                            db.clone(),
+                            workspace.clone(),
+                            programs_root.clone(),
+                            shared_long_term_memory.clone(),
+                            client.clone(),
+                            model.to_string(),
+                            app_handle.clone(),
+                            instance.read_only,
+                            output_limit.clone(),
+                        ),
+                        &output_limit,
+                    ),
+                    &tool_budget,
+                ),
+                &tool_plan,
+            )
         };
 
+        // Create provider-specific agent with tools, summary extractor, and fact extractor
+        let client = build_client(&instance.provider, instance.api_base_url.as_deref())?;
+        let agent = AgentProvider::new(
+            &client,
+            &instance.model,
+            &system_prompt,
+            &instance.name,
+            build_tools(&client, &instance.model),
+            instance.stream_reasoning,
+        );
+        let summary_extractor =
+            SummaryExtractorProvider::new(&client, &instance.model, &summary_preamble);
+        let fact_extractor = FactExtractorProvider::new(&client, &instance.model, &fact_preamble);
+
+        // Fallback agents; an entry that cannot be built (e.g. missing API key)
+        // is skipped so the primary provider keeps working
+        let fallbacks: Vec<FallbackAgent> = instance
+            .fallback_providers
+            .iter()
+            .filter_map(|fallback| {
+                let label = format!("{}/{}", fallback.provider, fallback.model);
+                match build_client(&fallback.provider, fallback.api_base_url.as_deref()) {
+                    Ok(client) => Some(FallbackAgent {
+                        agent: AgentProvider::new(
+                            &client,
+                            &fallback.model,
+                            &system_prompt,
+                            &instance.name,
+                            build_tools(&client, &fallback.model),
+                            instance.stream_reasoning,
+                        ),
+                        label,
+                    }),
+                    Err(e) => {
+                        tracing::warn!("Skipping fallback provider {}: {}", label, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
         // Set the summary extractor and long-term memory on the SummarizationAgent
         context_builder
             .summarization_agent_mut()
@@ -272,9 +269,31 @@ impl OwnAIAgent {
             .summarization_agent_mut()
             .set_long_term_memory(shared_long_term_memory.clone());
 
+        let fact_extractor = Arc::new(fact_extractor);
+        if let Some(days) = instance.memory_consolidation_days {
+            let db = db.clone();
+            let extractor = fact_extractor.clone();
+            let memory = shared_long_term_memory.clone();
+            let instance_id = instance.id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = crate::memory::consolidation::consolidate_if_due(
+                    &instance_id,
+                    &db,
+                    extractor.as_ref(),
+                    &memory,
+                    days,
+                )
+                .await
+                {
+                    tracing::warn!("Scheduled memory consolidation failed: {}", e);
+                }
+            });
+        }
+
         Ok(OwnAIAgent {
             agent,
-            fact_extractor: Arc::new(fact_extractor),
+            fact_extractor,
+            client,
             context_builder,
             db,
             todo_list,
@@ -284,9 +303,38 @@ impl OwnAIAgent {
             provider_name: instance.provider.to_string(),
             model: instance.model.clone(),
             system_prompt,
+            stop_on_repeated_tool_error: instance.stop_on_repeated_tool_error,
+            stream_reasoning: instance.stream_reasoning,
+            max_tool_turns: max_tool_turns(instance),
+            tool_budget,
+            tool_plan,
+            fallbacks,
+            fact_extraction: instance.fact_extraction.clone(),
+            fact_batch: FactBatch::default(),
+            context_limit: context_budget::context_limit(instance),
         })
     }
 
+    /// Tool-calling turn limit passed to every prompt request
+    pub fn max_tool_turns(&self) -> usize {
+        self.max_tool_turns
+    }
+
+    /// Shared fact extractor (used by the manual extraction command)
+    pub(crate) fn fact_extractor(&self) -> Arc<FactExtractorProvider> {
+        self.fact_extractor.clone()
+    }
+
+    /// One-off extractor returning JSON that follows `schema` (used by the
+    /// Canvas bridge `chatJson` method)
+    pub(crate) fn json_extractor(&self, schema: &serde_json::Value) -> JsonExtractorProvider {
+        JsonExtractorProvider::new(
+            &self.client,
+            &self.model,
+            &Self::json_output_preamble(schema),
+        )
+    }
+
     /// Public accessor for context builder (used by memory stats command)
     pub fn context_builder(&self) -> &ContextBuilder {
         &self.context_builder
@@ -366,28 +414,59 @@ impl OwnAIAgent {
         }
     }
 
-    /// Spawn fact extraction as a background task so it does not block
-    /// the completion of chat/streaming. The task runs independently and
-    /// logs any errors via tracing.
-    ///
-    /// After extraction, sets the `importance_score` on the user message
-    /// to the maximum importance of all extracted facts.
-    ///
-    /// Creates an instrumented tracing span so Langfuse can display the
-    /// fact extraction as a named trace with Input/Output.
+    /// Hand a finished turn to fact extraction according to the instance's
+    /// `fact_extraction` mode: extract now, add it to the current batch
+    /// (extracting once the batch is full), or skip it.
     pub(crate) fn spawn_fact_extraction(
-        &self,
+        &mut self,
         user_message: &str,
         agent_response: &str,
         user_msg_id: &str,
         agent_msg_id: &str,
     ) {
+        let turn = PendingTurn {
+            user_message: user_message.to_string(),
+            agent_response: agent_response.to_string(),
+            user_msg_id: user_msg_id.to_string(),
+            agent_msg_id: agent_msg_id.to_string(),
+        };
+        match self.fact_extraction {
+            FactExtractionMode::EveryTurn => self.spawn_batch_extraction(vec![turn]),
+            FactExtractionMode::Batched { turns } => {
+                if let Some(batch) = self.fact_batch.push(turn, turns) {
+                    self.spawn_batch_extraction(batch);
+                } else {
+                    tracing::debug!(
+                        "Fact extraction deferred ({}/{} turns batched)",
+                        self.fact_batch.len(),
+                        turns
+                    );
+                }
+            }
+            FactExtractionMode::Disabled => {}
+        }
+    }
+
+    /// Spawn fact extraction over `turns` as a background task so it does not
+    /// block the completion of chat/streaming. The task runs independently and
+    /// logs any errors via tracing.
+    ///
+    /// After extraction, sets the `importance_score` on the batch's user
+    /// messages to the maximum importance of all extracted facts. Facts are
+    /// attributed to the last agent message of the batch.
+    ///
+    /// Creates an instrumented tracing span so Langfuse can display the
+    /// fact extraction as a named trace with Input/Output.
+    fn spawn_batch_extraction(&self, turns: Vec<PendingTurn>) {
+        let Some(last_turn) = turns.last() else {
+            return;
+        };
         let fact_extractor = self.fact_extractor.clone();
         let long_term_memory = self.context_builder.long_term_memory().clone();
         let db = self.db.clone();
-        let conversation_turn = format!("User: {}\nAgent: {}", user_message, agent_response);
-        let user_msg_id = user_msg_id.to_string();
-        let agent_msg_id = agent_msg_id.to_string();
+        let conversation_turn = batch_transcript(&turns);
+        let agent_msg_id = last_turn.agent_msg_id.clone();
+        let user_msg_ids: Vec<String> = turns.into_iter().map(|t| t.user_msg_id).collect();
 
         // Create span before spawning so Langfuse context is attached
         let extraction_span = tracing::info_span!(
@@ -403,7 +482,7 @@ impl OwnAIAgent {
         tokio::spawn(
             async move {
                 // Extract facts using LLM
-                match fact_extractor.extract(&conversation_turn).await {
+                match fact_extractor.extract_facts(&conversation_turn).await {
                     Ok(extraction) => {
                         let current_span = tracing::Span::current();
 
@@ -436,8 +515,10 @@ impl OwnAIAgent {
                             .map(|f| f.importance)
                             .fold(0.0_f32, f32::max);
 
-                        // Update importance_score on the user message
-                        Self::update_importance_score(&db, &user_msg_id, max_importance).await;
+                        // Update importance_score on the user messages
+                        for user_msg_id in &user_msg_ids {
+                            Self::update_importance_score(&db, user_msg_id, max_importance).await;
+                        }
 
                         // Convert extracted facts to memory entries and store them
                         let mut mem = long_term_memory.lock().await;
@@ -460,3 +541,35 @@ impl OwnAIAgent {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance_with_turns(max_tool_turns: Option<usize>) -> AIInstance {
+        serde_json::from_value(serde_json::json!({
+            "id": "inst-1",
+            "name": "Test",
+            "provider": "ollama",
+            "model": "qwen3:8b",
+            "max_tool_turns": max_tool_turns,
+            "created_at": "2026-01-01T00:00:00Z",
+            "last_active": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_max_tool_turns_from_instance() {
+        assert_eq!(
+            max_tool_turns(&instance_with_turns(None)),
+            DEFAULT_MAX_TOOL_TURNS
+        );
+        assert_eq!(max_tool_turns(&instance_with_turns(Some(120))), 120);
+        // Out-of-range values in hand-edited configs are clamped
+        assert_eq!(
+            max_tool_turns(&instance_with_turns(Some(10_000))),
+            MAX_TOOL_TURNS_LIMIT
+        );
+    }
+}
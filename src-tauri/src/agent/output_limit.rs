@@ -0,0 +1,355 @@
+//! Length limit for tool results fed back to the model.
+//!
+//! Tools such as `grep` or `program_read_file` can return very large strings
+//! that inflate the next request. Every tool's result is cut to the
+//! instance's limit and ends with a marker saying how much was omitted. The
+//! full output can be saved to a workspace file so the agent can still read
+//! it in parts with `read_file`. Only the most recent outputs are kept, and
+//! reads of saved outputs are not saved again. Outputs are never saved into
+//! a workspace override, which is a directory of the user's own.
+
+use rig::completion::ToolDefinition;
+use rig::tool::{ToolDyn, ToolError};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use crate::ai_instances::AIInstance;
+
+/// Default maximum length of a tool result, in characters (~12k tokens)
+pub const DEFAULT_MAX_TOOL_OUTPUT_CHARS: usize = 50_000;
+
+/// Smallest accepted limit override
+pub const MIN_TOOL_OUTPUT_CHARS: usize = 1_000;
+
+/// Workspace directory for full outputs of truncated tool results
+pub const TOOL_OUTPUT_DIR: &str = ".tool_outputs";
+
+/// Number of saved full outputs kept in `TOOL_OUTPUT_DIR`; older ones are
+/// deleted when a new one is saved
+pub const MAX_SAVED_TOOL_OUTPUTS: usize = 20;
+
+/// Tool output limit for an instance (configured or default).
+pub(crate) fn max_tool_output_chars(instance: &AIInstance) -> usize {
+    instance
+        .max_tool_output_chars
+        .unwrap_or(DEFAULT_MAX_TOOL_OUTPUT_CHARS)
+        .max(MIN_TOOL_OUTPUT_CHARS)
+}
+
+/// Length limit of tool results and where full outputs are saved.
+#[derive(Debug, Clone)]
+pub struct OutputLimit {
+    pub max_chars: usize,
+    /// Workspace to save full outputs of truncated results in, if any
+    pub save_to: Option<PathBuf>,
+}
+
+impl OutputLimit {
+    /// Limit for the agents of an instance working in `workspace`. Read-only
+    /// instances must not write full outputs, and a workspace override is
+    /// left as the user keeps it.
+    pub(crate) fn for_instance(instance: &AIInstance, workspace: &Path) -> Self {
+        let saves = !instance.read_only && instance.workspace_override.is_none();
+        Self {
+            max_chars: max_tool_output_chars(instance),
+            save_to: saves.then(|| workspace.to_path_buf()),
+        }
+    }
+}
+
+impl Default for OutputLimit {
+    fn default() -> Self {
+        Self {
+            max_chars: DEFAULT_MAX_TOOL_OUTPUT_CHARS,
+            save_to: None,
+        }
+    }
+}
+
+/// Cut `text` to `max_chars` characters. Returns the kept prefix and the
+/// number of omitted characters, or `None` if `text` fits.
+pub(crate) fn truncate_chars(text: &str, max_chars: usize) -> Option<(&str, usize)> {
+    let (cut, _) = text.char_indices().nth(max_chars)?;
+    Some((&text[..cut], text[cut..].chars().count()))
+}
+
+/// Marker appended to a truncated result.
+fn truncation_marker(omitted: usize, saved_to: Option<&str>) -> String {
+    match saved_to {
+        Some(path) => format!(
+            "\n[output truncated, {} chars omitted; full output saved to {} \
+             (read it in parts with read_file start_line/end_line)]",
+            omitted, path
+        ),
+        None => format!("\n[output truncated, {} chars omitted]", omitted),
+    }
+}
+
+/// Write the full output of `tool_name` below `workspace`. Returns the path
+/// relative to the workspace.
+fn save_full_output(workspace: &Path, tool_name: &str, text: &str) -> std::io::Result<String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let relative = format!("{}/{}-{}.txt", TOOL_OUTPUT_DIR, tool_name, &id[..8]);
+    let path = workspace.join(&relative);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, text)?;
+    if let Err(e) = prune_saved_outputs(&workspace.join(TOOL_OUTPUT_DIR)) {
+        tracing::warn!("Failed to prune {}: {}", TOOL_OUTPUT_DIR, e);
+    }
+    Ok(relative)
+}
+
+/// Delete the oldest files in `dir` beyond `MAX_SAVED_TOOL_OUTPUTS`.
+fn prune_saved_outputs(dir: &Path) -> std::io::Result<()> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((metadata.modified()?, entry.path()));
+        }
+    }
+    if files.len() <= MAX_SAVED_TOOL_OUTPUTS {
+        return Ok(());
+    }
+    files.sort();
+    for (_, path) in &files[..files.len() - MAX_SAVED_TOOL_OUTPUTS] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Whether a `read_file` call reads a saved output. Such results are only
+/// truncated, so paging through a saved output doesn't save it again.
+fn reads_saved_output(tool_name: &str, args: &str) -> bool {
+    if tool_name != "read_file" {
+        return false;
+    }
+    serde_json::from_str::<serde_json::Value>(args)
+        .ok()
+        .and_then(|args| args.get("path")?.as_str().map(str::to_owned))
+        .is_some_and(|path| {
+            Path::new(&path)
+                .components()
+                .any(|c| c.as_os_str() == TOOL_OUTPUT_DIR)
+        })
+}
+
+/// Truncate a serialized tool result to `max_chars`. Results are JSON; a JSON
+/// string is truncated on its decoded text so the result stays valid JSON.
+/// With `workspace`, the full text is saved there first.
+pub(crate) fn limit_output(
+    output: String,
+    tool_name: &str,
+    max_chars: usize,
+    workspace: Option<&Path>,
+) -> String {
+    let decoded = match serde_json::from_str::<serde_json::Value>(&output) {
+        Ok(serde_json::Value::String(text)) => Some(text),
+        _ => None,
+    };
+    let text = decoded.as_deref().unwrap_or(&output);
+    let Some((kept, omitted)) = truncate_chars(text, max_chars) else {
+        return output;
+    };
+
+    let saved_to = workspace.and_then(|dir| match save_full_output(dir, tool_name, text) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            tracing::warn!("Failed to save full output of {}: {}", tool_name, e);
+            None
+        }
+    });
+    tracing::debug!("Truncated {} output: {} chars omitted", tool_name, omitted);
+
+    let truncated = format!(
+        "{}{}",
+        kept,
+        truncation_marker(omitted, saved_to.as_deref())
+    );
+    if decoded.is_some() {
+        serde_json::Value::String(truncated).to_string()
+    } else {
+        truncated
+    }
+}
+
+/// A tool whose results are limited to `max_chars`.
+struct OutputLimitedTool {
+    inner: Box<dyn ToolDyn>,
+    max_chars: usize,
+    workspace: Option<PathBuf>,
+}
+
+impl ToolDyn for OutputLimitedTool {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn definition<'a>(
+        &'a self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + 'a>> {
+        self.inner.definition(prompt)
+    }
+
+    fn call<'a>(
+        &'a self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + 'a>> {
+        Box::pin(async move {
+            let name = self.inner.name();
+            let workspace = if reads_saved_output(&name, &args) {
+                None
+            } else {
+                self.workspace.as_deref()
+            };
+            let output = self.inner.call(args).await?;
+            Ok(limit_output(output, &name, self.max_chars, workspace))
+        })
+    }
+}
+
+/// Limit the results of every tool to `limit.max_chars`. With
+/// `limit.save_to`, full outputs of truncated results are saved below
+/// `TOOL_OUTPUT_DIR`.
+pub(crate) fn apply_output_limit(
+    tools: Vec<Box<dyn ToolDyn>>,
+    limit: &OutputLimit,
+) -> Vec<Box<dyn ToolDyn>> {
+    tools
+        .into_iter()
+        .map(|tool| -> Box<dyn ToolDyn> {
+            Box::new(OutputLimitedTool {
+                inner: tool,
+                max_chars: limit.max_chars,
+                workspace: limit.save_to.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_output_is_truncated_with_marker() {
+        let output = serde_json::Value::String("é".repeat(1_500)).to_string();
+        let limited = limit_output(output, "grep", 1_000, None);
+
+        let text: String = serde_json::from_str(&limited).unwrap();
+        assert!(text.starts_with(&"é".repeat(1_000)));
+        assert!(text.ends_with("\n[output truncated, 500 chars omitted]"));
+
+        // Short outputs are returned unchanged
+        let short = serde_json::Value::String("match".to_string()).to_string();
+        assert_eq!(limit_output(short.clone(), "grep", 1_000, None), short);
+    }
+
+    #[test]
+    fn test_full_output_saved_to_workspace() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let full: String = (0..500).map(|i| format!("line {}\n", i)).collect();
+        let output = serde_json::Value::String(full.clone()).to_string();
+
+        let limited = limit_output(output, "program_read_file", 1_000, Some(workspace.path()));
+        let text: String = serde_json::from_str(&limited).unwrap();
+        let path = text
+            .split("full output saved to ")
+            .nth(1)
+            .and_then(|rest| rest.split(' ').next())
+            .unwrap();
+        assert!(path.starts_with(".tool_outputs/program_read_file-"));
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join(path)).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_saved_outputs_are_capped() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let output = serde_json::Value::String("x".repeat(2_000)).to_string();
+        for _ in 0..MAX_SAVED_TOOL_OUTPUTS + 5 {
+            limit_output(output.clone(), "grep", 1_000, Some(workspace.path()));
+        }
+        let saved = std::fs::read_dir(workspace.path().join(TOOL_OUTPUT_DIR))
+            .unwrap()
+            .count();
+        assert_eq!(saved, MAX_SAVED_TOOL_OUTPUTS);
+    }
+
+    #[test]
+    fn test_reads_of_saved_outputs_are_not_saved() {
+        assert!(reads_saved_output(
+            "read_file",
+            r#"{"path": ".tool_outputs/grep-1a2b3c4d.txt", "start_line": 1}"#
+        ));
+        assert!(reads_saved_output(
+            "read_file",
+            r#"{"path": "./.tool_outputs/grep-1a2b3c4d.txt"}"#
+        ));
+        assert!(!reads_saved_output(
+            "read_file",
+            r#"{"path": "src/main.rs"}"#
+        ));
+        assert!(!reads_saved_output(
+            "grep",
+            r#"{"path": ".tool_outputs/grep-1a2b3c4d.txt"}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_limited_tool_truncates_non_string_results() {
+        let todo_list = crate::tools::planning::create_shared_todo_list();
+        let tools = apply_output_limit(
+            vec![Box::new(crate::tools::planning::ReadTodosTool::new(
+                todo_list,
+            ))],
+            &OutputLimit {
+                max_chars: 5,
+                save_to: None,
+            },
+        );
+        let output = tools[0].call("{}".to_string()).await.unwrap();
+        assert!(output.contains("[output truncated,"), "{}", output);
+    }
+
+    #[test]
+    fn test_full_outputs_stay_out_of_workspace_overrides() {
+        let instance = |extra: serde_json::Value| -> AIInstance {
+            let mut value = serde_json::json!({
+                "id": "inst-1",
+                "name": "Test",
+                "provider": "ollama",
+                "model": "qwen3:8b",
+                "created_at": "2026-01-01T00:00:00Z",
+                "last_active": "2026-01-01T00:00:00Z",
+            });
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            serde_json::from_value(value).unwrap()
+        };
+        let workspace = Path::new("/data/inst-1/workspace");
+
+        let limit = OutputLimit::for_instance(&instance(serde_json::json!({})), workspace);
+        assert_eq!(limit.max_chars, DEFAULT_MAX_TOOL_OUTPUT_CHARS);
+        assert_eq!(limit.save_to.as_deref(), Some(workspace));
+
+        let overridden = instance(serde_json::json!({ "workspace_override": "/home/sam/project" }));
+        let limit = OutputLimit::for_instance(&overridden, Path::new("/home/sam/project"));
+        assert_eq!(limit.save_to, None);
+
+        let read_only = instance(serde_json::json!({ "read_only": true }));
+        assert_eq!(
+            OutputLimit::for_instance(&read_only, workspace).save_to,
+            None
+        );
+    }
+}
@@ -109,3 +109,41 @@ impl OwnAIAgent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_history_window_limits_loaded_messages() {
+        let db = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+
+        let start = chrono::Utc::now() - chrono::Duration::minutes(30);
+        for i in 0..20 {
+            sqlx::query(
+                "INSERT INTO messages (id, role, content, timestamp) VALUES (?, 'user', ?, ?)",
+            )
+            .bind(format!("msg-{}", i))
+            .bind(format!("Message {}", i))
+            .bind(start + chrono::Duration::minutes(i))
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        let loaded = OwnAIAgent::load_recent_messages_from_db(&db, 5)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = loaded.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["msg-15", "msg-16", "msg-17", "msg-18", "msg-19"]);
+
+        let all = OwnAIAgent::load_recent_messages_from_db(&db, 100)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 20);
+    }
+}
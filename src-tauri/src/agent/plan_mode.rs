@@ -0,0 +1,253 @@
+//! Plan-only (dry-run) chat turns.
+//!
+//! In plan mode the agent sees the usual tool definitions, but tool calls are
+//! intercepted: each call is recorded with its arguments and answered with a
+//! placeholder result instead of executing. The model can keep going through
+//! the multi-turn loop, so the recorded calls form the sequence it would run.
+
+use anyhow::Result;
+use rig::completion::ToolDefinition;
+use rig::tool::{ToolDyn, ToolError};
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
+
+use super::fallback::fallback_note;
+use super::usage::TokenUsage;
+use super::OwnAIAgent;
+
+/// Result returned to the model for an intercepted tool call
+const PLANNED_TOOL_RESULT: &str = "Plan mode: this tool call was recorded but not executed. \
+     Assume it succeeds and continue with the remaining steps of your plan.";
+
+/// A tool call the agent intended to make during a plan-only turn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedToolCall {
+    pub tool: String,
+    /// Arguments as sent by the model (a string if they were not valid JSON)
+    pub args: serde_json::Value,
+}
+
+/// Result of a plan-only turn.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanResult {
+    /// The agent's final answer describing its plan
+    pub text: String,
+    /// Intended tool calls in the order the agent made them
+    pub tool_calls: Vec<PlannedToolCall>,
+    pub usage: TokenUsage,
+}
+
+/// Whether tool calls are currently intercepted, and the calls recorded so far.
+#[derive(Debug, Default)]
+pub struct ToolPlan {
+    active: AtomicBool,
+    calls: Mutex<Vec<PlannedToolCall>>,
+}
+
+/// Plan state shared between the agent and its wrapped tools.
+pub type SharedToolPlan = Arc<ToolPlan>;
+
+impl ToolPlan {
+    /// Start intercepting tool calls with an empty plan.
+    pub fn begin(&self) {
+        self.lock().clear();
+        self.active.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop intercepting and return the recorded calls.
+    pub fn finish(&self) -> Vec<PlannedToolCall> {
+        self.active.store(false, Ordering::SeqCst);
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn record(&self, tool: String, args: &str) {
+        let args = serde_json::from_str(args)
+            .unwrap_or_else(|_| serde_json::Value::String(args.to_string()));
+        self.lock().push(PlannedToolCall { tool, args });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<PlannedToolCall>> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A tool whose calls are recorded instead of executed while plan mode is active.
+struct PlannedTool {
+    inner: Box<dyn ToolDyn>,
+    plan: SharedToolPlan,
+}
+
+impl ToolDyn for PlannedTool {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn definition<'a>(
+        &'a self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + 'a>> {
+        self.inner.definition(prompt)
+    }
+
+    fn call<'a>(
+        &'a self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + 'a>> {
+        Box::pin(async move {
+            if self.plan.is_active() {
+                self.plan.record(self.inner.name(), &args);
+                // Serialized like the output of a real tool call
+                return Ok(serde_json::Value::from(PLANNED_TOOL_RESULT).to_string());
+            }
+            self.inner.call(args).await
+        })
+    }
+}
+
+/// Wrap every tool so it can be intercepted by `plan`.
+pub(super) fn apply_plan_mode(
+    tools: Vec<Box<dyn ToolDyn>>,
+    plan: &SharedToolPlan,
+) -> Vec<Box<dyn ToolDyn>> {
+    tools
+        .into_iter()
+        .map(|tool| -> Box<dyn ToolDyn> {
+            Box::new(PlannedTool {
+                inner: tool,
+                plan: plan.clone(),
+            })
+        })
+        .collect()
+}
+
+impl OwnAIAgent {
+    /// Plan-only chat turn: the agent answers `user_message` with tool calls
+    /// intercepted (see module docs). Nothing is saved to the database or
+    /// working memory, and no facts are extracted.
+    pub async fn plan(&mut self, user_message: &str) -> Result<PlanResult> {
+        let plan_span = tracing::info_span!(
+            "ownai.plan",
+            instance_id = %self.instance_id,
+            instance_name = %self.instance_name,
+        );
+        self.attach_langfuse_context(&plan_span);
+        self.tool_budget.reset();
+
+        self.tool_plan.begin();
+        let result = self.plan_inner(user_message).instrument(plan_span).await;
+        let tool_calls = self.tool_plan.finish();
+
+        let (text, usage) = result?;
+        tracing::info!(
+            "Planned {} tool calls for instance {}",
+            tool_calls.len(),
+            self.instance_id
+        );
+        Ok(PlanResult {
+            text,
+            tool_calls,
+            usage,
+        })
+    }
+
+    async fn plan_inner(&mut self, user_message: &str) -> Result<(String, TokenUsage)> {
+        let mut history = self.build_history_with_time_markers();
+        let context = self
+            .build_prompt_context(&mut history, user_message)
+            .await?;
+
+        let prompt = if !context.is_empty() {
+            format!("[Context from memory]\n{}\n\n{}", context, user_message)
+        } else {
+            user_message.to_string()
+        };
+
+        let (prompt_response, _, answered_by) =
+            self.prompt_with_fallback(&prompt, &history).await?;
+        let usage = TokenUsage::from_provider(
+            Some(prompt_response.total_usage),
+            &prompt,
+            &prompt_response.output,
+        );
+        let text = match answered_by {
+            Some(label) => format!(
+                "{}{}",
+                fallback_note(&self.provider_label(), &label),
+                prompt_response.output
+            ),
+            None => prompt_response.output,
+        };
+        Ok((text, usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::registry::RhaiToolRegistry;
+    use crate::tools::rhai_bridge_tool::{RhaiExecuteTool, SharedRegistry};
+    use std::path::PathBuf;
+
+    async fn test_registry() -> SharedRegistry {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&pool)
+            .await
+            .unwrap();
+        let mut registry = RhaiToolRegistry::new(pool, PathBuf::from("/tmp"), None, None);
+        registry
+            .register_tool("counter", "Counting tool", "42", vec![], None, None)
+            .await
+            .unwrap();
+        Arc::new(tokio::sync::RwLock::new(registry))
+    }
+
+    async fn usage_count(registry: &SharedRegistry) -> i32 {
+        registry
+            .read()
+            .await
+            .get_tool("counter")
+            .await
+            .unwrap()
+            .unwrap()
+            .usage_count
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_plan_mode_records_calls_without_executing() {
+        let registry = test_registry().await;
+        let plan: SharedToolPlan = Arc::new(ToolPlan::default());
+        let tools = apply_plan_mode(
+            vec![Box::new(RhaiExecuteTool::new(
+                registry.clone(),
+                vec![("counter".into(), "Counting tool".into())],
+            ))],
+            &plan,
+        );
+        let args = r#"{"tool_name":"counter","parameters":{"n":1}}"#;
+
+        plan.begin();
+        let result = tools[0].call(args.to_string()).await.unwrap();
+        assert!(result.contains("recorded but not executed"));
+        tools[0].call("not json".to_string()).await.unwrap();
+        let calls = plan.finish();
+
+        assert_eq!(usage_count(&registry).await, 0);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].tool, "execute_dynamic_tool");
+        assert_eq!(calls[0].args["parameters"]["n"], 1);
+        assert_eq!(calls[1].args, serde_json::json!("not json"));
+
+        // Outside plan mode, calls execute again
+        assert_eq!(tools[0].call(args.to_string()).await.unwrap(), "\"42\"");
+        assert_eq!(usage_count(&registry).await, 1);
+        assert!(plan.finish().is_empty());
+    }
+}
@@ -1,11 +1,22 @@
 use anyhow::Result;
 use rig::agent::Agent;
+use rig::client::{CompletionClient, Nothing};
 use rig::extractor::Extractor;
 use rig::providers::{anthropic, ollama, openai};
+use rig::tool::ToolDyn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::pin::Pin;
 
-use crate::memory::{FactExtractionResponse, SummaryExtractor, SummaryResponse};
+use crate::ai_instances::{APIKeyStorage, LLMProvider};
+use crate::canvas::bridge::JsonGenerator;
+use crate::memory::{FactExtractionResponse, FactExtractor, SummaryExtractor, SummaryResponse};
+use crate::tools::subagents::ClientProvider;
+
+/// Token budget of Anthropic extended thinking, below the agent's
+/// `max_tokens` of 32768
+const ANTHROPIC_THINKING_BUDGET_TOKENS: u64 = 16_384;
 
 /// Provider-specific agent wrapper.
 /// Each variant holds a fully-built Agent with tools registered.
@@ -15,6 +26,55 @@ pub(crate) enum AgentProvider {
     Ollama(Agent<ollama::CompletionModel>),
 }
 
+impl AgentProvider {
+    /// Build the chat agent for `client` with the given tools. With
+    /// `reasoning`, Anthropic models are asked for extended thinking so there
+    /// is reasoning to stream; other providers send it on their own.
+    pub(crate) fn new(
+        client: &ClientProvider,
+        model: &str,
+        preamble: &str,
+        name: &str,
+        tools: Vec<Box<dyn ToolDyn>>,
+        reasoning: bool,
+    ) -> Self {
+        match client {
+            ClientProvider::Anthropic(c) => {
+                let builder = c.agent(model).preamble(preamble).max_tokens(32768);
+                // Extended thinking only works with the default temperature
+                let builder = if reasoning {
+                    builder.additional_params(serde_json::json!({
+                        "thinking": {
+                            "type": "enabled",
+                            "budget_tokens": ANTHROPIC_THINKING_BUDGET_TOKENS,
+                        }
+                    }))
+                } else {
+                    builder.temperature(0.7)
+                };
+                Self::Anthropic(builder.name(name).tools(tools).build())
+            }
+            ClientProvider::OpenAI(c) => Self::OpenAI(
+                c.clone()
+                    .completions_api()
+                    .agent(model)
+                    .preamble(preamble)
+                    .temperature(0.7)
+                    .name(name)
+                    .tools(tools)
+                    .build(),
+            ),
+            ClientProvider::Ollama(c) => Self::Ollama(
+                c.agent(model)
+                    .preamble(preamble)
+                    .name(name)
+                    .tools(tools)
+                    .build(),
+            ),
+        }
+    }
+}
+
 /// Provider-specific extractor for structured summary extraction from LLM.
 /// Uses rig Extractors for type-safe structured output via tool-based extraction.
 pub(crate) enum SummaryExtractorProvider {
@@ -23,6 +83,31 @@ pub(crate) enum SummaryExtractorProvider {
     Ollama(Extractor<ollama::CompletionModel, SummaryResponse>),
 }
 
+impl SummaryExtractorProvider {
+    pub(crate) fn new(client: &ClientProvider, model: &str, preamble: &str) -> Self {
+        match client {
+            ClientProvider::Anthropic(c) => Self::Anthropic(
+                c.extractor::<SummaryResponse>(model)
+                    .preamble(preamble)
+                    .max_tokens(8192)
+                    .build(),
+            ),
+            ClientProvider::OpenAI(c) => Self::OpenAI(
+                c.clone()
+                    .completions_api()
+                    .extractor::<SummaryResponse>(model)
+                    .preamble(preamble)
+                    .build(),
+            ),
+            ClientProvider::Ollama(c) => Self::Ollama(
+                c.extractor::<SummaryResponse>(model)
+                    .preamble(preamble)
+                    .build(),
+            ),
+        }
+    }
+}
+
 impl SummaryExtractor for SummaryExtractorProvider {
     fn extract_summary<'a>(
         &'a self,
@@ -47,12 +132,195 @@ pub(crate) enum FactExtractorProvider {
 }
 
 impl FactExtractorProvider {
-    /// Extract facts from a conversation turn
-    pub(crate) async fn extract(&self, text: &str) -> Result<FactExtractionResponse> {
-        match self {
-            Self::Anthropic(e) => Ok(e.extract(text).await?),
-            Self::OpenAI(e) => Ok(e.extract(text).await?),
-            Self::Ollama(e) => Ok(e.extract(text).await?),
+    pub(crate) fn new(client: &ClientProvider, model: &str, preamble: &str) -> Self {
+        match client {
+            ClientProvider::Anthropic(c) => Self::Anthropic(
+                c.extractor::<FactExtractionResponse>(model)
+                    .preamble(preamble)
+                    .max_tokens(4096)
+                    .build(),
+            ),
+            ClientProvider::OpenAI(c) => Self::OpenAI(
+                c.clone()
+                    .completions_api()
+                    .extractor::<FactExtractionResponse>(model)
+                    .preamble(preamble)
+                    .build(),
+            ),
+            ClientProvider::Ollama(c) => Self::Ollama(
+                c.extractor::<FactExtractionResponse>(model)
+                    .preamble(preamble)
+                    .build(),
+            ),
         }
     }
 }
+
+impl FactExtractor for FactExtractorProvider {
+    fn extract_facts<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<FactExtractionResponse>> + Send + 'a>> {
+        Box::pin(async move {
+            match self {
+                Self::Anthropic(e) => Ok(e.extract(text).await?),
+                Self::OpenAI(e) => Ok(e.extract(text).await?),
+                Self::Ollama(e) => Ok(e.extract(text).await?),
+            }
+        })
+    }
+}
+
+/// Structured output with a caller-provided schema. Extractors need a static
+/// type, so the schema only constrains the `data` field via the preamble.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct JsonOutput {
+    /// The answer, as JSON conforming to the requested schema
+    pub data: serde_json::Value,
+}
+
+/// Provider-specific one-off extractor returning free-form JSON.
+pub(crate) enum JsonExtractorProvider {
+    Anthropic(Extractor<anthropic::completion::CompletionModel, JsonOutput>),
+    OpenAI(Extractor<openai::CompletionModel, JsonOutput>),
+    Ollama(Extractor<ollama::CompletionModel, JsonOutput>),
+}
+
+impl JsonExtractorProvider {
+    pub(crate) fn new(client: &ClientProvider, model: &str, preamble: &str) -> Self {
+        match client {
+            ClientProvider::Anthropic(c) => Self::Anthropic(
+                c.extractor::<JsonOutput>(model)
+                    .preamble(preamble)
+                    .max_tokens(4096)
+                    .build(),
+            ),
+            ClientProvider::OpenAI(c) => Self::OpenAI(
+                c.clone()
+                    .completions_api()
+                    .extractor::<JsonOutput>(model)
+                    .preamble(preamble)
+                    .build(),
+            ),
+            ClientProvider::Ollama(c) => {
+                Self::Ollama(c.extractor::<JsonOutput>(model).preamble(preamble).build())
+            }
+        }
+    }
+}
+
+impl JsonGenerator for JsonExtractorProvider {
+    fn generate_json<'a>(
+        &'a self,
+        prompt: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value>> + Send + 'a>> {
+        Box::pin(async move {
+            let output = match self {
+                Self::Anthropic(e) => e.extract(prompt).await?,
+                Self::OpenAI(e) => e.extract(prompt).await?,
+                Self::Ollama(e) => e.extract(prompt).await?,
+            };
+            Ok(output.data)
+        })
+    }
+}
+
+/// Build the LLM client for `provider`. The API key is loaded from the
+/// keychain for providers that need one.
+pub(crate) fn build_client(
+    provider: &LLMProvider,
+    api_base_url: Option<&str>,
+) -> Result<ClientProvider> {
+    let api_key = if provider.needs_api_key() {
+        APIKeyStorage::load(provider, api_base_url)?
+            .ok_or_else(|| anyhow::anyhow!("API key not found for provider: {}", provider))?
+    } else {
+        String::new()
+    };
+
+    let client = match provider {
+        LLMProvider::Anthropic => {
+            ClientProvider::Anthropic(anthropic::Client::builder().api_key(&api_key).build()?)
+        }
+        LLMProvider::OpenAI | LLMProvider::OpenAICompatible => {
+            ClientProvider::OpenAI(openai_client(provider, &api_key, api_base_url)?)
+        }
+        LLMProvider::Ollama => ClientProvider::Ollama(match api_base_url {
+            Some(url) => ollama::Client::builder()
+                .api_key(Nothing)
+                .base_url(url)
+                .build()?,
+            None => ollama::Client::new(Nothing)?,
+        }),
+    };
+    Ok(client)
+}
+
+/// Build an OpenAI client for `provider`. `OpenAICompatible` instances
+/// (Mistral, Groq, ...) must set `api_base_url`; plain OpenAI uses it as an
+/// optional override of the default endpoint.
+pub(crate) fn openai_client(
+    provider: &LLMProvider,
+    api_key: &str,
+    api_base_url: Option<&str>,
+) -> Result<openai::Client> {
+    let builder = openai::Client::builder().api_key(api_key);
+    let client = match (provider, api_base_url) {
+        (_, Some(url)) => builder.base_url(url).build()?,
+        (LLMProvider::OpenAICompatible, None) => {
+            anyhow::bail!("Provider {} requires an API base URL", provider)
+        }
+        (_, None) => builder.build()?,
+    };
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_compatible_client_uses_base_url() {
+        let url = "https://api.groq.com/openai/v1";
+        let client = openai_client(&LLMProvider::OpenAICompatible, "gsk-test", Some(url)).unwrap();
+        assert_eq!(client.base_url(), url);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_agent_thinks_when_streaming_reasoning() {
+        let client = ClientProvider::Anthropic(
+            anthropic::Client::builder()
+                .api_key("sk-ant-test")
+                .build()
+                .unwrap(),
+        );
+        let build = |reasoning| match AgentProvider::new(
+            &client,
+            "claude-sonnet-4-5",
+            "",
+            "Test",
+            vec![],
+            reasoning,
+        ) {
+            AgentProvider::Anthropic(agent) => agent,
+            _ => unreachable!(),
+        };
+
+        let agent = build(true);
+        assert_eq!(agent.temperature, None);
+        let thinking = &agent.additional_params.unwrap()["thinking"];
+        assert_eq!(thinking["type"], "enabled");
+        assert!(thinking["budget_tokens"].as_u64().unwrap() < agent.max_tokens.unwrap());
+
+        let agent = build(false);
+        assert_eq!(agent.temperature, Some(0.7));
+        assert!(agent.additional_params.is_none());
+    }
+
+    #[test]
+    fn test_openai_compatible_client_requires_base_url() {
+        assert!(openai_client(&LLMProvider::OpenAICompatible, "key", None).is_err());
+        let client = openai_client(&LLMProvider::OpenAI, "sk-test", None).unwrap();
+        assert_eq!(client.base_url(), "https://api.openai.com/v1");
+    }
+}
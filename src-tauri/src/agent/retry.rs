@@ -0,0 +1,202 @@
+//! Retry policy for transient LLM provider errors.
+//!
+//! Rate limits, timeouts and temporary server errors are retried with
+//! exponential backoff. Authentication and validation errors are surfaced
+//! immediately since retrying cannot fix them. Errors are classified by the
+//! rig `CompletionError` (HTTP status or provider error type), not by text.
+
+use rig::completion::CompletionError;
+use rig::http_client::Error as HttpClientError;
+use std::time::Duration;
+
+/// Maximum number of retries after the initial attempt.
+pub(super) const MAX_LLM_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled on each subsequent attempt.
+pub(super) const RETRY_BASE_DELAY_MS: u64 = 1000;
+
+/// Error types in JSON provider error bodies that mark transient failures
+/// (Anthropic `error.type`, OpenAI `error.type` / `error.code`).
+const TRANSIENT_ERROR_TYPES: &[&str] = &[
+    "rate_limit_error",
+    "overloaded_error",
+    "api_error",
+    "timeout_error",
+    "server_error",
+    "rate_limit_exceeded",
+];
+
+/// Rate limits, request timeouts and server errors (including Anthropic's 529).
+fn is_retryable_status(status: u16) -> bool {
+    status == 408 || status == 429 || (500..600).contains(&status)
+}
+
+/// HTTP status of a failed request that rig only reports as text
+/// (`Invalid status code: 529 ...`, `Got error status code ...: 503 ...`).
+fn status_in_message(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("status code")?;
+    let digits: String = rest
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Whether a JSON provider error body reports a transient error type.
+fn is_transient_error_body(body: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return false;
+    };
+    ["type", "code"]
+        .iter()
+        .filter_map(|key| value["error"][key].as_str())
+        .any(|kind| TRANSIENT_ERROR_TYPES.contains(&kind))
+}
+
+fn is_retryable_http_error(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::InvalidStatusCode(status)
+        | HttpClientError::InvalidStatusCodeWithMessage(status, _) => {
+            is_retryable_status(status.as_u16())
+        }
+        // Transport failures without a response (connect, timeout, reset)
+        HttpClientError::Instance(_) | HttpClientError::StreamEnded => true,
+        _ => false,
+    }
+}
+
+/// Whether a rig completion error is transient. Authentication and
+/// validation failures (other 4xx statuses and error types) are not.
+pub(super) fn is_retryable_completion_error(error: &CompletionError) -> bool {
+    match error {
+        CompletionError::HttpError(e) => is_retryable_http_error(e),
+        CompletionError::ProviderError(body) => match status_in_message(body) {
+            Some(status) => is_retryable_status(status),
+            None => is_transient_error_body(body),
+        },
+        _ => false,
+    }
+}
+
+/// The rig completion error behind `error` (possibly wrapped in a
+/// `PromptError`, `StreamingError` or context), if any.
+pub(super) fn completion_error(error: &anyhow::Error) -> Option<&CompletionError> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CompletionError>())
+}
+
+/// Decide whether an LLM error is transient and the request should be retried.
+pub(super) fn is_retryable_error(error: &anyhow::Error) -> bool {
+    completion_error(error).is_some_and(is_retryable_completion_error)
+}
+
+/// Backoff delay before retry number `attempt` (0-based).
+pub(super) fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(RETRY_BASE_DELAY_MS.saturating_mul(1u64 << attempt.min(10)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::completion::PromptError;
+    use tauri::http::StatusCode;
+
+    fn provider_error(body: &str) -> anyhow::Error {
+        PromptError::CompletionError(CompletionError::ProviderError(body.to_string())).into()
+    }
+
+    fn status_error(status: u16) -> anyhow::Error {
+        let status = StatusCode::from_u16(status).unwrap();
+        CompletionError::HttpError(HttpClientError::InvalidStatusCode(status)).into()
+    }
+
+    #[test]
+    fn test_rate_limit_is_retryable() {
+        assert!(is_retryable_error(&status_error(429)));
+        assert!(is_retryable_error(&provider_error(
+            r#"{"type":"error","error":{"type":"rate_limit_error","message":"slow down"}}"#
+        )));
+        assert!(is_retryable_error(&provider_error(
+            r#"{"error":{"type":"requests","code":"rate_limit_exceeded"}}"#
+        )));
+    }
+
+    #[test]
+    fn test_timeouts_and_server_errors_are_retryable() {
+        for status in [408, 500, 502, 503, 504, 529] {
+            assert!(is_retryable_error(&status_error(status)), "{}", status);
+        }
+        let transport: Box<dyn std::error::Error + Send + Sync> = "connection reset by peer".into();
+        assert!(is_retryable_error(
+            &CompletionError::HttpError(HttpClientError::Instance(transport)).into()
+        ));
+        assert!(is_retryable_error(&provider_error(
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+        )));
+        // Statuses that rig only reports as text (streaming requests)
+        assert!(is_retryable_error(&provider_error(
+            "SSE Error: Invalid status code: 529 <unknown status code>"
+        )));
+        assert!(is_retryable_error(&provider_error(
+            "Got error status code trying to send a request to Ollama: 503 Service Unavailable"
+        )));
+    }
+
+    #[test]
+    fn test_auth_errors_are_not_retryable() {
+        assert!(!is_retryable_error(&status_error(401)));
+        assert!(!is_retryable_error(&status_error(403)));
+        assert!(!is_retryable_error(&provider_error(
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#
+        )));
+        assert!(!is_retryable_error(&provider_error(
+            "SSE Error: Invalid status code: 401 Unauthorized"
+        )));
+    }
+
+    #[test]
+    fn test_validation_errors_are_not_retryable() {
+        assert!(!is_retryable_error(&status_error(400)));
+        assert!(!is_retryable_error(&provider_error(
+            r#"{"type":"error","error":{"type":"invalid_request_error","message":"timeout must be positive"}}"#
+        )));
+    }
+
+    #[test]
+    fn test_non_completion_errors_are_not_retryable() {
+        // Error text alone never makes an error retryable
+        assert!(!is_retryable_error(&anyhow::anyhow!(
+            "503 Service Unavailable: request timed out"
+        )));
+        assert!(!is_retryable_error(
+            &PromptError::MaxTurnsError {
+                max_turns: 5,
+                chat_history: Box::default(),
+                prompt: Box::new(rig::message::Message::user("hi")),
+            }
+            .into()
+        ));
+    }
+
+    #[test]
+    fn test_wrapped_completion_errors_are_found() {
+        let error = status_error(429).context("Streaming error");
+        assert!(completion_error(&error).is_some());
+        assert!(is_retryable_error(&error));
+    }
+
+    #[test]
+    fn test_retry_delay_is_exponential() {
+        assert_eq!(retry_delay(0), Duration::from_millis(RETRY_BASE_DELAY_MS));
+        assert_eq!(
+            retry_delay(1),
+            Duration::from_millis(RETRY_BASE_DELAY_MS * 2)
+        );
+        assert_eq!(
+            retry_delay(2),
+            Duration::from_millis(RETRY_BASE_DELAY_MS * 4)
+        );
+    }
+}
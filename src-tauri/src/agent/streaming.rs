@@ -4,22 +4,108 @@ use futures::StreamExt;
 use rig::agent::MultiTurnStreamItem;
 use rig::message::ToolResultContent as RigToolResultContent;
 use rig::streaming::{StreamedAssistantContent, StreamedUserContent, StreamingChat};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::memory::working_memory::Message;
 
+use super::fallback::{fallback_note, next_fallback};
 use super::providers::AgentProvider;
+use super::retry::{is_retryable_error, retry_delay, MAX_LLM_RETRIES};
+use super::usage::{ChatResult, TokenUsage};
 use super::OwnAIAgent;
-use super::MAX_TOOL_TURNS;
+
+/// Event emitted to the streaming callback.
+/// Text chunks carry the model output; tool events let the UI show
+/// progress indicators (e.g. "Running grep...") while tools execute.
+/// Reasoning chunks ("thinking") are only emitted when the instance enables
+/// `stream_reasoning`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Text { text: String },
+    Reasoning { text: String },
+    ToolCallStart { id: String, name: String },
+    ToolCallResult { id: String, name: String },
+}
+
+/// Number of consecutive failures of the same tool after which a streamed
+/// turn is aborted when the instance enables `stop_on_repeated_tool_error`.
+pub const MAX_CONSECUTIVE_TOOL_FAILURES: usize = 3;
+
+/// Whether a tool result text is an error reported by rig's tool server
+/// (failed call, unknown tool, or invalid JSON arguments).
+pub(crate) fn is_tool_error_result(text: &str) -> bool {
+    [
+        "Toolset error:",
+        "ToolCallError:",
+        "ToolNotFoundError:",
+        "JsonError:",
+    ]
+    .iter()
+    .any(|prefix| text.starts_with(prefix))
+}
+
+/// Counts consecutive failures of the same tool within one streamed turn.
+/// Any successful tool result resets the count; a failure of a different
+/// tool starts a new count for that tool.
+#[derive(Debug, Default)]
+pub(crate) struct ToolFailureTracker {
+    last_failed_tool: Option<String>,
+    consecutive_failures: usize,
+}
+
+impl ToolFailureTracker {
+    /// Record a tool result. Returns the number of consecutive failures of
+    /// `tool_name` so far (0 if the call succeeded).
+    pub(crate) fn record(&mut self, tool_name: &str, failed: bool) -> usize {
+        if !failed {
+            self.last_failed_tool = None;
+            self.consecutive_failures = 0;
+        } else if self.last_failed_tool.as_deref() == Some(tool_name) {
+            self.consecutive_failures += 1;
+        } else {
+            self.last_failed_tool = Some(tool_name.to_string());
+            self.consecutive_failures = 1;
+        }
+        self.consecutive_failures
+    }
+}
+
+/// Message shown instead of further turns when a tool keeps failing.
+fn repeated_tool_error_message(tool_name: &str, failures: usize, last_error: &str) -> String {
+    format!(
+        "Stopped: the tool '{}' failed {} times in a row, so the turn was aborted instead of retrying further.\n\nLast error: {}",
+        tool_name, failures, last_error
+    )
+}
 
 /// Macro to process streaming responses uniformly across providers.
 /// Handles text chunks, tool calls, tool results, and multi-turn items.
 /// Captures intermediate tool messages for DB persistence and the
 /// `FinalResponse` (if any) so callers can extract token usage.
+/// Sets `$emitted` once any text or tool call has been produced, and
+/// evaluates to `Err` if the stream fails.
+/// Stops early (evaluating to `Ok`) when `$cancel` is cancelled; whatever was
+/// produced so far stays in `$full_response` / `$intermediate_messages`.
+/// If `$stop_on_tool_error` is set and the same tool fails
+/// `MAX_CONSECUTIVE_TOOL_FAILURES` times in a row, the stream is dropped at
+/// the end of that assistant turn and an explanatory message becomes the
+/// response (also evaluating to `Ok`).
+/// If `$stream_reasoning` is set, reasoning content is forwarded as
+/// `StreamEvent::Reasoning`; otherwise it is dropped. Reasoning is never part
+/// of `$full_response`.
+#[rustfmt::skip]
 macro_rules! process_stream {
-    ($stream:expr, $callback:expr, $full_response:expr, $final_response:expr, $intermediate_messages:expr) => {
+    ($stream:expr, $callback:expr, $full_response:expr, $final_response:expr, $intermediate_messages:expr, $emitted:expr, $cancel:expr, $stop_on_tool_error:expr, $stream_reasoning:expr) => {
         {
+            let mut _stream_error: Option<anyhow::Error> = None;
+            let mut _tool_failures = ToolFailureTracker::default();
+            let mut _abort_message: Option<String> = None;
+            // Tool names by call ID, so result events can report which tool finished
+            let mut _tool_names: std::collections::HashMap<String, String> = std::collections::HashMap::new();
             let mut _current_turn_text = String::new();
             let mut _current_turn_tool_calls: Vec<crate::memory::working_memory::ToolCallData> = Vec::new();
             // Buffer tool results so they are pushed AFTER the agent+tool_calls
@@ -28,65 +114,120 @@ macro_rules! process_stream {
             // delivers ToolResult events before the Final event that closes the
             // assistant turn.
             let mut _pending_tool_results: Vec<crate::memory::working_memory::Message> = Vec::new();
+            // Providers that stream reasoning deltas (e.g. Anthropic) also send the
+            // complete reasoning block afterwards; it is only forwarded if no
+            // deltas were seen, so the UI does not get the text twice.
+            let mut _reasoning_streamed = false;
 
-            while let Some(result) = $stream.next().await {
+            loop {
+                // Checked before every item, which includes the start of each turn
+                if $cancel.is_cancelled() {
+                    break;
+                }
+                let next = tokio::select! {
+                    biased;
+                    _ = $cancel.cancelled() => break,
+                    next = $stream.next() => next,
+                };
+                let Some(result) = next else {
+                    break;
+                };
+                // Abort once the failing turn has been flushed
+                if _abort_message.is_some() && _current_turn_tool_calls.is_empty() {
+                    break;
+                }
                 match result {
                     Ok(item) => match item {
                         MultiTurnStreamItem::StreamAssistantItem(content) => match content {
                             StreamedAssistantContent::Text(text) => {
-                                $callback(text.text.clone());
+                                $emitted = true;
+                                $callback(StreamEvent::Text { text: text.text.clone() });
                                 $full_response.push_str(&text.text);
                                 _current_turn_text.push_str(&text.text);
                             }
+                            StreamedAssistantContent::ReasoningDelta { reasoning, .. } => {
+                                _reasoning_streamed = true;
+                                if $stream_reasoning && !reasoning.is_empty() {
+                                    $emitted = true;
+                                    $callback(StreamEvent::Reasoning { text: reasoning });
+                                }
+                            }
+                            StreamedAssistantContent::Reasoning(reasoning) => {
+                                let already_streamed = std::mem::take(&mut _reasoning_streamed);
+                                let text = reasoning.reasoning.join("");
+                                if $stream_reasoning && !already_streamed && !text.is_empty() {
+                                    $emitted = true;
+                                    $callback(StreamEvent::Reasoning { text });
+                                }
+                            }
                             StreamedAssistantContent::ToolCall { tool_call, .. } => {
+                                $emitted = true;
                                 _current_turn_tool_calls.push(crate::memory::working_memory::ToolCallData {
                                     id: tool_call.id.clone(),
                                     call_id: tool_call.call_id.clone(),
                                     name: tool_call.function.name.clone(),
                                     arguments: tool_call.function.arguments.clone(),
                                 });
+                                _tool_names.insert(tool_call.id.clone(), tool_call.function.name.clone());
+                                $callback(StreamEvent::ToolCallStart {
+                                    id: tool_call.id.clone(),
+                                    name: tool_call.function.name.clone(),
+                                });
                             }
-                            StreamedAssistantContent::Final(_) => {
-                                // End of assistant turn: if tool calls were made, save as intermediate.
+                            StreamedAssistantContent::Final(_) if !_current_turn_tool_calls.is_empty() => {
+                                // End of assistant turn with tool calls: save as intermediate.
                                 // Push agent+tool_calls FIRST, then any buffered tool results,
                                 // so the DB ordering matches the expected sequence:
                                 //   assistant(tool_use) -> user(tool_result)
-                                if !_current_turn_tool_calls.is_empty() {
-                                    $intermediate_messages.push(crate::memory::working_memory::Message {
-                                        id: uuid::Uuid::new_v4().to_string(),
-                                        role: "agent".to_string(),
-                                        content: _current_turn_text.clone(),
-                                        timestamp: chrono::Utc::now(),
-                                        importance_score: None,
-                                        metadata: Some(crate::memory::working_memory::MessageMetadata::ToolCalls {
-                                            calls: _current_turn_tool_calls.drain(..).collect(),
-                                        }),
-                                    });
-                                    // Now flush buffered tool results after the agent message
-                                    for mut tr in _pending_tool_results.drain(..) {
-                                        // Ensure tool result timestamp is after agent message
-                                        tr.timestamp = chrono::Utc::now();
-                                        $intermediate_messages.push(tr);
-                                    }
-                                    // Remove intermediate turn text from full_response so only
-                                    // the final turn's text remains as the agent message.
-                                    if $full_response.ends_with(&_current_turn_text) {
-                                        let new_len = $full_response.len() - _current_turn_text.len();
-                                        $full_response.truncate(new_len);
-                                    }
-                                    _current_turn_text.clear();
+                                $intermediate_messages.push(crate::memory::working_memory::Message {
+                                    id: uuid::Uuid::new_v4().to_string(),
+                                    role: "agent".to_string(),
+                                    content: _current_turn_text.clone(),
+                                    timestamp: chrono::Utc::now(),
+                                    importance_score: None,
+                                    metadata: Some(crate::memory::working_memory::MessageMetadata::ToolCalls {
+                                        calls: _current_turn_tool_calls.drain(..).collect(),
+                                    }),
+                                });
+                                // Now flush buffered tool results after the agent message
+                                for mut tr in _pending_tool_results.drain(..) {
+                                    // Ensure tool result timestamp is after agent message
+                                    tr.timestamp = chrono::Utc::now();
+                                    $intermediate_messages.push(tr);
+                                }
+                                // Remove intermediate turn text from full_response so only
+                                // the final turn's text remains as the agent message.
+                                if $full_response.ends_with(&_current_turn_text) {
+                                    let new_len = $full_response.len() - _current_turn_text.len();
+                                    $full_response.truncate(new_len);
                                 }
+                                _current_turn_text.clear();
                             }
                             _ => {} // Ignore other content types
                         },
                         MultiTurnStreamItem::StreamUserItem(user_content) => {
                             let StreamedUserContent::ToolResult { tool_result, .. } = user_content;
                             {
+                                let tool_name = _tool_names.get(&tool_result.id).cloned().unwrap_or_default();
+                                $callback(StreamEvent::ToolCallResult {
+                                    id: tool_result.id.clone(),
+                                    name: tool_name.clone(),
+                                });
+
                                 let result_text = tool_result.content.iter().map(|c| match c {
                                     RigToolResultContent::Text(t) => t.text.clone(),
                                     _ => String::new(),
                                 }).collect::<Vec<_>>().join("");
 
+                                let failures = _tool_failures.record(&tool_name, is_tool_error_result(&result_text));
+                                if $stop_on_tool_error
+                                    && failures >= MAX_CONSECUTIVE_TOOL_FAILURES
+                                    && _abort_message.is_none()
+                                {
+                                    tracing::warn!("Tool '{}' failed {} times in a row; aborting turn", tool_name, failures);
+                                    _abort_message = Some(repeated_tool_error_message(&tool_name, failures, &result_text));
+                                }
+
                                 // Buffer tool results instead of pushing directly.
                                 // They will be flushed in the correct order (after
                                 // the agent+tool_calls message) on the Final event.
@@ -111,8 +252,20 @@ macro_rules! process_stream {
                         _ => {} // Future variants
                     },
                     Err(e) => {
-                        return Err(anyhow::anyhow!("Streaming error: {}", e));
+                        _stream_error = Some(anyhow::Error::from(e).context("Streaming error"));
+                        break;
+                    }
+                }
+            }
+
+            match _stream_error {
+                Some(e) => Err(e),
+                None => {
+                    if let Some(message) = _abort_message {
+                        $callback(StreamEvent::Text { text: message.clone() });
+                        $full_response.push_str(&message);
                     }
+                    Ok(())
                 }
             }
         }
@@ -128,15 +281,17 @@ impl OwnAIAgent {
     pub async fn stream_chat(
         &mut self,
         user_message: &str,
-        callback: impl FnMut(String) + Send + 'static,
-    ) -> Result<String> {
+        cancel: CancellationToken,
+        callback: impl FnMut(StreamEvent) + Send + 'static,
+    ) -> Result<ChatResult> {
         let stream_span = tracing::info_span!(
             "ownai.stream_chat",
             instance_id = %self.instance_id,
             instance_name = %self.instance_name,
         );
         self.attach_langfuse_context(&stream_span);
-        self.stream_chat_inner(user_message, callback)
+        self.tool_budget.reset();
+        self.stream_chat_inner(user_message, cancel, callback)
             .instrument(stream_span)
             .await
     }
@@ -145,8 +300,9 @@ impl OwnAIAgent {
     async fn stream_chat_inner(
         &mut self,
         user_message: &str,
-        mut callback: impl FnMut(String) + Send + 'static,
-    ) -> Result<String> {
+        cancel: CancellationToken,
+        mut callback: impl FnMut(StreamEvent) + Send + 'static,
+    ) -> Result<ChatResult> {
         // Set GenAI semantic convention attributes on the parent span so that
         // Langfuse can display Input/Output and render the flow diagram.
         let current_span = tracing::Span::current();
@@ -170,11 +326,14 @@ impl OwnAIAgent {
         self.save_message_to_db(&user_msg).await?;
         self.add_to_working_memory(user_msg).await;
 
-        // 2. Build context
-        let context = self.context_builder.build_context(user_message).await?;
+        // 2. Build chat history from working memory (with time gap markers)
+        let mut history = self.build_history_with_time_markers();
 
-        // 3. Build chat history from working memory (with time gap markers)
-        let history = self.build_history_with_time_markers();
+        // 3. Build context, trimmed together with the history to fit the
+        //    model's window
+        let context = self
+            .build_prompt_context(&mut history, user_message)
+            .await?;
 
         // 4. Prepare prompt with memory context
         let prompt = if !context.is_empty() {
@@ -183,53 +342,117 @@ impl OwnAIAgent {
             user_message.to_string()
         };
 
-        // 5. Stream with multi-turn tool calling support
+        // 5. Stream with multi-turn tool calling support.
+        //    Transient provider errors are retried with exponential backoff,
+        //    but only while nothing has been emitted yet (no tokens streamed to
+        //    the UI and no tools executed), so a retry never duplicates output.
+        //    Under the same condition, provider failures move on to the next
+        //    fallback provider, announced by a note in the response.
         let mut full_response = String::new();
         let mut final_response: Option<rig::agent::FinalResponse> = None;
         let mut intermediate_messages: Vec<Message> = Vec::new();
+        let mut emitted = false;
+        let mut attempt = 0;
+        let mut current = 0;
 
-        match &self.agent {
-            AgentProvider::Anthropic(agent) => {
-                let mut stream = agent
-                    .stream_chat(&prompt, history)
-                    .multi_turn(MAX_TOOL_TURNS)
-                    .await;
-                process_stream!(
-                    stream,
-                    callback,
-                    full_response,
-                    final_response,
-                    intermediate_messages
-                );
-            }
-            AgentProvider::OpenAI(agent) => {
-                let mut stream = agent
-                    .stream_chat(&prompt, history)
-                    .multi_turn(MAX_TOOL_TURNS)
-                    .await;
-                process_stream!(
-                    stream,
-                    callback,
-                    full_response,
-                    final_response,
-                    intermediate_messages
-                );
-            }
-            AgentProvider::Ollama(agent) => {
-                let mut stream = agent
-                    .stream_chat(&prompt, history)
-                    .multi_turn(MAX_TOOL_TURNS)
-                    .await;
-                process_stream!(
-                    stream,
-                    callback,
-                    full_response,
-                    final_response,
-                    intermediate_messages
-                );
+        loop {
+            let history = history.clone();
+            let outcome: Result<()> = match self.agent_at(current) {
+                AgentProvider::Anthropic(agent) => {
+                    let mut stream = agent
+                        .stream_chat(&prompt, history)
+                        .multi_turn(self.max_tool_turns)
+                        .await;
+                    process_stream!(
+                        stream,
+                        callback,
+                        full_response,
+                        final_response,
+                        intermediate_messages,
+                        emitted,
+                        cancel,
+                        self.stop_on_repeated_tool_error,
+                        self.stream_reasoning
+                    )
+                }
+                AgentProvider::OpenAI(agent) => {
+                    let mut stream = agent
+                        .stream_chat(&prompt, history)
+                        .multi_turn(self.max_tool_turns)
+                        .await;
+                    process_stream!(
+                        stream,
+                        callback,
+                        full_response,
+                        final_response,
+                        intermediate_messages,
+                        emitted,
+                        cancel,
+                        self.stop_on_repeated_tool_error,
+                        self.stream_reasoning
+                    )
+                }
+                AgentProvider::Ollama(agent) => {
+                    let mut stream = agent
+                        .stream_chat(&prompt, history)
+                        .multi_turn(self.max_tool_turns)
+                        .await;
+                    process_stream!(
+                        stream,
+                        callback,
+                        full_response,
+                        final_response,
+                        intermediate_messages,
+                        emitted,
+                        cancel,
+                        self.stop_on_repeated_tool_error,
+                        self.stream_reasoning
+                    )
+                }
+            };
+
+            match outcome {
+                Ok(()) => break,
+                Err(e)
+                    if !emitted
+                        && !cancel.is_cancelled()
+                        && attempt < MAX_LLM_RETRIES
+                        && is_retryable_error(&e) =>
+                {
+                    let delay = retry_delay(attempt);
+                    attempt += 1;
+                    tracing::warn!(
+                        "Transient streaming error (attempt {}/{}), retrying in {:?}: {}",
+                        attempt,
+                        MAX_LLM_RETRIES,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) if !emitted && !cancel.is_cancelled() => {
+                    let Some(next) = next_fallback(&e, current, self.fallbacks.len()) else {
+                        return Err(e);
+                    };
+                    let label = self.fallbacks[next - 1].label.clone();
+                    tracing::warn!("Streaming failed, falling back to {}: {}", label, e);
+                    let note = fallback_note(&self.agent_label(current), &label);
+                    callback(StreamEvent::Text { text: note.clone() });
+                    full_response.push_str(&note);
+                    current = next;
+                    attempt = 0;
+                }
+                Err(e) => return Err(e),
             }
         }
 
+        if cancel.is_cancelled() {
+            tracing::info!(
+                "Stream cancelled by user; saving partial response ({} chars)",
+                full_response.len()
+            );
+        }
+
         // Record token usage from FinalResponse and persist to DB
         if let Some(ref res) = final_response {
             let usage = res.usage();
@@ -286,6 +509,417 @@ impl OwnAIAgent {
         current_span.set_attribute("gen_ai.completion.0.role", "assistant");
         current_span.set_attribute("gen_ai.completion.0.content", full_response.clone());
 
-        Ok(full_response)
+        let usage = TokenUsage::from_provider(
+            final_response.as_ref().map(|res| res.usage()),
+            &prompt,
+            &full_response,
+        );
+
+        Ok(ChatResult {
+            text: full_response,
+            usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::agent::StreamingError;
+    use rig::completion::CompletionError;
+    use rig::message::{Reasoning, ToolCall, ToolFunction, ToolResult};
+    use rig::OneOrMany;
+
+    type FakeItem = Result<MultiTurnStreamItem<()>, StreamingError>;
+
+    fn tool_call_item(id: &str, name: &str) -> FakeItem {
+        Ok(MultiTurnStreamItem::StreamAssistantItem(
+            StreamedAssistantContent::ToolCall {
+                tool_call: ToolCall::new(
+                    id.to_string(),
+                    ToolFunction {
+                        name: name.to_string(),
+                        arguments: serde_json::json!({"pattern": "TODO"}),
+                    },
+                ),
+                internal_call_id: format!("internal-{}", id),
+            },
+        ))
+    }
+
+    fn tool_result_item(id: &str, text: &str) -> FakeItem {
+        Ok(MultiTurnStreamItem::StreamUserItem(
+            StreamedUserContent::ToolResult {
+                tool_result: ToolResult {
+                    id: id.to_string(),
+                    call_id: None,
+                    content: OneOrMany::one(RigToolResultContent::text(text)),
+                },
+                internal_call_id: format!("internal-{}", id),
+            },
+        ))
+    }
+
+    /// Everything the stream macro produced for a fake stream.
+    struct FakeStreamRun {
+        outcome: Result<()>,
+        events: Vec<StreamEvent>,
+        full_response: String,
+        final_response: Option<rig::agent::FinalResponse>,
+        intermediate_messages: Vec<Message>,
+        emitted: bool,
+    }
+
+    /// Drive the stream macro over a fake stream and collect what it produces.
+    async fn run_fake_stream(items: Vec<FakeItem>, cancel: CancellationToken) -> FakeStreamRun {
+        run_fake_stream_with_policy(items, cancel, false, false).await
+    }
+
+    async fn run_fake_stream_with_policy(
+        items: Vec<FakeItem>,
+        cancel: CancellationToken,
+        stop_on_tool_error: bool,
+        stream_reasoning: bool,
+    ) -> FakeStreamRun {
+        let mut stream = futures::stream::iter(items);
+        let mut events = Vec::new();
+        let mut callback = |event: StreamEvent| events.push(event);
+        let mut full_response = String::new();
+        let mut final_response: Option<rig::agent::FinalResponse> = None;
+        let mut intermediate_messages: Vec<Message> = Vec::new();
+        let mut emitted = false;
+
+        let outcome: Result<()> = process_stream!(
+            stream,
+            callback,
+            full_response,
+            final_response,
+            intermediate_messages,
+            emitted,
+            cancel,
+            stop_on_tool_error,
+            stream_reasoning
+        );
+
+        FakeStreamRun {
+            outcome,
+            events,
+            full_response,
+            final_response,
+            intermediate_messages,
+            emitted,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_emits_tool_events_in_order() {
+        let items = vec![
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Let me search. "),
+            )),
+            tool_call_item("call-1", "grep"),
+            tool_result_item("call-1", "main.rs:1: TODO"),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Final(()),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Found one TODO."),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Final(()),
+            )),
+            Ok(MultiTurnStreamItem::FinalResponse(
+                rig::agent::FinalResponse::empty(),
+            )),
+        ];
+
+        let run = run_fake_stream(items, CancellationToken::new()).await;
+
+        assert!(run.outcome.is_ok());
+        assert!(run.emitted);
+        assert!(run.final_response.is_some());
+        assert_eq!(
+            run.events,
+            vec![
+                StreamEvent::Text {
+                    text: "Let me search. ".to_string()
+                },
+                StreamEvent::ToolCallStart {
+                    id: "call-1".to_string(),
+                    name: "grep".to_string()
+                },
+                StreamEvent::ToolCallResult {
+                    id: "call-1".to_string(),
+                    name: "grep".to_string()
+                },
+                StreamEvent::Text {
+                    text: "Found one TODO.".to_string()
+                },
+            ]
+        );
+        // Text accumulation is unchanged: only the final turn's text remains
+        assert_eq!(run.full_response, "Found one TODO.");
+        assert_eq!(run.intermediate_messages.len(), 2);
+        assert_eq!(run.intermediate_messages[0].role, "agent");
+        assert_eq!(run.intermediate_messages[1].role, "tool_result");
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_returned_without_events() {
+        let items: Vec<FakeItem> = vec![Err(StreamingError::Completion(
+            CompletionError::ProviderError(
+                "SSE Error: Invalid status code: 429 Too Many Requests".to_string(),
+            ),
+        ))];
+
+        let run = run_fake_stream(items, CancellationToken::new()).await;
+
+        // The rig error type is kept, so the failure is classified as transient
+        assert!(is_retryable_error(run.outcome.as_ref().unwrap_err()));
+        assert!(run.events.is_empty());
+        assert!(run.full_response.is_empty());
+        assert!(run.final_response.is_none());
+        assert!(!run.emitted);
+    }
+
+    #[tokio::test]
+    async fn test_pre_cancelled_token_stops_immediately() {
+        let items = vec![
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Should not be seen"),
+            )),
+            tool_call_item("call-1", "grep"),
+        ];
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let run = run_fake_stream(items, cancel).await;
+
+        assert!(run.outcome.is_ok());
+        assert!(run.events.is_empty());
+        assert!(run.full_response.is_empty());
+        assert!(run.intermediate_messages.is_empty());
+        assert!(!run.emitted);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_stream_keeps_partial_response() {
+        let cancel = CancellationToken::new();
+        let cancel_after_first = cancel.clone();
+        let items: Vec<FakeItem> = vec![
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Partial "),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("never streamed"),
+            )),
+        ];
+        // Cancel as soon as the first chunk is observed downstream
+        let mut stream = futures::stream::iter(items).inspect(move |_| {
+            if !cancel_after_first.is_cancelled() {
+                cancel_after_first.cancel();
+            }
+        });
+        let mut events = Vec::new();
+        let mut callback = |event: StreamEvent| events.push(event);
+        let mut full_response = String::new();
+        let mut final_response: Option<rig::agent::FinalResponse> = None;
+        let mut intermediate_messages: Vec<Message> = Vec::new();
+        let mut emitted = false;
+
+        let outcome: Result<()> = process_stream!(
+            stream,
+            callback,
+            full_response,
+            final_response,
+            intermediate_messages,
+            emitted,
+            cancel,
+            false,
+            false
+        );
+
+        assert!(outcome.is_ok());
+        assert!(final_response.is_none());
+        assert!(intermediate_messages.is_empty());
+        assert!(emitted);
+        assert_eq!(full_response, "Partial ");
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_tool_failure_tracker_counts_consecutive_failures() {
+        let mut tracker = ToolFailureTracker::default();
+        assert_eq!(tracker.record("grep", true), 1);
+        assert_eq!(tracker.record("grep", true), 2);
+        // A success resets the count
+        assert_eq!(tracker.record("grep", false), 0);
+        assert_eq!(tracker.record("grep", true), 1);
+        // A different failing tool starts its own count
+        assert_eq!(tracker.record("read_file", true), 1);
+        assert_eq!(tracker.record("grep", true), 1);
+        assert_eq!(tracker.record("grep", true), 2);
+        assert_eq!(tracker.record("grep", true), 3);
+    }
+
+    #[test]
+    fn test_is_tool_error_result() {
+        assert!(is_tool_error_result(
+            "Toolset error: ToolCallError: File not found"
+        ));
+        assert!(is_tool_error_result("ToolNotFoundError: fly"));
+        assert!(is_tool_error_result("JsonError: missing field `path`"));
+        assert!(!is_tool_error_result("main.rs:1: ToolCallError: in source"));
+        assert!(!is_tool_error_result("ok"));
+    }
+
+    /// One assistant turn that calls `name` and gets `result` back.
+    fn tool_turn(id: &str, name: &str, result: &str) -> Vec<FakeItem> {
+        vec![
+            tool_call_item(id, name),
+            tool_result_item(id, result),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Final(()),
+            )),
+        ]
+    }
+
+    fn failing_turns(count: usize) -> Vec<FakeItem> {
+        let mut items: Vec<FakeItem> = (0..count)
+            .flat_map(|i| {
+                tool_turn(
+                    &format!("call-{}", i),
+                    "read_file",
+                    "Toolset error: ToolCallError: File not found",
+                )
+            })
+            .collect();
+        items.push(Ok(MultiTurnStreamItem::StreamAssistantItem(
+            StreamedAssistantContent::text("Giving up."),
+        )));
+        items
+    }
+
+    #[tokio::test]
+    async fn test_repeated_tool_error_aborts_turn_when_enabled() {
+        let run = run_fake_stream_with_policy(
+            failing_turns(MAX_CONSECUTIVE_TOOL_FAILURES + 2),
+            CancellationToken::new(),
+            true,
+            false,
+        )
+        .await;
+
+        assert!(run.outcome.is_ok());
+        assert!(run
+            .full_response
+            .starts_with("Stopped: the tool 'read_file' failed 3 times"));
+        assert!(run.full_response.contains("File not found"));
+        // Only the failing turns up to the limit were recorded
+        assert_eq!(
+            run.intermediate_messages.len(),
+            2 * MAX_CONSECUTIVE_TOOL_FAILURES
+        );
+        assert!(matches!(run.events.last(), Some(StreamEvent::Text { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_tool_error_ignored_when_disabled() {
+        let run = run_fake_stream_with_policy(
+            failing_turns(MAX_CONSECUTIVE_TOOL_FAILURES + 2),
+            CancellationToken::new(),
+            false,
+            false,
+        )
+        .await;
+
+        assert!(run.outcome.is_ok());
+        assert_eq!(run.full_response, "Giving up.");
+        assert_eq!(
+            run.intermediate_messages.len(),
+            2 * (MAX_CONSECUTIVE_TOOL_FAILURES + 2)
+        );
+    }
+
+    /// A turn with streamed reasoning deltas, the complete reasoning block
+    /// (as sent by Anthropic), a reasoning-only block, and the answer text.
+    fn reasoning_turn() -> Vec<FakeItem> {
+        vec![
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::ReasoningDelta {
+                    id: None,
+                    reasoning: "The user wants ".to_string(),
+                },
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::ReasoningDelta {
+                    id: None,
+                    reasoning: "a greeting.".to_string(),
+                },
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Reasoning(Reasoning::new("The user wants a greeting.")),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Reasoning(Reasoning::new("Keep it short.")),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Hello!"),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Final(()),
+            )),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_routes_to_reasoning_callback_when_enabled() {
+        let run =
+            run_fake_stream_with_policy(reasoning_turn(), CancellationToken::new(), false, true)
+                .await;
+
+        assert!(run.outcome.is_ok());
+        let mut reasoning = Vec::new();
+        let mut text = Vec::new();
+        for event in run.events {
+            match event {
+                StreamEvent::Reasoning { text: chunk } => reasoning.push(chunk),
+                StreamEvent::Text { text: chunk } => text.push(chunk),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        // The complete block after the deltas is not repeated
+        assert_eq!(
+            reasoning,
+            vec!["The user wants ", "a greeting.", "Keep it short."]
+        );
+        assert_eq!(text, vec!["Hello!"]);
+        // Reasoning never becomes part of the saved response
+        assert_eq!(run.full_response, "Hello!");
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_dropped_when_disabled() {
+        let run = run_fake_stream(reasoning_turn(), CancellationToken::new()).await;
+
+        assert!(run.outcome.is_ok());
+        assert_eq!(
+            run.events,
+            vec![StreamEvent::Text {
+                text: "Hello!".to_string()
+            }]
+        );
+        assert_eq!(run.full_response, "Hello!");
+    }
+
+    #[test]
+    fn test_stream_event_serialization() {
+        let event = StreamEvent::ToolCallStart {
+            id: "call-1".to_string(),
+            name: "grep".to_string(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "tool_call_start");
+        assert_eq!(json["name"], "grep");
     }
 }
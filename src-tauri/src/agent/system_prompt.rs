@@ -5,7 +5,57 @@ use super::OwnAIAgent;
 impl OwnAIAgent {
     /// System prompt for ownAI -- includes identity, delegation instructions,
     /// and shared tool documentation from `base_tools_prompt()`.
-    pub(super) fn system_prompt(instance_name: &str) -> String {
+    /// Optional user instructions are appended in a delimited section so the
+    /// core instructions stay intact.
+    pub(super) fn system_prompt(instance_name: &str, custom_instructions: Option<&str>) -> String {
+        let base = Self::base_system_prompt(instance_name);
+        match custom_instructions.map(str::trim).filter(|c| !c.is_empty()) {
+            Some(custom) => format!(
+                "{base}\n\n## Custom Instructions\n\n\
+                The user provided the following additional instructions. Follow them \
+                unless they conflict with the guidelines above.\n\n\
+                <custom_instructions>\n{custom}\n</custom_instructions>"
+            ),
+            None => base,
+        }
+    }
+
+    /// Preamble of the summary extractor. With a `language`, the summary is
+    /// written in that language.
+    pub(super) fn summary_preamble(language: Option<&str>) -> String {
+        with_language(
+            "Extract a structured summary from the conversation below. \
+            Identify the key facts discussed, any tools that were used or mentioned, \
+            and the main topics covered. Be concise but thorough.",
+            "Write the summary",
+            language,
+        )
+    }
+
+    /// Preamble of the fact extractor. With a `language`, facts are written in
+    /// that language.
+    pub(super) fn fact_preamble(language: Option<&str>) -> String {
+        with_language(
+            "Extract important, long-term relevant facts from this conversation turn. \
+            Focus on: user preferences, skills they mention, factual information about the user, \
+            important context for future conversations, and successful tool usage patterns. \
+            Ignore temporary context or trivial details. Each fact should be concise and self-contained.",
+            "Write each fact",
+            language,
+        )
+    }
+
+    /// Preamble of the one-off JSON extractor used by the Canvas bridge
+    /// `chatJson` method. The schema describes the submitted `data` field.
+    pub(super) fn json_output_preamble(schema: &serde_json::Value) -> String {
+        format!(
+            "Answer the request below. Submit your answer as the `data` field; it must be \
+            JSON that conforms to this JSON schema:\n{}",
+            serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string())
+        )
+    }
+
+    fn base_system_prompt(instance_name: &str) -> String {
         format!(
             r#"You are {name}, a personal AI agent that evolves with your user.
 
@@ -61,3 +111,61 @@ Remember: You are building a long-term relationship with this user."#,
         )
     }
 }
+
+/// Append an instruction to write the output in `language` (if set).
+fn with_language(preamble: &str, subject: &str, language: Option<&str>) -> String {
+    match language.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(language) => format!(
+            "{preamble} {subject} in {language}, even if parts of the conversation \
+            are in another language."
+        ),
+        None => preamble.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_prompt_appends_custom_instructions() {
+        let prompt = OwnAIAgent::system_prompt("Ava", Some("  Always answer in German.  "));
+
+        assert!(prompt.starts_with("You are Ava, a personal AI agent"));
+        assert!(prompt.contains("## Response Guidelines"));
+        assert!(prompt.contains(
+            "## Custom Instructions\n\nThe user provided the following additional instructions."
+        ));
+        assert!(prompt
+            .ends_with("<custom_instructions>\nAlways answer in German.\n</custom_instructions>"));
+        // Custom section comes after the core instructions
+        assert!(
+            prompt.find("## Response Guidelines").unwrap()
+                < prompt.find("## Custom Instructions").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_extraction_preambles_include_language() {
+        let facts = OwnAIAgent::fact_preamble(Some("German"));
+        assert!(facts.starts_with("Extract important, long-term relevant facts"));
+        assert!(facts.contains("Write each fact in German"));
+
+        let summary = OwnAIAgent::summary_preamble(Some("German"));
+        assert!(summary.contains("Write the summary in German"));
+
+        // Unset or blank language keeps the default preambles
+        assert!(!OwnAIAgent::fact_preamble(None).contains(" in German"));
+        assert_eq!(
+            OwnAIAgent::summary_preamble(Some("  ")),
+            OwnAIAgent::summary_preamble(None)
+        );
+    }
+
+    #[test]
+    fn test_system_prompt_without_custom_instructions() {
+        let base = OwnAIAgent::system_prompt("Ava", None);
+        assert!(!base.contains("## Custom Instructions"));
+        assert_eq!(OwnAIAgent::system_prompt("Ava", Some("   ")), base);
+    }
+}
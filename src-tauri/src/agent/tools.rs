@@ -4,8 +4,9 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
 use crate::canvas::tools::{
-    CreateProgramTool, ListProgramsTool, OpenProgramTool, ProgramEditFileTool, ProgramLsTool,
-    ProgramReadFileTool, ProgramWriteFileTool,
+    CloneProgramTool, CreateProgramTool, ListProgramsTool, OpenProgramTool, ProgramEditFileTool,
+    ProgramLsTool, ProgramReadFileTool, ProgramWriteFileBase64Tool, ProgramWriteFileTool,
+    RenameProgramTool,
 };
 use crate::memory::SharedLongTermMemory;
 use crate::scheduler::{
@@ -16,16 +17,24 @@ use crate::tools::collection_tools::{
     CreateKnowledgeCollectionTool, DeleteKnowledgeCollectionTool, IngestDocumentTool,
     ListKnowledgeCollectionsTool,
 };
-use crate::tools::filesystem::{EditFileTool, GrepTool, LsTool, ReadFileTool, WriteFileTool};
-use crate::tools::memory_tools::{AddMemoryTool, DeleteMemoryTool, SearchMemoryTool};
+use crate::tools::confirmation::ConfirmationGate;
+use crate::tools::filesystem::{
+    DeleteFileTool, EditFileTool, GrepTool, LsTool, MoveFileTool, ReadFileTool, WriteFileTool,
+};
+use crate::tools::memory_tools::{
+    AddMemoryTool, DeleteMemoryTool, ForgetMemoryTool, PinMemoryTool, SearchMemoryTool,
+};
 use crate::tools::planning::{ReadTodosTool, SharedTodoList, WriteTodosTool};
+use crate::tools::read_only::without_write_tools;
 use crate::tools::rhai_bridge_tool::{RhaiExecuteTool, SharedRegistry};
 use crate::tools::subagents::{ClientProvider, DelegateTaskTool};
-use crate::utils::paths;
+
+use super::output_limit::OutputLimit;
 
 /// Helper: Create the set of tools for an instance.
 /// Includes all tools: filesystem, planning, dynamic tools, self-programming,
 /// canvas, memory, and task delegation (sub-agents).
+/// With `read_only`, every tool that writes is left out (sub-agents included).
 #[allow(clippy::too_many_arguments)]
 pub(super) fn create_tools(
     instance_id: &str,
@@ -34,14 +43,16 @@ pub(super) fn create_tools(
     registry: SharedRegistry,
     available_dynamic_tools: Vec<(String, String)>,
     db: Pool<Sqlite>,
+    workspace: PathBuf,
     programs_root: PathBuf,
     long_term_memory: SharedLongTermMemory,
     client_provider: ClientProvider,
     model: String,
     app_handle: Option<AppHandle>,
+    read_only: bool,
+    output_limit: OutputLimit,
 ) -> Vec<Box<dyn ToolDyn>> {
-    let workspace =
-        paths::get_instance_workspace_path(instance_id).unwrap_or_else(|_| PathBuf::from("."));
+    let confirmation = ConfirmationGate::new(app_handle.clone(), instance_id);
 
     let mut tools: Vec<Box<dyn ToolDyn>> = vec![
         // Filesystem tools
@@ -49,10 +60,15 @@ pub(super) fn create_tools(
         Box::new(ReadFileTool::new(workspace.clone())),
         Box::new(WriteFileTool::new(workspace.clone())),
         Box::new(EditFileTool::new(workspace.clone())),
+        Box::new(MoveFileTool::new(workspace.clone()).with_confirmation(confirmation.clone())),
+        Box::new(DeleteFileTool::new(workspace.clone()).with_confirmation(confirmation.clone())),
         Box::new(GrepTool::new(workspace.clone())),
         // Planning tools
         Box::new(ReadTodosTool::new(todo_list.clone())),
-        Box::new(WriteTodosTool::new(todo_list)),
+        Box::new(
+            WriteTodosTool::new(todo_list, app_handle.clone())
+                .with_persistence(db.clone(), instance_id),
+        ),
         // Dynamic Rhai tool executor
         Box::new(RhaiExecuteTool::new(
             registry.clone(),
@@ -70,6 +86,17 @@ pub(super) fn create_tools(
             app_handle.clone(),
         )),
         Box::new(ListProgramsTool::new(db.clone(), instance_id.to_string())),
+        Box::new(RenameProgramTool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+            app_handle.clone(),
+        )),
+        Box::new(CloneProgramTool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+        )),
         Box::new(OpenProgramTool::new(
             db.clone(),
             instance_id.to_string(),
@@ -83,6 +110,12 @@ pub(super) fn create_tools(
             programs_root.clone(),
             app_handle.clone(),
         )),
+        Box::new(ProgramWriteFileBase64Tool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+            app_handle.clone(),
+        )),
         Box::new(ProgramEditFileTool::new(
             db.clone(),
             instance_id.to_string(),
@@ -93,18 +126,25 @@ pub(super) fn create_tools(
         Box::new(SearchMemoryTool::new(long_term_memory.clone(), db.clone())),
         Box::new(AddMemoryTool::new(long_term_memory.clone(), db.clone())),
         Box::new(DeleteMemoryTool::new(long_term_memory.clone())),
+        Box::new(ForgetMemoryTool::new(long_term_memory.clone())),
+        Box::new(PinMemoryTool::new(long_term_memory.clone())),
         // Task delegation (sub-agents)
-        Box::new(DelegateTaskTool::new(
-            client_provider,
-            model,
-            instance_id.to_string(),
-            instance_name.to_string(),
-            registry,
-            db.clone(),
-            programs_root,
-            long_term_memory.clone(),
-            app_handle.clone(),
-        )),
+        Box::new(
+            DelegateTaskTool::new(
+                client_provider,
+                model,
+                instance_id.to_string(),
+                instance_name.to_string(),
+                registry,
+                db.clone(),
+                workspace.clone(),
+                programs_root,
+                long_term_memory.clone(),
+                app_handle.clone(),
+            )
+            .with_read_only(read_only)
+            .with_output_limit(output_limit),
+        ),
         // Knowledge collection tools (document ingestion & organization)
         Box::new(CreateKnowledgeCollectionTool::new(db.clone())),
         Box::new(ListKnowledgeCollectionsTool::new(db.clone())),
@@ -135,10 +175,103 @@ pub(super) fn create_tools(
                     db.clone(),
                     instance_id.to_string(),
                 )));
-                tools.push(Box::new(DeleteScheduledTaskTool::new(db, scheduler)));
+                tools.push(Box::new(
+                    DeleteScheduledTaskTool::new(db, scheduler).with_confirmation(confirmation),
+                ));
             }
         }
     }
 
+    if read_only {
+        return without_write_tools(tools);
+    }
     tools
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::LongTermMemory;
+    use crate::tools::registry::RhaiToolRegistry;
+    use rig::client::Nothing;
+    use rig::providers::{anthropic, ollama, openai};
+    use std::sync::Arc;
+
+    async fn tool_names(read_only: bool) -> Vec<String> {
+        tool_names_for(
+            ClientProvider::Ollama(ollama::Client::new(Nothing).unwrap()),
+            read_only,
+        )
+        .await
+    }
+
+    async fn tool_names_for(client_provider: ClientProvider, read_only: bool) -> Vec<String> {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+        let registry = RhaiToolRegistry::new(db.clone(), PathBuf::from("/tmp"), None, None);
+        let memory = LongTermMemory::without_model(db.clone());
+
+        create_tools(
+            "test-instance",
+            "Test",
+            crate::tools::planning::create_shared_todo_list(),
+            Arc::new(tokio::sync::RwLock::new(registry)),
+            Vec::new(),
+            db,
+            PathBuf::from("/tmp"),
+            PathBuf::from("/tmp/programs"),
+            Arc::new(tokio::sync::Mutex::new(memory)),
+            client_provider,
+            "llama3".to_string(),
+            None,
+            read_only,
+            OutputLimit::default(),
+        )
+        .iter()
+        .map(|tool| tool.name())
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_read_only_excludes_write_tools() {
+        let all = tool_names(false).await;
+        assert!(all.iter().any(|n| n == "write_file"));
+        assert!(all.iter().any(|n| n == "create_tool"));
+
+        let read_only = tool_names(true).await;
+        assert!(!read_only.iter().any(|n| n == "write_file"));
+        assert!(!read_only.iter().any(|n| n == "create_tool"));
+        for name in [
+            "read_file",
+            "grep",
+            "ls",
+            "program_read_file",
+            "search_memory",
+        ] {
+            assert!(read_only.iter().any(|n| n == name), "{} missing", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delegate_task_available_for_every_provider() {
+        let providers = [
+            ClientProvider::Anthropic(
+                anthropic::Client::builder()
+                    .api_key("test-key")
+                    .build()
+                    .unwrap(),
+            ),
+            ClientProvider::OpenAI(
+                openai::Client::builder()
+                    .api_key("test-key")
+                    .build()
+                    .unwrap(),
+            ),
+            ClientProvider::Ollama(ollama::Client::new(Nothing).unwrap()),
+        ];
+        for client_provider in providers {
+            let names = tool_names_for(client_provider, false).await;
+            assert!(names.iter().any(|n| n == "delegate_task"), "{:?}", names);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Token usage reporting for chat turns.
+//!
+//! Providers report input/output tokens with the final response. When they
+//! don't (all counts zero), usage is estimated at ~4 characters per token,
+//! the same heuristic used for working memory and summary token savings.
+
+use serde::Serialize;
+
+/// Tokens consumed by one chat turn (all tool-calling turns combined).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// True if the provider did not report usage and the counts are estimates
+    pub estimated: bool,
+}
+
+impl TokenUsage {
+    /// Use the provider-reported usage, or estimate it from the prompt and
+    /// response text if the provider reported nothing.
+    pub(super) fn from_provider(
+        usage: Option<rig::completion::Usage>,
+        prompt: &str,
+        response: &str,
+    ) -> Self {
+        match usage {
+            Some(u) if u.input_tokens > 0 || u.output_tokens > 0 => Self {
+                input_tokens: u.input_tokens,
+                output_tokens: u.output_tokens,
+                estimated: false,
+            },
+            _ => Self {
+                input_tokens: estimate_tokens(prompt),
+                output_tokens: estimate_tokens(response),
+                estimated: true,
+            },
+        }
+    }
+}
+
+/// Rough token estimate (~4 characters per token).
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.len() / 4) as u64
+}
+
+/// Response text of a chat turn together with its token usage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatResult {
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reported_usage_is_used() {
+        let mut reported = rig::completion::Usage::new();
+        reported.input_tokens = 1200;
+        reported.output_tokens = 80;
+
+        let usage = TokenUsage::from_provider(Some(reported), "prompt", "response");
+        assert_eq!(
+            usage,
+            TokenUsage {
+                input_tokens: 1200,
+                output_tokens: 80,
+                estimated: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_usage_falls_back_to_estimate() {
+        let prompt = "a".repeat(400);
+        let response = "b".repeat(41);
+
+        let expected = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 10,
+            estimated: true,
+        };
+        assert_eq!(
+            TokenUsage::from_provider(None, &prompt, &response),
+            expected
+        );
+        // Providers that report zeros are treated as not reporting usage
+        assert_eq!(
+            TokenUsage::from_provider(Some(rig::completion::Usage::new()), &prompt, &response),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 0);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+    }
+}
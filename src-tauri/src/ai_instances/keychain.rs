@@ -1,56 +1,119 @@
+use super::keyfile::EncryptedKeyFile;
 use super::models::LLMProvider;
 use anyhow::{Context, Result};
 use keyring::Entry;
 
 const SERVICE_NAME: &str = "ownai";
 
-/// Secure API key storage using OS keychain
+/// Secure API key storage.
+///
+/// Uses the OS keychain. When the keychain is unavailable and a passphrase is
+/// set via `OWNAI_KEY_PASSPHRASE`, keys are stored in an encrypted file
+/// instead (see [`EncryptedKeyFile`]).
+///
+/// Keys are stored per provider. `OpenAICompatible` services differ by base
+/// URL, so their keys are stored per `api_base_url` (see `key_account`).
 pub struct APIKeyStorage;
 
 impl APIKeyStorage {
-    /// Save API key to OS keychain
-    pub fn save(provider: &LLMProvider, api_key: &str) -> Result<()> {
-        let username = provider.to_string();
-        let entry =
-            Entry::new(SERVICE_NAME, &username).context("Failed to create keychain entry")?;
+    /// Save API key to the OS keychain, falling back to the encrypted file
+    pub fn save(provider: &LLMProvider, api_base_url: Option<&str>, api_key: &str) -> Result<()> {
+        let account = key_account(provider, api_base_url);
+        match Self::keychain_save(&account, api_key) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let Some(file) = EncryptedKeyFile::from_env()? else {
+                    return Err(e);
+                };
+                tracing::warn!(
+                    "Keychain unavailable ({:#}), saving API key to encrypted file",
+                    e
+                );
+                file.save(&account, api_key)?;
+                tracing::info!("Saved API key to encrypted file for: {}", account);
+                Ok(())
+            }
+        }
+    }
+
+    /// Load API key from the OS keychain, falling back to the encrypted file.
+    /// An `OpenAICompatible` key saved before keys were stored per base URL
+    /// is used when there is none for `api_base_url`.
+    pub fn load(provider: &LLMProvider, api_base_url: Option<&str>) -> Result<Option<String>> {
+        let account = key_account(provider, api_base_url);
+        match Self::load_account(&account)? {
+            None if account != provider.to_string() => Self::load_account(&provider.to_string()),
+            key => Ok(key),
+        }
+    }
+
+    /// Delete API key from the OS keychain and the encrypted file
+    pub fn delete(provider: &LLMProvider, api_base_url: Option<&str>) -> Result<()> {
+        let account = key_account(provider, api_base_url);
+        let keychain_result = Self::keychain_delete(&account);
+
+        match EncryptedKeyFile::from_env()? {
+            Some(file) => {
+                if let Err(e) = keychain_result {
+                    tracing::debug!("Keychain delete failed: {:#}", e);
+                }
+                file.delete(&account)
+            }
+            None => keychain_result,
+        }
+    }
+
+    /// Check if an API key exists for a provider
+    pub fn exists(provider: &LLMProvider, api_base_url: Option<&str>) -> Result<bool> {
+        Ok(Self::load(provider, api_base_url)?.is_some())
+    }
+
+    fn load_account(account: &str) -> Result<Option<String>> {
+        let keychain_result = Self::keychain_load(account);
+        if let Ok(Some(key)) = keychain_result {
+            return Ok(Some(key));
+        }
+
+        match (EncryptedKeyFile::from_env()?, keychain_result) {
+            (Some(file), _) => file.load(account),
+            (None, result) => result,
+        }
+    }
+
+    fn keychain_save(account: &str, api_key: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keychain entry")?;
 
         entry
             .set_password(api_key)
             .context("Failed to save API key to keychain")?;
 
-        tracing::info!("Saved API key to keychain for provider: {}", provider);
+        tracing::info!("Saved API key to keychain for: {}", account);
 
         Ok(())
     }
 
-    /// Load API key from OS keychain
-    pub fn load(provider: &LLMProvider) -> Result<Option<String>> {
-        let username = provider.to_string();
-        let entry =
-            Entry::new(SERVICE_NAME, &username).context("Failed to create keychain entry")?;
+    fn keychain_load(account: &str) -> Result<Option<String>> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keychain entry")?;
 
         match entry.get_password() {
             Ok(key) => {
-                tracing::debug!("Loaded API key from keychain for provider: {}", provider);
+                tracing::debug!("Loaded API key from keychain for: {}", account);
                 Ok(Some(key))
             }
             Err(keyring::Error::NoEntry) => {
-                tracing::debug!("No API key found in keychain for provider: {}", provider);
+                tracing::debug!("No API key found in keychain for: {}", account);
                 Ok(None)
             }
             Err(e) => Err(e).context("Failed to load API key from keychain"),
         }
     }
 
-    /// Delete API key from OS keychain
-    pub fn delete(provider: &LLMProvider) -> Result<()> {
-        let username = provider.to_string();
-        let entry =
-            Entry::new(SERVICE_NAME, &username).context("Failed to create keychain entry")?;
+    fn keychain_delete(account: &str) -> Result<()> {
+        let entry = Entry::new(SERVICE_NAME, account).context("Failed to create keychain entry")?;
 
         match entry.delete_credential() {
             Ok(_) => {
-                tracing::info!("Deleted API key from keychain for provider: {}", provider);
+                tracing::info!("Deleted API key from keychain for: {}", account);
                 Ok(())
             }
             Err(keyring::Error::NoEntry) => {
@@ -60,10 +123,17 @@ impl APIKeyStorage {
             Err(e) => Err(e).context("Failed to delete API key from keychain"),
         }
     }
+}
 
-    /// Check if an API key exists for a provider
-    pub fn exists(provider: &LLMProvider) -> Result<bool> {
-        Ok(Self::load(provider)?.is_some())
+/// Keychain account (and encrypted file entry) of a provider's key: the
+/// provider id, plus the base URL for `OpenAICompatible` services.
+fn key_account(provider: &LLMProvider, api_base_url: Option<&str>) -> String {
+    let url = api_base_url
+        .map(|url| url.trim().trim_end_matches('/'))
+        .filter(|url| !url.is_empty());
+    match (provider, url) {
+        (LLMProvider::OpenAICompatible, Some(url)) => format!("{}:{}", provider, url),
+        _ => provider.to_string(),
     }
 }
 
@@ -78,25 +148,55 @@ mod tests {
         let api_key = "test-api-key-secret";
 
         // Save
-        APIKeyStorage::save(&provider, api_key).unwrap();
+        APIKeyStorage::save(&provider, None, api_key).unwrap();
 
         // Load
-        let loaded = APIKeyStorage::load(&provider).unwrap();
+        let loaded = APIKeyStorage::load(&provider, None).unwrap();
         assert_eq!(loaded, Some(api_key.to_string()));
 
         // Exists
-        let exists = APIKeyStorage::exists(&provider).unwrap();
+        let exists = APIKeyStorage::exists(&provider, None).unwrap();
         assert!(exists);
 
         // Delete
-        APIKeyStorage::delete(&provider).unwrap();
+        APIKeyStorage::delete(&provider, None).unwrap();
 
         // Verify deleted
-        let loaded_after_delete = APIKeyStorage::load(&provider).unwrap();
+        let loaded_after_delete = APIKeyStorage::load(&provider, None).unwrap();
         assert_eq!(loaded_after_delete, None);
 
         // Exists after delete
-        let exists_after_delete = APIKeyStorage::exists(&provider).unwrap();
+        let exists_after_delete = APIKeyStorage::exists(&provider, None).unwrap();
         assert!(!exists_after_delete);
     }
+
+    #[test]
+    fn test_key_account_per_compatible_base_url() {
+        assert_eq!(key_account(&LLMProvider::Anthropic, None), "anthropic");
+        assert_eq!(
+            key_account(&LLMProvider::OpenAI, Some("https://api.openai.com/v1")),
+            "openai"
+        );
+        assert_eq!(
+            key_account(
+                &LLMProvider::OpenAICompatible,
+                Some(" https://api.groq.com/openai/v1/ ")
+            ),
+            "openai_compatible:https://api.groq.com/openai/v1"
+        );
+        assert_ne!(
+            key_account(
+                &LLMProvider::OpenAICompatible,
+                Some("https://api.groq.com/openai/v1")
+            ),
+            key_account(
+                &LLMProvider::OpenAICompatible,
+                Some("https://api.mistral.ai/v1")
+            )
+        );
+        assert_eq!(
+            key_account(&LLMProvider::OpenAICompatible, None),
+            "openai_compatible"
+        );
+    }
 }
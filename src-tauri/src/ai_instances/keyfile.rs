@@ -0,0 +1,283 @@
+use anyhow::{bail, Context, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the passphrase for the encrypted key file.
+pub const PASSPHRASE_ENV: &str = "OWNAI_KEY_PASSPHRASE";
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// A single encrypted secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedEntry {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// On-disk format of the key file. The salt is shared by all entries;
+/// every entry has its own random nonce.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFileContents {
+    version: u32,
+    salt: String,
+    entries: BTreeMap<String, EncryptedEntry>,
+}
+
+/// Passphrase-encrypted secret storage in a local file.
+///
+/// Fallback for environments without an OS keychain (headless Linux,
+/// containers). The encryption key is derived from the passphrase with
+/// Argon2id; secrets are encrypted with ChaCha20-Poly1305.
+pub struct EncryptedKeyFile {
+    path: PathBuf,
+    passphrase: String,
+}
+
+impl EncryptedKeyFile {
+    pub fn new(path: PathBuf, passphrase: impl Into<String>) -> Self {
+        Self {
+            path,
+            passphrase: passphrase.into(),
+        }
+    }
+
+    /// Open the default key file (~/.ownai/api_keys.enc) if a passphrase is
+    /// configured via `OWNAI_KEY_PASSPHRASE`.
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => Ok(Some(Self::new(
+                crate::utils::paths::get_encrypted_keys_path()?,
+                passphrase,
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Encrypt and store a secret under `name`, replacing any previous value.
+    pub fn save(&self, name: &str, secret: &str) -> Result<()> {
+        let mut contents = self.read()?.unwrap_or_else(|| KeyFileContents {
+            version: FILE_VERSION,
+            salt: BASE64.encode(random_salt()),
+            entries: BTreeMap::new(),
+        });
+        let cipher = self.cipher(&contents)?;
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+
+        contents.entries.insert(
+            name.to_string(),
+            EncryptedEntry {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(ciphertext),
+            },
+        );
+        self.write(&contents)
+    }
+
+    /// Load and decrypt the secret stored under `name`.
+    /// Fails if the passphrase is wrong or the file was tampered with.
+    pub fn load(&self, name: &str) -> Result<Option<String>> {
+        let Some(contents) = self.read()? else {
+            return Ok(None);
+        };
+        let Some(entry) = contents.entries.get(name) else {
+            return Ok(None);
+        };
+        let cipher = self.cipher(&contents)?;
+
+        let nonce = BASE64
+            .decode(&entry.nonce)
+            .context("Invalid nonce in key file")?;
+        if nonce.len() != 12 {
+            bail!("Invalid nonce length in key file");
+        }
+        let ciphertext = BASE64
+            .decode(&entry.ciphertext)
+            .context("Invalid ciphertext in key file")?;
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt key file entry (wrong passphrase?)"))?;
+
+        Ok(Some(
+            String::from_utf8(plaintext).context("Decrypted secret is not valid UTF-8")?,
+        ))
+    }
+
+    /// Remove the secret stored under `name`. Missing entries are not an error.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let Some(mut contents) = self.read()? else {
+            return Ok(());
+        };
+        if contents.entries.remove(name).is_some() {
+            self.write(&contents)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Option<KeyFileContents>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let raw = std::fs::read_to_string(&self.path).context("Failed to read key file")?;
+        let contents: KeyFileContents =
+            serde_json::from_str(&raw).context("Failed to parse key file")?;
+        if contents.version != FILE_VERSION {
+            bail!("Unsupported key file version: {}", contents.version);
+        }
+        Ok(Some(contents))
+    }
+
+    fn write(&self, contents: &KeyFileContents) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create key file directory")?;
+        }
+        let json =
+            serde_json::to_string_pretty(contents).context("Failed to serialize key file")?;
+
+        // Write a private temp file and rename it over the key file, so the
+        // keys are never readable by others and a crash mid-write leaves the
+        // previous file intact
+        let mut tmp_name = self.path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = self.path.with_file_name(tmp_name);
+        // A leftover temp file would keep its old permissions
+        match std::fs::remove_file(&tmp_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context("Failed to remove stale key file");
+            }
+            _ => {}
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&tmp_path)
+            .context("Failed to create key file")?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.sync_all())
+            .context("Failed to write key file")?;
+        drop(file);
+
+        std::fs::rename(&tmp_path, &self.path).context("Failed to replace key file")?;
+        Ok(())
+    }
+
+    fn cipher(&self, contents: &KeyFileContents) -> Result<ChaCha20Poly1305> {
+        let salt = BASE64
+            .decode(&contents.salt)
+            .context("Invalid salt in key file")?;
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+}
+
+fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_load_delete_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = EncryptedKeyFile::new(temp_dir.path().join("keys.enc"), "correct horse");
+
+        assert_eq!(store.load("anthropic").unwrap(), None);
+
+        store.save("anthropic", "sk-ant-secret").unwrap();
+        store.save("openai", "sk-openai-secret").unwrap();
+        assert_eq!(
+            store.load("anthropic").unwrap(),
+            Some("sk-ant-secret".to_string())
+        );
+        assert_eq!(
+            store.load("openai").unwrap(),
+            Some("sk-openai-secret".to_string())
+        );
+
+        // Secrets are not stored in plain text
+        let raw = std::fs::read_to_string(store.path()).unwrap();
+        assert!(!raw.contains("sk-ant-secret"));
+
+        // Overwrite
+        store.save("anthropic", "sk-ant-rotated").unwrap();
+        assert_eq!(
+            store.load("anthropic").unwrap(),
+            Some("sk-ant-rotated".to_string())
+        );
+
+        store.delete("anthropic").unwrap();
+        assert_eq!(store.load("anthropic").unwrap(), None);
+        assert!(store.load("openai").unwrap().is_some());
+
+        // Deleting a missing entry is fine
+        store.delete("anthropic").unwrap();
+    }
+
+    #[test]
+    fn test_wrong_passphrase_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("keys.enc");
+        EncryptedKeyFile::new(path.clone(), "right")
+            .save("openai", "sk-secret")
+            .unwrap();
+
+        let err = EncryptedKeyFile::new(path, "wrong")
+            .load("openai")
+            .unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_file_is_private_and_replaced_atomically() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("keys.enc");
+        // A stale, world-readable temp file from an interrupted write
+        let stale = temp_dir.path().join("keys.enc.tmp");
+        std::fs::write(&stale, "partial").unwrap();
+        std::fs::set_permissions(&stale, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let store = EncryptedKeyFile::new(path.clone(), "passphrase");
+        store.save("anthropic", "sk-ant-secret").unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!stale.exists());
+        assert_eq!(
+            store.load("anthropic").unwrap(),
+            Some("sk-ant-secret".to_string())
+        );
+    }
+}
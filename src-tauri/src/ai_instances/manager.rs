@@ -1,4 +1,9 @@
-use super::models::{AIInstance, LLMProvider};
+use super::models::{AIInstance, FactExtractionMode, InstanceSettingsPatch, LLMProvider};
+use crate::agent::{
+    MAX_HISTORY_WINDOW, MAX_TOOL_TURNS_LIMIT, MIN_CONTEXT_LIMIT_TOKENS, MIN_TOOL_OUTPUT_CHARS,
+};
+use crate::canvas::bridge::MAX_PROGRAM_DATA_QUOTA_BYTES;
+use crate::canvas::rate_limit::MAX_BRIDGE_CHAT_PER_MINUTE;
 use crate::utils::paths::{
     get_config_path, get_instance_db_path, get_instance_workspace_path, get_instances_path,
 };
@@ -6,8 +11,12 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+/// Upper bound for the number of turns in a fact extraction batch
+const MAX_FACT_EXTRACTION_BATCH: usize = 50;
+
 /// Manages all AI instances
 pub struct AIInstanceManager {
     pub instances: HashMap<String, AIInstance>,
@@ -32,7 +41,10 @@ impl AIInstanceManager {
         provider: LLMProvider,
         model: String,
         api_base_url: Option<String>,
+        max_tool_turns: Option<usize>,
     ) -> Result<AIInstance> {
+        validate_max_tool_turns(max_tool_turns)?;
+
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
@@ -45,6 +57,23 @@ impl AIInstanceManager {
             provider,
             model,
             api_base_url,
+            program_data_quota_bytes: None,
+            custom_instructions: None,
+            stop_on_repeated_tool_error: false,
+            stream_reasoning: false,
+            require_confirmation_for_destructive: false,
+            read_only: false,
+            history_window: None,
+            max_tool_turns,
+            tool_budgets: HashMap::new(),
+            language: None,
+            fact_extraction: FactExtractionMode::default(),
+            memory_consolidation_days: None,
+            context_limit_tokens: None,
+            max_tool_output_chars: None,
+            bridge_chat_per_minute: None,
+            fallback_providers: Vec::new(),
+            workspace_override: None,
             db_path: Some(db_path),
             created_at: now,
             last_active: now,
@@ -72,6 +101,158 @@ impl AIInstanceManager {
         Ok(instance)
     }
 
+    /// Create a new instance with the same configuration (provider, model,
+    /// base URL, quotas) as `source_id` but a fresh id and empty data.
+    /// The clone uses the same per-provider API key. The active instance is
+    /// not changed.
+    pub fn clone_instance(&mut self, source_id: &str, new_name: String) -> Result<AIInstance> {
+        let source = self
+            .instances
+            .get(source_id)
+            .with_context(|| format!("Instance not found: {}", source_id))?;
+
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() {
+            anyhow::bail!("Instance name must not be empty");
+        }
+
+        let mut instance = clone_config(source, Uuid::new_v4().to_string(), new_name, Utc::now());
+        instance.db_path = Some(get_instance_db_path(&instance.id)?);
+
+        self.create_instance_directories(&instance.id)?;
+        self.instances.insert(instance.id.clone(), instance.clone());
+        self.save_instances()?;
+
+        tracing::info!(
+            "Cloned AI instance {} into {} ({})",
+            source_id,
+            instance.name,
+            instance.id
+        );
+
+        Ok(instance)
+    }
+
+    /// Rename an instance and persist the change.
+    /// The name is part of the agent's system prompt, so callers must drop any
+    /// cached agent for this instance afterwards.
+    pub fn rename_instance(&mut self, id: &str, new_name: String) -> Result<AIInstance> {
+        let instance = self.apply_rename(id, new_name)?;
+        self.save_instances()?;
+
+        tracing::info!("Renamed AI instance {} to '{}'", id, instance.name);
+
+        Ok(instance)
+    }
+
+    /// Update the in-memory name of an instance (without saving).
+    fn apply_rename(&mut self, id: &str, new_name: String) -> Result<AIInstance> {
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() {
+            anyhow::bail!("Instance name must not be empty");
+        }
+
+        let instance = self
+            .instances
+            .get_mut(id)
+            .with_context(|| format!("Instance not found: {}", id))?;
+        instance.name = new_name;
+
+        Ok(instance.clone())
+    }
+
+    /// Apply a settings patch to an instance and persist the change. The
+    /// patch is validated as a whole, so an invalid field changes nothing.
+    /// Most settings are read when the agent is built, so callers must drop
+    /// any cached agent for this instance afterwards.
+    pub fn update_instance_settings(
+        &mut self,
+        id: &str,
+        patch: InstanceSettingsPatch,
+    ) -> Result<AIInstance> {
+        let instance = self.apply_settings(id, patch)?;
+        self.save_instances()?;
+
+        tracing::info!("Updated settings for AI instance {}", id);
+
+        Ok(instance)
+    }
+
+    /// Validate a settings patch and apply it in memory (without saving).
+    fn apply_settings(&mut self, id: &str, patch: InstanceSettingsPatch) -> Result<AIInstance> {
+        validate_settings(&patch)?;
+        let workspace_override = patch
+            .workspace_override
+            .map(|path| path.as_deref().map(validate_workspace_override).transpose())
+            .transpose()?;
+
+        let instance = self
+            .instances
+            .get_mut(id)
+            .with_context(|| format!("Instance not found: {}", id))?;
+        if let Some(instructions) = patch.custom_instructions {
+            instance.custom_instructions = non_blank(instructions);
+        }
+        if let Some(language) = patch.language {
+            instance.language = non_blank(language);
+        }
+        if let Some(mode) = patch.fact_extraction {
+            instance.fact_extraction = mode;
+        }
+        if let Some(enabled) = patch.stop_on_repeated_tool_error {
+            instance.stop_on_repeated_tool_error = enabled;
+        }
+        if let Some(enabled) = patch.stream_reasoning {
+            instance.stream_reasoning = enabled;
+        }
+        if let Some(enabled) = patch.require_confirmation_for_destructive {
+            instance.require_confirmation_for_destructive = enabled;
+        }
+        if let Some(enabled) = patch.read_only {
+            instance.read_only = enabled;
+        }
+        if let Some(window) = patch.history_window {
+            instance.history_window = window;
+        }
+        if let Some(turns) = patch.max_tool_turns {
+            instance.max_tool_turns = turns;
+        }
+        if let Some(budgets) = patch.tool_budgets {
+            instance.tool_budgets = budgets;
+        }
+        if let Some(days) = patch.memory_consolidation_days {
+            instance.memory_consolidation_days = days;
+        }
+        if let Some(limit) = patch.context_limit_tokens {
+            instance.context_limit_tokens = limit;
+        }
+        if let Some(max_chars) = patch.max_tool_output_chars {
+            instance.max_tool_output_chars = max_chars;
+        }
+        if let Some(per_minute) = patch.bridge_chat_per_minute {
+            instance.bridge_chat_per_minute = per_minute;
+        }
+        if let Some(quota) = patch.program_data_quota_bytes {
+            instance.program_data_quota_bytes = quota;
+        }
+        if let Some(fallback_providers) = patch.fallback_providers {
+            instance.fallback_providers = fallback_providers;
+        }
+        if let Some(path) = workspace_override {
+            instance.workspace_override = path;
+        }
+
+        Ok(instance.clone())
+    }
+
+    /// Workspace directory of an instance (its override, if one is set).
+    pub fn workspace_path(&self, id: &str) -> Result<PathBuf> {
+        let instance = self
+            .get_instance(id)
+            .with_context(|| format!("Instance not found: {}", id))?;
+        get_instance_workspace_path(id, instance.workspace_override.as_deref())
+    }
+
     /// List all AI instances
     pub fn list_instances(&self) -> Vec<AIInstance> {
         self.instances.values().cloned().collect()
@@ -144,7 +325,7 @@ impl AIInstanceManager {
         fs::create_dir_all(&base_path).context("Failed to create instance directory")?;
 
         // Create subdirectories
-        get_instance_workspace_path(id)?;
+        get_instance_workspace_path(id, None)?;
 
         tracing::debug!("Created directories for instance: {}", id);
 
@@ -184,3 +365,335 @@ impl AIInstanceManager {
         Ok(())
     }
 }
+
+/// Copy the configuration of `source` into a new instance record.
+/// Data paths (including a workspace override) are not copied; the caller
+/// assigns them for the new id.
+fn clone_config(
+    source: &AIInstance,
+    id: String,
+    name: String,
+    now: chrono::DateTime<Utc>,
+) -> AIInstance {
+    AIInstance {
+        id,
+        name,
+        db_path: None,
+        workspace_override: None,
+        created_at: now,
+        last_active: now,
+        ..source.clone()
+    }
+}
+
+/// Trim optional text, treating blank text as unset.
+fn non_blank(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Reject out-of-range values in a settings patch. The workspace override is
+/// checked separately, since it is also resolved to an absolute path.
+fn validate_settings(patch: &InstanceSettingsPatch) -> Result<()> {
+    if let Some(FactExtractionMode::Batched { turns }) = patch.fact_extraction {
+        if !(1..=MAX_FACT_EXTRACTION_BATCH).contains(&turns) {
+            anyhow::bail!(
+                "Fact extraction batch must be between 1 and {} turns, got {}",
+                MAX_FACT_EXTRACTION_BATCH,
+                turns
+            );
+        }
+    }
+    if let Some(Some(window)) = patch.history_window {
+        if !(1..=MAX_HISTORY_WINDOW).contains(&window) {
+            anyhow::bail!(
+                "History window must be between 1 and {} messages, got {}",
+                MAX_HISTORY_WINDOW,
+                window
+            );
+        }
+    }
+    if let Some(turns) = patch.max_tool_turns {
+        validate_max_tool_turns(turns)?;
+    }
+    if patch.memory_consolidation_days == Some(Some(0)) {
+        anyhow::bail!("Memory consolidation interval must be at least 1 day");
+    }
+    if let Some(Some(limit)) = patch.context_limit_tokens {
+        if limit < MIN_CONTEXT_LIMIT_TOKENS {
+            anyhow::bail!(
+                "Context limit must be at least {} tokens",
+                MIN_CONTEXT_LIMIT_TOKENS
+            );
+        }
+    }
+    if let Some(Some(max_chars)) = patch.max_tool_output_chars {
+        if max_chars < MIN_TOOL_OUTPUT_CHARS {
+            anyhow::bail!(
+                "Tool output limit must be at least {} characters",
+                MIN_TOOL_OUTPUT_CHARS
+            );
+        }
+    }
+    if let Some(Some(per_minute)) = patch.bridge_chat_per_minute {
+        if per_minute == 0 || per_minute > MAX_BRIDGE_CHAT_PER_MINUTE {
+            anyhow::bail!(
+                "Bridge chat limit must be between 1 and {} calls per minute",
+                MAX_BRIDGE_CHAT_PER_MINUTE
+            );
+        }
+    }
+    if let Some(Some(quota)) = patch.program_data_quota_bytes {
+        if quota == 0 || quota > MAX_PROGRAM_DATA_QUOTA_BYTES {
+            anyhow::bail!(
+                "Program data quota must be between 1 and {} bytes",
+                MAX_PROGRAM_DATA_QUOTA_BYTES
+            );
+        }
+    }
+    if let Some(fallback) = patch
+        .fallback_providers
+        .iter()
+        .flatten()
+        .find(|f| f.model.trim().is_empty())
+    {
+        anyhow::bail!("Fallback provider {} needs a model", fallback.provider);
+    }
+    Ok(())
+}
+
+/// Reject a configured tool-calling turn limit outside 1..=MAX_TOOL_TURNS_LIMIT.
+pub fn validate_max_tool_turns(turns: Option<usize>) -> Result<()> {
+    match turns {
+        Some(turns) if !(1..=MAX_TOOL_TURNS_LIMIT).contains(&turns) => anyhow::bail!(
+            "Max tool turns must be between 1 and {}, got {}",
+            MAX_TOOL_TURNS_LIMIT,
+            turns
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve a workspace override to an absolute path, rejecting paths that
+/// are not existing directories.
+pub fn validate_workspace_override(path: &Path) -> Result<PathBuf> {
+    if !path.is_dir() {
+        anyhow::bail!(
+            "Workspace path must be an existing directory: {}",
+            path.display()
+        );
+    }
+    path.canonicalize()
+        .with_context(|| format!("Failed to resolve workspace path: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_instances::FallbackProvider;
+
+    fn sample_instance() -> AIInstance {
+        let created = Utc::now() - chrono::Duration::days(3);
+        AIInstance {
+            id: "source-id".to_string(),
+            name: "Research".to_string(),
+            provider: LLMProvider::Ollama,
+            model: "qwen3:8b".to_string(),
+            api_base_url: Some("http://localhost:11434".to_string()),
+            program_data_quota_bytes: Some(1024),
+            custom_instructions: Some("Answer in German.".to_string()),
+            stop_on_repeated_tool_error: true,
+            stream_reasoning: true,
+            require_confirmation_for_destructive: true,
+            read_only: true,
+            history_window: Some(250),
+            max_tool_turns: Some(120),
+            tool_budgets: HashMap::from([("delegate_task".to_string(), 2)]),
+            language: Some("German".to_string()),
+            fact_extraction: FactExtractionMode::Batched { turns: 5 },
+            memory_consolidation_days: Some(30),
+            context_limit_tokens: Some(64_000),
+            max_tool_output_chars: Some(20_000),
+            bridge_chat_per_minute: Some(30),
+            fallback_providers: vec![FallbackProvider {
+                provider: LLMProvider::Ollama,
+                model: "llama3".to_string(),
+                api_base_url: None,
+            }],
+            workspace_override: Some(std::path::PathBuf::from("/home/user/code/project")),
+            db_path: Some(std::path::PathBuf::from("/tmp/source/ownai.db")),
+            created_at: created,
+            last_active: created,
+        }
+    }
+
+    #[test]
+    fn test_validate_max_tool_turns() {
+        assert!(validate_max_tool_turns(None).is_ok());
+        assert!(validate_max_tool_turns(Some(1)).is_ok());
+        assert!(validate_max_tool_turns(Some(MAX_TOOL_TURNS_LIMIT)).is_ok());
+        assert!(validate_max_tool_turns(Some(0)).is_err());
+        assert!(validate_max_tool_turns(Some(MAX_TOOL_TURNS_LIMIT + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_workspace_override() {
+        let project = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            validate_workspace_override(project.path()).unwrap(),
+            project.path().canonicalize().unwrap()
+        );
+
+        let file = project.path().join("notes.txt");
+        fs::write(&file, "x").unwrap();
+        assert!(validate_workspace_override(&file).is_err());
+        assert!(validate_workspace_override(&project.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_clone_config() {
+        let source = sample_instance();
+        let now = Utc::now();
+
+        let clone = clone_config(
+            &source,
+            "new-id".to_string(),
+            "Research copy".to_string(),
+            now,
+        );
+
+        assert_eq!(clone.id, "new-id");
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.name, "Research copy");
+        assert_eq!(clone.provider, source.provider);
+        assert_eq!(clone.model, source.model);
+        assert_eq!(clone.api_base_url, source.api_base_url);
+        assert_eq!(
+            clone.program_data_quota_bytes,
+            source.program_data_quota_bytes
+        );
+        assert_eq!(clone.custom_instructions, source.custom_instructions);
+        assert_eq!(
+            clone.stop_on_repeated_tool_error,
+            source.stop_on_repeated_tool_error
+        );
+        assert_eq!(clone.stream_reasoning, source.stream_reasoning);
+        assert_eq!(
+            clone.require_confirmation_for_destructive,
+            source.require_confirmation_for_destructive
+        );
+        assert_eq!(clone.read_only, source.read_only);
+        assert_eq!(clone.history_window, source.history_window);
+        assert_eq!(clone.max_tool_turns, source.max_tool_turns);
+        assert_eq!(clone.tool_budgets, source.tool_budgets);
+        assert_eq!(clone.language, source.language);
+        assert_eq!(clone.fact_extraction, source.fact_extraction);
+        assert_eq!(
+            clone.memory_consolidation_days,
+            source.memory_consolidation_days
+        );
+        assert_eq!(clone.context_limit_tokens, source.context_limit_tokens);
+        assert_eq!(clone.max_tool_output_chars, source.max_tool_output_chars);
+        assert_eq!(clone.bridge_chat_per_minute, source.bridge_chat_per_minute);
+        assert_eq!(clone.fallback_providers, source.fallback_providers);
+        assert!(clone.db_path.is_none());
+        assert!(clone.workspace_override.is_none());
+        assert_eq!(clone.created_at, now);
+        assert_eq!(clone.last_active, now);
+    }
+
+    #[test]
+    fn test_apply_rename() {
+        let source = sample_instance();
+        let mut manager = AIInstanceManager {
+            instances: HashMap::from([(source.id.clone(), source)]),
+            active_instance_id: None,
+        };
+
+        let renamed = manager
+            .apply_rename("source-id", "  Lab Notes ".to_string())
+            .unwrap();
+        assert_eq!(renamed.name, "Lab Notes");
+        assert_eq!(manager.get_instance("source-id").unwrap().name, "Lab Notes");
+
+        assert!(manager
+            .apply_rename("source-id", "   ".to_string())
+            .is_err());
+        assert!(manager.apply_rename("missing", "X".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_apply_settings() {
+        let source = sample_instance();
+        let mut manager = AIInstanceManager {
+            instances: HashMap::from([(source.id.clone(), source)]),
+            active_instance_id: None,
+        };
+
+        // Absent fields are kept, `null` clears, blank text is unset
+        let patch: InstanceSettingsPatch = serde_json::from_value(serde_json::json!({
+            "custom_instructions": "  ",
+            "read_only": false,
+            "max_tool_turns": null,
+            "tool_budgets": {},
+        }))
+        .unwrap();
+        let updated = manager.apply_settings("source-id", patch).unwrap();
+        assert_eq!(updated.custom_instructions, None);
+        assert!(!updated.read_only);
+        assert_eq!(updated.max_tool_turns, None);
+        assert!(updated.tool_budgets.is_empty());
+        assert_eq!(updated.history_window, Some(250));
+        assert_eq!(updated.language.as_deref(), Some("German"));
+        assert!(updated.stream_reasoning);
+
+        // One invalid field rejects the whole patch
+        let patch: InstanceSettingsPatch = serde_json::from_value(serde_json::json!({
+            "stream_reasoning": false,
+            "context_limit_tokens": 1,
+        }))
+        .unwrap();
+        assert!(manager.apply_settings("source-id", patch).is_err());
+        assert!(manager.get_instance("source-id").unwrap().stream_reasoning);
+
+        assert!(manager
+            .apply_settings("missing", InstanceSettingsPatch::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let invalid = [
+            serde_json::json!({ "fact_extraction": { "mode": "batched", "turns": 0 } }),
+            serde_json::json!({ "history_window": 0 }),
+            serde_json::json!({ "history_window": MAX_HISTORY_WINDOW + 1 }),
+            serde_json::json!({ "max_tool_turns": MAX_TOOL_TURNS_LIMIT + 1 }),
+            serde_json::json!({ "memory_consolidation_days": 0 }),
+            serde_json::json!({ "max_tool_output_chars": MIN_TOOL_OUTPUT_CHARS - 1 }),
+            serde_json::json!({ "bridge_chat_per_minute": 0 }),
+            serde_json::json!({ "program_data_quota_bytes": 0 }),
+            serde_json::json!({ "program_data_quota_bytes": MAX_PROGRAM_DATA_QUOTA_BYTES + 1 }),
+            serde_json::json!({
+                "fallback_providers": [{ "provider": "ollama", "model": " " }]
+            }),
+        ];
+        for value in invalid {
+            let patch: InstanceSettingsPatch = serde_json::from_value(value.clone()).unwrap();
+            assert!(validate_settings(&patch).is_err(), "{}", value);
+        }
+
+        let patch: InstanceSettingsPatch = serde_json::from_value(serde_json::json!({
+            "history_window": null,
+            "memory_consolidation_days": 7,
+            "bridge_chat_per_minute": MAX_BRIDGE_CHAT_PER_MINUTE,
+            "program_data_quota_bytes": 1024,
+        }))
+        .unwrap();
+        assert!(validate_settings(&patch).is_ok());
+
+        let patch: InstanceSettingsPatch =
+            serde_json::from_value(serde_json::json!({ "history_window": MAX_HISTORY_WINDOW }))
+                .unwrap();
+        assert!(validate_settings(&patch).is_ok());
+    }
+}
@@ -1,9 +1,14 @@
 pub mod keychain;
+pub mod keyfile;
 pub mod langfuse;
 pub mod manager;
 pub mod models;
+pub mod provider_api;
 
 pub use keychain::APIKeyStorage;
 pub use langfuse::LangfuseKeyStorage;
 pub use manager::AIInstanceManager;
-pub use models::{AIInstance, CreateInstanceRequest, LLMProvider, ProviderInfo};
+pub use models::{
+    AIInstance, ApiKeyStatus, CreateInstanceRequest, FactExtractionMode, FallbackProvider,
+    InstanceSettingsPatch, LLMProvider, ProviderInfo,
+};
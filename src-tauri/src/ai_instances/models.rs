@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// LLM Provider types
@@ -9,15 +10,29 @@ pub enum LLMProvider {
     Anthropic,
     OpenAI,
     Ollama,
+    /// Any OpenAI-compatible API (Mistral, Groq, ...) at the instance's `api_base_url`
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
 }
 
 impl LLMProvider {
+    /// All supported providers, in display order
+    pub fn all() -> Vec<LLMProvider> {
+        vec![
+            LLMProvider::Anthropic,
+            LLMProvider::OpenAI,
+            LLMProvider::Ollama,
+            LLMProvider::OpenAICompatible,
+        ]
+    }
+
     /// Returns suggested models for this provider (user can also enter custom)
     pub fn suggested_models(&self) -> Vec<&'static str> {
         match self {
             LLMProvider::Anthropic => vec!["claude-sonnet-4-5-20250929"],
             LLMProvider::OpenAI => vec!["gpt-5.2-2025-12-11", "gpt-5-mini-2025-08-07"],
             LLMProvider::Ollama => vec![], // User enters their own
+            LLMProvider::OpenAICompatible => vec![],
         }
     }
 
@@ -27,6 +42,7 @@ impl LLMProvider {
             LLMProvider::Anthropic => true,
             LLMProvider::OpenAI => true,
             LLMProvider::Ollama => false,
+            LLMProvider::OpenAICompatible => true,
         }
     }
 
@@ -36,6 +52,7 @@ impl LLMProvider {
             LLMProvider::Anthropic => Some("claude-sonnet-4-5-20250929"),
             LLMProvider::OpenAI => Some("gpt-5.2-2025-12-11"),
             LLMProvider::Ollama => None, // No default
+            LLMProvider::OpenAICompatible => None,
         }
     }
 }
@@ -46,10 +63,35 @@ impl std::fmt::Display for LLMProvider {
             LLMProvider::Anthropic => write!(f, "anthropic"),
             LLMProvider::OpenAI => write!(f, "openai"),
             LLMProvider::Ollama => write!(f, "ollama"),
+            LLMProvider::OpenAICompatible => write!(f, "openai_compatible"),
         }
     }
 }
 
+/// When facts are extracted from conversation turns into long-term memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FactExtractionMode {
+    /// Run the fact extractor after every turn
+    #[default]
+    EveryTurn,
+    /// Run it once per `turns` turns, over all of them
+    Batched { turns: usize },
+    /// Never extract automatically; only explicit `add_memory` calls store facts
+    Disabled,
+}
+
+/// A provider/model tried when the instance's own provider fails
+/// (see `AIInstance::fallback_providers`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallbackProvider {
+    pub provider: LLMProvider,
+    pub model: String,
+    /// Base URL for this provider; required for `OpenAICompatible`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+}
+
 /// Represents an AI instance with its metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIInstance {
@@ -62,6 +104,94 @@ pub struct AIInstance {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_base_url: Option<String>,
 
+    /// Optional per-program storage quota (bytes) for Canvas bridge data.
+    /// Falls back to `canvas::bridge::DEFAULT_PROGRAM_DATA_QUOTA_BYTES`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_data_quota_bytes: Option<u64>,
+
+    /// Optional user-provided instructions appended to the system prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_instructions: Option<String>,
+
+    /// Abort a streamed turn when the same tool fails several times in a row
+    /// (see `agent::streaming::MAX_CONSECUTIVE_TOOL_FAILURES`)
+    #[serde(default)]
+    pub stop_on_repeated_tool_error: bool,
+
+    /// Forward the model's reasoning ("thinking") content to the UI as
+    /// `agent:reasoning` events while streaming. Turns on extended thinking
+    /// for Anthropic, which the model must support.
+    #[serde(default)]
+    pub stream_reasoning: bool,
+
+    /// Ask the user (via `tool:confirm_request`) before destructive tool
+    /// actions such as deleting files (see `tools::confirmation`)
+    #[serde(default)]
+    pub require_confirmation_for_destructive: bool,
+
+    /// Safe mode: the agent can read but not change anything (see
+    /// `tools::read_only`)
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Number of recent messages reloaded into working memory when the agent
+    /// starts (1-2000). Falls back to `agent::DEFAULT_HISTORY_WINDOW`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_window: Option<i32>,
+
+    /// Maximum number of tool-calling turns per message (1-200). Falls back
+    /// to `agent::DEFAULT_MAX_TOOL_TURNS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_turns: Option<usize>,
+
+    /// Per-turn call limits by tool name, overriding
+    /// `agent::budget::DEFAULT_TOOL_BUDGETS` for the listed tools
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_budgets: HashMap<String, usize>,
+
+    /// Language for extracted facts and conversation summaries (e.g.
+    /// "German"). Unset keeps the extractors' default behavior.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Automatic fact extraction after each turn, batched, or off
+    #[serde(default)]
+    pub fact_extraction: FactExtractionMode,
+
+    /// Consolidate long-term memory (see `memory::consolidation`) when the
+    /// agent loads and the last run is at least this many days old. Only
+    /// checked when the agent is built, so an agent kept loaded for longer
+    /// than the interval waits for its next build. Unset runs consolidation
+    /// only on demand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_consolidation_days: Option<u32>,
+
+    /// Context window of the model in tokens, overriding the default for the
+    /// provider/model. Prompt and history are trimmed to fit it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_limit_tokens: Option<usize>,
+
+    /// Maximum length of a tool result in characters before it is truncated.
+    /// Falls back to `agent::DEFAULT_MAX_TOOL_OUTPUT_CHARS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_output_chars: Option<usize>,
+
+    /// Bridge `chat` calls allowed per Canvas program and minute. Falls back
+    /// to `canvas::rate_limit::DEFAULT_BRIDGE_CHAT_PER_MINUTE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge_chat_per_minute: Option<u32>,
+
+    /// Providers tried in order when a chat turn fails on the instance's own
+    /// provider (e.g. cloud -> local Ollama). Empty disables fallback.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_providers: Vec<FallbackProvider>,
+
+    /// Existing directory used as the workspace instead of the default
+    /// directory under the instance's data folder (see
+    /// `utils::paths::get_instance_workspace_path`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_override: Option<PathBuf>,
+
     /// Path to instance database
     #[serde(skip)]
     pub db_path: Option<PathBuf>,
@@ -74,11 +204,58 @@ pub struct AIInstance {
 #[derive(Debug, Deserialize)]
 pub struct CreateInstanceRequest {
     pub name: String,
-    pub provider: String, // "anthropic" | "openai" | "ollama"
+    pub provider: String, // "anthropic" | "openai" | "ollama" | "openai_compatible"
     pub model: String,
     /// Optional custom base URL (e.g., for Ollama: http://localhost:11434)
     #[serde(default)]
     pub api_base_url: Option<String>,
+    /// Optional tool-calling turn limit (see `AIInstance::max_tool_turns`)
+    #[serde(default)]
+    pub max_tool_turns: Option<usize>,
+}
+
+/// Settings to change on an instance. Absent fields are left as they are;
+/// for optional settings an explicit `null` clears the value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct InstanceSettingsPatch {
+    #[serde(deserialize_with = "some_value")]
+    pub custom_instructions: Option<Option<String>>,
+    #[serde(deserialize_with = "some_value")]
+    pub language: Option<Option<String>>,
+    pub fact_extraction: Option<FactExtractionMode>,
+    pub stop_on_repeated_tool_error: Option<bool>,
+    pub stream_reasoning: Option<bool>,
+    pub require_confirmation_for_destructive: Option<bool>,
+    pub read_only: Option<bool>,
+    #[serde(deserialize_with = "some_value")]
+    pub history_window: Option<Option<i32>>,
+    #[serde(deserialize_with = "some_value")]
+    pub max_tool_turns: Option<Option<usize>>,
+    pub tool_budgets: Option<HashMap<String, usize>>,
+    #[serde(deserialize_with = "some_value")]
+    pub memory_consolidation_days: Option<Option<u32>>,
+    #[serde(deserialize_with = "some_value")]
+    pub context_limit_tokens: Option<Option<usize>>,
+    #[serde(deserialize_with = "some_value")]
+    pub max_tool_output_chars: Option<Option<usize>>,
+    #[serde(deserialize_with = "some_value")]
+    pub bridge_chat_per_minute: Option<Option<u32>>,
+    #[serde(deserialize_with = "some_value")]
+    pub program_data_quota_bytes: Option<Option<u64>>,
+    pub fallback_providers: Option<Vec<FallbackProvider>>,
+    #[serde(deserialize_with = "some_value")]
+    pub workspace_override: Option<Option<PathBuf>>,
+}
+
+/// Deserialize a present field (including `null`) as `Some`, so that a
+/// missing field (`None` via `#[serde(default)]`) can be told apart from `null`.
+fn some_value<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 /// Information about a provider for the frontend
@@ -91,3 +268,12 @@ pub struct ProviderInfo {
     pub suggested_models: Vec<&'static str>,
     pub default_model: Option<&'static str>,
 }
+
+/// API key status of a provider for the setup screen
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiKeyStatus {
+    /// Whether the provider requires an API key at all (false for Ollama)
+    pub needs_api_key: bool,
+    /// Whether a key is stored (always false if no key is required)
+    pub has_api_key: bool,
+}
@@ -0,0 +1,537 @@
+use super::models::LLMProvider;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Base URL of the Anthropic API
+pub const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com";
+
+/// Base URL of the OpenAI API
+pub const OPENAI_API_BASE: &str = "https://api.openai.com";
+
+/// Default Ollama server URL (used when an instance has no `api_base_url`)
+pub const OLLAMA_DEFAULT_BASE: &str = "http://localhost:11434";
+
+/// Anthropic API version header value
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Timeout for provider API requests made outside of chat
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Models per page requested from Anthropic's paginated model list (its maximum)
+const ANTHROPIC_MODELS_PAGE_LIMIT: u32 = 1000;
+
+/// Most model list pages fetched, in case a server never ends the list
+const MAX_MODEL_PAGES: usize = 20;
+
+/// Default base URL for a provider (None if the instance must supply one)
+pub fn default_base_url(provider: &LLMProvider) -> Option<&'static str> {
+    match provider {
+        LLMProvider::Anthropic => Some(ANTHROPIC_API_BASE),
+        LLMProvider::OpenAI => Some(OPENAI_API_BASE),
+        LLMProvider::Ollama => Some(OLLAMA_DEFAULT_BASE),
+        LLMProvider::OpenAICompatible => None,
+    }
+}
+
+/// The configured base URL, or the provider's default.
+fn resolve_base_url<'a>(provider: &LLMProvider, base_url: Option<&'a str>) -> Result<&'a str> {
+    base_url
+        .or_else(|| default_base_url(provider))
+        .with_context(|| format!("Provider {} requires an API base URL", provider))
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+/// Build an authenticated request to the provider's model listing endpoint.
+fn models_request(
+    client: &reqwest::Client,
+    provider: &LLMProvider,
+    base_url: &str,
+    api_key: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let base_url = base_url.trim_end_matches('/');
+    match provider {
+        LLMProvider::Anthropic => client
+            .get(format!("{}/v1/models", base_url))
+            .header("x-api-key", api_key.unwrap_or_default())
+            .header("anthropic-version", ANTHROPIC_VERSION),
+        LLMProvider::OpenAI => client
+            .get(format!("{}/v1/models", base_url))
+            .bearer_auth(api_key.unwrap_or_default()),
+        LLMProvider::Ollama => client.get(format!("{}/api/tags", base_url)),
+        // Compatible base URLs already include the version path (e.g. .../v1)
+        LLMProvider::OpenAICompatible => client
+            .get(format!("{}/models", base_url))
+            .bearer_auth(api_key.unwrap_or_default()),
+    }
+}
+
+/// Map a non-success HTTP status from a provider to a clear error.
+fn check_status(provider: &LLMProvider, status: reqwest::StatusCode) -> Result<()> {
+    if status.is_success() {
+        return Ok(());
+    }
+    match status.as_u16() {
+        401 | 403 => bail!("The API key was rejected by {} ({})", provider, status),
+        _ => bail!("{} API request failed with status {}", provider, status),
+    }
+}
+
+/// Check that `api_key` is accepted by the provider by listing its models.
+/// `base_url` overrides the provider's default API URL.
+pub async fn validate_api_key(
+    provider: &LLMProvider,
+    api_key: &str,
+    base_url: Option<&str>,
+) -> Result<()> {
+    if !provider.needs_api_key() {
+        return Ok(());
+    }
+
+    let base_url = resolve_base_url(provider, base_url)?;
+    let response = models_request(&http_client()?, provider, base_url, Some(api_key))
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {} to validate the API key", provider))?;
+
+    check_status(provider, response.status())
+}
+
+/// List the model names available from a provider.
+/// `api_key` is required for providers that need one; `base_url` overrides the
+/// provider's default API URL (e.g. the instance's Ollama server).
+pub async fn list_models(
+    provider: &LLMProvider,
+    api_key: Option<&str>,
+    base_url: Option<&str>,
+) -> Result<Vec<String>> {
+    if provider.needs_api_key() && api_key.is_none() {
+        bail!("API key not configured for provider: {}", provider);
+    }
+
+    let base_url = resolve_base_url(provider, base_url)?;
+    let client = http_client()?;
+    let mut body = fetch_model_list(&client, provider, base_url, api_key, None).await?;
+
+    match provider {
+        LLMProvider::Anthropic => {
+            // The list is paginated; follow `last_id` while `has_more` is set
+            let mut models = parse_data_models(&body)?;
+            for _ in 1..MAX_MODEL_PAGES {
+                if body.get("has_more").and_then(|v| v.as_bool()) != Some(true) {
+                    break;
+                }
+                let Some(last_id) = body.get("last_id").and_then(|v| v.as_str()) else {
+                    break;
+                };
+                let after_id = last_id.to_string();
+                body =
+                    fetch_model_list(&client, provider, base_url, api_key, Some(&after_id)).await?;
+                models.extend(parse_data_models(&body)?);
+            }
+            Ok(sorted_names(models.iter().map(String::as_str)))
+        }
+        LLMProvider::OpenAI | LLMProvider::OpenAICompatible => parse_data_models(&body),
+        LLMProvider::Ollama => parse_ollama_tags(&body),
+    }
+}
+
+/// Fetch one page of a provider's model list. `after_id` continues an
+/// Anthropic list after that model.
+async fn fetch_model_list(
+    client: &reqwest::Client,
+    provider: &LLMProvider,
+    base_url: &str,
+    api_key: Option<&str>,
+    after_id: Option<&str>,
+) -> Result<serde_json::Value> {
+    let mut request = models_request(client, provider, base_url, api_key)
+        .build()
+        .context("Failed to build model list request")?;
+    if *provider == LLMProvider::Anthropic {
+        let mut query = request.url_mut().query_pairs_mut();
+        query.append_pair("limit", &ANTHROPIC_MODELS_PAGE_LIMIT.to_string());
+        if let Some(after_id) = after_id {
+            query.append_pair("after_id", after_id);
+        }
+    }
+
+    let response = client
+        .execute(request)
+        .await
+        .with_context(|| format!("Failed to reach {} at {}", provider, base_url))?;
+    check_status(provider, response.status())?;
+
+    response
+        .json()
+        .await
+        .with_context(|| format!("Invalid model list response from {}", provider))
+}
+
+/// Parse an Anthropic/OpenAI style `{"data": [{"id": ...}]}` model list.
+fn parse_data_models(body: &serde_json::Value) -> Result<Vec<String>> {
+    let data = body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .context("Model list response has no 'data' array")?;
+    Ok(sorted_names(
+        data.iter()
+            .filter_map(|m| m.get("id").and_then(|id| id.as_str())),
+    ))
+}
+
+/// Parse an Ollama `/api/tags` response (`{"models": [{"name": ...}]}`).
+fn parse_ollama_tags(body: &serde_json::Value) -> Result<Vec<String>> {
+    let models = body
+        .get("models")
+        .and_then(|m| m.as_array())
+        .context("Ollama response has no 'models' array")?;
+    Ok(sorted_names(models.iter().filter_map(|m| {
+        m.get("name")
+            .or_else(|| m.get("model"))
+            .and_then(|n| n.as_str())
+    })))
+}
+
+fn sorted_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut names: Vec<String> = names.map(|n| n.to_string()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Result of an instance connection health check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthStatus {
+    /// Whether the provider API (for Ollama: the base URL) responded
+    pub reachable: bool,
+    /// Whether the instance's model is in the provider's model list
+    pub model_available: bool,
+    /// Round-trip time of the model list request
+    pub latency_ms: Option<u64>,
+    /// The Ollama or OpenAI-compatible server URL that was checked
+    /// (None for hosted providers)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Human-readable reason if the check failed
+    pub error: Option<String>,
+}
+
+/// Ping the provider by listing its models and check that `model` exists.
+pub async fn check_health(
+    provider: &LLMProvider,
+    api_key: Option<&str>,
+    base_url: Option<&str>,
+    model: &str,
+) -> HealthStatus {
+    let checked_url = match provider {
+        LLMProvider::Ollama => Some(base_url.unwrap_or(OLLAMA_DEFAULT_BASE).to_string()),
+        LLMProvider::OpenAICompatible => base_url.map(str::to_string),
+        _ => None,
+    };
+
+    let started = Instant::now();
+    let result = list_models(provider, api_key, base_url).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(models) => {
+            let model_available = model_in_list(&models, model);
+            HealthStatus {
+                reachable: true,
+                model_available,
+                latency_ms: Some(latency_ms),
+                base_url: checked_url,
+                error: (!model_available)
+                    .then(|| format!("Model '{}' is not available from {}", model, provider)),
+            }
+        }
+        Err(e) => HealthStatus {
+            // An HTTP error status still means the server answered
+            reachable: !is_connection_error(&e) && api_key_present(provider, api_key),
+            model_available: false,
+            latency_ms: None,
+            base_url: checked_url,
+            error: Some(health_error_message(provider, &e)),
+        },
+    }
+}
+
+fn api_key_present(provider: &LLMProvider, api_key: Option<&str>) -> bool {
+    !provider.needs_api_key() || api_key.is_some()
+}
+
+/// Whether `model` is in `models`. Ollama reports untagged models as
+/// `name:latest`, so `llama3` matches `llama3:latest`.
+fn model_in_list(models: &[String], model: &str) -> bool {
+    models
+        .iter()
+        .any(|m| m == model || m.strip_suffix(":latest") == Some(model))
+}
+
+/// Whether an error means the provider could not be reached at all.
+fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect() || e.is_timeout())
+}
+
+/// Turn a provider request error into a short diagnostic for the UI.
+fn health_error_message(provider: &LLMProvider, err: &anyhow::Error) -> String {
+    let reqwest_err = err.chain().find_map(|e| e.downcast_ref::<reqwest::Error>());
+    match reqwest_err {
+        Some(e) if e.is_timeout() => format!("Request to {} timed out", provider),
+        Some(e) if e.is_connect() => match provider {
+            LLMProvider::Ollama => {
+                "Could not connect to the Ollama server. Is Ollama running?".to_string()
+            }
+            _ => format!(
+                "Could not connect to {}. Check your internet connection.",
+                provider
+            ),
+        },
+        _ => format!("{:#}", err),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_server {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a single canned HTTP response on a local port and return its base URL.
+    pub async fn serve_once(status_line: &str, body: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status_line,
+            body.len(),
+            body
+        );
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_server::serve_once;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validate_api_key_accepted() {
+        let base = serve_once("200 OK", r#"{"data": []}"#).await;
+        validate_api_key(&LLMProvider::OpenAI, "sk-valid", Some(&base))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validate_api_key_rejected() {
+        let base = serve_once("401 Unauthorized", r#"{"error": "invalid x-api-key"}"#).await;
+        let err = validate_api_key(&LLMProvider::Anthropic, "sk-typo", Some(&base))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("rejected by anthropic"));
+    }
+
+    const OLLAMA_TAGS: &str = r#"{
+        "models": [
+            {
+                "name": "qwen3:8b",
+                "model": "qwen3:8b",
+                "modified_at": "2025-05-01T10:00:00Z",
+                "size": 5200000000,
+                "details": {"family": "qwen3", "parameter_size": "8.2B"}
+            },
+            {
+                "name": "llama3.2:latest",
+                "model": "llama3.2:latest",
+                "size": 2000000000
+            },
+            {"model": "mistral:7b"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_ollama_tags() {
+        let body: serde_json::Value = serde_json::from_str(OLLAMA_TAGS).unwrap();
+        let models = parse_ollama_tags(&body).unwrap();
+        assert_eq!(models, vec!["llama3.2:latest", "mistral:7b", "qwen3:8b"]);
+
+        let empty: serde_json::Value = serde_json::json!({"models": []});
+        assert!(parse_ollama_tags(&empty).unwrap().is_empty());
+
+        let invalid: serde_json::Value = serde_json::json!({"error": "not found"});
+        assert!(parse_ollama_tags(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_data_models() {
+        let body = serde_json::json!({
+            "data": [
+                {"id": "gpt-5-mini-2025-08-07", "object": "model"},
+                {"id": "gpt-5.2-2025-12-11", "object": "model"}
+            ]
+        });
+        assert_eq!(
+            parse_data_models(&body).unwrap(),
+            vec!["gpt-5-mini-2025-08-07", "gpt-5.2-2025-12-11"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_from_ollama_server() {
+        let base = serve_once("200 OK", OLLAMA_TAGS).await;
+        let models = list_models(&LLMProvider::Ollama, None, Some(&base))
+            .await
+            .unwrap();
+        assert_eq!(models.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_list_models_follows_anthropic_pages() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let pages = [
+            r#"{"data": [{"id": "claude-sonnet-4-5"}], "has_more": true, "last_id": "claude-sonnet-4-5"}"#,
+            r#"{"data": [{"id": "claude-haiku-4-5"}], "has_more": false, "last_id": "claude-haiku-4-5"}"#,
+        ];
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        // Answer each page in turn and keep the request lines
+        let server = tokio::spawn(async move {
+            let mut request_lines = Vec::new();
+            for body in pages {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                request_lines.push(request.lines().next().unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                let _ = socket.shutdown().await;
+            }
+            request_lines
+        });
+
+        let models = list_models(&LLMProvider::Anthropic, Some("key"), Some(&base))
+            .await
+            .unwrap();
+        assert_eq!(models, vec!["claude-haiku-4-5", "claude-sonnet-4-5"]);
+
+        let request_lines = server.await.unwrap();
+        assert!(request_lines[0].contains("limit=1000"));
+        assert!(!request_lines[0].contains("after_id"));
+        assert!(request_lines[1].contains("after_id=claude-sonnet-4-5"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_from_openai_compatible_server() {
+        let body = r#"{"data": [{"id": "mistral-large-latest"}, {"id": "codestral-latest"}]}"#;
+        let base = serve_once("200 OK", body).await;
+        let models = list_models(
+            &LLMProvider::OpenAICompatible,
+            Some("key"),
+            Some(&format!("{}/v1", base)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(models, vec!["codestral-latest", "mistral-large-latest"]);
+
+        let err = list_models(&LLMProvider::OpenAICompatible, Some("key"), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("requires an API base URL"));
+    }
+
+    #[test]
+    fn test_health_status_serialization() {
+        let status = HealthStatus {
+            reachable: true,
+            model_available: false,
+            latency_ms: Some(42),
+            base_url: None,
+            error: Some("Model 'x' is not available from openai".to_string()),
+        };
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["reachable"], true);
+        assert_eq!(json["model_available"], false);
+        assert_eq!(json["latency_ms"], 42);
+        assert!(json.get("base_url").is_none());
+        assert!(json["error"].as_str().unwrap().contains("not available"));
+    }
+
+    #[test]
+    fn test_health_error_message() {
+        let err = anyhow::anyhow!("The API key was rejected by openai (401 Unauthorized)");
+        assert_eq!(
+            health_error_message(&LLMProvider::OpenAI, &err),
+            "The API key was rejected by openai (401 Unauthorized)"
+        );
+        assert!(!is_connection_error(&err));
+    }
+
+    #[test]
+    fn test_model_in_list() {
+        let models = vec!["llama3:latest".to_string(), "qwen3:8b".to_string()];
+        assert!(model_in_list(&models, "llama3"));
+        assert!(model_in_list(&models, "qwen3:8b"));
+        assert!(!model_in_list(&models, "qwen3"));
+    }
+
+    #[tokio::test]
+    async fn test_check_health_ollama_down() {
+        // Bind and drop a listener to get a port with nothing listening
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let base = format!("http://127.0.0.1:{}", port);
+
+        let status = check_health(&LLMProvider::Ollama, None, Some(&base), "llama3").await;
+        assert!(!status.reachable);
+        assert!(!status.model_available);
+        assert_eq!(status.base_url.as_deref(), Some(base.as_str()));
+        assert!(status.error.unwrap().contains("Is Ollama running?"));
+    }
+
+    #[tokio::test]
+    async fn test_check_health_ollama_up() {
+        let base = serve_once("200 OK", OLLAMA_TAGS).await;
+        let status = check_health(&LLMProvider::Ollama, None, Some(&base), "qwen3:8b").await;
+        assert!(status.reachable);
+        assert!(status.model_available);
+        assert!(status.latency_ms.is_some());
+        assert!(status.error.is_none());
+    }
+
+    #[test]
+    fn test_check_status() {
+        assert!(check_status(&LLMProvider::OpenAI, reqwest::StatusCode::OK).is_ok());
+        let err = check_status(&LLMProvider::OpenAI, reqwest::StatusCode::FORBIDDEN).unwrap_err();
+        assert!(err.to_string().contains("rejected"));
+        let err = check_status(&LLMProvider::OpenAI, reqwest::StatusCode::BAD_GATEWAY).unwrap_err();
+        assert!(err.to_string().contains("502"));
+    }
+}
@@ -5,13 +5,20 @@
 //! from the React frontend to Tauri commands, and then dispatched here.
 
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Row, Sqlite};
+use std::future::Future;
 use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
 use tauri::AppHandle;
 use tokio::fs;
 
+use super::storage;
+use crate::tools::registry::ToolStatus;
+use crate::tools::rhai_bridge_tool::SharedRegistry;
+
 // ---------------------------------------------------------------------------
 // Types
 // ---------------------------------------------------------------------------
@@ -22,6 +29,11 @@ use tokio::fs;
 pub enum BridgeRequest {
     #[serde(rename = "chat")]
     Chat { prompt: String },
+    #[serde(rename = "chatJson")]
+    ChatJson {
+        prompt: String,
+        schema: serde_json::Value,
+    },
     #[serde(rename = "storeData")]
     StoreData {
         key: String,
@@ -29,6 +41,17 @@ pub enum BridgeRequest {
     },
     #[serde(rename = "loadData")]
     LoadData { key: String },
+    #[serde(rename = "listKeys")]
+    ListKeys,
+    #[serde(rename = "deleteData")]
+    DeleteData { key: String },
+    #[serde(rename = "getMetadata")]
+    GetMetadata,
+    #[serde(rename = "executeTool")]
+    ExecuteTool {
+        name: String,
+        params: serde_json::Value,
+    },
     #[serde(rename = "notify")]
     Notify {
         message: String,
@@ -38,6 +61,8 @@ pub enum BridgeRequest {
     ReadFile { path: String },
     #[serde(rename = "writeFile")]
     WriteFile { path: String, content: String },
+    #[serde(rename = "writeFileBase64")]
+    WriteFileBase64 { path: String, base64: String },
 }
 
 /// A bridge response sent back to the Canvas program.
@@ -78,9 +103,15 @@ impl BridgeResponse {
 // Program Data (key-value storage per program)
 // ---------------------------------------------------------------------------
 
+/// Default maximum total size (bytes) of stored values per program.
+pub const DEFAULT_PROGRAM_DATA_QUOTA_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Largest configurable per-program storage quota (bytes).
+pub const MAX_PROGRAM_DATA_QUOTA_BYTES: u64 = 1024 * 1024 * 1024;
+
 /// Store a key-value pair for a program.
-pub async fn store_program_data(
-    db: &Pool<Sqlite>,
+pub async fn store_program_data<'e>(
+    db: impl sqlx::Executor<'e, Database = Sqlite>,
     program_name: &str,
     key: &str,
     value: &serde_json::Value,
@@ -137,23 +168,117 @@ pub async fn load_program_data(
     }
 }
 
+/// List all stored keys for a program, sorted alphabetically.
+pub async fn list_program_data_keys(db: &Pool<Sqlite>, program_name: &str) -> Result<Vec<String>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT key FROM program_data
+        WHERE program_name = ?
+        ORDER BY key ASC
+        "#,
+    )
+    .bind(program_name)
+    .fetch_all(db)
+    .await
+    .context("Failed to list program data keys")?;
+
+    Ok(rows.iter().map(|r| r.get("key")).collect())
+}
+
+/// Delete a stored key for a program. Returns whether a key was removed.
+pub async fn delete_program_data(db: &Pool<Sqlite>, program_name: &str, key: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM program_data
+        WHERE program_name = ? AND key = ?
+        "#,
+    )
+    .bind(program_name)
+    .bind(key)
+    .execute(db)
+    .await
+    .context("Failed to delete program data")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Total size in bytes of all stored values for a program.
+/// If `exclude_key` is given, that key is left out (used when overwriting it).
+pub async fn program_data_size<'e>(
+    db: impl sqlx::Executor<'e, Database = Sqlite>,
+    program_name: &str,
+    exclude_key: Option<&str>,
+) -> Result<u64> {
+    let size: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(SUM(LENGTH(CAST(value AS BLOB))), 0) FROM program_data
+        WHERE program_name = ? AND (? IS NULL OR key != ?)
+        "#,
+    )
+    .bind(program_name)
+    .bind(exclude_key)
+    .bind(exclude_key)
+    .fetch_one(db)
+    .await
+    .context("Failed to compute program data size")?;
+
+    Ok(size.max(0) as u64)
+}
+
 // ---------------------------------------------------------------------------
 // Bridge handlers
 // ---------------------------------------------------------------------------
 
 /// Handle a storeData bridge request.
+///
+/// Rejects the write if the program's total stored data (with this key's new
+/// value replacing any previous one) would exceed `quota_bytes`.
 pub async fn handle_store_data(
     db: &Pool<Sqlite>,
     program_name: &str,
     key: &str,
     value: &serde_json::Value,
+    quota_bytes: u64,
 ) -> BridgeResponse {
-    match store_program_data(db, program_name, key, value).await {
-        Ok(()) => BridgeResponse::ok_empty(),
+    match store_within_quota(db, program_name, key, value, quota_bytes).await {
+        Ok(None) => BridgeResponse::ok_empty(),
+        Ok(Some((existing_size, new_size))) => BridgeResponse::err(format!(
+            "Storage quota exceeded: {} of {} bytes used, value needs {} bytes",
+            existing_size, quota_bytes, new_size
+        )),
         Err(e) => BridgeResponse::err(format!("Failed to store data: {}", e)),
     }
 }
 
+/// Store a value and check the quota in one transaction, rolling the write
+/// back if it would exceed `quota_bytes`. Writing first takes the database
+/// write lock, so concurrent stores cannot both pass the check. Returns the
+/// size used by the other keys and the value size if the quota was exceeded.
+async fn store_within_quota(
+    db: &Pool<Sqlite>,
+    program_name: &str,
+    key: &str,
+    value: &serde_json::Value,
+    quota_bytes: u64,
+) -> Result<Option<(u64, u64)>> {
+    let new_size = serde_json::to_string(value)
+        .context("Failed to serialize value")?
+        .len() as u64;
+    let mut tx = db.begin().await.context("Failed to begin transaction")?;
+
+    store_program_data(&mut *tx, program_name, key, value).await?;
+    let existing_size = program_data_size(&mut *tx, program_name, Some(key)).await?;
+    if existing_size + new_size > quota_bytes {
+        tx.rollback()
+            .await
+            .context("Failed to roll back program data")?;
+        return Ok(Some((existing_size, new_size)));
+    }
+
+    tx.commit().await.context("Failed to commit program data")?;
+    Ok(None)
+}
+
 /// Handle a loadData bridge request.
 pub async fn handle_load_data(db: &Pool<Sqlite>, program_name: &str, key: &str) -> BridgeResponse {
     match load_program_data(db, program_name, key).await {
@@ -163,6 +288,163 @@ pub async fn handle_load_data(db: &Pool<Sqlite>, program_name: &str, key: &str)
     }
 }
 
+/// Handle a listKeys bridge request.
+pub async fn handle_list_keys(db: &Pool<Sqlite>, program_name: &str) -> BridgeResponse {
+    match list_program_data_keys(db, program_name).await {
+        Ok(keys) => BridgeResponse::ok(serde_json::json!(keys)),
+        Err(e) => BridgeResponse::err(format!("Failed to list keys: {}", e)),
+    }
+}
+
+/// Handle a deleteData bridge request. Returns whether the key existed.
+pub async fn handle_delete_data(
+    db: &Pool<Sqlite>,
+    program_name: &str,
+    key: &str,
+) -> BridgeResponse {
+    match delete_program_data(db, program_name, key).await {
+        Ok(deleted) => BridgeResponse::ok(serde_json::Value::Bool(deleted)),
+        Err(e) => BridgeResponse::err(format!("Failed to delete data: {}", e)),
+    }
+}
+
+/// Handle a getMetadata bridge request.
+/// Returns the calling program's `ProgramMetadata` (name, version, description, ...).
+pub async fn handle_get_metadata(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+) -> BridgeResponse {
+    match storage::get_program_by_name(db, instance_id, program_name).await {
+        Ok(Some(metadata)) => match serde_json::to_value(metadata) {
+            Ok(value) => BridgeResponse::ok(value),
+            Err(e) => BridgeResponse::err(format!("Failed to serialize metadata: {}", e)),
+        },
+        Ok(None) => BridgeResponse::err(format!("Program '{}' not found", program_name)),
+        Err(e) => BridgeResponse::err(format!("Failed to load metadata: {}", e)),
+    }
+}
+
+/// Handle an executeTool bridge request.
+///
+/// Runs a dynamic Rhai tool directly, without going through the LLM.
+/// Only tools with status `active` may be called from Canvas programs.
+pub async fn handle_execute_tool(
+    registry: &SharedRegistry,
+    name: &str,
+    params: serde_json::Value,
+) -> BridgeResponse {
+    let reg = registry.read().await;
+
+    match reg.get_tool(name).await {
+        Ok(Some(tool)) if tool.status == ToolStatus::Active => {}
+        Ok(Some(tool)) => {
+            return BridgeResponse::err(format!(
+                "Tool '{}' is not active (status: {})",
+                name, tool.status
+            ))
+        }
+        Ok(None) => return BridgeResponse::err(format!("Tool not found: {}", name)),
+        Err(e) => return BridgeResponse::err(format!("Failed to look up tool: {}", e)),
+    }
+
+    match reg.execute_tool(name, params).await {
+        Ok(output) => BridgeResponse::ok(serde_json::Value::String(output)),
+        Err(e) => BridgeResponse::err(format!("Tool execution failed: {}", e)),
+    }
+}
+
+/// Produces JSON for a prompt (implemented by the agent's one-off extractor,
+/// replaceable in tests).
+pub trait JsonGenerator: Send + Sync {
+    fn generate_json<'a>(
+        &'a self,
+        prompt: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value>> + Send + 'a>>;
+}
+
+/// Handle a chatJson bridge request: ask the model for JSON conforming to
+/// `schema` and check the result against it before returning it.
+pub async fn handle_chat_json(
+    generator: &dyn JsonGenerator,
+    prompt: &str,
+    schema: &serde_json::Value,
+) -> BridgeResponse {
+    if !schema.is_object() {
+        return BridgeResponse::err("Schema must be a JSON schema object");
+    }
+
+    match generator.generate_json(prompt).await {
+        Ok(value) => match check_schema(&value, schema, "$") {
+            Ok(()) => BridgeResponse::ok(value),
+            Err(e) => BridgeResponse::err(format!("Response does not match schema: {}", e)),
+        },
+        Err(e) => BridgeResponse::err(format!("Chat error: {}", e)),
+    }
+}
+
+/// Check `value` against the commonly used subset of JSON schema: `type`,
+/// `enum`, `properties`/`required` for objects and `items` for arrays.
+/// Other keywords are ignored.
+fn check_schema(
+    value: &serde_json::Value,
+    schema: &serde_json::Value,
+    at: &str,
+) -> Result<(), String> {
+    use serde_json::Value;
+
+    let type_matches = |name: &str| match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    };
+    match schema.get("type") {
+        Some(Value::String(name)) if !type_matches(name) => {
+            return Err(format!("{} should be of type {}", at, name));
+        }
+        Some(Value::Array(names)) if !names.iter().filter_map(Value::as_str).any(type_matches) => {
+            return Err(format!("{} has none of the allowed types", at));
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            return Err(format!("{} is not one of the allowed values", at));
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(key) {
+                    return Err(format!("{} is missing required field '{}'", at, key));
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (key, field_schema) in properties {
+                if let Some(field) = fields.get(key) {
+                    check_schema(field, field_schema, &format!("{}.{}", at, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check_schema(item, item_schema, &format!("{}[{}]", at, i))?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Handle a notify bridge request.
 ///
 /// Sends a native OS notification via `tauri-plugin-notification` when an
@@ -247,6 +529,40 @@ pub async fn handle_read_file(workspace: &Path, path: &str) -> BridgeResponse {
 
 /// Handle a writeFile bridge request (scoped to workspace directory).
 pub async fn handle_write_file(workspace: &Path, path: &str, content: &str) -> BridgeResponse {
+    write_workspace_file(workspace, path, content.as_bytes()).await
+}
+
+/// Handle a writeFileBase64 bridge request: decode `base64` and write the raw
+/// bytes (scoped to workspace directory). Lets programs save binary assets
+/// such as canvas-rendered PNGs.
+pub async fn handle_write_file_base64(
+    workspace: &Path,
+    path: &str,
+    base64: &str,
+) -> BridgeResponse {
+    match decode_base64_content(base64) {
+        Ok(bytes) => write_workspace_file(workspace, path, &bytes).await,
+        Err(e) => BridgeResponse::err(e),
+    }
+}
+
+/// Decode base64 file content. A data URL prefix (as returned by
+/// `canvas.toDataURL()`, e.g. `data:image/png;base64,`) is accepted and skipped.
+pub(crate) fn decode_base64_content(encoded: &str) -> Result<Vec<u8>, String> {
+    let encoded = encoded.trim();
+    let payload = match encoded.strip_prefix("data:") {
+        Some(rest) => match rest.split_once(";base64,") {
+            Some((_, data)) => data,
+            None => return Err("Data URL must be base64-encoded".to_string()),
+        },
+        None => encoded,
+    };
+    BASE64
+        .decode(payload)
+        .map_err(|e| format!("Invalid base64 content: {}", e))
+}
+
+async fn write_workspace_file(workspace: &Path, path: &str, bytes: &[u8]) -> BridgeResponse {
     let resolved = match resolve_workspace_path(workspace, path) {
         Ok(p) => p,
         Err(e) => return BridgeResponse::err(e),
@@ -259,16 +575,21 @@ pub async fn handle_write_file(workspace: &Path, path: &str, content: &str) -> B
         }
     }
 
-    match fs::write(&resolved, content).await {
+    match fs::write(&resolved, bytes).await {
         Ok(()) => BridgeResponse::ok_empty(),
         Err(e) => BridgeResponse::err(format!("Failed to write file '{}': {}", path, e)),
     }
 }
 
+/// Marker comment contained in the bridge script, used to detect HTML that
+/// already includes it (so the protocol handler does not inject it twice).
+pub const BRIDGE_SCRIPT_MARKER: &str = "ownai-bridge-script";
+
 /// Returns the JavaScript bridge code that gets injected into Canvas HTML files.
 /// This script provides the `window.ownai` API object.
 pub fn bridge_script() -> &'static str {
     r#"<script>
+/* ownai-bridge-script */
 (function() {
   "use strict";
   var pending = {};
@@ -289,11 +610,17 @@ pub fn bridge_script() -> &'static str {
 
   window.ownai = {
     chat: function(prompt) { return call("chat", { prompt: prompt }); },
+    chatJson: function(prompt, schema) { return call("chatJson", { prompt: prompt, schema: schema }); },
     storeData: function(key, value) { return call("storeData", { key: key, value: value }); },
     loadData: function(key) { return call("loadData", { key: key }); },
+    listKeys: function() { return call("listKeys", {}); },
+    deleteData: function(key) { return call("deleteData", { key: key }); },
+    getMetadata: function() { return call("getMetadata", {}); },
+    executeTool: function(name, params) { return call("executeTool", { name: name, params: params || {} }); },
     notify: function(message, delay_ms) { return call("notify", { message: message, delay_ms: delay_ms }); },
     readFile: function(path) { return call("readFile", { path: path }); },
-    writeFile: function(path, content) { return call("writeFile", { path: path, content: content }); }
+    writeFile: function(path, content) { return call("writeFile", { path: path, content: content }); },
+    writeFileBase64: function(path, base64) { return call("writeFileBase64", { path: path, base64: base64 }); }
   };
 
   window.addEventListener("message", function(event) {
@@ -397,11 +724,83 @@ mod tests {
         let db = setup_test_db().await;
         let value = serde_json::json!(42);
 
-        let response = handle_store_data(&db, "test", "key", &value).await;
+        let response =
+            handle_store_data(&db, "test", "key", &value, DEFAULT_PROGRAM_DATA_QUOTA_BYTES).await;
         assert!(response.success);
         assert!(response.data.is_none());
     }
 
+    #[tokio::test]
+    async fn test_list_keys() {
+        let db = setup_test_db().await;
+
+        store_program_data(&db, "chess", "b", &serde_json::json!(2))
+            .await
+            .unwrap();
+        store_program_data(&db, "chess", "a", &serde_json::json!(1))
+            .await
+            .unwrap();
+        store_program_data(&db, "todo", "c", &serde_json::json!(3))
+            .await
+            .unwrap();
+
+        let keys = list_program_data_keys(&db, "chess").await.unwrap();
+        assert_eq!(keys, vec!["a", "b"]);
+
+        let response = handle_list_keys(&db, "todo").await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(serde_json::json!(["c"])));
+    }
+
+    #[tokio::test]
+    async fn test_delete_data() {
+        let db = setup_test_db().await;
+
+        store_program_data(&db, "chess", "score", &serde_json::json!(100))
+            .await
+            .unwrap();
+
+        let response = handle_delete_data(&db, "chess", "score").await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(serde_json::json!(true)));
+        assert_eq!(
+            load_program_data(&db, "chess", "score").await.unwrap(),
+            None
+        );
+
+        // Deleting again reports that nothing was removed
+        let response = handle_delete_data(&db, "chess", "score").await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(serde_json::json!(false)));
+    }
+
+    #[tokio::test]
+    async fn test_store_data_quota_exceeded() {
+        let db = setup_test_db().await;
+        let quota = 20;
+
+        // "\"0123456789\"" serializes to 12 bytes
+        let value = serde_json::json!("0123456789");
+        let response = handle_store_data(&db, "chess", "a", &value, quota).await;
+        assert!(response.success);
+
+        // Overwriting the same key does not count the old value twice
+        let response = handle_store_data(&db, "chess", "a", &value, quota).await;
+        assert!(response.success);
+
+        // A second key would bring the total to 24 bytes
+        let response = handle_store_data(&db, "chess", "b", &value, quota).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("quota exceeded"));
+        assert_eq!(load_program_data(&db, "chess", "b").await.unwrap(), None);
+
+        // Other programs have their own quota
+        let response = handle_store_data(&db, "todo", "b", &value, quota).await;
+        assert!(response.success);
+
+        assert_eq!(program_data_size(&db, "chess", None).await.unwrap(), 12);
+    }
+
     #[tokio::test]
     async fn test_handle_load_data_exists() {
         let db = setup_test_db().await;
@@ -425,6 +824,103 @@ mod tests {
         assert_eq!(response.data, Some(serde_json::Value::Null));
     }
 
+    #[tokio::test]
+    async fn test_handle_get_metadata() {
+        let db = setup_test_db().await;
+        let temp_dir = TempDir::new().unwrap();
+
+        storage::create_program_in_db(&db, "inst-1", "chess", "A chess game", temp_dir.path())
+            .await
+            .unwrap();
+        let version = storage::update_program_version(&db, "inst-1", "chess")
+            .await
+            .unwrap();
+
+        let response = handle_get_metadata(&db, "inst-1", "chess").await;
+        assert!(response.success);
+        let data = response.data.unwrap();
+        assert_eq!(data["name"], "chess");
+        assert_eq!(data["description"], "A chess game");
+        assert_eq!(data["version"], version.as_str());
+        assert!(data["updated_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_handle_get_metadata_missing_program() {
+        let db = setup_test_db().await;
+
+        let response = handle_get_metadata(&db, "inst-1", "nope").await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("not found"));
+    }
+
+    fn test_registry(db: Pool<Sqlite>) -> SharedRegistry {
+        std::sync::Arc::new(tokio::sync::RwLock::new(
+            crate::tools::registry::RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None),
+        ))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_handle_execute_tool() {
+        let db = setup_test_db().await;
+        let registry = test_registry(db);
+
+        registry
+            .write()
+            .await
+            .register_tool(
+                "adder",
+                "Adds a and b",
+                r#"let p = json_parse(params_json); p["a"] + p["b"]"#,
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let response =
+            handle_execute_tool(&registry, "adder", serde_json::json!({"a": 40, "b": 2})).await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(serde_json::json!("42")));
+
+        // Runs alongside other readers of the registry (e.g. a tool the agent
+        // is executing) instead of waiting for exclusive access
+        let _reader = registry.read().await;
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            handle_execute_tool(&registry, "adder", serde_json::json!({"a": 1, "b": 2})),
+        )
+        .await
+        .expect("executeTool waited for the registry lock");
+        assert_eq!(response.data, Some(serde_json::json!("3")));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_handle_execute_tool_rejects_inactive() {
+        let db = setup_test_db().await;
+        let registry = test_registry(db.clone());
+
+        registry
+            .write()
+            .await
+            .register_tool("old", "Old tool", "1", vec![], None, None)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE tools SET status = 'testing' WHERE name = 'old'")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let response = handle_execute_tool(&registry, "old", serde_json::json!({})).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("not active"));
+
+        let response = handle_execute_tool(&registry, "missing", serde_json::json!({})).await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("not found"));
+    }
+
     #[tokio::test]
     async fn test_handle_notify_without_app_handle() {
         let response = handle_notify(None, "ownAI", "Test notification", None).await;
@@ -533,18 +1029,127 @@ mod tests {
         assert!(response.error.unwrap().contains("Absolute paths"));
     }
 
+    #[tokio::test]
+    async fn test_handle_write_file_base64_writes_decoded_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path();
+        let png_header = [0x89u8, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff];
+        let encoded = BASE64.encode(png_header);
+
+        let response = handle_write_file_base64(workspace, "images/logo.png", &encoded).await;
+        assert!(response.success);
+        assert_eq!(
+            std::fs::read(workspace.join("images").join("logo.png")).unwrap(),
+            png_header
+        );
+
+        // Data URLs from canvas.toDataURL() are accepted too
+        let data_url = format!("data:image/png;base64,{}", encoded);
+        let response = handle_write_file_base64(workspace, "frame.png", &data_url).await;
+        assert!(response.success);
+        assert_eq!(
+            std::fs::read(workspace.join("frame.png")).unwrap(),
+            png_header
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_write_file_base64_rejects_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path();
+
+        let response = handle_write_file_base64(workspace, "bad.png", "not base64!!").await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("Invalid base64"));
+        assert!(!workspace.join("bad.png").exists());
+
+        let response = handle_write_file_base64(workspace, "../evil.png", "AAAA").await;
+        assert!(!response.success);
+        assert!(response.error.unwrap().contains("traversal"));
+    }
+
     #[tokio::test]
     async fn test_bridge_script_contains_ownai() {
         let script = bridge_script();
         assert!(script.contains("window.ownai"));
+        assert!(script.contains(BRIDGE_SCRIPT_MARKER));
         assert!(script.contains("ownai-bridge-request"));
         assert!(script.contains("ownai-bridge-response"));
         assert!(script.contains("chat"));
         assert!(script.contains("storeData"));
         assert!(script.contains("loadData"));
+        assert!(script.contains("listKeys"));
+        assert!(script.contains("deleteData"));
+        assert!(script.contains("getMetadata"));
+        assert!(script.contains("executeTool"));
         assert!(script.contains("notify"));
         assert!(script.contains("readFile"));
         assert!(script.contains("writeFile"));
+        assert!(script.contains("writeFileBase64"));
+        assert!(script.contains("chatJson"));
+    }
+
+    /// Returns a fixed JSON value instead of calling a model.
+    struct MockJsonGenerator(serde_json::Value);
+
+    impl JsonGenerator for MockJsonGenerator {
+        fn generate_json<'a>(
+            &'a self,
+            _prompt: &'a str,
+        ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value>> + Send + 'a>> {
+            Box::pin(async move { Ok(self.0.clone()) })
+        }
+    }
+
+    fn quiz_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "question": { "type": "string" },
+                "options": { "type": "array", "items": { "type": "string" } },
+                "answer": { "type": "integer" }
+            },
+            "required": ["question", "options", "answer"]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_handle_chat_json_returns_valid_json() {
+        let quiz = serde_json::json!({
+            "question": "Capital of France?",
+            "options": ["Berlin", "Paris"],
+            "answer": 1
+        });
+        let generator = MockJsonGenerator(quiz.clone());
+
+        let response = handle_chat_json(&generator, "Make a quiz question", &quiz_schema()).await;
+        assert!(response.success);
+        assert_eq!(response.data, Some(quiz));
+    }
+
+    #[tokio::test]
+    async fn test_handle_chat_json_rejects_mismatching_json() {
+        let generator = MockJsonGenerator(serde_json::json!({
+            "question": "Capital of France?",
+            "options": ["Berlin", 2],
+            "answer": 1
+        }));
+        let response = handle_chat_json(&generator, "quiz", &quiz_schema()).await;
+        assert!(!response.success);
+        assert!(response
+            .error
+            .unwrap()
+            .contains("$.options[1] should be of type string"));
+
+        let generator = MockJsonGenerator(serde_json::json!({ "question": "?" }));
+        let response = handle_chat_json(&generator, "quiz", &quiz_schema()).await;
+        assert!(response
+            .error
+            .unwrap()
+            .contains("missing required field 'options'"));
+
+        let response = handle_chat_json(&generator, "quiz", &serde_json::json!("string")).await;
+        assert!(response.error.unwrap().contains("JSON schema object"));
     }
 
     #[tokio::test]
@@ -1,5 +1,6 @@
 pub mod bridge;
 pub mod protocol;
+pub mod rate_limit;
 pub mod storage;
 pub mod tools;
 
@@ -18,6 +19,49 @@ pub struct ProgramMetadata {
     pub updated_at: String,
 }
 
+/// Disk usage of a program directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProgramUsage {
+    pub total_bytes: u64,
+    pub file_count: u64,
+}
+
+/// Result of pruning a program directory to a size cap.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProgramPruneResult {
+    /// Deleted files, relative to the program directory using `/` as separator.
+    pub deleted: Vec<String>,
+    pub bytes_freed: u64,
+    /// Size of the program directory after pruning.
+    pub total_bytes: u64,
+}
+
+/// A file in a program directory, as listed for the Canvas file view.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramFile {
+    /// Path relative to the program directory, using `/` as separator.
+    pub path: String,
+    pub size: u64,
+    pub modified: chrono::DateTime<chrono::Utc>,
+    /// Number of stored earlier versions (see `storage::restore_program_file`).
+    pub versions: i64,
+}
+
+/// A stored snapshot of a program file's earlier content.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramFileVersion {
+    /// Per-file version number, starting at 1.
+    pub version: i64,
+    pub size: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Returns whether a program name is safe to use as a directory name
+/// (non-empty, no path separators or traversal).
+pub fn is_valid_program_name(name: &str) -> bool {
+    !(name.is_empty() || name.contains('/') || name.contains('\\') || name.contains(".."))
+}
+
 /// Resolves a user-provided relative file path within a program directory.
 /// Prevents directory traversal attacks and ensures the path stays within the program root.
 pub fn resolve_program_path(
@@ -26,11 +70,7 @@ pub fn resolve_program_path(
     user_path: &str,
 ) -> Result<PathBuf, String> {
     // Validate program name (no path separators or traversal)
-    if program_name.contains('/')
-        || program_name.contains('\\')
-        || program_name.contains("..")
-        || program_name.is_empty()
-    {
+    if !is_valid_program_name(program_name) {
         return Err("Invalid program name".to_string());
     }
 
@@ -93,6 +133,15 @@ mod tests {
         assert!(resolve_program_path(&root, "", "index.html").is_err());
     }
 
+    #[test]
+    fn test_is_valid_program_name() {
+        assert!(is_valid_program_name("chess-board"));
+        assert!(!is_valid_program_name(""));
+        assert!(!is_valid_program_name("a/b"));
+        assert!(!is_valid_program_name("a\\b"));
+        assert!(!is_valid_program_name(".."));
+    }
+
     #[test]
     fn test_resolve_program_path_current_dir() {
         let root = PathBuf::from("/programs");
@@ -1,7 +1,23 @@
+use chrono::{DateTime, Utc};
 use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 use super::bridge;
 
+/// Result of a conditional program file request (see `load_program_file_conditional`).
+#[derive(Debug)]
+pub enum ConditionalFile {
+    /// The client's cached copy is still current (HTTP 304).
+    NotModified { etag: String },
+    /// The file changed (or was not cached); full contents are returned.
+    Modified {
+        bytes: Vec<u8>,
+        mime: String,
+        etag: String,
+        last_modified: Option<String>,
+    },
+}
+
 /// Load a file from a program directory.
 /// Returns the file bytes and MIME type.
 /// For HTML files, the Bridge API script is automatically injected.
@@ -10,6 +26,70 @@ pub fn load_program_file(
     program_name: &str,
     file_path: &str,
 ) -> Result<(Vec<u8>, String), String> {
+    let full_path = resolve_program_file(programs_root, program_name, file_path)?;
+    read_program_file(&full_path, file_path)
+}
+
+/// Load a file from a program directory, honoring an `If-None-Match` header.
+///
+/// A weak ETag is derived from the file size and modification time. When it
+/// matches `if_none_match`, the file is not read and `NotModified` is returned.
+pub fn load_program_file_conditional(
+    programs_root: &Path,
+    program_name: &str,
+    file_path: &str,
+    if_none_match: Option<&str>,
+) -> Result<ConditionalFile, String> {
+    let full_path = resolve_program_file(programs_root, program_name, file_path)?;
+    let metadata =
+        std::fs::metadata(&full_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let modified = metadata.modified().ok();
+
+    let etag = weak_etag(metadata.len(), modified);
+    if if_none_match.is_some_and(|header| etag_matches(header, &etag)) {
+        return Ok(ConditionalFile::NotModified { etag });
+    }
+
+    let (bytes, mime) = read_program_file(&full_path, file_path)?;
+    let last_modified = modified.map(|t| {
+        DateTime::<Utc>::from(t)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string()
+    });
+
+    Ok(ConditionalFile::Modified {
+        bytes,
+        mime,
+        etag,
+        last_modified,
+    })
+}
+
+/// Build a weak ETag from file size and modification time.
+fn weak_etag(size: u64, modified: Option<std::time::SystemTime>) -> String {
+    let mtime_nanos = modified
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("W/\"{:x}-{:x}\"", size, mtime_nanos)
+}
+
+/// Check an `If-None-Match` header value (a comma-separated list or `*`)
+/// against an ETag, using weak comparison.
+fn etag_matches(header: &str, etag: &str) -> bool {
+    let strip_weak = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = strip_weak(etag);
+    header
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || strip_weak(candidate) == etag)
+}
+
+/// Validate the program name and file path and resolve the file on disk.
+fn resolve_program_file(
+    programs_root: &Path,
+    program_name: &str,
+    file_path: &str,
+) -> Result<PathBuf, String> {
     // Validate program name
     if program_name.contains('/')
         || program_name.contains('\\')
@@ -37,7 +117,13 @@ pub fn load_program_file(
         return Err(format!("File not found: {}", file_path));
     }
 
-    let bytes = std::fs::read(&full_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(full_path)
+}
+
+/// Read a resolved program file and determine its MIME type.
+/// For HTML files, the Bridge API script is injected.
+fn read_program_file(full_path: &Path, file_path: &str) -> Result<(Vec<u8>, String), String> {
+    let bytes = std::fs::read(full_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
     let mime = guess_mime_type(file_path);
 
@@ -53,13 +139,21 @@ pub fn load_program_file(
 
 /// Inject the Bridge API JavaScript into an HTML file.
 /// Inserts the script before `</head>` if present, otherwise before `</body>`,
-/// otherwise prepends it to the document.
+/// otherwise prepends it to the document. HTML that already contains the
+/// bridge script (e.g. added manually by the agent) is returned unchanged.
 fn inject_bridge_script(html_bytes: &[u8]) -> Vec<u8> {
     let html = String::from_utf8_lossy(html_bytes);
     let script = bridge::bridge_script();
 
+    if html.contains(bridge::BRIDGE_SCRIPT_MARKER) {
+        return html_bytes.to_vec();
+    }
+
+    // ASCII lowercasing keeps byte offsets aligned with the original string
+    let lower = html.to_ascii_lowercase();
+
     // Try to insert before </head>
-    if let Some(pos) = html.to_lowercase().find("</head>") {
+    if let Some(pos) = lower.find("</head>") {
         let mut result = String::with_capacity(html.len() + script.len());
         result.push_str(&html[..pos]);
         result.push_str(script);
@@ -69,7 +163,7 @@ fn inject_bridge_script(html_bytes: &[u8]) -> Vec<u8> {
     }
 
     // Try to insert before </body>
-    if let Some(pos) = html.to_lowercase().find("</body>") {
+    if let Some(pos) = lower.find("</body>") {
         let mut result = String::with_capacity(html.len() + script.len());
         result.push_str(&html[..pos]);
         result.push_str(script);
@@ -122,10 +216,15 @@ pub fn parse_protocol_url(url: &str) -> Result<(String, String, String), String>
     Ok((instance_id, program_name, file_path))
 }
 
-/// Guess MIME type from file extension.
+/// Guess MIME type from file extension (case-insensitive).
+/// Unknown extensions fall back to `application/octet-stream`.
 pub fn guess_mime_type(file_path: &str) -> String {
     let path = PathBuf::from(file_path);
-    match path.extension().and_then(|e| e.to_str()) {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
         Some("html") | Some("htm") => "text/html".to_string(),
         Some("css") => "text/css".to_string(),
         Some("js") | Some("mjs") => "text/javascript".to_string(),
@@ -135,6 +234,8 @@ pub fn guess_mime_type(file_path: &str) -> String {
         Some("jpg") | Some("jpeg") => "image/jpeg".to_string(),
         Some("gif") => "image/gif".to_string(),
         Some("webp") => "image/webp".to_string(),
+        Some("avif") => "image/avif".to_string(),
+        Some("bmp") => "image/bmp".to_string(),
         Some("ico") => "image/x-icon".to_string(),
         Some("woff") => "font/woff".to_string(),
         Some("woff2") => "font/woff2".to_string(),
@@ -143,7 +244,16 @@ pub fn guess_mime_type(file_path: &str) -> String {
         Some("xml") => "application/xml".to_string(),
         Some("txt") => "text/plain".to_string(),
         Some("md") => "text/markdown".to_string(),
+        Some("csv") => "text/csv".to_string(),
+        Some("map") => "application/json".to_string(),
+        Some("webmanifest") => "application/manifest+json".to_string(),
         Some("wasm") => "application/wasm".to_string(),
+        Some("pdf") => "application/pdf".to_string(),
+        Some("mp3") => "audio/mpeg".to_string(),
+        Some("wav") => "audio/wav".to_string(),
+        Some("ogg") => "audio/ogg".to_string(),
+        Some("mp4") => "video/mp4".to_string(),
+        Some("webm") => "video/webm".to_string(),
         _ => "application/octet-stream".to_string(),
     }
 }
@@ -171,6 +281,51 @@ mod tests {
         assert_eq!(guess_mime_type("noext"), "application/octet-stream");
     }
 
+    #[test]
+    fn test_guess_mime_type_table() {
+        let cases = [
+            ("index.html", "text/html"),
+            ("page.htm", "text/html"),
+            ("style.css", "text/css"),
+            ("app.js", "text/javascript"),
+            ("module.mjs", "text/javascript"),
+            ("data.json", "application/json"),
+            ("app.js.map", "application/json"),
+            ("site.webmanifest", "application/manifest+json"),
+            ("module.wasm", "application/wasm"),
+            ("icon.svg", "image/svg+xml"),
+            ("photo.png", "image/png"),
+            ("photo.jpg", "image/jpeg"),
+            ("photo.jpeg", "image/jpeg"),
+            ("anim.gif", "image/gif"),
+            ("photo.webp", "image/webp"),
+            ("photo.avif", "image/avif"),
+            ("image.bmp", "image/bmp"),
+            ("favicon.ico", "image/x-icon"),
+            ("font.woff", "font/woff"),
+            ("font.woff2", "font/woff2"),
+            ("font.ttf", "font/ttf"),
+            ("font.otf", "font/otf"),
+            ("feed.xml", "application/xml"),
+            ("notes.txt", "text/plain"),
+            ("README.md", "text/markdown"),
+            ("table.csv", "text/csv"),
+            ("doc.pdf", "application/pdf"),
+            ("sound.mp3", "audio/mpeg"),
+            ("sound.wav", "audio/wav"),
+            ("sound.ogg", "audio/ogg"),
+            ("clip.mp4", "video/mp4"),
+            ("clip.webm", "video/webm"),
+            ("LOGO.PNG", "image/png"),
+            ("unknown.xyz", "application/octet-stream"),
+            ("noext", "application/octet-stream"),
+        ];
+
+        for (file, expected) in cases {
+            assert_eq!(guess_mime_type(file), expected, "MIME type for {}", file);
+        }
+    }
+
     #[test]
     fn test_parse_protocol_url_full() {
         let (inst, prog, file) =
@@ -274,6 +429,81 @@ mod tests {
         assert!(result.starts_with("<script>"));
     }
 
+    #[test]
+    fn test_inject_bridge_script_skips_already_injected() {
+        let html = b"<html><head></head><body></body></html>";
+        let once = inject_bridge_script(html);
+        let twice = inject_bridge_script(&once);
+        assert_eq!(once, twice);
+
+        let text = String::from_utf8(twice).unwrap();
+        assert_eq!(text.matches(bridge::BRIDGE_SCRIPT_MARKER).count(), 1);
+    }
+
+    #[test]
+    fn test_inject_bridge_script_non_ascii_before_head() {
+        // Non-ASCII text before </head> must not shift the insertion point
+        let html = "<html><head><title>İstanbul Straße</title></HEAD><body></body></html>";
+        let result = String::from_utf8(inject_bridge_script(html.as_bytes())).unwrap();
+        assert!(result.contains("<title>İstanbul Straße</title>"));
+        let script_pos = result.find("window.ownai").unwrap();
+        let head_close_pos = result.find("</HEAD>").unwrap();
+        assert!(script_pos < head_close_pos);
+        assert!(result.find("</title>").unwrap() < script_pos);
+    }
+
+    #[test]
+    fn test_load_program_file_conditional_etag() {
+        let temp_dir = TempDir::new().unwrap();
+        let programs_root = temp_dir.path();
+
+        let program_dir = programs_root.join("chess");
+        fs::create_dir_all(&program_dir).unwrap();
+        fs::write(program_dir.join("app.js"), "let x = 1;").unwrap();
+
+        // First request: full response with an ETag
+        let etag =
+            match load_program_file_conditional(programs_root, "chess", "app.js", None).unwrap() {
+                ConditionalFile::Modified {
+                    bytes,
+                    mime,
+                    etag,
+                    last_modified,
+                } => {
+                    assert_eq!(bytes, b"let x = 1;");
+                    assert_eq!(mime, "text/javascript");
+                    assert!(etag.starts_with("W/\""));
+                    assert!(last_modified.unwrap().ends_with("GMT"));
+                    etag
+                }
+                other => panic!("Expected Modified, got {:?}", other),
+            };
+
+        // Matching If-None-Match: 304
+        let result =
+            load_program_file_conditional(programs_root, "chess", "app.js", Some(&etag)).unwrap();
+        assert!(matches!(result, ConditionalFile::NotModified { etag: ref e } if *e == etag));
+
+        // Mismatching If-None-Match: 200 with bytes
+        let result =
+            load_program_file_conditional(programs_root, "chess", "app.js", Some("W/\"deadbeef\""))
+                .unwrap();
+        match result {
+            ConditionalFile::Modified { bytes, .. } => assert_eq!(bytes, b"let x = 1;"),
+            other => panic!("Expected Modified, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "W/\"a-1\"";
+        assert!(etag_matches("W/\"a-1\"", etag));
+        assert!(etag_matches("\"a-1\"", etag));
+        assert!(etag_matches("\"x\", W/\"a-1\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("W/\"a-2\"", etag));
+    }
+
     #[test]
     fn test_load_program_file_not_found() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,119 @@
+//! Rate limiting for the Canvas bridge `chat` method.
+//!
+//! `window.ownai.chat()` runs a full agent turn, so a buggy program calling it
+//! in a loop would burn through the user's API budget. Each program gets a
+//! token bucket: it may burst up to the per-minute limit, after which calls
+//! are rejected until tokens refill.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Default number of bridge chat calls per program and minute.
+pub const DEFAULT_BRIDGE_CHAT_PER_MINUTE: u32 = 10;
+
+/// Upper bound accepted for the configurable per-minute limit.
+pub const MAX_BRIDGE_CHAT_PER_MINUTE: u32 = 600;
+
+/// A token bucket holding up to `capacity` tokens, refilled continuously
+/// at `capacity` tokens per minute.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket allowing `per_minute` calls per minute.
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            tokens: per_minute as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Take one token if available at time `now`.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn per_minute(&self) -> u32 {
+        self.capacity as u32
+    }
+}
+
+/// Token buckets per (instance, program), managed as Tauri state.
+#[derive(Default)]
+pub struct BridgeChatLimiter {
+    buckets: Mutex<HashMap<(String, String), TokenBucket>>,
+}
+
+/// Shared bridge chat limiter.
+pub type SharedBridgeChatLimiter = Arc<BridgeChatLimiter>;
+
+impl BridgeChatLimiter {
+    /// Record a chat call from `program_name`. Returns false if the program
+    /// exceeded `per_minute` calls. A changed limit starts a fresh bucket.
+    pub fn check(&self, instance_id: &str, program_name: &str, per_minute: u32) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets
+            .entry((instance_id.to_string(), program_name.to_string()))
+            .or_insert_with(|| TokenBucket::new(per_minute, now));
+        if bucket.per_minute() != per_minute {
+            *bucket = TokenBucket::new(per_minute, now);
+        }
+        bucket.try_acquire(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bucket_allows_burst_then_rejects() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(3, start);
+
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start));
+
+        // One token refills every 20 seconds at 3 per minute
+        assert!(!bucket.try_acquire(start + Duration::from_secs(10)));
+        assert!(bucket.try_acquire(start + Duration::from_secs(21)));
+        assert!(!bucket.try_acquire(start + Duration::from_secs(21)));
+
+        // Refill never exceeds the capacity
+        let later = start + Duration::from_secs(3600);
+        assert_eq!((0..5).filter(|_| bucket.try_acquire(later)).count(), 3);
+    }
+
+    #[test]
+    fn test_limiter_is_per_program() {
+        let limiter = BridgeChatLimiter::default();
+        assert!(limiter.check("inst", "chess", 1));
+        assert!(!limiter.check("inst", "chess", 1));
+        assert!(limiter.check("inst", "notes", 1));
+        assert!(limiter.check("other", "chess", 1));
+
+        // Raising the limit takes effect immediately
+        assert!(limiter.check("inst", "chess", 5));
+    }
+}
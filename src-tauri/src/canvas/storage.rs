@@ -1,9 +1,20 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
 use sqlx::{Pool, Row, Sqlite};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
 
-use super::ProgramMetadata;
+use super::{
+    is_valid_program_name, resolve_program_path, ProgramFile, ProgramFileVersion, ProgramMetadata,
+    ProgramPruneResult, ProgramUsage,
+};
+use crate::utils::files::walk_files;
+
+/// Entry point of every program; never deleted by `prune_program_files`.
+const PROGRAM_ENTRY_FILE: &str = "index.html";
+
+/// Number of snapshots kept per program file; older ones are dropped.
+pub const MAX_FILE_VERSIONS: i64 = 20;
 
 /// Create a new program entry in the database and its directory on disk.
 pub async fn create_program_in_db(
@@ -138,6 +149,12 @@ pub async fn delete_program_from_db(
         return Err(anyhow::anyhow!("Program '{}' not found", program_name));
     }
 
+    sqlx::query("DELETE FROM program_file_versions WHERE program_name = ?")
+        .bind(program_name)
+        .execute(db)
+        .await
+        .context("Failed to delete program file versions")?;
+
     // Remove program directory from disk
     let program_dir = programs_root.join(program_name);
     if program_dir.exists() {
@@ -149,6 +166,171 @@ pub async fn delete_program_from_db(
     Ok(())
 }
 
+/// Rename a program: updates its DB row (and stored program data) and moves
+/// its directory under `programs_root`. The DB changes are committed only
+/// after the directory was moved, and the move is reverted if the commit fails.
+pub async fn rename_program(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    old_name: &str,
+    new_name: &str,
+    programs_root: &Path,
+) -> Result<ProgramMetadata> {
+    if !is_valid_program_name(new_name) {
+        return Err(anyhow::anyhow!(
+            "Invalid program name '{}'. Use lowercase letters, numbers, and hyphens.",
+            new_name
+        ));
+    }
+    if old_name == new_name {
+        return Err(anyhow::anyhow!("Program is already named '{}'", new_name));
+    }
+
+    if get_program_by_name(db, instance_id, old_name)
+        .await?
+        .is_none()
+    {
+        return Err(anyhow::anyhow!("Program '{}' not found", old_name));
+    }
+
+    let old_dir = programs_root.join(old_name);
+    let new_dir = programs_root.join(new_name);
+    if get_program_by_name(db, instance_id, new_name)
+        .await?
+        .is_some()
+        || new_dir.exists()
+    {
+        return Err(anyhow::anyhow!("Program '{}' already exists", new_name));
+    }
+
+    let now = Utc::now();
+    let mut tx = db.begin().await.context("Failed to begin transaction")?;
+
+    sqlx::query(
+        r#"
+        UPDATE programs
+        SET name = ?, updated_at = ?
+        WHERE instance_id = ? AND name = ?
+        "#,
+    )
+    .bind(new_name)
+    .bind(now)
+    .bind(instance_id)
+    .bind(old_name)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to rename program in database")?;
+
+    sqlx::query("UPDATE program_data SET program_name = ? WHERE program_name = ?")
+        .bind(new_name)
+        .bind(old_name)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move program data")?;
+
+    sqlx::query("UPDATE program_file_versions SET program_name = ? WHERE program_name = ?")
+        .bind(new_name)
+        .bind(old_name)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move program file versions")?;
+
+    // Move the directory; dropping `tx` on error rolls back the DB changes
+    let moved_dir = old_dir.exists();
+    if moved_dir {
+        tokio::fs::rename(&old_dir, &new_dir)
+            .await
+            .context("Failed to rename program directory")?;
+    }
+
+    if let Err(e) = tx.commit().await {
+        if moved_dir {
+            let _ = tokio::fs::rename(&new_dir, &old_dir).await;
+        }
+        return Err(e).context("Failed to commit program rename");
+    }
+
+    get_program_by_name(db, instance_id, new_name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Program '{}' not found after rename", new_name))
+}
+
+/// Duplicate ("fork") a program: copies its directory tree to `new_name` and
+/// registers it as a new program with version 1.0.0. Stored program data
+/// (bridge key-value storage) is not copied.
+pub async fn duplicate_program(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    source_name: &str,
+    new_name: &str,
+    programs_root: &Path,
+) -> Result<ProgramMetadata> {
+    if !is_valid_program_name(new_name) {
+        return Err(anyhow::anyhow!(
+            "Invalid program name '{}'. Use lowercase letters, numbers, and hyphens.",
+            new_name
+        ));
+    }
+
+    let source = get_program_by_name(db, instance_id, source_name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Program '{}' not found", source_name))?;
+
+    let source_dir = programs_root.join(source_name);
+    let target_dir = programs_root.join(new_name);
+    if get_program_by_name(db, instance_id, new_name)
+        .await?
+        .is_some()
+        || target_dir.exists()
+    {
+        return Err(anyhow::anyhow!("Program '{}' already exists", new_name));
+    }
+
+    if source_dir.exists() {
+        copy_dir_recursive(&source_dir, &target_dir)
+            .await
+            .context("Failed to copy program directory")?;
+    }
+
+    match create_program_in_db(
+        db,
+        instance_id,
+        new_name,
+        &source.description,
+        programs_root,
+    )
+    .await
+    {
+        Ok(metadata) => Ok(metadata),
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&target_dir).await;
+            Err(e)
+        }
+    }
+}
+
+/// Recursively copy a directory tree. Symlinks are skipped.
+async fn copy_dir_recursive(source: &Path, target: &Path) -> Result<()> {
+    let mut pending = vec![(source.to_path_buf(), target.to_path_buf())];
+
+    while let Some((from_dir, to_dir)) = pending.pop() {
+        tokio::fs::create_dir_all(&to_dir).await?;
+
+        let mut entries = tokio::fs::read_dir(&from_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let to_path = to_dir.join(entry.file_name());
+            if file_type.is_dir() {
+                pending.push((entry.path(), to_path));
+            } else if file_type.is_file() {
+                tokio::fs::copy(entry.path(), &to_path).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Increment the version of a program and update its timestamp.
 pub async fn update_program_version(
     db: &Pool<Sqlite>,
@@ -193,6 +375,290 @@ pub async fn update_program_version(
     Ok(new_version)
 }
 
+/// Resolve the directory of an existing program, failing if the program is
+/// unknown or its name is not a valid directory name.
+async fn existing_program_dir(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+    programs_root: &Path,
+) -> Result<PathBuf> {
+    if !is_valid_program_name(program_name) {
+        anyhow::bail!("Invalid program name: '{}'", program_name);
+    }
+    if get_program_by_name(db, instance_id, program_name)
+        .await?
+        .is_none()
+    {
+        anyhow::bail!("Program '{}' not found", program_name);
+    }
+    Ok(programs_root.join(program_name))
+}
+
+/// Total size and number of files in a program directory.
+/// Symlinks are not followed.
+pub async fn program_size(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+    programs_root: &Path,
+) -> Result<ProgramUsage> {
+    let program_dir = existing_program_dir(db, instance_id, program_name, programs_root).await?;
+    if !program_dir.exists() {
+        return Ok(ProgramUsage::default());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let mut usage = ProgramUsage::default();
+        walk_files(&program_dir, |_, _, metadata| {
+            usage.total_bytes += metadata.len();
+            usage.file_count += 1;
+            Ok(())
+        })?;
+        Ok::<_, std::io::Error>(usage)
+    })
+    .await
+    .context("Failed to measure program")?
+    .context("Failed to measure program")
+}
+
+/// List all files of a program recursively, sorted by path, with their size,
+/// modification time and number of stored versions. Hidden files (starting
+/// with `.`) and symlinks are skipped.
+pub async fn list_program_files(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+    programs_root: &Path,
+) -> Result<Vec<ProgramFile>> {
+    let program_dir = existing_program_dir(db, instance_id, program_name, programs_root).await?;
+    if !program_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        walk_files(&program_dir, |_, rel, metadata| {
+            if rel.split('/').any(|part| part.starts_with('.')) {
+                return Ok(());
+            }
+            files.push(ProgramFile {
+                path: rel.to_string(),
+                size: metadata.len(),
+                modified: metadata.modified()?.into(),
+                versions: 0,
+            });
+            Ok(())
+        })?;
+        Ok::<_, std::io::Error>(files)
+    })
+    .await
+    .context("Failed to list program files")?
+    .context("Failed to list program files")?;
+
+    let counts: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT path, COUNT(*) FROM program_file_versions WHERE program_name = ? GROUP BY path",
+    )
+    .bind(program_name)
+    .fetch_all(db)
+    .await
+    .context("Failed to count file versions")?;
+    let counts: std::collections::HashMap<String, i64> = counts.into_iter().collect();
+
+    for file in &mut files {
+        file.versions = counts.get(&file.path).copied().unwrap_or(0);
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Delete program files, least recently modified first, until the program
+/// directory is at most `max_bytes`. `index.html` is always kept, so the
+/// result may stay above the cap. Directories and symlinks are left alone.
+pub async fn prune_program_files(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+    programs_root: &Path,
+    max_bytes: u64,
+) -> Result<ProgramPruneResult> {
+    let program_dir = existing_program_dir(db, instance_id, program_name, programs_root).await?;
+    if !program_dir.exists() {
+        return Ok(ProgramPruneResult::default());
+    }
+
+    tokio::task::spawn_blocking(move || prune_dir_to_size(&program_dir, max_bytes))
+        .await
+        .context("Failed to prune program")?
+        .context("Failed to prune program")
+}
+
+fn prune_dir_to_size(dir: &Path, max_bytes: u64) -> std::io::Result<ProgramPruneResult> {
+    let mut files: Vec<(SystemTime, String, PathBuf, u64)> = Vec::new();
+    walk_files(dir, |path, rel, metadata| {
+        files.push((
+            metadata.modified()?,
+            rel.to_string(),
+            path.to_path_buf(),
+            metadata.len(),
+        ));
+        Ok(())
+    })?;
+
+    let mut result = ProgramPruneResult {
+        total_bytes: files.iter().map(|(_, _, _, len)| len).sum(),
+        ..Default::default()
+    };
+    files.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    for (_, rel, path, len) in files {
+        if result.total_bytes <= max_bytes {
+            break;
+        }
+        if rel == PROGRAM_ENTRY_FILE {
+            continue;
+        }
+        std::fs::remove_file(&path)?;
+        result.total_bytes -= len;
+        result.bytes_freed += len;
+        result.deleted.push(rel);
+    }
+
+    Ok(result)
+}
+
+/// Key under which snapshots of `path` are stored (`./a//b.js` -> `a/b.js`).
+fn version_path_key(path: &str) -> String {
+    Path::new(path)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Store `content` as the next version of a program file, keeping only the
+/// newest `MAX_FILE_VERSIONS` snapshots. Returns the new version number.
+pub async fn snapshot_program_file(
+    db: &Pool<Sqlite>,
+    program_name: &str,
+    path: &str,
+    content: &[u8],
+) -> Result<i64> {
+    let key = version_path_key(path);
+    let mut tx = db.begin().await.context("Failed to begin transaction")?;
+
+    let version: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM program_file_versions WHERE program_name = ? AND path = ?",
+    )
+    .bind(program_name)
+    .bind(&key)
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to get next file version")?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO program_file_versions (program_name, path, version, content, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(program_name)
+    .bind(&key)
+    .bind(version)
+    .bind(content)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .context("Failed to store file version")?;
+
+    sqlx::query(
+        "DELETE FROM program_file_versions WHERE program_name = ? AND path = ? AND version <= ?",
+    )
+    .bind(program_name)
+    .bind(&key)
+    .bind(version - MAX_FILE_VERSIONS)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to prune file versions")?;
+
+    tx.commit().await.context("Failed to commit file version")?;
+    Ok(version)
+}
+
+/// List the stored snapshots of a program file, newest first.
+pub async fn list_program_file_versions(
+    db: &Pool<Sqlite>,
+    program_name: &str,
+    path: &str,
+) -> Result<Vec<ProgramFileVersion>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT version, LENGTH(content) AS size, created_at
+        FROM program_file_versions
+        WHERE program_name = ? AND path = ?
+        ORDER BY version DESC
+        "#,
+    )
+    .bind(program_name)
+    .bind(version_path_key(path))
+    .fetch_all(db)
+    .await
+    .context("Failed to list file versions")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ProgramFileVersion {
+            version: row.get("version"),
+            size: row.get::<i64, _>("size").max(0) as u64,
+            created_at: row.get("created_at"),
+        })
+        .collect())
+}
+
+/// Restore a program file to a stored snapshot. The current content is
+/// snapshotted first, so a restore can itself be undone. Bumps the program
+/// version and returns the new version string.
+pub async fn restore_program_file(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    program_name: &str,
+    path: &str,
+    version: i64,
+    programs_root: &Path,
+) -> Result<String> {
+    let file_path =
+        resolve_program_path(programs_root, program_name, path).map_err(anyhow::Error::msg)?;
+    existing_program_dir(db, instance_id, program_name, programs_root).await?;
+
+    let content: Vec<u8> = sqlx::query_scalar(
+        "SELECT content FROM program_file_versions WHERE program_name = ? AND path = ? AND version = ?",
+    )
+    .bind(program_name)
+    .bind(version_path_key(path))
+    .bind(version)
+    .fetch_optional(db)
+    .await
+    .context("Failed to load file version")?
+    .ok_or_else(|| anyhow::anyhow!("Version {} of '{}' not found", version, path))?;
+
+    if let Ok(current) = tokio::fs::read(&file_path).await {
+        snapshot_program_file(db, program_name, path, &current).await?;
+    }
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create directories")?;
+    }
+    tokio::fs::write(&file_path, &content)
+        .await
+        .context("Failed to write restored file")?;
+
+    update_program_version(db, instance_id, program_name).await
+}
+
 /// Increment a semver-style version string (e.g. "1.0.0" -> "1.0.1")
 fn increment_version(version: &str) -> String {
     let parts: Vec<&str> = version.split('.').collect();
@@ -347,6 +813,312 @@ mod tests {
         assert_eq!(v2, "1.0.2");
     }
 
+    #[tokio::test]
+    async fn test_rename_program() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+
+        let original = create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+        std::fs::write(
+            programs_root.join("chess").join("index.html"),
+            "<html></html>",
+        )
+        .unwrap();
+        crate::canvas::bridge::store_program_data(&db, "chess", "score", &serde_json::json!(7))
+            .await
+            .unwrap();
+
+        let renamed = rename_program(&db, "inst-1", "chess", "chess-pro", programs_root)
+            .await
+            .unwrap();
+
+        assert_eq!(renamed.id, original.id);
+        assert_eq!(renamed.name, "chess-pro");
+        assert_eq!(renamed.version, original.version);
+        assert!(programs_root.join("chess-pro").join("index.html").exists());
+        assert!(!programs_root.join("chess").exists());
+        assert!(get_program_by_name(&db, "inst-1", "chess")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Stored program data follows the program
+        let score = crate::canvas::bridge::load_program_data(&db, "chess-pro", "score")
+            .await
+            .unwrap();
+        assert_eq!(score, Some(serde_json::json!(7)));
+    }
+
+    #[tokio::test]
+    async fn test_rename_program_collision_fails() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+
+        create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+        create_program_in_db(&db, "inst-1", "todo", "Todo", programs_root)
+            .await
+            .unwrap();
+
+        let result = rename_program(&db, "inst-1", "chess", "todo", programs_root).await;
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+
+        // Nothing changed
+        assert!(programs_root.join("chess").exists());
+        assert!(get_program_by_name(&db, "inst-1", "chess")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rename_program_invalid_or_missing() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+
+        create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+
+        assert!(
+            rename_program(&db, "inst-1", "chess", "../evil", programs_root)
+                .await
+                .is_err()
+        );
+        let result = rename_program(&db, "inst-1", "nope", "other", programs_root).await;
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_program() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+
+        create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+        update_program_version(&db, "inst-1", "chess")
+            .await
+            .unwrap();
+        let source_dir = programs_root.join("chess");
+        std::fs::write(source_dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::create_dir_all(source_dir.join("js")).unwrap();
+        std::fs::write(source_dir.join("js").join("app.js"), "let x = 1;").unwrap();
+
+        let fork = duplicate_program(&db, "inst-1", "chess", "chess-v2", programs_root)
+            .await
+            .unwrap();
+
+        assert_eq!(fork.name, "chess-v2");
+        assert_eq!(fork.description, "Chess");
+        assert_eq!(fork.version, "1.0.0");
+
+        let fork_dir = programs_root.join("chess-v2");
+        assert_eq!(
+            std::fs::read_to_string(fork_dir.join("index.html")).unwrap(),
+            "<html></html>"
+        );
+        assert_eq!(
+            std::fs::read_to_string(fork_dir.join("js").join("app.js")).unwrap(),
+            "let x = 1;"
+        );
+        // Original is untouched
+        assert!(source_dir.join("index.html").exists());
+
+        let programs = list_programs_from_db(&db, "inst-1").await.unwrap();
+        assert_eq!(programs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_program_errors() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+
+        create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+
+        let result = duplicate_program(&db, "inst-1", "missing", "copy", programs_root).await;
+        assert!(result.unwrap_err().to_string().contains("not found"));
+
+        let result = duplicate_program(&db, "inst-1", "chess", "chess", programs_root).await;
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+
+        assert!(
+            duplicate_program(&db, "inst-1", "chess", "a/b", programs_root)
+                .await
+                .is_err()
+        );
+        assert_eq!(list_programs_from_db(&db, "inst-1").await.unwrap().len(), 1);
+    }
+
+    fn set_age(path: &Path, days: u64) {
+        let mtime = SystemTime::now() - std::time::Duration::from_secs(days * 24 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_program_size_and_prune() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+        create_program_in_db(&db, "inst-1", "gallery", "Images", programs_root)
+            .await
+            .unwrap();
+
+        let dir = programs_root.join("gallery");
+        std::fs::create_dir_all(dir.join("renders")).unwrap();
+        std::fs::write(dir.join("index.html"), vec![b'h'; 100]).unwrap();
+        std::fs::write(dir.join("renders").join("a.png"), vec![0u8; 300]).unwrap();
+        std::fs::write(dir.join("renders").join("b.png"), vec![0u8; 300]).unwrap();
+        std::fs::write(dir.join("renders").join("c.png"), vec![0u8; 300]).unwrap();
+        set_age(&dir.join("index.html"), 30);
+        set_age(&dir.join("renders").join("a.png"), 3);
+        set_age(&dir.join("renders").join("b.png"), 2);
+        set_age(&dir.join("renders").join("c.png"), 1);
+
+        let usage = program_size(&db, "inst-1", "gallery", programs_root)
+            .await
+            .unwrap();
+        assert_eq!(
+            usage,
+            ProgramUsage {
+                total_bytes: 1000,
+                file_count: 4
+            }
+        );
+
+        // Oldest renders go first; index.html is kept although it is the oldest
+        let result = prune_program_files(&db, "inst-1", "gallery", programs_root, 500)
+            .await
+            .unwrap();
+        assert_eq!(result.deleted, vec!["renders/a.png", "renders/b.png"]);
+        assert_eq!(result.bytes_freed, 600);
+        assert_eq!(result.total_bytes, 400);
+        assert!(dir.join("index.html").exists());
+        assert!(dir.join("renders").join("c.png").exists());
+
+        // Already under the cap: nothing to do
+        let result = prune_program_files(&db, "inst-1", "gallery", programs_root, 500)
+            .await
+            .unwrap();
+        assert!(result.deleted.is_empty());
+
+        assert!(program_size(&db, "inst-1", "missing", programs_root)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_program_file_version() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+        create_program_in_db(&db, "inst-1", "notes", "Notes", programs_root)
+            .await
+            .unwrap();
+        let file = programs_root.join("notes").join("app.js");
+
+        // Two edits: each snapshots the content it replaces
+        std::fs::write(&file, "v1").unwrap();
+        snapshot_program_file(&db, "notes", "app.js", b"v1")
+            .await
+            .unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        snapshot_program_file(&db, "notes", "./app.js", b"v2")
+            .await
+            .unwrap();
+        std::fs::write(&file, "v3 broken").unwrap();
+
+        let versions = list_program_file_versions(&db, "notes", "app.js")
+            .await
+            .unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+
+        let new_version = restore_program_file(&db, "inst-1", "notes", "app.js", 1, programs_root)
+            .await
+            .unwrap();
+        assert_eq!(new_version, "1.0.1");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+
+        // The replaced content became version 3, so the restore can be undone
+        restore_program_file(&db, "inst-1", "notes", "app.js", 3, programs_root)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v3 broken");
+
+        assert!(
+            restore_program_file(&db, "inst-1", "notes", "app.js", 99, programs_root)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_program_files_nested() {
+        let (db, temp_dir) = setup_test_db().await;
+        let programs_root = temp_dir.path();
+        create_program_in_db(&db, "inst-1", "site", "Site", programs_root)
+            .await
+            .unwrap();
+
+        let dir = programs_root.join("site");
+        std::fs::create_dir_all(dir.join("js").join("lib")).unwrap();
+        std::fs::write(dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(dir.join("js").join("app.js"), "run();").unwrap();
+        std::fs::write(dir.join("js").join("lib").join("util.js"), "x").unwrap();
+        std::fs::write(dir.join(".DS_Store"), "junk").unwrap();
+        snapshot_program_file(&db, "site", "js/app.js", b"old")
+            .await
+            .unwrap();
+
+        let files = list_program_files(&db, "inst-1", "site", programs_root)
+            .await
+            .unwrap();
+        let listed: Vec<(&str, u64, i64)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.size, f.versions))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("index.html", 13, 0),
+                ("js/app.js", 6, 1),
+                ("js/lib/util.js", 1, 0),
+            ]
+        );
+
+        assert!(list_program_files(&db, "inst-1", "missing", programs_root)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_keeps_newest_versions() {
+        let (db, _temp_dir) = setup_test_db().await;
+        for i in 0..MAX_FILE_VERSIONS + 3 {
+            snapshot_program_file(&db, "p", "index.html", i.to_string().as_bytes())
+                .await
+                .unwrap();
+        }
+
+        let versions = list_program_file_versions(&db, "p", "index.html")
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), MAX_FILE_VERSIONS as usize);
+        assert_eq!(versions[0].version, MAX_FILE_VERSIONS + 3);
+        assert_eq!(versions.last().unwrap().version, 4);
+    }
+
     #[test]
     fn test_increment_version() {
         assert_eq!(increment_version("1.0.0"), "1.0.1");
@@ -1,13 +1,16 @@
 //! Canvas program tools for the agent.
 //!
-//! Provides seven rig Tools that allow the agent to create and manage
+//! Provides ten rig Tools that allow the agent to create and manage
 //! HTML/CSS/JS programs (Canvas apps):
 //! - `CreateProgramTool`: Create a new program with initial HTML
 //! - `ListProgramsTool`: List all programs for the current instance
+//! - `RenameProgramTool`: Rename an existing program
+//! - `CloneProgramTool`: Fork a program into a new one (version reset to 1.0.0)
 //! - `OpenProgramTool`: Open an existing program in the frontend
 //! - `ProgramLsTool`: List files within a program directory
 //! - `ProgramReadFileTool`: Read a file from a program
 //! - `ProgramWriteFileTool`: Write/create a file in a program (emits update event)
+//! - `ProgramWriteFileBase64Tool`: Write a binary file from base64 (emits update event)
 //! - `ProgramEditFileTool`: Edit a file with search/replace (emits update event)
 
 use rig::completion::ToolDefinition;
@@ -16,11 +19,13 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Pool, Sqlite};
 use std::path::PathBuf;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tokio::fs;
 
-use super::resolve_program_path;
+use super::bridge::decode_base64_content;
 use super::storage;
+use super::{is_valid_program_name, resolve_program_path};
+use crate::database::activity;
 
 // ---------------------------------------------------------------------------
 // Error type
@@ -122,11 +127,7 @@ impl Tool for CreateProgramTool {
             .ok_or_else(|| CanvasToolError("Programs root not set".to_string()))?;
 
         // Validate program name
-        if args.name.is_empty()
-            || args.name.contains('/')
-            || args.name.contains('\\')
-            || args.name.contains("..")
-        {
+        if !is_valid_program_name(&args.name) {
             return Err(CanvasToolError(
                 "Invalid program name. Use lowercase letters, numbers, and hyphens.".to_string(),
             ));
@@ -243,6 +244,221 @@ impl Tool for ListProgramsTool {
     }
 }
 
+// ---------------------------------------------------------------------------
+// RenameProgramTool
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct RenameProgramArgs {
+    program_name: String,
+    new_name: String,
+}
+
+/// Agent tool to rename an existing Canvas program (directory and DB entry).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RenameProgramTool {
+    #[serde(skip)]
+    db: Option<Pool<Sqlite>>,
+    #[serde(skip)]
+    instance_id: Option<String>,
+    #[serde(skip)]
+    programs_root: Option<PathBuf>,
+    #[serde(skip)]
+    app_handle: Option<AppHandle>,
+}
+
+impl RenameProgramTool {
+    pub fn new(
+        db: Pool<Sqlite>,
+        instance_id: String,
+        programs_root: PathBuf,
+        app_handle: Option<AppHandle>,
+    ) -> Self {
+        Self {
+            db: Some(db),
+            instance_id: Some(instance_id),
+            programs_root: Some(programs_root),
+            app_handle,
+        }
+    }
+}
+
+impl Tool for RenameProgramTool {
+    const NAME: &'static str = "rename_program";
+    type Error = CanvasToolError;
+    type Args = RenameProgramArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "rename_program".to_string(),
+            description: "Rename an existing Canvas program. Files, stored program data \
+                and version are kept; only the name changes. Fails if a program \
+                with the new name already exists."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "program_name": {
+                        "type": "string",
+                        "description": "Current name of the program"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "New unique program name (lowercase, hyphens allowed)"
+                    }
+                },
+                "required": ["program_name", "new_name"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Database not initialized".to_string()))?;
+        let instance_id = self
+            .instance_id
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Instance ID not set".to_string()))?;
+        let programs_root = self
+            .programs_root
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Programs root not set".to_string()))?;
+
+        let metadata = storage::rename_program(
+            db,
+            instance_id,
+            &args.program_name,
+            &args.new_name,
+            programs_root,
+        )
+        .await
+        .map_err(|e| CanvasToolError(format!("Failed to rename program: {}", e)))?;
+
+        // Notify frontend so the program list is refreshed
+        activity::emit_and_record(
+            self.app_handle.as_ref(),
+            db,
+            "canvas:program_updated",
+            json!({ "program_name": metadata.name, "version": metadata.version }),
+        )
+        .await;
+
+        tracing::info!(
+            "Agent renamed program '{}' to '{}'",
+            args.program_name,
+            metadata.name
+        );
+
+        Ok(format!(
+            "Program '{}' renamed to '{}' (v{}).",
+            args.program_name, metadata.name, metadata.version
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CloneProgramTool
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct CloneProgramArgs {
+    source_name: String,
+    new_name: String,
+}
+
+/// Agent tool to fork an existing Canvas program under a new name.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CloneProgramTool {
+    #[serde(skip)]
+    db: Option<Pool<Sqlite>>,
+    #[serde(skip)]
+    instance_id: Option<String>,
+    #[serde(skip)]
+    programs_root: Option<PathBuf>,
+}
+
+impl CloneProgramTool {
+    pub fn new(db: Pool<Sqlite>, instance_id: String, programs_root: PathBuf) -> Self {
+        Self {
+            db: Some(db),
+            instance_id: Some(instance_id),
+            programs_root: Some(programs_root),
+        }
+    }
+}
+
+impl Tool for CloneProgramTool {
+    const NAME: &'static str = "clone_program";
+    type Error = CanvasToolError;
+    type Args = CloneProgramArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "clone_program".to_string(),
+            description: "Fork an existing Canvas program into a new program with all of \
+                its files. The copy starts at version 1.0.0 and the original is left \
+                untouched. Use this before risky changes to a working program."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "source_name": {
+                        "type": "string",
+                        "description": "Name of the program to copy"
+                    },
+                    "new_name": {
+                        "type": "string",
+                        "description": "Unique name for the copy (lowercase, hyphens allowed)"
+                    }
+                },
+                "required": ["source_name", "new_name"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let db = self
+            .db
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Database not initialized".to_string()))?;
+        let instance_id = self
+            .instance_id
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Instance ID not set".to_string()))?;
+        let programs_root = self
+            .programs_root
+            .as_ref()
+            .ok_or_else(|| CanvasToolError("Programs root not set".to_string()))?;
+
+        let metadata = storage::duplicate_program(
+            db,
+            instance_id,
+            &args.source_name,
+            &args.new_name,
+            programs_root,
+        )
+        .await
+        .map_err(|e| CanvasToolError(format!("Failed to clone program: {}", e)))?;
+
+        tracing::info!(
+            "Agent cloned program '{}' to '{}' ({})",
+            args.source_name,
+            metadata.name,
+            metadata.id
+        );
+
+        Ok(format!(
+            "Program '{}' cloned to '{}' (version {}). \
+             Edit the copy with program_write_file or program_edit_file.",
+            args.source_name, metadata.name, metadata.version
+        ))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ProgramLsTool
 // ---------------------------------------------------------------------------
@@ -524,6 +740,20 @@ impl Tool for ProgramWriteFileTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.write_bytes(&args.program_name, &args.path, args.content.as_bytes())
+            .await
+    }
+}
+
+impl ProgramWriteFileTool {
+    /// Write `bytes` to `path` in an existing program, bump its version and
+    /// notify the frontend. Shared by the text and base64 write tools.
+    async fn write_bytes(
+        &self,
+        program_name: &str,
+        file_path: &str,
+        bytes: &[u8],
+    ) -> Result<String, CanvasToolError> {
         let db = self
             .db
             .as_ref()
@@ -537,20 +767,27 @@ impl Tool for ProgramWriteFileTool {
             .as_ref()
             .ok_or_else(|| CanvasToolError("Programs root not set".to_string()))?;
 
-        let path = resolve_program_path(programs_root, &args.program_name, &args.path)
+        let path = resolve_program_path(programs_root, program_name, file_path)
             .map_err(CanvasToolError)?;
 
         // Verify program exists in DB
-        storage::get_program_by_name(db, instance_id, &args.program_name)
+        storage::get_program_by_name(db, instance_id, program_name)
             .await
             .map_err(|e| CanvasToolError(format!("Database error: {}", e)))?
             .ok_or_else(|| {
                 CanvasToolError(format!(
                     "Program '{}' not found. Create it first with create_program.",
-                    args.program_name
+                    program_name
                 ))
             })?;
 
+        // Keep the previous content so the write can be undone
+        if let Ok(previous) = fs::read(&path).await {
+            storage::snapshot_program_file(db, program_name, file_path, &previous)
+                .await
+                .map_err(|e| CanvasToolError(format!("Failed to snapshot file: {}", e)))?;
+        }
+
         // Create parent directories
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
@@ -558,33 +795,108 @@ impl Tool for ProgramWriteFileTool {
                 .map_err(|e| CanvasToolError(format!("Failed to create directories: {}", e)))?;
         }
 
-        fs::write(&path, &args.content)
+        fs::write(&path, bytes)
             .await
             .map_err(|e| CanvasToolError(format!("Failed to write file: {}", e)))?;
 
         // Increment program version
-        let new_version = storage::update_program_version(db, instance_id, &args.program_name)
+        let new_version = storage::update_program_version(db, instance_id, program_name)
             .await
             .map_err(|e| CanvasToolError(format!("Failed to update version: {}", e)))?;
 
         // Notify frontend that the program was updated
-        if let Some(handle) = &self.app_handle {
-            let _ = handle.emit(
-                "canvas:program_updated",
-                json!({ "program_name": args.program_name, "version": new_version }),
-            );
-        }
+        activity::emit_and_record(
+            self.app_handle.as_ref(),
+            db,
+            "canvas:program_updated",
+            json!({ "program_name": program_name, "version": new_version }),
+        )
+        .await;
 
         Ok(format!(
             "File written: {} ({} bytes) in program '{}' (now v{})",
-            args.path,
-            args.content.len(),
-            args.program_name,
+            file_path,
+            bytes.len(),
+            program_name,
             new_version
         ))
     }
 }
 
+// ---------------------------------------------------------------------------
+// ProgramWriteFileBase64Tool
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct ProgramWriteFileBase64Args {
+    program_name: String,
+    path: String,
+    base64: String,
+}
+
+/// Agent tool to write a binary file (e.g. a PNG) into a Canvas program
+/// directory from base64-encoded content.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProgramWriteFileBase64Tool {
+    inner: ProgramWriteFileTool,
+}
+
+impl ProgramWriteFileBase64Tool {
+    pub fn new(
+        db: Pool<Sqlite>,
+        instance_id: String,
+        programs_root: PathBuf,
+        app_handle: Option<AppHandle>,
+    ) -> Self {
+        Self {
+            inner: ProgramWriteFileTool::new(db, instance_id, programs_root, app_handle),
+        }
+    }
+}
+
+impl Tool for ProgramWriteFileBase64Tool {
+    const NAME: &'static str = "program_write_file_base64";
+    type Error = CanvasToolError;
+    type Args = ProgramWriteFileBase64Args;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "program_write_file_base64".to_string(),
+            description: "Write a binary file (image, audio, font, ...) to a Canvas program \
+                from base64-encoded content. A data URL prefix (data:image/png;base64,...) \
+                is accepted. Creates parent directories, overwrites existing files and \
+                increments the program version."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "program_name": {
+                        "type": "string",
+                        "description": "Name of the program"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Relative file path (e.g. 'assets/logo.png')"
+                    },
+                    "base64": {
+                        "type": "string",
+                        "description": "Base64-encoded file content"
+                    }
+                },
+                "required": ["program_name", "path", "base64"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let bytes = decode_base64_content(&args.base64).map_err(CanvasToolError)?;
+        self.inner
+            .write_bytes(&args.program_name, &args.path, &bytes)
+            .await
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ProgramEditFileTool
 // ---------------------------------------------------------------------------
@@ -699,6 +1011,11 @@ impl Tool for ProgramEditFileTool {
             )));
         }
 
+        // Keep the previous content so the edit can be undone
+        storage::snapshot_program_file(db, &args.program_name, &args.path, content.as_bytes())
+            .await
+            .map_err(|e| CanvasToolError(format!("Failed to snapshot file: {}", e)))?;
+
         let new_content = content.replace(&args.old_text, &args.new_text);
         fs::write(&path, &new_content)
             .await
@@ -710,12 +1027,13 @@ impl Tool for ProgramEditFileTool {
             .map_err(|e| CanvasToolError(format!("Failed to update version: {}", e)))?;
 
         // Notify frontend that the program was updated
-        if let Some(handle) = &self.app_handle {
-            let _ = handle.emit(
-                "canvas:program_updated",
-                json!({ "program_name": args.program_name, "version": new_version }),
-            );
-        }
+        activity::emit_and_record(
+            self.app_handle.as_ref(),
+            db,
+            "canvas:program_updated",
+            json!({ "program_name": args.program_name, "version": new_version }),
+        )
+        .await;
 
         Ok(format!(
             "File edited: {} in program '{}' (now v{})",
@@ -805,12 +1123,13 @@ impl Tool for OpenProgramTool {
             })?;
 
         // Emit event to open the program in the frontend
-        if let Some(handle) = &self.app_handle {
-            let _ = handle.emit(
-                "canvas:open_program",
-                json!({ "program_name": args.program_name }),
-            );
-        }
+        activity::emit_and_record(
+            self.app_handle.as_ref(),
+            db,
+            "canvas:open_program",
+            json!({ "program_name": args.program_name }),
+        )
+        .await;
 
         Ok(format!(
             "Program '{}' (v{}) is now displayed in the Canvas panel.",
@@ -886,6 +1205,55 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_rename_program_tool() {
+        let (db, temp_dir) = setup().await;
+        let programs_root = temp_dir.path().to_path_buf();
+
+        storage::create_program_in_db(&db, "inst-1", "chess", "Chess", &programs_root)
+            .await
+            .unwrap();
+
+        let tool = RenameProgramTool::new(db, "inst-1".to_string(), programs_root.clone(), None);
+        let result = tool
+            .call(RenameProgramArgs {
+                program_name: "chess".to_string(),
+                new_name: "chess-pro".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("renamed to 'chess-pro'"));
+        assert!(programs_root.join("chess-pro").exists());
+        assert!(!programs_root.join("chess").exists());
+    }
+
+    #[tokio::test]
+    async fn test_clone_program_tool() {
+        let (db, temp_dir) = setup().await;
+        let programs_root = temp_dir.path().to_path_buf();
+
+        storage::create_program_in_db(&db, "inst-1", "chess", "Chess", &programs_root)
+            .await
+            .unwrap();
+        std::fs::write(programs_root.join("chess").join("index.html"), "<p>hi</p>").unwrap();
+
+        let tool = CloneProgramTool::new(db, "inst-1".to_string(), programs_root.clone());
+        let result = tool
+            .call(CloneProgramArgs {
+                source_name: "chess".to_string(),
+                new_name: "chess-experiment".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("cloned to 'chess-experiment'"));
+        assert!(programs_root
+            .join("chess-experiment")
+            .join("index.html")
+            .exists());
+    }
+
     #[tokio::test]
     async fn test_list_programs_tool_empty() {
         let (db, _temp_dir) = setup().await;
@@ -1010,6 +1378,80 @@ mod tests {
         assert_eq!(content, "body { margin: 0; }");
     }
 
+    #[tokio::test]
+    async fn test_program_write_file_base64_tool() {
+        let (db, temp_dir) = setup().await;
+        let programs_root = temp_dir.path();
+
+        storage::create_program_in_db(&db, "inst-1", "paint", "Paint", programs_root)
+            .await
+            .unwrap();
+
+        let tool = ProgramWriteFileBase64Tool::new(
+            db.clone(),
+            "inst-1".to_string(),
+            programs_root.to_path_buf(),
+            None,
+        );
+
+        // "\x89PNG\r\n\x1a\n\0\xff"
+        let result = tool
+            .call(ProgramWriteFileBase64Args {
+                program_name: "paint".to_string(),
+                path: "assets/frame.png".to_string(),
+                base64: "iVBORw0KGgoA/w==".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(result.contains("File written: assets/frame.png (10 bytes)"));
+        assert!(result.contains("v1.0.1"));
+        assert_eq!(
+            std::fs::read(programs_root.join("paint").join("assets").join("frame.png")).unwrap(),
+            [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff]
+        );
+
+        let err = tool
+            .call(ProgramWriteFileBase64Args {
+                program_name: "paint".to_string(),
+                path: "bad.png".to_string(),
+                base64: "%%%".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid base64"));
+        assert!(!programs_root.join("paint").join("bad.png").exists());
+    }
+
+    #[tokio::test]
+    async fn test_program_update_recorded_in_activity_log() {
+        let (db, temp_dir) = setup().await;
+        let programs_root = temp_dir.path();
+
+        storage::create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+
+        let tool = ProgramWriteFileTool::new(
+            db.clone(),
+            "inst-1".to_string(),
+            programs_root.to_path_buf(),
+            None,
+        );
+        tool.call(ProgramWriteFileArgs {
+            program_name: "chess".to_string(),
+            path: "app.js".to_string(),
+            content: "console.log('hi');".to_string(),
+        })
+        .await
+        .unwrap();
+
+        let entries = activity::get_activity_log(&db, 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_type, "canvas:program_updated");
+        assert_eq!(entries[0].payload["program_name"], "chess");
+        assert_eq!(entries[0].payload["version"], "1.0.1");
+    }
+
     #[tokio::test]
     async fn test_program_write_file_nonexistent_program() {
         let (db, temp_dir) = setup().await;
@@ -1071,6 +1513,43 @@ mod tests {
         assert_eq!(content, "<html><body>Chess Board</body></html>");
     }
 
+    #[tokio::test]
+    async fn test_program_edits_can_be_restored() {
+        let (db, temp_dir) = setup().await;
+        let programs_root = temp_dir.path();
+
+        storage::create_program_in_db(&db, "inst-1", "chess", "Chess", programs_root)
+            .await
+            .unwrap();
+        let index = programs_root.join("chess").join("index.html");
+        std::fs::write(&index, "<p>one</p>").unwrap();
+
+        let tool = ProgramEditFileTool::new(
+            db.clone(),
+            "inst-1".to_string(),
+            programs_root.to_path_buf(),
+            None,
+        );
+        for (old_text, new_text) in [("one", "two"), ("two", "three")] {
+            tool.call(ProgramEditFileArgs {
+                program_name: "chess".to_string(),
+                path: "index.html".to_string(),
+                old_text: old_text.to_string(),
+                new_text: new_text.to_string(),
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&index).unwrap(), "<p>three</p>");
+
+        let new_version =
+            storage::restore_program_file(&db, "inst-1", "chess", "index.html", 1, programs_root)
+                .await
+                .unwrap();
+        assert_eq!(new_version, "1.0.3");
+        assert_eq!(std::fs::read_to_string(&index).unwrap(), "<p>one</p>");
+    }
+
     #[tokio::test]
     async fn test_program_edit_file_old_text_not_found() {
         let (db, temp_dir) = setup().await;
@@ -0,0 +1,29 @@
+use tauri::State;
+
+use crate::database::activity::{self, ActivityEntry};
+use crate::database::{get_or_init_db, DbCache};
+
+/// Default number of entries returned by `get_activity_log`
+const DEFAULT_ACTIVITY_LIMIT: u32 = 100;
+
+/// Upper bound for a caller-provided limit
+const MAX_ACTIVITY_LIMIT: u32 = 1_000;
+
+/// Get the instance's most recent activity (emitted events), newest first.
+#[tauri::command]
+pub async fn get_activity_log(
+    instance_id: String,
+    limit: Option<u32>,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<ActivityEntry>, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_ACTIVITY_LIMIT)
+        .clamp(1, MAX_ACTIVITY_LIMIT);
+    activity::get_activity_log(&db, limit)
+        .await
+        .map_err(|e| format!("Failed to load activity log: {}", e))
+}
@@ -4,10 +4,14 @@ use tokio::sync::Mutex;
 
 use crate::ai_instances::AIInstanceManager;
 use crate::canvas::bridge::{self, BridgeResponse};
+use crate::canvas::rate_limit::{SharedBridgeChatLimiter, DEFAULT_BRIDGE_CHAT_PER_MINUTE};
 use crate::canvas::storage;
-use crate::canvas::ProgramMetadata;
+use crate::canvas::{
+    ProgramFile, ProgramFileVersion, ProgramMetadata, ProgramPruneResult, ProgramUsage,
+};
 use crate::commands::chat::{get_or_create_agent, AgentCache};
-use crate::database::{get_or_init_db, DbCache};
+use crate::database::{activity, get_or_init_db, DbCache};
+use crate::tools::read_only;
 use crate::utils::paths;
 
 /// List all Canvas programs for an instance.
@@ -44,6 +48,164 @@ pub async fn delete_program(
         .map_err(|e| format!("Failed to delete program: {}", e))
 }
 
+/// Rename a Canvas program. Returns the updated metadata.
+#[tauri::command]
+pub async fn rename_program(
+    instance_id: String,
+    program_name: String,
+    new_name: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<ProgramMetadata, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let programs_root = paths::get_instance_programs_path(&instance_id)
+        .map_err(|e| format!("Failed to get programs path: {}", e))?;
+
+    storage::rename_program(
+        &pool,
+        &instance_id,
+        &program_name,
+        &new_name,
+        &programs_root,
+    )
+    .await
+    .map_err(|e| format!("Failed to rename program: {}", e))
+}
+
+/// Get the total size and file count of a Canvas program's directory.
+#[tauri::command]
+pub async fn get_program_size(
+    instance_id: String,
+    program_name: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<ProgramUsage, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let programs_root = paths::get_instance_programs_path(&instance_id)
+        .map_err(|e| format!("Failed to get programs path: {}", e))?;
+
+    storage::program_size(&pool, &instance_id, &program_name, &programs_root)
+        .await
+        .map_err(|e| format!("Failed to measure program: {}", e))
+}
+
+/// Delete a Canvas program's least recently modified files until its
+/// directory is at most `max_bytes` (`index.html` is always kept).
+#[tauri::command]
+pub async fn prune_program_files(
+    instance_id: String,
+    program_name: String,
+    max_bytes: u64,
+    db_cache: State<'_, DbCache>,
+) -> Result<ProgramPruneResult, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let programs_root = paths::get_instance_programs_path(&instance_id)
+        .map_err(|e| format!("Failed to get programs path: {}", e))?;
+
+    let result = storage::prune_program_files(
+        &pool,
+        &instance_id,
+        &program_name,
+        &programs_root,
+        max_bytes,
+    )
+    .await
+    .map_err(|e| format!("Failed to prune program: {}", e))?;
+
+    tracing::info!(
+        "Pruned program '{}' of instance {}: deleted {} files ({} bytes)",
+        program_name,
+        instance_id,
+        result.deleted.len(),
+        result.bytes_freed
+    );
+    Ok(result)
+}
+
+/// List all files of a Canvas program (recursively) with size, modification
+/// time and number of stored versions, for the in-app file view.
+#[tauri::command]
+pub async fn list_program_files(
+    instance_id: String,
+    program_name: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<ProgramFile>, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let programs_root = paths::get_instance_programs_path(&instance_id)
+        .map_err(|e| format!("Failed to get programs path: {}", e))?;
+
+    storage::list_program_files(&pool, &instance_id, &program_name, &programs_root)
+        .await
+        .map_err(|e| format!("Failed to list program files: {}", e))
+}
+
+/// List the stored earlier versions of a Canvas program file, newest first.
+#[tauri::command]
+pub async fn list_program_file_versions(
+    instance_id: String,
+    program_name: String,
+    path: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<ProgramFileVersion>, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    storage::list_program_file_versions(&pool, &program_name, &path)
+        .await
+        .map_err(|e| format!("Failed to list file versions: {}", e))
+}
+
+/// Restore a Canvas program file to an earlier version (undo). Returns the
+/// new program version and notifies the frontend to reload the program.
+#[tauri::command]
+pub async fn restore_program_file(
+    instance_id: String,
+    program_name: String,
+    path: String,
+    version: i64,
+    app_handle: tauri::AppHandle,
+    db_cache: State<'_, DbCache>,
+) -> Result<String, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let programs_root = paths::get_instance_programs_path(&instance_id)
+        .map_err(|e| format!("Failed to get programs path: {}", e))?;
+
+    let new_version = storage::restore_program_file(
+        &pool,
+        &instance_id,
+        &program_name,
+        &path,
+        version,
+        &programs_root,
+    )
+    .await
+    .map_err(|e| format!("Failed to restore file: {}", e))?;
+
+    activity::emit_and_record(
+        Some(&app_handle),
+        &pool,
+        "canvas:program_updated",
+        serde_json::json!({ "program_name": program_name, "version": new_version }),
+    )
+    .await;
+
+    Ok(new_version)
+}
+
 /// Get the custom protocol URL for a program (used by frontend to load in iframe).
 #[tauri::command]
 pub async fn get_program_url(
@@ -90,13 +252,28 @@ pub async fn bridge_request(
     instance_manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
     agent_cache: State<'_, AgentCache>,
     db_cache: State<'_, DbCache>,
+    chat_limiter: State<'_, SharedBridgeChatLimiter>,
 ) -> Result<BridgeResponse, String> {
     let pool = get_or_init_db(&db_cache, &instance_id)
         .await
         .map_err(|e| e.to_string())?;
 
-    let workspace = paths::get_instance_workspace_path(&instance_id)
-        .map_err(|e| format!("Failed to get workspace path: {}", e))?;
+    let (workspace, read_only) = {
+        let manager = instance_manager.lock().await;
+        let workspace = manager
+            .workspace_path(&instance_id)
+            .map_err(|e| format!("Failed to get workspace path: {}", e))?;
+        let read_only = manager
+            .get_instance(&instance_id)
+            .is_some_and(|i| i.read_only);
+        (workspace, read_only)
+    };
+
+    // Read-only instances refuse every method that writes files, program
+    // data, or runs dynamic tools
+    if let Some(error) = read_only::refuse_bridge_method(&method, read_only) {
+        return Ok(BridgeResponse::err(error));
+    }
 
     match method.as_str() {
         "chat" => {
@@ -106,6 +283,17 @@ pub async fn bridge_request(
                 .ok_or("Missing 'prompt' parameter")?
                 .to_string();
 
+            if let Some(rejected) = check_chat_rate_limit(
+                &instance_manager,
+                &chat_limiter,
+                &instance_id,
+                &program_name,
+            )
+            .await
+            {
+                return Ok(rejected);
+            }
+
             // Get or create agent (cache lock released immediately)
             let agent_arc = get_or_create_agent(
                 &instance_id,
@@ -120,11 +308,44 @@ pub async fn bridge_request(
             let mut agent = agent_arc.lock().await;
 
             match agent.chat(&prompt).await {
-                Ok(response) => Ok(BridgeResponse::ok(serde_json::Value::String(response))),
+                Ok(response) => Ok(BridgeResponse::ok(serde_json::Value::String(response.text))),
                 Err(e) => Ok(BridgeResponse::err(format!("Chat error: {}", e))),
             }
         }
 
+        "chatJson" => {
+            let prompt = params
+                .get("prompt")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing 'prompt' parameter")?;
+            let schema = params.get("schema").ok_or("Missing 'schema' parameter")?;
+
+            if let Some(rejected) = check_chat_rate_limit(
+                &instance_manager,
+                &chat_limiter,
+                &instance_id,
+                &program_name,
+            )
+            .await
+            {
+                return Ok(rejected);
+            }
+
+            let agent_arc = get_or_create_agent(
+                &instance_id,
+                instance_manager.inner(),
+                agent_cache.inner(),
+                db_cache.inner(),
+                &app_handle,
+            )
+            .await?;
+            // Build the extractor under the agent lock, then run it without
+            // blocking the agent
+            let extractor = agent_arc.lock().await.json_extractor(schema);
+
+            Ok(bridge::handle_chat_json(&extractor, prompt, schema).await)
+        }
+
         "storeData" => {
             let key = params
                 .get("key")
@@ -132,7 +353,14 @@ pub async fn bridge_request(
                 .ok_or("Missing 'key' parameter")?;
             let value = params.get("value").unwrap_or(&serde_json::Value::Null);
 
-            Ok(bridge::handle_store_data(&pool, &program_name, key, value).await)
+            let manager = instance_manager.lock().await;
+            let quota_bytes = manager
+                .get_instance(&instance_id)
+                .and_then(|i| i.program_data_quota_bytes)
+                .unwrap_or(bridge::DEFAULT_PROGRAM_DATA_QUOTA_BYTES);
+            drop(manager);
+
+            Ok(bridge::handle_store_data(&pool, &program_name, key, value, quota_bytes).await)
         }
 
         "loadData" => {
@@ -144,6 +372,42 @@ pub async fn bridge_request(
             Ok(bridge::handle_load_data(&pool, &program_name, key).await)
         }
 
+        "listKeys" => Ok(bridge::handle_list_keys(&pool, &program_name).await),
+
+        "deleteData" => {
+            let key = params
+                .get("key")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing 'key' parameter")?;
+
+            Ok(bridge::handle_delete_data(&pool, &program_name, key).await)
+        }
+
+        "getMetadata" => Ok(bridge::handle_get_metadata(&pool, &instance_id, &program_name).await),
+
+        "executeTool" => {
+            let name = params
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing 'name' parameter")?;
+            let tool_params = params
+                .get("params")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({}));
+
+            let agent_arc = get_or_create_agent(
+                &instance_id,
+                instance_manager.inner(),
+                agent_cache.inner(),
+                db_cache.inner(),
+                &app_handle,
+            )
+            .await?;
+            let registry = agent_arc.lock().await.tool_registry().clone();
+
+            Ok(bridge::handle_execute_tool(&registry, name, tool_params).await)
+        }
+
         "notify" => {
             let message = params
                 .get("message")
@@ -184,6 +448,48 @@ pub async fn bridge_request(
             Ok(bridge::handle_write_file(&workspace, path, content).await)
         }
 
+        "writeFileBase64" => {
+            let path = params
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing 'path' parameter")?;
+            let base64 = params
+                .get("base64")
+                .and_then(|v| v.as_str())
+                .ok_or("Missing 'base64' parameter")?;
+
+            Ok(bridge::handle_write_file_base64(&workspace, path, base64).await)
+        }
+
         _ => Ok(BridgeResponse::err(format!("Unknown method: {}", method))),
     }
 }
+
+/// Count a bridge chat call against the program's rate limit. Returns the
+/// error response to send if the limit is exceeded.
+async fn check_chat_rate_limit(
+    instance_manager: &Mutex<AIInstanceManager>,
+    chat_limiter: &SharedBridgeChatLimiter,
+    instance_id: &str,
+    program_name: &str,
+) -> Option<BridgeResponse> {
+    let per_minute = instance_manager
+        .lock()
+        .await
+        .get_instance(instance_id)
+        .and_then(|i| i.bridge_chat_per_minute)
+        .unwrap_or(DEFAULT_BRIDGE_CHAT_PER_MINUTE);
+    if chat_limiter.check(instance_id, program_name, per_minute) {
+        return None;
+    }
+
+    tracing::warn!(
+        "Bridge chat from program '{}' rate limited ({}/min)",
+        program_name,
+        per_minute
+    );
+    Some(BridgeResponse::err(format!(
+        "rate limited: at most {} chat calls per minute",
+        per_minute
+    )))
+}
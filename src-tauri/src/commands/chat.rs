@@ -4,10 +4,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{Emitter, Manager, State};
 use tokio::sync::{Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 
-use crate::agent::OwnAIAgent;
+use crate::agent::{OwnAIAgent, PlanResult, StreamEvent, TokenUsage};
 use crate::ai_instances::AIInstanceManager;
-use crate::database::{get_or_init_db, DbCache};
+use crate::database::{get_or_init_db, prompt_templates, DbCache};
+use crate::tools::confirmation::SharedConfirmations;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -21,9 +23,30 @@ pub struct Message {
 #[derive(Debug, Deserialize)]
 pub struct SendMessageRequest {
     pub instance_id: String,
+    /// Message text. Ignored (and may be omitted) when `template_id` is set.
+    #[serde(default)]
     pub content: String,
+    /// Send a saved prompt template instead of `content`, with its
+    /// `{{name}}` placeholders filled from `params`.
+    #[serde(default)]
+    pub template_id: Option<String>,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    /// Identifier used to cancel a streaming response via `cancel_stream`.
+    /// Defaults to the instance ID when omitted.
+    #[serde(default)]
+    pub stream_id: Option<String>,
+    /// Dry run: the agent plans its tool calls without executing them.
+    /// Nothing is saved; the intended calls are returned instead.
+    #[serde(default)]
+    pub plan_only: bool,
 }
 
+/// Cancellation tokens of in-flight streams, keyed by stream ID. Each stream
+/// registers its own `Arc`, so it can tell its entry apart from that of a
+/// later stream with the same ID.
+pub type StreamRegistry = Arc<Mutex<HashMap<String, Arc<CancellationToken>>>>;
+
 /// Agent cache to avoid recreating agents for each message.
 ///
 /// Uses an outer `RwLock` on the HashMap (locked briefly to look up / insert
@@ -79,6 +102,26 @@ pub async fn get_or_create_agent(
     Ok(entry.clone())
 }
 
+/// The text to send for a request: `content`, or the rendered prompt
+/// template if `template_id` is set.
+async fn resolve_content(
+    request: &SendMessageRequest,
+    db_cache: &DbCache,
+) -> Result<String, String> {
+    let Some(template_id) = &request.template_id else {
+        return Ok(request.content.clone());
+    };
+
+    let db = get_or_init_db(db_cache, &request.instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+    let template = prompt_templates::get_template(&db, template_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Prompt template not found: {}", template_id))?;
+    prompt_templates::render_template(&template.body, &request.params).map_err(|e| e.to_string())
+}
+
 /// Send a message and get AI response (non-streaming)
 #[tauri::command]
 pub async fn send_message(
@@ -89,6 +132,7 @@ pub async fn send_message(
     db_cache: State<'_, DbCache>,
 ) -> Result<Message, String> {
     // 1. Get or create agent (cache lock released immediately)
+    let content = resolve_content(&request, db_cache.inner()).await?;
     let agent_arc = get_or_create_agent(
         &request.instance_id,
         instance_manager.inner(),
@@ -101,16 +145,27 @@ pub async fn send_message(
     // 2. Lock only this instance's agent for the chat call
     let mut agent = agent_arc.lock().await;
 
-    let response_content = agent
-        .chat(&request.content)
+    if request.plan_only {
+        let plan = agent
+            .plan(&content)
+            .await
+            .map_err(|e| format!("Agent error: {}", e))?;
+        emit_usage(&app_handle, &request.instance_id, &plan.usage);
+        return Ok(plan_message(plan));
+    }
+
+    let result = agent
+        .chat(&content)
         .await
         .map_err(|e| format!("Agent error: {}", e))?;
 
+    emit_usage(&app_handle, &request.instance_id, &result.usage);
+
     // 3. Return response message
     let response = Message {
         id: uuid::Uuid::new_v4().to_string(),
         role: "agent".to_string(),
-        content: response_content,
+        content: result.text,
         timestamp: Utc::now().to_rfc3339(),
         metadata: None,
     };
@@ -132,8 +187,10 @@ pub async fn stream_message(
     instance_manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
     agent_cache: State<'_, AgentCache>,
     db_cache: State<'_, DbCache>,
+    stream_registry: State<'_, StreamRegistry>,
 ) -> Result<(), String> {
     // 1. Get or create agent (cache lock released immediately)
+    let content = resolve_content(&request, db_cache.inner()).await?;
     let agent_arc = get_or_create_agent(
         &request.instance_id,
         instance_manager.inner(),
@@ -143,55 +200,223 @@ pub async fn stream_message(
     )
     .await?;
 
+    // Plan-only turns are not streamed: the finished plan is emitted at once
+    if request.plan_only {
+        let plan = agent_arc
+            .lock()
+            .await
+            .plan(&content)
+            .await
+            .map_err(|e| format!("Streaming error: {}", e))?;
+        emit_usage(window.app_handle(), &request.instance_id, &plan.usage);
+        if let Err(e) = window.emit("agent:token", &plan.text) {
+            tracing::error!("Failed to emit stream event: {}", e);
+        }
+        if let Err(e) = window.emit("agent:plan", &plan.tool_calls) {
+            tracing::error!("Failed to emit plan event: {}", e);
+        }
+        return Ok(());
+    }
+
     // 2. Lock only this instance's agent for streaming
     let mut agent = agent_arc.lock().await;
 
+    // 3. Register a cancellation token so the stream can be stopped via
+    // cancel_stream. Only now, so a stream queued behind the agent lock
+    // doesn't replace the token of the one that is running.
+    let stream_id = request
+        .stream_id
+        .clone()
+        .unwrap_or_else(|| request.instance_id.clone());
+    let cancel = register_stream(stream_registry.inner(), &stream_id).await;
+
     let window_clone = window.clone();
     let instance_id = request.instance_id.clone();
 
-    agent
-        .stream_chat(&request.content, move |chunk| {
-            // Emit each chunk to the frontend
-            if let Err(e) = window_clone.emit("agent:token", chunk) {
-                tracing::error!("Failed to emit token: {}", e);
+    let result = agent
+        .stream_chat(&content, (*cancel).clone(), move |event| {
+            // Emit text chunks as tokens, reasoning chunks separately for the
+            // collapsible thinking section, tool activity as structured events
+            let result = match event {
+                StreamEvent::Text { text } => window_clone.emit("agent:token", text),
+                StreamEvent::Reasoning { text } => window_clone.emit("agent:reasoning", text),
+                other => window_clone.emit("agent:tool_event", other),
+            };
+            if let Err(e) = result {
+                tracing::error!("Failed to emit stream event: {}", e);
             }
         })
-        .await
-        .map_err(|e| format!("Streaming error: {}", e))?;
+        .await;
+
+    unregister_stream(stream_registry.inner(), &stream_id, &cancel).await;
+    let result = result.map_err(|e| format!("Streaming error: {}", e))?;
+
+    emit_usage(window.app_handle(), &instance_id, &result.usage);
 
     tracing::info!("Streaming completed for instance: {}", instance_id);
 
     Ok(())
 }
 
-/// Load messages from the database
+/// Register a new cancellation token for the stream `stream_id`.
+async fn register_stream(registry: &StreamRegistry, stream_id: &str) -> Arc<CancellationToken> {
+    let token = Arc::new(CancellationToken::new());
+    registry
+        .lock()
+        .await
+        .insert(stream_id.to_string(), token.clone());
+    token
+}
+
+/// Remove the entry of `stream_id` if it still holds `token`, leaving the
+/// entry of a later stream with the same ID in place.
+async fn unregister_stream(
+    registry: &StreamRegistry,
+    stream_id: &str,
+    token: &Arc<CancellationToken>,
+) {
+    let mut registry = registry.lock().await;
+    if registry
+        .get(stream_id)
+        .is_some_and(|current| Arc::ptr_eq(current, token))
+    {
+        registry.remove(stream_id);
+    }
+}
+
+/// Response message of a plan-only turn. The planned tool calls are returned
+/// in `metadata.planned_tool_calls`.
+fn plan_message(plan: PlanResult) -> Message {
+    Message {
+        id: uuid::Uuid::new_v4().to_string(),
+        role: "agent".to_string(),
+        content: plan.text,
+        timestamp: Utc::now().to_rfc3339(),
+        metadata: Some(serde_json::json!({
+            "plan_only": true,
+            "planned_tool_calls": plan.tool_calls,
+        })),
+    }
+}
+
+/// Emit the token usage of a completed chat turn as a `chat:usage` event.
+fn emit_usage(app_handle: &tauri::AppHandle, instance_id: &str, usage: &TokenUsage) {
+    let payload = serde_json::json!({ "instance_id": instance_id, "usage": usage });
+    if let Err(e) = app_handle.emit("chat:usage", payload) {
+        tracing::error!("Failed to emit usage event: {}", e);
+    }
+}
+
+/// Cancel an in-flight streaming response.
+/// The partial response produced so far is still saved.
+/// Returns `false` if no stream with this ID is running.
+#[tauri::command]
+pub async fn cancel_stream(
+    stream_id: String,
+    stream_registry: State<'_, StreamRegistry>,
+) -> Result<bool, String> {
+    let registry = stream_registry.lock().await;
+    match registry.get(&stream_id) {
+        Some(token) => {
+            token.cancel();
+            tracing::info!("Cancellation requested for stream: {}", stream_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Answer a `tool:confirm_request` event: approve or deny the pending
+/// destructive tool action with this ID.
+#[tauri::command]
+pub async fn confirm_tool_action(
+    id: String,
+    approved: bool,
+    confirmations: State<'_, SharedConfirmations>,
+) -> Result<(), String> {
+    if confirmations.resolve(&id, approved) {
+        tracing::info!(
+            "Tool action {} {}",
+            id,
+            if approved { "approved" } else { "denied" }
+        );
+        Ok(())
+    } else {
+        Err(format!("No pending confirmation (or it timed out): {}", id))
+    }
+}
+
+/// Default page size for `load_messages` when the frontend does not pass a limit.
+const DEFAULT_MESSAGE_PAGE_SIZE: i32 = 1000;
+
+/// A page of chat history plus the total number of stored messages,
+/// so the UI knows when it has scrolled back to the beginning.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePage {
+    /// Messages in chronological order (oldest first).
+    pub messages: Vec<Message>,
+    pub total_count: i64,
+}
+
+/// Load a page of messages from the database.
+/// `offset` counts back from the most recent message, so `offset = 0`
+/// returns the newest `limit` messages.
 #[tauri::command]
 pub async fn load_messages(
     instance_id: String,
-    limit: i32,
-    offset: i32,
+    limit: Option<i32>,
+    offset: Option<i32>,
     db_cache: State<'_, DbCache>,
-) -> Result<Vec<Message>, String> {
+) -> Result<MessagePage, String> {
     let pool = get_or_init_db(&db_cache, &instance_id)
         .await
         .map_err(|e| e.to_string())?;
 
-    let messages = sqlx::query_as::<_, (String, String, String, String, Option<String>)>(
+    let page = fetch_message_page(
+        &pool,
+        limit.unwrap_or(DEFAULT_MESSAGE_PAGE_SIZE),
+        offset.unwrap_or(0),
+    )
+    .await
+    .map_err(|e| format!("Failed to load messages: {}", e))?;
+
+    tracing::debug!(
+        "Loaded {} of {} messages for instance: {}",
+        page.messages.len(),
+        page.total_count,
+        instance_id
+    );
+
+    Ok(page)
+}
+
+/// Helper: query one page of messages (newest first, then reversed to
+/// chronological order) together with the total message count.
+async fn fetch_message_page(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    limit: i32,
+    offset: i32,
+) -> Result<MessagePage, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String, String, String, Option<String>)>(
         r#"
         SELECT id, role, content, timestamp, metadata
         FROM messages
-        ORDER BY timestamp ASC
+        ORDER BY timestamp DESC
         LIMIT ? OFFSET ?
         "#,
     )
     .bind(limit)
     .bind(offset)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| format!("Failed to load messages: {}", e))?;
+    .fetch_all(pool)
+    .await?;
+
+    let total_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(pool)
+        .await?;
 
-    let messages: Vec<Message> = messages
+    let messages: Vec<Message> = rows
         .into_iter()
+        .rev()
         .map(|(id, role, content, timestamp, metadata)| Message {
             id,
             role,
@@ -201,13 +426,424 @@ pub async fn load_messages(
         })
         .collect();
 
-    tracing::debug!(
-        "Loaded {} messages for instance: {}",
-        messages.len(),
+    Ok(MessagePage {
+        messages,
+        total_count,
+    })
+}
+
+/// Default and maximum number of results for `search_messages`.
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+const MAX_SEARCH_LIMIT: u32 = 500;
+
+/// Characters of context kept on each side of the match in a search snippet.
+const SNIPPET_RADIUS: usize = 60;
+
+/// A message matching a `search_messages` query.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageSearchResult {
+    pub id: String,
+    pub role: String,
+    pub timestamp: String,
+    /// Excerpt around the first match; `...` marks trimmed text.
+    pub snippet: String,
+}
+
+/// Search the conversation history for messages containing `query`
+/// (case-insensitive), newest first.
+#[tauri::command]
+pub async fn search_messages(
+    instance_id: String,
+    query: String,
+    limit: Option<u32>,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<MessageSearchResult>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query must not be empty".to_string());
+    }
+
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    search_messages_in_db(&pool, query, limit)
+        .await
+        .map_err(|e| format!("Failed to search messages: {}", e))
+}
+
+/// Helper: LIKE search over message content with `%`/`_` in the query
+/// matched literally.
+async fn search_messages_in_db(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<MessageSearchResult>, sqlx::Error> {
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let rows = sqlx::query_as::<_, (String, String, String, String)>(
+        r#"
+        SELECT id, role, content, timestamp
+        FROM messages
+        WHERE content LIKE ? ESCAPE '\'
+        ORDER BY timestamp DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(format!("%{}%", escaped))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, role, content, timestamp)| MessageSearchResult {
+            snippet: match_snippet(&content, query, SNIPPET_RADIUS),
+            id,
+            role,
+            timestamp,
+        })
+        .collect())
+}
+
+/// Cut `radius` characters of context around the first case-insensitive
+/// occurrence of `query` in `content`.
+fn match_snippet(content: &str, query: &str, radius: usize) -> String {
+    let lower = |s: &str| -> Vec<char> {
+        s.chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect()
+    };
+    let chars: Vec<char> = content.chars().collect();
+    let haystack = lower(content);
+    let needle = lower(query);
+
+    let start = if needle.is_empty() {
+        0
+    } else {
+        haystack
+            .windows(needle.len())
+            .position(|w| w == needle.as_slice())
+            .unwrap_or(0)
+    };
+    let from = start.saturating_sub(radius);
+    let to = (start + needle.len() + radius).min(chars.len());
+
+    let mut snippet: String = chars[from..to].iter().collect();
+    if from > 0 {
+        snippet = format!("...{}", snippet);
+    }
+    if to < chars.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+/// Delete a single chat message.
+///
+/// Summaries that start or end at this message are removed as well (their
+/// boundaries would otherwise dangle), and messages linked to those summaries
+/// are unlinked. The agent cache is invalidated so working memory is reloaded.
+#[tauri::command]
+pub async fn delete_message(
+    instance_id: String,
+    message_id: String,
+    agent_cache: State<'_, AgentCache>,
+    db_cache: State<'_, DbCache>,
+) -> Result<(), String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let deleted = delete_message_from_db(&pool, &message_id)
+        .await
+        .map_err(|e| format!("Failed to delete message: {}", e))?;
+
+    if !deleted {
+        return Err(format!("Message not found: {}", message_id));
+    }
+
+    agent_cache.write().await.remove(&instance_id);
+
+    tracing::info!(
+        "Deleted message {} for instance: {}",
+        message_id,
         instance_id
     );
 
-    Ok(messages)
+    Ok(())
+}
+
+/// Reset the conversation: remove all messages and summaries for an instance.
+/// Long-term memories are kept. The agent cache is invalidated afterwards.
+/// Returns the number of deleted messages.
+#[tauri::command]
+pub async fn clear_conversation(
+    instance_id: String,
+    agent_cache: State<'_, AgentCache>,
+    db_cache: State<'_, DbCache>,
+) -> Result<u64, String> {
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let deleted = clear_conversation_in_db(&pool)
+        .await
+        .map_err(|e| format!("Failed to clear conversation: {}", e))?;
+
+    agent_cache.write().await.remove(&instance_id);
+
+    tracing::info!(
+        "Cleared conversation ({} messages) for instance: {}",
+        deleted,
+        instance_id
+    );
+
+    Ok(deleted)
+}
+
+/// Helper: delete a message and any summaries bounded by it, in one transaction.
+/// Returns `false` if the message does not exist.
+async fn delete_message_from_db(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+    message_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Unlink messages from summaries that are anchored on this message
+    sqlx::query(
+        r#"
+        UPDATE messages SET summary_id = NULL
+        WHERE summary_id IN (
+            SELECT id FROM summaries
+            WHERE start_message_id = ? OR end_message_id = ?
+        )
+        "#,
+    )
+    .bind(message_id)
+    .bind(message_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM summaries WHERE start_message_id = ? OR end_message_id = ?")
+        .bind(message_id)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query("DELETE FROM messages WHERE id = ?")
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Helper: remove all messages and summaries in one transaction.
+/// Returns the number of deleted messages.
+async fn clear_conversation_in_db(pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // Break the messages -> summaries link first so both tables can be emptied
+    sqlx::query("UPDATE messages SET summary_id = NULL")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM summaries")
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM messages")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Output format of `export_conversation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "Unsupported export format '{}' (expected 'markdown' or 'json')",
+                other
+            )),
+        }
+    }
+}
+
+/// A session summary as included in a conversation export.
+#[derive(Debug, Clone, Serialize)]
+struct ExportedSummary {
+    timestamp: String,
+    summary: String,
+}
+
+/// Export the full conversation of an instance as a `markdown` transcript or
+/// as `json`. With `include_summaries`, session summaries are appended.
+/// If `path` is given the export is also written to that file.
+/// Returns the exported content.
+#[tauri::command]
+pub async fn export_conversation(
+    instance_id: String,
+    format: String,
+    include_summaries: Option<bool>,
+    path: Option<String>,
+    instance_manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+    db_cache: State<'_, DbCache>,
+) -> Result<String, String> {
+    let format = ExportFormat::parse(&format)?;
+    let instance_name = instance_manager
+        .lock()
+        .await
+        .get_instance(&instance_id)
+        .map(|i| i.name.clone())
+        .ok_or_else(|| format!("Instance not found: {}", instance_id))?;
+
+    let pool = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let messages = fetch_all_messages(&pool)
+        .await
+        .map_err(|e| format!("Failed to load messages: {}", e))?;
+    let summaries = if include_summaries.unwrap_or(false) {
+        fetch_summaries(&pool)
+            .await
+            .map_err(|e| format!("Failed to load summaries: {}", e))?
+    } else {
+        Vec::new()
+    };
+
+    let content = match format {
+        ExportFormat::Markdown => render_markdown_transcript(&instance_name, &messages, &summaries),
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "instance_id": instance_id,
+            "instance_name": instance_name,
+            "exported_at": Utc::now().to_rfc3339(),
+            "messages": messages,
+            "summaries": summaries,
+        }))
+        .map_err(|e| format!("Failed to serialize conversation: {}", e))?,
+    };
+
+    if let Some(path) = path {
+        std::fs::write(&path, &content)
+            .map_err(|e| format!("Failed to write export to {}: {}", path, e))?;
+        tracing::info!(
+            "Exported conversation of instance {} to {}",
+            instance_id,
+            path
+        );
+    }
+
+    Ok(content)
+}
+
+/// Helper: all messages in chronological order.
+async fn fetch_all_messages(pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<Vec<Message>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String, String, String, Option<String>)>(
+        "SELECT id, role, content, timestamp, metadata FROM messages ORDER BY timestamp",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, role, content, timestamp, metadata)| Message {
+            id,
+            role,
+            content,
+            timestamp,
+            metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        })
+        .collect())
+}
+
+/// Helper: all session summaries in chronological order.
+async fn fetch_summaries(
+    pool: &sqlx::Pool<sqlx::Sqlite>,
+) -> Result<Vec<ExportedSummary>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT timestamp, summary_text FROM summaries ORDER BY timestamp",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(timestamp, summary)| ExportedSummary { timestamp, summary })
+        .collect())
+}
+
+/// Render a readable Markdown transcript: one section per message with the
+/// speaker and time, tool results in code blocks, summaries at the end.
+fn render_markdown_transcript(
+    instance_name: &str,
+    messages: &[Message],
+    summaries: &[ExportedSummary],
+) -> String {
+    let mut out = format!("# Conversation with {}\n", instance_name);
+
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" => "User",
+            "agent" => instance_name,
+            "tool_result" => "Tool result",
+            other => other,
+        };
+        out.push_str(&format!(
+            "\n## {} ({})\n\n",
+            speaker,
+            format_export_timestamp(&message.timestamp)
+        ));
+        if message.role == "tool_result" {
+            out.push_str(&format!("```\n{}\n```\n", message.content.trim_end()));
+        } else {
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+        }
+    }
+
+    if !summaries.is_empty() {
+        out.push_str("\n---\n\n# Summaries\n");
+        for summary in summaries {
+            out.push_str(&format!(
+                "\n## {}\n\n{}\n",
+                format_export_timestamp(&summary.timestamp),
+                summary.summary.trim_end()
+            ));
+        }
+    }
+    out
+}
+
+/// Format a stored timestamp as `YYYY-MM-DD HH:MM UTC`, or return it
+/// unchanged if it cannot be parsed.
+fn format_export_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .or_else(|_| chrono::DateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .map(|t| {
+            t.with_timezone(&Utc)
+                .format("%Y-%m-%d %H:%M UTC")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
 }
 
 /// Clear agent cache for an instance (useful when switching models/settings)
@@ -223,3 +859,304 @@ pub async fn clear_agent_cache(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_streams_of_one_instance_keep_their_own_token() {
+        let registry: StreamRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let agent = Arc::new(Mutex::new(()));
+
+        // The first stream holds the agent and is registered
+        let first_agent = agent.lock().await;
+        let first = register_stream(&registry, "instance-1").await;
+
+        // A second stream of the same instance waits for the agent before
+        // it registers, so cancel_stream still reaches the first one
+        let second_task = tokio::spawn({
+            let registry = registry.clone();
+            let agent = agent.clone();
+            async move {
+                let _agent = agent.lock().await;
+                register_stream(&registry, "instance-1").await
+            }
+        });
+        tokio::task::yield_now().await;
+        registry.lock().await["instance-1"].cancel();
+        assert!(first.is_cancelled());
+
+        unregister_stream(&registry, "instance-1", &first).await;
+        drop(first_agent);
+        let second = second_task.await.unwrap();
+        assert!(!second.is_cancelled());
+
+        // A late removal by the first stream keeps the second one's entry
+        unregister_stream(&registry, "instance-1", &first).await;
+        let current = registry.lock().await["instance-1"].clone();
+        assert!(Arc::ptr_eq(&current, &second));
+
+        unregister_stream(&registry, "instance-1", &second).await;
+        assert!(registry.lock().await.is_empty());
+    }
+
+    async fn setup_test_db() -> sqlx::Pool<sqlx::Sqlite> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        crate::database::schema::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    async fn insert_messages(pool: &sqlx::Pool<sqlx::Sqlite>, count: i64) {
+        let base = Utc::now() - chrono::Duration::hours(1);
+        for i in 0..count {
+            sqlx::query("INSERT INTO messages (id, role, content, timestamp) VALUES (?, ?, ?, ?)")
+                .bind(format!("msg-{:02}", i))
+                .bind("user")
+                .bind(format!("Message {}", i))
+                .bind(base + chrono::Duration::seconds(i))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_message_page_paging() {
+        let pool = setup_test_db().await;
+        insert_messages(&pool, 30).await;
+
+        // Newest page comes first, in chronological order
+        let page = fetch_message_page(&pool, 10, 0).await.unwrap();
+        assert_eq!(page.total_count, 30);
+        let ids: Vec<&str> = page.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids.first(), Some(&"msg-20"));
+        assert_eq!(ids.last(), Some(&"msg-29"));
+
+        let page = fetch_message_page(&pool, 10, 10).await.unwrap();
+        assert_eq!(page.messages[0].id, "msg-10");
+        assert_eq!(page.messages[9].id, "msg-19");
+
+        // Last (partial) page reaches the beginning
+        let page = fetch_message_page(&pool, 25, 25).await.unwrap();
+        assert_eq!(page.messages.len(), 5);
+        assert_eq!(page.messages[0].id, "msg-00");
+
+        let page = fetch_message_page(&pool, 10, 30).await.unwrap();
+        assert!(page.messages.is_empty());
+        assert_eq!(page.total_count, 30);
+    }
+
+    async fn insert_summary(pool: &sqlx::Pool<sqlx::Sqlite>, id: &str, start: &str, end: &str) {
+        sqlx::query(
+            "INSERT INTO summaries (id, start_message_id, end_message_id, summary_text, key_facts, timestamp) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(start)
+        .bind(end)
+        .bind("Summary")
+        .bind("[]")
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .unwrap();
+
+        sqlx::query("UPDATE messages SET summary_id = ? WHERE id BETWEEN ? AND ?")
+            .bind(id)
+            .bind(start)
+            .bind(end)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn count(pool: &sqlx::Pool<sqlx::Sqlite>, sql: &str) -> i64 {
+        sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_message_removes_dependent_summary() {
+        let pool = setup_test_db().await;
+        insert_messages(&pool, 6).await;
+        insert_summary(&pool, "sum-a", "msg-00", "msg-02").await;
+        insert_summary(&pool, "sum-b", "msg-03", "msg-05").await;
+
+        // msg-02 is the end boundary of sum-a
+        assert!(delete_message_from_db(&pool, "msg-02").await.unwrap());
+
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM messages").await, 5);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM summaries").await, 1);
+        // Messages previously linked to sum-a are unlinked, sum-b is untouched
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM messages WHERE summary_id = 'sum-a'"
+            )
+            .await,
+            0
+        );
+        assert_eq!(
+            count(
+                &pool,
+                "SELECT COUNT(*) FROM messages WHERE summary_id = 'sum-b'"
+            )
+            .await,
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_message_inside_summary_keeps_summary() {
+        let pool = setup_test_db().await;
+        insert_messages(&pool, 3).await;
+        insert_summary(&pool, "sum-a", "msg-00", "msg-02").await;
+
+        assert!(delete_message_from_db(&pool, "msg-01").await.unwrap());
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM messages").await, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM summaries").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_nonexistent_message() {
+        let pool = setup_test_db().await;
+        assert!(!delete_message_from_db(&pool, "missing").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_clear_conversation_removes_messages_and_summaries() {
+        let pool = setup_test_db().await;
+        insert_messages(&pool, 6).await;
+        insert_summary(&pool, "sum-a", "msg-00", "msg-02").await;
+        insert_summary(&pool, "sum-b", "msg-03", "msg-05").await;
+
+        let deleted = clear_conversation_in_db(&pool).await.unwrap();
+        assert_eq!(deleted, 6);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM messages").await, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM summaries").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_messages_by_keyword() {
+        let pool = setup_test_db().await;
+        insert_messages(&pool, 3).await;
+        let now = Utc::now();
+        for (id, content) in [
+            ("budget-1", "Our Budget for Q3 is 10k"),
+            ("budget-2", "Remind me about the budget review"),
+            ("percent", "Growth was 100% this year"),
+        ] {
+            sqlx::query(
+                "INSERT INTO messages (id, role, content, timestamp) VALUES (?, 'user', ?, ?)",
+            )
+            .bind(id)
+            .bind(content)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let results = search_messages_in_db(&pool, "budget", 10).await.unwrap();
+        let mut ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["budget-1", "budget-2"]);
+        assert!(results.iter().all(|r| r.role == "user"));
+
+        // LIKE wildcards in the query match literally
+        let results = search_messages_in_db(&pool, "0%", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "percent");
+
+        assert!(search_messages_in_db(&pool, "nothing here", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_match_snippet_trims_context() {
+        let content = format!("{}Budget meeting{}", "a".repeat(100), "b".repeat(100));
+        let snippet = match_snippet(&content, "budget", 5);
+        assert_eq!(snippet, "...aaaaaBudget meet...");
+        assert_eq!(match_snippet("short budget", "budget", 60), "short budget");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_message_page_empty() {
+        let pool = setup_test_db().await;
+        let page = fetch_message_page(&pool, DEFAULT_MESSAGE_PAGE_SIZE, 0)
+            .await
+            .unwrap();
+        assert!(page.messages.is_empty());
+        assert_eq!(page.total_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_markdown_export_lists_messages_in_order() {
+        let pool = setup_test_db().await;
+        let base = Utc::now() - chrono::Duration::hours(1);
+        for (i, (role, content)) in [
+            ("user", "What's in notes.txt?"),
+            ("tool_result", "buy milk"),
+            ("agent", "It says: buy milk."),
+        ]
+        .iter()
+        .enumerate()
+        {
+            sqlx::query("INSERT INTO messages (id, role, content, timestamp) VALUES (?, ?, ?, ?)")
+                .bind(format!("msg-{}", i))
+                .bind(role)
+                .bind(content)
+                .bind(base + chrono::Duration::seconds(i as i64))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let messages = fetch_all_messages(&pool).await.unwrap();
+        let summaries = vec![ExportedSummary {
+            timestamp: Utc::now().to_rfc3339(),
+            summary: "User asked about their notes.".to_string(),
+        }];
+        let markdown = render_markdown_transcript("Ava", &messages, &summaries);
+
+        assert!(markdown.starts_with("# Conversation with Ava\n"));
+        let positions: Vec<usize> = [
+            "## User (",
+            "What's in notes.txt?",
+            "## Tool result (",
+            "```\nbuy milk\n```",
+            "## Ava (",
+            "It says: buy milk.",
+            "# Summaries",
+            "User asked about their notes.",
+        ]
+        .iter()
+        .map(|needle| {
+            markdown
+                .find(needle)
+                .unwrap_or_else(|| panic!("missing {}", needle))
+        })
+        .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", markdown);
+        // Stored timestamps are rendered in a readable form
+        assert!(markdown.contains(" UTC)\n"), "{}", markdown);
+    }
+
+    #[test]
+    fn test_export_format_parse() {
+        assert_eq!(
+            ExportFormat::parse("Markdown").unwrap(),
+            ExportFormat::Markdown
+        );
+        assert_eq!(ExportFormat::parse("json").unwrap(), ExportFormat::Json);
+        assert!(ExportFormat::parse("pdf").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::database::{self, get_or_init_db, DbCache};
+
+/// Database size before and after a vacuum
+#[derive(Debug, Serialize)]
+pub struct VacuumResult {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Compact an instance's database (SQLite `VACUUM`) to reclaim space left by
+/// deleted messages, tool executions and summaries.
+#[tauri::command]
+pub async fn vacuum_instance(
+    instance_id: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<VacuumResult, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let bytes_before = database::database_size(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+    database::vacuum(&db).await.map_err(|e| e.to_string())?;
+    let bytes_after = database::database_size(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Vacuumed database for instance {}: {} -> {} bytes",
+        instance_id,
+        bytes_before,
+        bytes_after
+    );
+
+    Ok(VacuumResult {
+        bytes_before,
+        bytes_after,
+    })
+}
+
+/// Write a consistent copy of an instance's database to `dest_path`.
+/// The destination must not exist yet. Returns the backup size in bytes.
+#[tauri::command]
+pub async fn backup_database(
+    instance_id: String,
+    dest_path: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<u64, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let dest = PathBuf::from(dest_path);
+    database::backup_to(&db, &dest)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let size = std::fs::metadata(&dest).map(|m| m.len()).unwrap_or(0);
+    tracing::info!(
+        "Backed up database for instance {} to {} ({} bytes)",
+        instance_id,
+        dest.display(),
+        size
+    );
+
+    Ok(size)
+}
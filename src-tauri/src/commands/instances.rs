@@ -1,10 +1,13 @@
 use crate::ai_instances::{
-    AIInstance, AIInstanceManager, APIKeyStorage, CreateInstanceRequest, LLMProvider, ProviderInfo,
+    provider_api, AIInstance, AIInstanceManager, APIKeyStorage, ApiKeyStatus,
+    CreateInstanceRequest, InstanceSettingsPatch, LLMProvider, ProviderInfo,
 };
+use crate::commands::chat::AgentCache;
 use crate::database::{remove_cached_db, DbCache};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
 // ============================================================================
 // Provider & API Key Commands
@@ -13,17 +16,11 @@ use tokio::sync::Mutex;
 /// Get information about all available providers
 #[tauri::command]
 pub fn get_providers() -> Result<Vec<ProviderInfo>, String> {
-    let providers = vec![
-        LLMProvider::Anthropic,
-        LLMProvider::OpenAI,
-        LLMProvider::Ollama,
-    ];
-
-    let provider_infos: Vec<ProviderInfo> = providers
+    let provider_infos: Vec<ProviderInfo> = LLMProvider::all()
         .into_iter()
         .map(|p| {
             let has_key = if p.needs_api_key() {
-                APIKeyStorage::exists(&p).unwrap_or(false)
+                APIKeyStorage::exists(&p, None).unwrap_or(false)
             } else {
                 true // Ollama doesn't need a key
             };
@@ -34,6 +31,7 @@ pub fn get_providers() -> Result<Vec<ProviderInfo>, String> {
                     LLMProvider::Anthropic => "Anthropic".to_string(),
                     LLMProvider::OpenAI => "OpenAI".to_string(),
                     LLMProvider::Ollama => "Ollama".to_string(),
+                    LLMProvider::OpenAICompatible => "OpenAI-compatible".to_string(),
                 },
                 needs_api_key: p.needs_api_key(),
                 has_api_key: has_key,
@@ -46,40 +44,151 @@ pub fn get_providers() -> Result<Vec<ProviderInfo>, String> {
     Ok(provider_infos)
 }
 
-/// Save an API key for a provider
+/// Save an API key for a provider.
+/// With `validate` set, the key is first checked against the provider's API
+/// (by listing models) and rejected keys are not saved. Defaults to false so
+/// keys can be saved offline.
+/// `api_base_url` is required for `openai_compatible`, whose keys are stored
+/// per service URL; other providers use it only for validation.
 #[tauri::command]
-pub fn save_api_key(provider: String, api_key: String) -> Result<(), String> {
+pub async fn save_api_key(
+    provider: String,
+    api_key: String,
+    validate: Option<bool>,
+    api_base_url: Option<String>,
+) -> Result<(), String> {
     let provider = parse_provider(&provider)?;
 
     if !provider.needs_api_key() {
         return Err(format!("Provider {} does not require an API key", provider));
     }
+    if provider == LLMProvider::OpenAICompatible && api_base_url.is_none() {
+        return Err(format!("Provider {} requires an API base URL", provider));
+    }
+
+    if validate.unwrap_or(false) {
+        provider_api::validate_api_key(&provider, &api_key, api_base_url.as_deref())
+            .await
+            .map_err(|e| format!("API key validation failed: {:#}", e))?;
+    }
 
-    APIKeyStorage::save(&provider, &api_key)
+    APIKeyStorage::save(&provider, api_base_url.as_deref(), &api_key)
         .map_err(|e| format!("Failed to save API key: {}", e))?;
 
     tracing::info!("Saved API key for provider: {}", provider);
     Ok(())
 }
 
-/// Check if an API key exists for a provider
+/// Check if an API key exists for a provider (and, for `openai_compatible`,
+/// the service at `api_base_url`)
 #[tauri::command]
-pub fn has_api_key(provider: String) -> Result<bool, String> {
+pub fn has_api_key(provider: String, api_base_url: Option<String>) -> Result<bool, String> {
     let provider = parse_provider(&provider)?;
 
     if !provider.needs_api_key() {
         return Ok(true); // Ollama doesn't need a key, so it's always "available"
     }
 
-    APIKeyStorage::exists(&provider).map_err(|e| format!("Failed to check API key: {}", e))
+    APIKeyStorage::exists(&provider, api_base_url.as_deref())
+        .map_err(|e| format!("Failed to check API key: {}", e))
+}
+
+/// Get the API key status of every provider in one call, keyed by provider id
+#[tauri::command]
+pub fn get_api_key_status() -> Result<BTreeMap<String, ApiKeyStatus>, String> {
+    Ok(api_key_status(|p| {
+        APIKeyStorage::exists(p, None).unwrap_or(false)
+    }))
+}
+
+/// Build the status map, using `has_key` to check stored keys
+fn api_key_status(has_key: impl Fn(&LLMProvider) -> bool) -> BTreeMap<String, ApiKeyStatus> {
+    LLMProvider::all()
+        .into_iter()
+        .map(|p| {
+            let needs_api_key = p.needs_api_key();
+            let status = ApiKeyStatus {
+                needs_api_key,
+                has_api_key: needs_api_key && has_key(&p),
+            };
+            (p.to_string(), status)
+        })
+        .collect()
 }
 
-/// Delete an API key for a provider
+/// List the models available from a provider, for the model dropdown.
+/// Uses the stored API key; for Ollama, `api_base_url` selects the server.
+/// If the provider cannot be queried, its curated suggestions are returned.
 #[tauri::command]
-pub fn delete_api_key(provider: String) -> Result<(), String> {
+pub async fn list_models(
+    provider: String,
+    api_base_url: Option<String>,
+) -> Result<Vec<String>, String> {
     let provider = parse_provider(&provider)?;
 
-    APIKeyStorage::delete(&provider).map_err(|e| format!("Failed to delete API key: {}", e))?;
+    let api_key = if provider.needs_api_key() {
+        APIKeyStorage::load(&provider, api_base_url.as_deref())
+            .map_err(|e| format!("Failed to load API key: {}", e))?
+    } else {
+        None
+    };
+
+    match provider_api::list_models(&provider, api_key.as_deref(), api_base_url.as_deref()).await {
+        Ok(models) => Ok(models),
+        Err(e) if !provider.suggested_models().is_empty() => {
+            tracing::warn!(
+                "Failed to list models for {}, using suggestions: {:#}",
+                provider,
+                e
+            );
+            Ok(provider
+                .suggested_models()
+                .into_iter()
+                .map(|m| m.to_string())
+                .collect())
+        }
+        Err(e) => Err(format!("Failed to list models: {:#}", e)),
+    }
+}
+
+/// Check that an instance's provider is reachable and its model exists,
+/// so the UI can surface connection problems before a chat starts.
+#[tauri::command]
+pub async fn check_instance_health(
+    instance_id: String,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<provider_api::HealthStatus, String> {
+    let instance = manager
+        .lock()
+        .await
+        .get_instance(&instance_id)
+        .cloned()
+        .ok_or_else(|| format!("Instance not found: {}", instance_id))?;
+
+    let api_key = if instance.provider.needs_api_key() {
+        APIKeyStorage::load(&instance.provider, instance.api_base_url.as_deref())
+            .map_err(|e| format!("Failed to load API key: {}", e))?
+    } else {
+        None
+    };
+
+    Ok(provider_api::check_health(
+        &instance.provider,
+        api_key.as_deref(),
+        instance.api_base_url.as_deref(),
+        &instance.model,
+    )
+    .await)
+}
+
+/// Delete an API key for a provider (and, for `openai_compatible`, the
+/// service at `api_base_url`)
+#[tauri::command]
+pub fn delete_api_key(provider: String, api_base_url: Option<String>) -> Result<(), String> {
+    let provider = parse_provider(&provider)?;
+
+    APIKeyStorage::delete(&provider, api_base_url.as_deref())
+        .map_err(|e| format!("Failed to delete API key: {}", e))?;
 
     tracing::info!("Deleted API key for provider: {}", provider);
     Ok(())
@@ -91,6 +200,7 @@ fn parse_provider(provider: &str) -> Result<LLMProvider, String> {
         "anthropic" => Ok(LLMProvider::Anthropic),
         "openai" => Ok(LLMProvider::OpenAI),
         "ollama" => Ok(LLMProvider::Ollama),
+        "openai_compatible" => Ok(LLMProvider::OpenAICompatible),
         _ => Err(format!("Invalid provider: {}", provider)),
     }
 }
@@ -110,13 +220,19 @@ pub async fn create_ai_instance(
         "anthropic" => LLMProvider::Anthropic,
         "openai" => LLMProvider::OpenAI,
         "ollama" => LLMProvider::Ollama,
+        "openai_compatible" => LLMProvider::OpenAICompatible,
         _ => return Err(format!("Invalid provider: {}", request.provider)),
     };
 
+    if provider == LLMProvider::OpenAICompatible && request.api_base_url.is_none() {
+        return Err(format!("Provider {} requires an API base URL", provider));
+    }
+
     // Validate API key exists for providers that need it
-    // API keys are stored per provider, not per instance
+    // API keys are stored per provider (per base URL for openai_compatible),
+    // not per instance
     if provider.needs_api_key() {
-        let has_key = APIKeyStorage::exists(&provider)
+        let has_key = APIKeyStorage::exists(&provider, request.api_base_url.as_deref())
             .map_err(|e| format!("Failed to check API key: {}", e))?;
 
         if !has_key {
@@ -135,6 +251,7 @@ pub async fn create_ai_instance(
             provider.clone(),
             request.model,
             request.api_base_url,
+            request.max_tool_turns,
         )
         .map_err(|e| e.to_string())?;
 
@@ -147,6 +264,66 @@ pub async fn create_ai_instance(
     Ok(instance)
 }
 
+/// Create a copy of an instance's configuration with fresh memory, workspace
+/// and programs
+#[tauri::command]
+pub async fn clone_ai_instance(
+    source_id: String,
+    new_name: String,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<AIInstance, String> {
+    let mut manager = manager.lock().await;
+    manager
+        .clone_instance(&source_id, new_name)
+        .map_err(|e| e.to_string())
+}
+
+/// Rename an AI instance. The cached agent is dropped so the next chat
+/// rebuilds its system prompt with the new name.
+#[tauri::command]
+pub async fn rename_ai_instance(
+    instance_id: String,
+    new_name: String,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<AIInstance, String> {
+    let instance = manager
+        .lock()
+        .await
+        .rename_instance(&instance_id, new_name)
+        .map_err(|e| e.to_string())?;
+
+    evict_cached(&agent_cache, &instance_id).await;
+
+    Ok(instance)
+}
+
+/// Change settings of an instance (see `InstanceSettingsPatch`). Only the
+/// fields present in `settings` are changed. The cached agent is dropped so
+/// the next chat uses the new settings.
+#[tauri::command]
+pub async fn update_instance_settings(
+    instance_id: String,
+    settings: InstanceSettingsPatch,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<AIInstance, String> {
+    let instance = manager
+        .lock()
+        .await
+        .update_instance_settings(&instance_id, settings)
+        .map_err(|e| e.to_string())?;
+
+    evict_cached(&agent_cache, &instance_id).await;
+
+    Ok(instance)
+}
+
+/// Drop the cached entry for an instance. Returns whether one was cached.
+async fn evict_cached<V>(cache: &RwLock<HashMap<String, V>>, instance_id: &str) -> bool {
+    cache.write().await.remove(instance_id).is_some()
+}
+
 /// List all AI instances
 #[tauri::command]
 pub async fn list_ai_instances(
@@ -194,3 +371,60 @@ pub async fn delete_ai_instance(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_evict_cached_drops_stale_entry() {
+        let cache: RwLock<HashMap<String, &str>> = RwLock::new(HashMap::from([
+            ("renamed".to_string(), "agent with old name"),
+            ("other".to_string(), "other agent"),
+        ]));
+
+        assert!(evict_cached(&cache, "renamed").await);
+        assert!(!evict_cached(&cache, "renamed").await);
+
+        let cache = cache.into_inner();
+        assert!(!cache.contains_key("renamed"));
+        assert!(cache.contains_key("other"));
+    }
+
+    #[test]
+    fn test_api_key_status_map() {
+        // Seed a key for OpenAI only
+        let status = api_key_status(|p| *p == LLMProvider::OpenAI);
+
+        assert_eq!(status.len(), 4);
+        assert_eq!(
+            status["anthropic"],
+            ApiKeyStatus {
+                needs_api_key: true,
+                has_api_key: false,
+            }
+        );
+        assert_eq!(
+            status["openai"],
+            ApiKeyStatus {
+                needs_api_key: true,
+                has_api_key: true,
+            }
+        );
+        // Ollama is reported as "not required"
+        assert_eq!(
+            status["ollama"],
+            ApiKeyStatus {
+                needs_api_key: false,
+                has_api_key: false,
+            }
+        );
+        assert_eq!(
+            status["openai_compatible"],
+            ApiKeyStatus {
+                needs_api_key: true,
+                has_api_key: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,8 @@
+/// Get the directory containing the application log files, so users can
+/// attach them to bug reports. Files are rotated daily (`ownai.<date>.log`).
+#[tauri::command]
+pub async fn get_log_path() -> Result<String, String> {
+    crate::observability::log_dir()
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to get log directory: {}", e))
+}
@@ -3,17 +3,10 @@ use tauri::State;
 
 use super::chat::AgentCache;
 use crate::database::{get_or_init_db, DbCache};
-use crate::memory::fact_extraction;
-
-/// Memory statistics for debugging/monitoring
-#[derive(Debug, Serialize)]
-pub struct MemoryStats {
-    pub working_memory_count: usize,
-    pub working_memory_tokens: usize,
-    pub working_memory_utilization: f32,
-    pub long_term_memory_count: i64,
-    pub summaries_count: i64,
-}
+use crate::memory::consolidation::{self, ConsolidationResult};
+use crate::memory::long_term::normalize_tags;
+use crate::memory::transcript_import::{self, TranscriptFormat};
+use crate::memory::{fact_extraction, long_term, MemoryEntry, MemoryStats, SummarizationAgent};
 
 /// Result of a memory search with similarity score
 #[derive(Debug, Serialize)]
@@ -25,7 +18,9 @@ pub struct MemorySearchResult {
     pub similarity: f32,
 }
 
-/// Get memory statistics for an AI instance
+/// Get memory statistics for an AI instance.
+/// Includes per-type long-term counts, summary token savings and
+/// working memory usage vs. budget (when the agent is loaded).
 #[tauri::command]
 pub async fn get_memory_stats(
     instance_id: String,
@@ -34,50 +29,58 @@ pub async fn get_memory_stats(
 ) -> Result<MemoryStats, String> {
     // Read-lock the cache briefly to clone the Arc, then release it
     // before locking the agent (avoids holding cache lock during agent wait).
-    let (wm_count, wm_tokens, wm_utilization) = {
-        let agent_arc = {
-            let cache = agent_cache.read().await;
-            cache.get(&instance_id).cloned()
-        };
-        if let Some(agent_arc) = agent_arc {
-            let agent = agent_arc.lock().await;
-            let wm = agent.context_builder().working_memory();
-            (wm.message_count(), wm.current_tokens(), wm.utilization())
-        } else {
-            (0, 0, 0.0)
-        }
+    let agent_arc = {
+        let cache = agent_cache.read().await;
+        cache.get(&instance_id).cloned()
     };
+    if let Some(agent_arc) = agent_arc {
+        let agent = agent_arc.lock().await;
+        return agent
+            .context_builder()
+            .memory_stats()
+            .await
+            .map_err(|e| format!("Failed to collect memory stats: {}", e));
+    }
 
-    // Get DB-based stats (summaries count, long-term memory count)
+    // Agent not loaded yet: report DB-based stats only (working memory is empty)
     let db = get_or_init_db(&db_cache, &instance_id)
         .await
         .map_err(|e| format!("Failed to initialize database: {}", e))?;
 
-    let summaries_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM summaries")
-        .fetch_one(&db)
+    let long_term_by_type = long_term::count_by_type(&db)
         .await
-        .unwrap_or(0);
+        .map_err(|e| format!("Failed to count memory entries: {}", e))?;
 
-    let long_term_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memory_entries")
-        .fetch_one(&db)
+    let summarization_agent = SummarizationAgent::new(db);
+    let summaries_count = summarization_agent
+        .count_summaries()
+        .await
+        .map_err(|e| format!("Failed to count summaries: {}", e))?;
+    let total_token_savings = summarization_agent
+        .total_token_savings()
         .await
-        .unwrap_or(0);
+        .map_err(|e| format!("Failed to sum token savings: {}", e))?;
 
     Ok(MemoryStats {
-        working_memory_count: wm_count,
-        working_memory_tokens: wm_tokens,
-        working_memory_utilization: wm_utilization,
-        long_term_memory_count: long_term_count,
+        long_term_memory_count: long_term_by_type.values().sum(),
+        long_term_by_type,
         summaries_count,
+        total_token_savings,
+        ..Default::default()
     })
 }
 
-/// Search long-term memory semantically
+/// Search long-term memory semantically.
+/// `half_life_days` optionally weights results by recency; `None` ranks by
+/// pure similarity. With `tags`, only entries carrying at least one of them
+/// are returned.
 #[tauri::command]
 pub async fn search_memory(
     instance_id: String,
     query: String,
     limit: usize,
+    half_life_days: Option<f32>,
+    tags: Option<Vec<String>>,
     agent_cache: State<'_, AgentCache>,
 ) -> Result<Vec<MemorySearchResult>, String> {
     // Read-lock cache briefly to get the agent Arc, then lock agent briefly
@@ -95,10 +98,12 @@ pub async fn search_memory(
         // agent lock dropped here
     };
 
+    let tags = normalize_tags(&tags.unwrap_or_default());
+
     // Perform semantic search (no cache or agent lock held)
     let mut mem = long_term_memory.lock().await;
     let memories = mem
-        .recall(&query, limit, 0.0) // min_importance = 0.0 to include all
+        .recall_with_collection(&query, limit, 0.0, None, &tags, half_life_days) // min_importance = 0.0 to include all
         .await
         .map_err(|e| format!("Failed to search memory: {}", e))?;
 
@@ -153,6 +158,8 @@ pub async fn add_memory_entry(
         tags: Vec::new(),
         source_message_ids: Vec::new(),
         collection_id: None,
+        expires_at: None,
+        pinned: false,
     };
 
     let entry_id = entry.id.clone();
@@ -168,6 +175,212 @@ pub async fn add_memory_entry(
     Ok(entry_id)
 }
 
+/// Source recorded on memory entries created by `extract_facts_from_text`
+const MANUAL_EXTRACTION_SOURCE: &str = "manual_extraction";
+
+/// Maximum number of characters `extract_facts_from_text` accepts
+const MAX_EXTRACTION_TEXT_CHARS: usize = 50_000;
+
+/// Extract facts from arbitrary text (e.g. a pasted document) with the
+/// instance's fact extractor and store them in long-term memory, without a
+/// chat turn. The text may be at most `MAX_EXTRACTION_TEXT_CHARS` long.
+/// Returns the stored entries; empty if no facts were found.
+#[tauri::command]
+pub async fn extract_facts_from_text(
+    instance_id: String,
+    text: String,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<Vec<MemoryEntry>, String> {
+    if text.trim().is_empty() {
+        return Err("Text must not be empty".to_string());
+    }
+    if text.chars().count() > MAX_EXTRACTION_TEXT_CHARS {
+        return Err(format!(
+            "Text too long (max {} characters)",
+            MAX_EXTRACTION_TEXT_CHARS
+        ));
+    }
+
+    // Read-lock cache briefly, then lock agent briefly to clone shared refs
+    let (fact_extractor, long_term_memory) = {
+        let cache = agent_cache.read().await;
+        let agent_arc = cache
+            .get(&instance_id)
+            .ok_or_else(|| "Agent not in cache - please send a message first".to_string())?
+            .clone();
+        drop(cache);
+
+        let agent = agent_arc.lock().await;
+        (
+            agent.fact_extractor(),
+            agent.context_builder().long_term_memory().clone(),
+        )
+    };
+
+    // Extract and store (no cache or agent lock held)
+    let entries = fact_extraction::extract_and_store(
+        fact_extractor.as_ref(),
+        &long_term_memory,
+        &text,
+        MANUAL_EXTRACTION_SOURCE,
+    )
+    .await
+    .map_err(|e| format!("Failed to extract facts: {}", e))?;
+
+    tracing::info!(
+        "Manually extracted {} facts for instance {}",
+        entries.len(),
+        instance_id
+    );
+
+    Ok(entries)
+}
+
+/// Source recorded on memory entries extracted by `import_transcript`
+const TRANSCRIPT_IMPORT_SOURCE: &str = "transcript_import";
+
+/// Number of imported messages handed to the fact extractor at once
+const IMPORT_EXTRACTION_CHUNK: usize = 20;
+
+/// Result of `import_transcript`
+#[derive(Debug, Serialize)]
+pub struct TranscriptImportResult {
+    /// Messages added to the conversation history
+    pub imported: usize,
+    /// Tool and system messages that were left out
+    pub skipped: usize,
+    /// Facts stored in long-term memory (0 unless `extract_facts` was set)
+    pub facts_extracted: usize,
+}
+
+/// Import a conversation transcript from another tool (`markdown` or `json`,
+/// see `memory::transcript_import`) into the instance's message history.
+/// With `extract_facts`, the instance's fact extractor also runs over the
+/// imported messages. The agent is reloaded afterwards so working memory
+/// includes the imported history.
+#[tauri::command]
+pub async fn import_transcript(
+    instance_id: String,
+    text: String,
+    format: String,
+    extract_facts: Option<bool>,
+    agent_cache: State<'_, AgentCache>,
+    db_cache: State<'_, DbCache>,
+) -> Result<TranscriptImportResult, String> {
+    let format = TranscriptFormat::parse(&format).map_err(|e| e.to_string())?;
+    let parsed = transcript_import::parse_transcript(&text, format)
+        .map_err(|e| format!("Failed to parse transcript: {:#}", e))?;
+
+    // Fact extraction needs the loaded agent; check before storing anything
+    let extraction = if extract_facts.unwrap_or(false) {
+        let cache = agent_cache.read().await;
+        let agent_arc = cache
+            .get(&instance_id)
+            .ok_or_else(|| "Agent not in cache - please send a message first".to_string())?
+            .clone();
+        drop(cache);
+
+        let agent = agent_arc.lock().await;
+        Some((
+            agent.fact_extractor(),
+            agent.context_builder().long_term_memory().clone(),
+        ))
+    } else {
+        None
+    };
+
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+    transcript_import::store_messages(&db, &parsed.messages)
+        .await
+        .map_err(|e| format!("Failed to store transcript: {}", e))?;
+    let imported = parsed.messages.len();
+
+    let mut facts_extracted = 0;
+    if let Some((fact_extractor, long_term_memory)) = extraction {
+        for chunk in parsed.messages.chunks(IMPORT_EXTRACTION_CHUNK) {
+            let text = chunk
+                .iter()
+                .map(|m| {
+                    let speaker = if m.role == "user" { "User" } else { "Agent" };
+                    format!("{}: {}", speaker, m.content)
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let entries = fact_extraction::extract_and_store(
+                fact_extractor.as_ref(),
+                &long_term_memory,
+                &text,
+                TRANSCRIPT_IMPORT_SOURCE,
+            )
+            .await
+            .map_err(|e| {
+                format!(
+                    "Imported {} messages, but fact extraction failed: {}",
+                    imported, e
+                )
+            })?;
+            facts_extracted += entries.len();
+        }
+    }
+
+    // Reload working memory with the imported history on the next message
+    agent_cache.write().await.remove(&instance_id);
+
+    tracing::info!(
+        "Imported {} messages ({} skipped, {} facts) for instance {}",
+        imported,
+        parsed.skipped,
+        facts_extracted,
+        instance_id
+    );
+
+    Ok(TranscriptImportResult {
+        imported,
+        skipped: parsed.skipped,
+        facts_extracted,
+    })
+}
+
+/// Update the content (and optionally the type) of a memory entry in place.
+/// The entry keeps its ID and creation time; its embedding is recomputed.
+#[tauri::command]
+pub async fn update_memory_entry(
+    instance_id: String,
+    entry_id: String,
+    content: String,
+    entry_type: Option<String>,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<(), String> {
+    // Read-lock cache briefly, then lock agent briefly to clone shared ref
+    let long_term_memory = {
+        let cache = agent_cache.read().await;
+        let agent_arc = cache
+            .get(&instance_id)
+            .ok_or_else(|| "Agent not in cache - please send a message first".to_string())?
+            .clone();
+        drop(cache);
+
+        let agent = agent_arc.lock().await;
+        agent.context_builder().long_term_memory().clone()
+    };
+
+    let memory_type = entry_type
+        .as_deref()
+        .map(fact_extraction::parse_memory_type);
+
+    // Update in long-term memory (no cache or agent lock held)
+    let mut mem = long_term_memory.lock().await;
+    mem.update_entry(&entry_id, &content, memory_type)
+        .await
+        .map_err(|e| format!("Failed to update memory entry: {}", e))?;
+
+    tracing::info!("Updated memory entry: {}", entry_id);
+
+    Ok(())
+}
+
 /// Delete a memory entry from long-term memory
 #[tauri::command]
 pub async fn delete_memory_entry(
@@ -198,3 +411,86 @@ pub async fn delete_memory_entry(
 
     Ok(())
 }
+
+/// Pin or unpin a memory entry. Pinned entries keep full weight in recall and
+/// are never removed by deduplication or expiry.
+#[tauri::command]
+pub async fn pin_memory_entry(
+    instance_id: String,
+    entry_id: String,
+    pinned: bool,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<(), String> {
+    // Read-lock cache briefly, then lock agent briefly to clone shared ref
+    let long_term_memory = {
+        let cache = agent_cache.read().await;
+        let agent_arc = cache
+            .get(&instance_id)
+            .ok_or_else(|| "Agent not in cache - please send a message first".to_string())?
+            .clone();
+        drop(cache);
+
+        let agent = agent_arc.lock().await;
+        agent.context_builder().long_term_memory().clone()
+    };
+
+    let mem = long_term_memory.lock().await;
+    mem.set_pinned(&entry_id, pinned)
+        .await
+        .map_err(|e| format!("Failed to pin memory entry: {}", e))
+}
+
+/// Remove near-duplicate memory entries, keeping pinned, more important, and
+/// older entries. Returns the number of deleted entries.
+#[tauri::command]
+pub async fn deduplicate_memory(
+    instance_id: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<u64, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    long_term::deduplicate(&db)
+        .await
+        .map_err(|e| format!("Failed to deduplicate memory: {}", e))
+}
+
+/// Merge clusters of similar long-term memories into canonical facts with the
+/// instance's fact extractor (see `memory::consolidation`). Pinned entries are
+/// kept as they are.
+#[tauri::command]
+pub async fn consolidate_memory(
+    instance_id: String,
+    agent_cache: State<'_, AgentCache>,
+    db_cache: State<'_, DbCache>,
+) -> Result<ConsolidationResult, String> {
+    // Read-lock cache briefly, then lock agent briefly to clone shared refs
+    let (fact_extractor, long_term_memory) = {
+        let cache = agent_cache.read().await;
+        let agent_arc = cache
+            .get(&instance_id)
+            .ok_or_else(|| "Agent not in cache - please send a message first".to_string())?
+            .clone();
+        drop(cache);
+
+        let agent = agent_arc.lock().await;
+        (
+            agent.fact_extractor(),
+            agent.context_builder().long_term_memory().clone(),
+        )
+    };
+
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    consolidation::consolidate(
+        &instance_id,
+        &db,
+        fact_extractor.as_ref(),
+        &long_term_memory,
+    )
+    .await
+    .map_err(|e| format!("Failed to consolidate memory: {}", e))
+}
@@ -1,8 +1,13 @@
+pub mod activity;
 pub mod canvas;
 pub mod chat;
+pub mod database;
 pub mod instances;
 pub mod langfuse;
+pub mod logs;
 pub mod memory;
 pub mod scheduler;
+pub mod settings;
+pub mod templates;
 pub mod tools;
 pub mod workspace;
@@ -6,7 +6,12 @@ use tokio::sync::Mutex;
 
 use crate::ai_instances::AIInstanceManager;
 use crate::database::{get_or_init_db, DbCache};
-use crate::scheduler::{storage, SharedScheduler};
+use crate::scheduler::{
+    storage, validate_cron_expression, ScheduledTask, ScheduledTaskRun, SharedScheduler,
+};
+
+/// Default number of runs returned by `get_task_history`
+const DEFAULT_TASK_HISTORY_LIMIT: u32 = 50;
 
 /// List all scheduled tasks for an instance.
 #[tauri::command]
@@ -30,6 +35,23 @@ pub async fn list_scheduled_tasks(
     Ok(result)
 }
 
+/// Get the run history of a scheduled task, newest first.
+#[tauri::command]
+pub async fn get_task_history(
+    instance_id: String,
+    task_id: String,
+    limit: Option<u32>,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<ScheduledTaskRun>, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    storage::load_task_runs(&db, &task_id, limit.unwrap_or(DEFAULT_TASK_HISTORY_LIMIT))
+        .await
+        .map_err(|e| format!("Failed to load task history: {}", e))
+}
+
 /// Delete a scheduled task.
 #[tauri::command]
 pub async fn delete_scheduled_task(
@@ -59,6 +81,75 @@ pub async fn delete_scheduled_task(
     Ok(())
 }
 
+/// Edit a scheduled task in place, keeping its id and run history.
+/// Unspecified fields are unchanged; an enabled task is re-registered so the
+/// new schedule and prompt take effect.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn update_scheduled_task(
+    instance_id: String,
+    task_id: String,
+    name: Option<String>,
+    cron_expression: Option<String>,
+    task_prompt: Option<String>,
+    notify: Option<bool>,
+    notify_on_failure: Option<bool>,
+    scheduler: State<'_, SharedScheduler>,
+    instance_manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+    app_handle: tauri::AppHandle,
+    db_cache: State<'_, DbCache>,
+) -> Result<ScheduledTask, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let mut task = storage::get_task(&db, &task_id)
+        .await
+        .map_err(|e| format!("Failed to get task: {}", e))?
+        .ok_or_else(|| "Task not found".to_string())?;
+
+    if let Some(cron_expression) = cron_expression {
+        validate_cron_expression(&cron_expression)?;
+        task.cron_expression = cron_expression;
+    }
+    if let Some(name) = name {
+        task.name = name;
+    }
+    if let Some(task_prompt) = task_prompt {
+        task.task_prompt = task_prompt;
+    }
+    if let Some(notify) = notify {
+        task.notify = notify;
+    }
+    if let Some(notify_on_failure) = notify_on_failure {
+        task.notify_on_failure = notify_on_failure;
+    }
+
+    storage::update_task(&db, &task)
+        .await
+        .map_err(|e| format!("Failed to update task: {}", e))?;
+
+    if task.enabled {
+        // Replaces the job registered under the old schedule
+        crate::scheduler::runner::register_task_job(
+            &scheduler,
+            task.id.clone(),
+            &task.cron_expression,
+            task.name.clone(),
+            task.task_prompt.clone(),
+            task.instance_id.clone(),
+            task.notify,
+            task.notify_on_failure,
+            instance_manager.inner().clone(),
+            app_handle,
+        )
+        .await
+        .map_err(|e| format!("Failed to register job: {}", e))?;
+    }
+
+    Ok(task)
+}
+
 /// Toggle a scheduled task's enabled state.
 #[tauri::command]
 pub async fn toggle_scheduled_task(
@@ -94,6 +185,7 @@ pub async fn toggle_scheduled_task(
             task.task_prompt,
             task.instance_id,
             task.notify,
+            task.notify_on_failure,
             instance_manager.inner().clone(),
             app_handle,
         )
@@ -0,0 +1,20 @@
+use crate::utils::offline;
+
+/// Enable or disable offline mode. While enabled, the sandbox HTTP functions
+/// (and thus all network-using tools) fail instead of connecting.
+/// Returns the new state.
+#[tauri::command]
+pub async fn set_offline_mode(enabled: bool) -> Result<bool, String> {
+    offline::set_offline(enabled);
+    tracing::info!(
+        "Offline mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(enabled)
+}
+
+/// Whether offline mode is currently enabled.
+#[tauri::command]
+pub async fn get_offline_mode() -> Result<bool, String> {
+    Ok(offline::is_offline())
+}
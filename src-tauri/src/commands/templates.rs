@@ -0,0 +1,58 @@
+use tauri::State;
+
+use crate::database::prompt_templates::{self, PromptTemplate};
+use crate::database::{get_or_init_db, DbCache};
+
+/// Save a prompt template. A template with the same name is replaced.
+/// `{{name}}` placeholders in the body are filled when the template is sent
+/// via `send_message`/`stream_message` with a `template_id`.
+#[tauri::command]
+pub async fn save_prompt_template(
+    instance_id: String,
+    name: String,
+    body: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<PromptTemplate, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    prompt_templates::save_template(&db, &name, &body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// List the instance's prompt templates, sorted by name.
+#[tauri::command]
+pub async fn list_prompt_templates(
+    instance_id: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<Vec<PromptTemplate>, String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    prompt_templates::list_templates(&db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a prompt template.
+#[tauri::command]
+pub async fn delete_prompt_template(
+    instance_id: String,
+    template_id: String,
+    db_cache: State<'_, DbCache>,
+) -> Result<(), String> {
+    let db = get_or_init_db(&db_cache, &instance_id)
+        .await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+    let deleted = prompt_templates::delete_template(&db, &template_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !deleted {
+        return Err(format!("Prompt template not found: {}", template_id));
+    }
+    Ok(())
+}
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use super::chat::AgentCache;
-use crate::tools::registry::ParameterDef;
+use crate::tools::registry::{self, normalize_category, ParameterDef, ToolUsageSummary};
 use crate::tools::rhai_bridge_tool::SharedRegistry;
 
 /// Serializable tool info for the frontend.
@@ -28,6 +28,10 @@ pub struct ToolInfo {
     pub parameters: Vec<ParameterDef>,
     pub created_at: String,
     pub last_used: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 /// Helper: read-lock the cache briefly and clone the SharedRegistry from the agent.
@@ -47,17 +51,19 @@ async fn get_registry(
     Ok(agent.tool_registry().clone())
 }
 
-/// List all active dynamic tools for an instance.
+/// List all active dynamic tools for an instance, optionally only those in
+/// `category`.
 #[tauri::command]
 pub async fn list_dynamic_tools(
     instance_id: String,
+    category: Option<String>,
     agent_cache: State<'_, AgentCache>,
 ) -> Result<Vec<ToolInfo>, String> {
     let registry = get_registry(&instance_id, agent_cache.inner()).await?;
 
     let reg = registry.read().await;
     let tools = reg
-        .list_tools(None)
+        .list_tools(None, category.as_deref())
         .await
         .map_err(|e| format!("Failed to list tools: {}", e))?;
 
@@ -75,11 +81,16 @@ pub async fn list_dynamic_tools(
             parameters: t.parameters,
             created_at: t.created_at.to_rfc3339(),
             last_used: t.last_used.map(|d| d.to_rfc3339()),
+            allowed_domains: t.allowed_domains,
+            category: t.category,
         })
         .collect())
 }
 
 /// Create a new dynamic tool with a Rhai script.
+/// `allowed_domains` optionally restricts the hosts its HTTP calls may reach;
+/// `category` optionally groups it in the UI.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn create_dynamic_tool(
     instance_id: String,
@@ -87,13 +98,22 @@ pub async fn create_dynamic_tool(
     description: String,
     script_content: String,
     parameters: Vec<ParameterDef>,
+    allowed_domains: Option<Vec<String>>,
+    category: Option<String>,
     agent_cache: State<'_, AgentCache>,
 ) -> Result<ToolInfo, String> {
     let registry = get_registry(&instance_id, agent_cache.inner()).await?;
 
     let mut reg = registry.write().await;
     let tool = reg
-        .register_tool(&name, &description, &script_content, parameters)
+        .register_tool(
+            &name,
+            &description,
+            &script_content,
+            parameters,
+            allowed_domains,
+            category.as_deref(),
+        )
         .await
         .map_err(|e| format!("Failed to create tool: {}", e))?;
 
@@ -115,6 +135,8 @@ pub async fn create_dynamic_tool(
         parameters: tool.parameters,
         created_at: tool.created_at.to_rfc3339(),
         last_used: tool.last_used.map(|d| d.to_rfc3339()),
+        allowed_domains: tool.allowed_domains,
+        category: tool.category,
     })
 }
 
@@ -141,7 +163,86 @@ pub async fn delete_dynamic_tool(
     Ok(())
 }
 
-/// Update an existing dynamic tool's script and optionally its description.
+/// Usage statistics for all dynamic tools of an instance (including
+/// deprecated ones), most used first.
+#[tauri::command]
+pub async fn get_tool_analytics(
+    instance_id: String,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<Vec<ToolUsageSummary>, String> {
+    let registry = get_registry(&instance_id, agent_cache.inner()).await?;
+
+    let reg = registry.read().await;
+    reg.usage_report()
+        .await
+        .map_err(|e| format!("Failed to build tool analytics: {}", e))
+}
+
+/// Reactivate a previously deleted (deprecated) dynamic tool.
+#[tauri::command]
+pub async fn reactivate_dynamic_tool(
+    instance_id: String,
+    name: String,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<ToolInfo, String> {
+    let registry = get_registry(&instance_id, agent_cache.inner()).await?;
+
+    let mut reg = registry.write().await;
+    let tool = reg
+        .reactivate_tool(&name)
+        .await
+        .map_err(|e| format!("Failed to reactivate tool: {}", e))?;
+
+    tracing::info!(
+        "Reactivated dynamic tool '{}' for instance {}",
+        name,
+        instance_id
+    );
+
+    Ok(ToolInfo {
+        id: tool.id,
+        name: tool.name,
+        description: tool.description,
+        version: tool.version,
+        status: tool.status.to_string(),
+        usage_count: tool.usage_count,
+        success_count: tool.success_count,
+        failure_count: tool.failure_count,
+        parameters: tool.parameters,
+        created_at: tool.created_at.to_rfc3339(),
+        last_used: tool.last_used.map(|d| d.to_rfc3339()),
+        allowed_domains: tool.allowed_domains,
+        category: tool.category,
+    })
+}
+
+/// Permanently delete a deprecated dynamic tool and its execution history.
+/// Returns the number of deleted execution records.
+#[tauri::command]
+pub async fn purge_dynamic_tool(
+    instance_id: String,
+    name: String,
+    agent_cache: State<'_, AgentCache>,
+) -> Result<u64, String> {
+    let registry = get_registry(&instance_id, agent_cache.inner()).await?;
+
+    let mut reg = registry.write().await;
+    let executions = reg
+        .purge_tool(&name)
+        .await
+        .map_err(|e| format!("Failed to purge tool: {}", e))?;
+
+    tracing::info!(
+        "Purged dynamic tool '{}' for instance {}",
+        name,
+        instance_id
+    );
+
+    Ok(executions)
+}
+
+/// Update an existing dynamic tool's script and optionally its description
+/// and category (an empty category clears it).
 #[tauri::command]
 pub async fn update_dynamic_tool(
     instance_id: String,
@@ -149,15 +250,22 @@ pub async fn update_dynamic_tool(
     script_content: String,
     description: Option<String>,
     parameters: Option<Vec<ParameterDef>>,
+    category: Option<String>,
     agent_cache: State<'_, AgentCache>,
 ) -> Result<ToolInfo, String> {
     let registry = get_registry(&instance_id, agent_cache.inner()).await?;
 
     let mut reg = registry.write().await;
-    let tool = reg
+    let mut tool = reg
         .update_tool(&name, &script_content, description.as_deref(), parameters)
         .await
         .map_err(|e| format!("Failed to update tool: {}", e))?;
+    if category.is_some() {
+        reg.set_category(&name, category.as_deref())
+            .await
+            .map_err(|e| format!("Failed to set category: {}", e))?;
+        tool.category = category.as_deref().and_then(normalize_category);
+    }
 
     tracing::info!(
         "Updated dynamic tool '{}' to version {} for instance {}",
@@ -178,9 +286,29 @@ pub async fn update_dynamic_tool(
         parameters: tool.parameters,
         created_at: tool.created_at.to_rfc3339(),
         last_used: tool.last_used.map(|d| d.to_rfc3339()),
+        allowed_domains: tool.allowed_domains,
+        category: tool.category,
     })
 }
 
+/// Cancel running executions of dynamic tools for an instance (all of them,
+/// or only those of `name`). Returns how many executions were cancelled.
+/// Scripts stuck in an HTTP request stop when the request returns or times
+/// out (30 seconds).
+#[tauri::command]
+pub async fn cancel_tool_execution(
+    instance_id: String,
+    name: Option<String>,
+) -> Result<usize, String> {
+    let count = registry::cancel_tool_executions(&instance_id, name.as_deref());
+    tracing::info!(
+        "Cancelled {} running tool execution(s) for instance {}",
+        count,
+        instance_id
+    );
+    Ok(count)
+}
+
 /// Execute a dynamic tool with the given parameters.
 #[tauri::command]
 pub async fn execute_dynamic_tool(
@@ -191,7 +319,7 @@ pub async fn execute_dynamic_tool(
 ) -> Result<String, String> {
     let registry = get_registry(&instance_id, agent_cache.inner()).await?;
 
-    let mut reg = registry.write().await;
+    let reg = registry.read().await;
     reg.execute_tool(&name, params)
         .await
         .map_err(|e| format!("Tool execution failed: {}", e))
@@ -1,9 +1,68 @@
-use crate::utils::paths;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tauri::State;
+use tokio::sync::Mutex;
+
+use crate::ai_instances::AIInstanceManager;
+use crate::utils::files::walk_files;
+use crate::utils::workspace_ignore::WorkspaceIgnore;
+
+/// Default number of directory levels returned by `list_workspace_tree`.
+const DEFAULT_TREE_DEPTH: usize = 5;
+
+/// OS-generated files that are never shown in the workspace tree.
+const SYSTEM_FILES: &[&str] = &["Thumbs.db", "desktop.ini"];
+
+/// A file or directory in the workspace tree.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceEntry {
+    pub name: String,
+    /// Path relative to the workspace root, using `/` as separator.
+    pub path: String,
+    pub is_dir: bool,
+    /// File size in bytes; for directories, the total size of listed descendants.
+    pub size: u64,
+    /// Directory contents. `None` for files and for directories beyond `max_depth`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<WorkspaceEntry>>,
+}
+
+/// Disk usage of an instance workspace.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorkspaceUsage {
+    pub total_bytes: u64,
+    pub file_count: u64,
+}
+
+/// Result of a workspace cleanup.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupResult {
+    /// Deleted files, relative to the workspace root using `/` as separator.
+    pub deleted: Vec<String>,
+    pub bytes_freed: u64,
+}
+
+/// Workspace directory of an instance (its override, if one is set).
+async fn workspace_path(
+    manager: &Mutex<AIInstanceManager>,
+    instance_id: &str,
+) -> Result<PathBuf, String> {
+    manager
+        .lock()
+        .await
+        .workspace_path(instance_id)
+        .map_err(|e| e.to_string())
+}
 
 /// Open the workspace directory for the given instance in the system file manager.
 #[tauri::command]
-pub async fn open_workspace(instance_id: String) -> Result<String, String> {
-    let workspace = paths::get_instance_workspace_path(&instance_id).map_err(|e| e.to_string())?;
+pub async fn open_workspace(
+    instance_id: String,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<String, String> {
+    let workspace = workspace_path(&manager, &instance_id).await?;
 
     // Ensure the directory exists
     std::fs::create_dir_all(&workspace).map_err(|e| {
@@ -42,3 +101,362 @@ pub async fn open_workspace(instance_id: String) -> Result<String, String> {
 
     Ok(workspace.to_string_lossy().to_string())
 }
+
+/// List the instance workspace as a nested tree of files and directories.
+///
+/// `max_depth` limits how many directory levels are expanded (default 5).
+/// Hidden entries (starting with `.`), OS system files, symlinks and paths
+/// excluded by `.ownaiignore` are skipped, so the listing never leaves the
+/// workspace directory.
+#[tauri::command]
+pub async fn list_workspace_tree(
+    instance_id: String,
+    max_depth: Option<usize>,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<Vec<WorkspaceEntry>, String> {
+    let workspace = workspace_path(&manager, &instance_id).await?;
+
+    if !workspace.exists() {
+        return Ok(Vec::new());
+    }
+
+    let depth = max_depth.unwrap_or(DEFAULT_TREE_DEPTH);
+    tokio::task::spawn_blocking(move || {
+        let ignore = WorkspaceIgnore::load(&workspace);
+        build_tree(&workspace, "", depth, &ignore)
+    })
+    .await
+    .map_err(|e| format!("Failed to list workspace: {}", e))?
+    .map_err(|e| format!("Failed to list workspace: {}", e))
+}
+
+/// Compute the total size and number of files in the instance workspace.
+/// Symlinks are not followed.
+#[tauri::command]
+pub async fn get_workspace_size(
+    instance_id: String,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<WorkspaceUsage, String> {
+    let workspace = workspace_path(&manager, &instance_id).await?;
+
+    if !workspace.exists() {
+        return Ok(WorkspaceUsage::default());
+    }
+
+    tokio::task::spawn_blocking(move || workspace_usage(&workspace))
+        .await
+        .map_err(|e| format!("Failed to measure workspace: {}", e))?
+        .map_err(|e| format!("Failed to measure workspace: {}", e))
+}
+
+/// Delete workspace files that have not been modified within the last
+/// `older_than_days` days. Directories are kept; symlinks are neither
+/// followed nor deleted. Refused for a workspace override, which is a
+/// directory the user owns.
+#[tauri::command]
+pub async fn cleanup_workspace(
+    instance_id: String,
+    older_than_days: u64,
+    manager: State<'_, Arc<Mutex<AIInstanceManager>>>,
+) -> Result<CleanupResult, String> {
+    let workspace = {
+        let manager = manager.lock().await;
+        let has_override = manager
+            .get_instance(&instance_id)
+            .is_some_and(|i| i.workspace_override.is_some());
+        if has_override {
+            return Err("Cleanup is not available for a custom workspace directory".to_string());
+        }
+        manager
+            .workspace_path(&instance_id)
+            .map_err(|e| e.to_string())?
+    };
+
+    if !workspace.exists() {
+        return Ok(CleanupResult::default());
+    }
+
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(
+            older_than_days.saturating_mul(24 * 60 * 60),
+        ))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let result = tokio::task::spawn_blocking(move || cleanup_older_than(&workspace, cutoff))
+        .await
+        .map_err(|e| format!("Failed to clean up workspace: {}", e))?
+        .map_err(|e| format!("Failed to clean up workspace: {}", e))?;
+
+    tracing::info!(
+        "Workspace cleanup for instance {}: deleted {} files ({} bytes)",
+        instance_id,
+        result.deleted.len(),
+        result.bytes_freed
+    );
+    Ok(result)
+}
+
+fn workspace_usage(root: &Path) -> std::io::Result<WorkspaceUsage> {
+    let mut usage = WorkspaceUsage::default();
+    walk_files(root, |_, _, metadata| {
+        usage.total_bytes += metadata.len();
+        usage.file_count += 1;
+        Ok(())
+    })?;
+    Ok(usage)
+}
+
+fn cleanup_older_than(root: &Path, cutoff: SystemTime) -> std::io::Result<CleanupResult> {
+    let mut result = CleanupResult::default();
+    walk_files(root, |path, rel, metadata| {
+        if metadata.modified()? < cutoff {
+            std::fs::remove_file(path)?;
+            result.deleted.push(rel.to_string());
+            result.bytes_freed += metadata.len();
+        }
+        Ok(())
+    })?;
+    result.deleted.sort();
+    Ok(result)
+}
+
+/// Whether a directory entry should be hidden from the workspace tree.
+fn is_hidden_entry(name: &str) -> bool {
+    name.starts_with('.') || SYSTEM_FILES.contains(&name)
+}
+
+/// Recursively list `dir`. `rel_prefix` is the path of `dir` relative to the
+/// workspace root. Directories are listed first, then files, each sorted by name.
+fn build_tree(
+    dir: &Path,
+    rel_prefix: &str,
+    depth: usize,
+    ignore: &WorkspaceIgnore,
+) -> std::io::Result<Vec<WorkspaceEntry>> {
+    let mut entries = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_hidden_entry(&name) {
+            continue;
+        }
+
+        // symlink_metadata does not follow links; symlinks are skipped entirely
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+
+        let path = if rel_prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", rel_prefix, name)
+        };
+        if ignore.is_ignored(Path::new(&path), metadata.is_dir()) {
+            continue;
+        }
+
+        if metadata.is_dir() {
+            let children = if depth > 1 {
+                Some(build_tree(&entry.path(), &path, depth - 1, ignore)?)
+            } else {
+                None
+            };
+            let size = children
+                .as_ref()
+                .map(|c| c.iter().map(|e| e.size).sum())
+                .unwrap_or(0);
+            entries.push(WorkspaceEntry {
+                name,
+                path,
+                is_dir: true,
+                size,
+                children,
+            });
+        } else if metadata.is_file() {
+            entries.push(WorkspaceEntry {
+                name,
+                path,
+                is_dir: false,
+                size: metadata.len(),
+                children: None,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_workspace() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        std::fs::write(root.join("notes.txt"), "hello").unwrap();
+        std::fs::write(root.join(".hidden"), "secret").unwrap();
+        std::fs::write(root.join("Thumbs.db"), "x").unwrap();
+        std::fs::create_dir_all(root.join("docs").join("drafts")).unwrap();
+        std::fs::write(root.join("docs").join("a.md"), "12345678").unwrap();
+        std::fs::write(root.join("docs").join("drafts").join("b.md"), "123").unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+
+        temp_dir
+    }
+
+    #[test]
+    fn test_build_tree_shape() {
+        let temp_dir = setup_workspace();
+
+        let tree = build_tree(
+            temp_dir.path(),
+            "",
+            DEFAULT_TREE_DEPTH,
+            &WorkspaceIgnore::load(temp_dir.path()),
+        )
+        .unwrap();
+
+        // Directories first, hidden and system files skipped
+        let names: Vec<&str> = tree.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["docs", "notes.txt"]);
+
+        let docs = &tree[0];
+        assert!(docs.is_dir);
+        assert_eq!(docs.path, "docs");
+        assert_eq!(docs.size, 11);
+
+        let docs_children = docs.children.as_ref().unwrap();
+        assert_eq!(docs_children.len(), 2);
+        assert_eq!(docs_children[0].path, "docs/drafts");
+        assert_eq!(docs_children[1].path, "docs/a.md");
+        assert_eq!(docs_children[1].size, 8);
+
+        let drafts = docs_children[0].children.as_ref().unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].path, "docs/drafts/b.md");
+        assert!(drafts[0].children.is_none());
+
+        let notes = &tree[1];
+        assert!(!notes.is_dir);
+        assert_eq!(notes.size, 5);
+        assert!(notes.children.is_none());
+    }
+
+    #[test]
+    fn test_build_tree_respects_max_depth() {
+        let temp_dir = setup_workspace();
+
+        let tree = build_tree(
+            temp_dir.path(),
+            "",
+            1,
+            &WorkspaceIgnore::load(temp_dir.path()),
+        )
+        .unwrap();
+        let docs = tree.iter().find(|e| e.name == "docs").unwrap();
+        assert!(docs.children.is_none());
+
+        let tree = build_tree(
+            temp_dir.path(),
+            "",
+            2,
+            &WorkspaceIgnore::load(temp_dir.path()),
+        )
+        .unwrap();
+        let docs = tree.iter().find(|e| e.name == "docs").unwrap();
+        let drafts = docs
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|e| e.name == "drafts")
+            .unwrap();
+        assert!(drafts.children.is_none());
+    }
+
+    fn set_age(path: &Path, days: u64) {
+        let mtime = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_workspace_usage() {
+        let temp_dir = setup_workspace();
+
+        let usage = workspace_usage(temp_dir.path()).unwrap();
+        // notes.txt (5) + .hidden (6) + Thumbs.db (1) + a.md (8) + b.md (3)
+        assert_eq!(
+            usage,
+            WorkspaceUsage {
+                total_bytes: 23,
+                file_count: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_cleanup_older_than() {
+        let temp_dir = setup_workspace();
+        let root = temp_dir.path();
+        set_age(&root.join("notes.txt"), 40);
+        set_age(&root.join("docs").join("drafts").join("b.md"), 10);
+        set_age(&root.join("docs").join("a.md"), 1);
+
+        let cutoff = SystemTime::now() - Duration::from_secs(7 * 24 * 60 * 60);
+        let result = cleanup_older_than(root, cutoff).unwrap();
+
+        assert_eq!(result.deleted, vec!["docs/drafts/b.md", "notes.txt"]);
+        assert_eq!(result.bytes_freed, 8);
+        assert!(!root.join("notes.txt").exists());
+        assert!(!root.join("docs").join("drafts").join("b.md").exists());
+        assert!(root.join("docs").join("a.md").exists());
+        // Directories are kept
+        assert!(root.join("docs").join("drafts").is_dir());
+
+        let usage = workspace_usage(root).unwrap();
+        assert_eq!(usage.file_count, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cleanup_does_not_follow_symlinks() {
+        let temp_dir = setup_workspace();
+        let outside = TempDir::new().unwrap();
+        let outside_file = outside.path().join("keep.txt");
+        std::fs::write(&outside_file, "outside").unwrap();
+        set_age(&outside_file, 100);
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+
+        let result = cleanup_older_than(temp_dir.path(), SystemTime::now()).unwrap();
+        assert!(result.deleted.iter().all(|p| !p.starts_with("escape")));
+        assert!(outside_file.exists());
+        assert_eq!(workspace_usage(temp_dir.path()).unwrap().file_count, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_tree_skips_symlinks() {
+        let temp_dir = setup_workspace();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+
+        let tree = build_tree(
+            temp_dir.path(),
+            "",
+            DEFAULT_TREE_DEPTH,
+            &WorkspaceIgnore::load(temp_dir.path()),
+        )
+        .unwrap();
+        assert!(tree.iter().all(|e| e.name != "escape"));
+    }
+}
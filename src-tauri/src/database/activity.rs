@@ -0,0 +1,113 @@
+//! Persistent activity log of events emitted to the frontend.
+//!
+//! Tools call `emit_and_record` instead of `AppHandle::emit` for events that
+//! belong in the activity feed, so the UI can show what happened even if it
+//! was not listening at the time.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, Emitter};
+
+/// Number of most recent entries kept per instance; older ones are pruned.
+const MAX_ACTIVITY_ENTRIES: i64 = 1_000;
+
+/// A recorded event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Store an event in the activity log, pruning entries beyond the limit.
+pub async fn record_event(
+    db: &Pool<Sqlite>,
+    event_type: &str,
+    payload: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query("INSERT INTO activity_log (event_type, payload, timestamp) VALUES (?, ?, ?)")
+        .bind(event_type)
+        .bind(payload.to_string())
+        .bind(chrono::Utc::now())
+        .execute(db)
+        .await
+        .context("Failed to record activity")?;
+
+    sqlx::query("DELETE FROM activity_log WHERE id <= (SELECT MAX(id) FROM activity_log) - ?")
+        .bind(MAX_ACTIVITY_ENTRIES)
+        .execute(db)
+        .await
+        .context("Failed to prune activity log")?;
+    Ok(())
+}
+
+/// Emit an event to the frontend (if an `AppHandle` is available) and record
+/// it in the activity log. Failures are logged, never returned, so a broken
+/// log cannot fail the action that produced the event.
+pub async fn emit_and_record(
+    app_handle: Option<&AppHandle>,
+    db: &Pool<Sqlite>,
+    event_type: &str,
+    payload: serde_json::Value,
+) {
+    if let Some(handle) = app_handle {
+        if let Err(e) = handle.emit(event_type, &payload) {
+            tracing::warn!("Failed to emit {} event: {}", event_type, e);
+        }
+    }
+    if let Err(e) = record_event(db, event_type, &payload).await {
+        tracing::warn!("Failed to record {} event: {}", event_type, e);
+    }
+}
+
+/// The most recent `limit` entries, newest first.
+pub async fn get_activity_log(db: &Pool<Sqlite>, limit: u32) -> Result<Vec<ActivityEntry>> {
+    let rows: Vec<(i64, String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT id, event_type, payload, timestamp FROM activity_log ORDER BY id DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await
+    .context("Failed to load activity log")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, event_type, payload, timestamp)| ActivityEntry {
+            id,
+            event_type,
+            payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+            timestamp,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_activity_log_newest_first_and_limited() {
+        let db = setup_test_db().await;
+        for i in 0..5 {
+            record_event(&db, "canvas:open_program", &json!({ "n": i }))
+                .await
+                .unwrap();
+        }
+
+        let entries = get_activity_log(&db, 3).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].payload["n"], 4);
+        assert_eq!(entries[2].payload["n"], 2);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod activity;
+pub mod prompt_templates;
 pub mod schema;
 
 use crate::utils::paths::get_instance_db_path;
@@ -7,6 +9,7 @@ use sqlx::{
     Pool, Sqlite,
 };
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -67,3 +70,101 @@ pub async fn init_database(instance_id: &str) -> Result<Pool<Sqlite>> {
 
     Ok(pool)
 }
+
+/// Current size of the database in bytes (page count * page size).
+pub async fn database_size(pool: &Pool<Sqlite>) -> Result<u64> {
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(pool)
+        .await
+        .context("Failed to read page count")?;
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await
+        .context("Failed to read page size")?;
+    Ok((page_count.max(0) * page_size.max(0)) as u64)
+}
+
+/// Rebuild the database file to reclaim space freed by deleted rows.
+/// Runs on the given (cached) pool so no conflicting connection is opened.
+pub async fn vacuum(pool: &Pool<Sqlite>) -> Result<()> {
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .context("Failed to vacuum database")?;
+    Ok(())
+}
+
+/// Write a consistent copy of the database to `dest` using `VACUUM INTO`.
+/// Works while the pool is in use; fails if `dest` already exists.
+pub async fn backup_to(pool: &Pool<Sqlite>, dest: &Path) -> Result<()> {
+    if dest.exists() {
+        anyhow::bail!("Backup destination already exists: {}", dest.display());
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create backup directory")?;
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(dest.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .context("Failed to back up database")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_populated_db() -> Pool<Sqlite> {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        schema::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        for i in 0..50 {
+            sqlx::query(
+                "INSERT INTO messages (id, role, content, timestamp) VALUES (?, 'user', ?, ?)",
+            )
+            .bind(format!("msg-{}", i))
+            .bind("x".repeat(1_000))
+            .bind(chrono::Utc::now())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_vacuum_populated_db() {
+        let pool = setup_populated_db().await;
+        sqlx::query("DELETE FROM messages WHERE id != 'msg-0'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        vacuum(&pool).await.unwrap();
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(database_size(&pool).await.unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_backup_refuses_existing_destination() {
+        let pool = setup_populated_db().await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest = temp_dir.path().join("existing.db");
+        std::fs::write(&dest, b"keep me").unwrap();
+
+        assert!(backup_to(&pool, &dest).await.is_err());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"keep me");
+    }
+}
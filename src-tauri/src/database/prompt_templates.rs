@@ -0,0 +1,207 @@
+//! Saved prompt templates.
+//!
+//! A template body may contain `{{name}}` placeholders. When a template is
+//! sent (`send_message` with a `template_id`), every placeholder is replaced
+//! with the value of the same name from the request's params.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// `{{name}}` placeholders, optionally with spaces inside the braces
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// A saved prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+type TemplateRow = (
+    String,
+    String,
+    String,
+    chrono::DateTime<chrono::Utc>,
+    chrono::DateTime<chrono::Utc>,
+);
+
+const TEMPLATE_COLUMNS: &str = "id, name, body, created_at, updated_at";
+
+fn from_row((id, name, body, created_at, updated_at): TemplateRow) -> PromptTemplate {
+    PromptTemplate {
+        id,
+        name,
+        body,
+        created_at,
+        updated_at,
+    }
+}
+
+/// Names of the placeholders in `body`, in order of first appearance.
+pub fn template_variables(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in PLACEHOLDER.captures_iter(body) {
+        let name = &caps[1];
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Fill the placeholders in `body` from `params`. Fails listing every
+/// placeholder without a value; unused params are ignored.
+pub fn render_template(body: &str, params: &HashMap<String, String>) -> Result<String> {
+    let missing: Vec<String> = template_variables(body)
+        .into_iter()
+        .filter(|name| !params.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!("Missing template variables: {}", missing.join(", "));
+    }
+
+    Ok(PLACEHOLDER
+        .replace_all(body, |caps: &regex::Captures<'_>| params[&caps[1]].clone())
+        .into_owned())
+}
+
+/// Save a template under `name`, replacing the body of an existing template
+/// with that name.
+pub async fn save_template(db: &Pool<Sqlite>, name: &str, body: &str) -> Result<PromptTemplate> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Template name must not be empty");
+    }
+    if body.trim().is_empty() {
+        anyhow::bail!("Template body must not be empty");
+    }
+
+    let now = chrono::Utc::now();
+    sqlx::query(
+        "INSERT INTO prompt_templates (id, name, body, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?) \
+         ON CONFLICT(name) DO UPDATE SET body = excluded.body, updated_at = excluded.updated_at",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(name)
+    .bind(body)
+    .bind(now)
+    .bind(now)
+    .execute(db)
+    .await
+    .context("Failed to save prompt template")?;
+
+    let row: TemplateRow = sqlx::query_as(&format!(
+        "SELECT {} FROM prompt_templates WHERE name = ?",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(name)
+    .fetch_one(db)
+    .await
+    .context("Failed to load saved prompt template")?;
+    Ok(from_row(row))
+}
+
+/// All templates, sorted by name.
+pub async fn list_templates(db: &Pool<Sqlite>) -> Result<Vec<PromptTemplate>> {
+    let rows: Vec<TemplateRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM prompt_templates ORDER BY name",
+        TEMPLATE_COLUMNS
+    ))
+    .fetch_all(db)
+    .await
+    .context("Failed to list prompt templates")?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+/// The template with this ID, if any.
+pub async fn get_template(db: &Pool<Sqlite>, id: &str) -> Result<Option<PromptTemplate>> {
+    let row: Option<TemplateRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM prompt_templates WHERE id = ?",
+        TEMPLATE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+    .context("Failed to load prompt template")?;
+    Ok(row.map(from_row))
+}
+
+/// Delete a template. Returns false if it did not exist.
+pub async fn delete_template(db: &Pool<Sqlite>, id: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM prompt_templates WHERE id = ?")
+        .bind(id)
+        .execute(db)
+        .await
+        .context("Failed to delete prompt template")?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_variables() {
+        let body = "Summarize {{ topic }} for {{audience}}. Focus on {{topic}}.";
+        assert_eq!(template_variables(body), vec!["topic", "audience"]);
+
+        let rendered = render_template(
+            body,
+            &params(&[
+                ("topic", "Rust"),
+                ("audience", "beginners"),
+                ("unused", "x"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "Summarize Rust for beginners. Focus on Rust.");
+    }
+
+    #[test]
+    fn test_render_reports_missing_variables() {
+        let err = render_template("{{a}} {{b}} {{c}}", &params(&[("b", "")])).unwrap_err();
+        assert_eq!(err.to_string(), "Missing template variables: a, c");
+
+        // Text without valid placeholders is left untouched
+        assert_eq!(
+            render_template("{{}} {{ 1x }} {single}", &HashMap::new()).unwrap(),
+            "{{}} {{ 1x }} {single}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_replaces_template_with_same_name() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+
+        let first = save_template(&db, "review", "Review {{file}}")
+            .await
+            .unwrap();
+        let second = save_template(&db, " review ", "Review {{file}} carefully")
+            .await
+            .unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(second.body, "Review {{file}} carefully");
+        assert_eq!(list_templates(&db).await.unwrap().len(), 1);
+
+        assert!(delete_template(&db, &first.id).await.unwrap());
+        assert!(get_template(&db, &first.id).await.unwrap().is_none());
+        assert!(!delete_template(&db, &first.id).await.unwrap());
+    }
+}
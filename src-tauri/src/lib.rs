@@ -31,6 +31,11 @@ pub fn run() {
             // All file I/O here is synchronous (std::fs::read), which is fine
             // for serving local program files.
             let url = request.uri().to_string();
+            let if_none_match = request
+                .headers()
+                .get("If-None-Match")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
 
             let (instance_id, program_name, file_path) = match protocol::parse_protocol_url(&url) {
                 Ok(parsed) => parsed,
@@ -58,16 +63,40 @@ pub fn run() {
                 }
             };
 
-            match protocol::load_program_file(&programs_root, &program_name, &file_path) {
-                Ok((bytes, mime)) => {
+            match protocol::load_program_file_conditional(
+                &programs_root,
+                &program_name,
+                &file_path,
+                if_none_match.as_deref(),
+            ) {
+                Ok(protocol::ConditionalFile::NotModified { etag }) => {
                     let response = tauri::http::Response::builder()
-                        .status(200)
-                        .header("Content-Type", &mime)
+                        .status(304)
+                        .header("ETag", &etag)
                         .header("Access-Control-Allow-Origin", "*")
-                        .body(bytes)
+                        .body(Vec::new())
                         .unwrap();
                     responder.respond(response);
                 }
+                Ok(protocol::ConditionalFile::Modified {
+                    bytes,
+                    mime,
+                    etag,
+                    last_modified,
+                }) => {
+                    // no-cache: the iframe may cache but must revalidate via ETag
+                    let mut builder = tauri::http::Response::builder()
+                        .status(200)
+                        .header("Content-Type", &mime)
+                        .header("ETag", &etag)
+                        .header("Cache-Control", "no-cache")
+                        .header("Access-Control-Allow-Origin", "*");
+                    if let Some(last_modified) = last_modified {
+                        builder = builder.header("Last-Modified", last_modified);
+                    }
+                    let response = builder.body(bytes).unwrap();
+                    responder.respond(response);
+                }
                 Err(e) => {
                     tracing::warn!(
                         "Failed to load program file '{}/{}': {}",
@@ -95,6 +124,20 @@ pub fn run() {
                 Arc::new(tokio::sync::RwLock::new(HashMap::new()));
             app.manage(agent_cache);
 
+            // Initialize Stream Registry (cancellation tokens for in-flight streams)
+            let stream_registry: commands::chat::StreamRegistry =
+                Arc::new(Mutex::new(HashMap::new()));
+            app.manage(stream_registry);
+
+            // Initialize pending confirmations for destructive tool actions
+            let confirmations: tools::confirmation::SharedConfirmations = Default::default();
+            app.manage(confirmations);
+
+            // Initialize per-program rate limits for the Canvas bridge chat method
+            let bridge_chat_limiter: canvas::rate_limit::SharedBridgeChatLimiter =
+                Default::default();
+            app.manage(bridge_chat_limiter);
+
             // Initialize Database Cache (pools per instance, avoids repeated init_database())
             let db_cache: database::DbCache = Arc::new(Mutex::new(HashMap::new()));
             app.manage(db_cache.clone());
@@ -160,44 +203,87 @@ pub fn run() {
             commands::instances::get_providers,
             commands::instances::save_api_key,
             commands::instances::has_api_key,
+            commands::instances::get_api_key_status,
+            commands::instances::list_models,
+            commands::instances::check_instance_health,
             commands::instances::delete_api_key,
             // AI Instance Management
             commands::instances::create_ai_instance,
             commands::instances::list_ai_instances,
+            commands::instances::clone_ai_instance,
+            commands::instances::rename_ai_instance,
+            commands::instances::update_instance_settings,
             commands::instances::set_active_instance,
             commands::instances::get_active_instance,
             commands::instances::delete_ai_instance,
             // Chat Commands
             commands::chat::send_message,
             commands::chat::stream_message,
+            commands::chat::cancel_stream,
+            commands::chat::confirm_tool_action,
             commands::chat::load_messages,
+            commands::chat::search_messages,
+            commands::chat::delete_message,
+            commands::chat::clear_conversation,
+            commands::chat::export_conversation,
             commands::chat::clear_agent_cache,
+            commands::templates::save_prompt_template,
+            commands::templates::list_prompt_templates,
+            commands::templates::delete_prompt_template,
             // Memory
             commands::memory::get_memory_stats,
             commands::memory::search_memory,
             commands::memory::add_memory_entry,
+            commands::memory::extract_facts_from_text,
+            commands::memory::import_transcript,
+            commands::memory::update_memory_entry,
             commands::memory::delete_memory_entry,
+            commands::memory::pin_memory_entry,
+            commands::memory::deduplicate_memory,
+            commands::memory::consolidate_memory,
+            commands::database::vacuum_instance,
+            commands::database::backup_database,
             // Dynamic Tools (Rhai)
             commands::tools::list_dynamic_tools,
             commands::tools::create_dynamic_tool,
             commands::tools::update_dynamic_tool,
             commands::tools::delete_dynamic_tool,
+            commands::tools::reactivate_dynamic_tool,
+            commands::tools::purge_dynamic_tool,
+            commands::tools::get_tool_analytics,
             commands::tools::execute_dynamic_tool,
+            commands::tools::cancel_tool_execution,
             // Canvas Programs
             commands::canvas::list_programs,
             commands::canvas::delete_program,
+            commands::canvas::rename_program,
+            commands::canvas::get_program_size,
+            commands::canvas::prune_program_files,
+            commands::canvas::list_program_files,
+            commands::canvas::list_program_file_versions,
+            commands::canvas::restore_program_file,
             commands::canvas::get_program_url,
             commands::canvas::bridge_request,
             // Workspace
             commands::workspace::open_workspace,
+            commands::workspace::list_workspace_tree,
+            commands::workspace::get_workspace_size,
+            commands::workspace::cleanup_workspace,
             // Scheduled Tasks
             commands::scheduler::list_scheduled_tasks,
+            commands::scheduler::get_task_history,
+            commands::scheduler::update_scheduled_task,
             commands::scheduler::delete_scheduled_task,
             commands::scheduler::toggle_scheduled_task,
+            // Activity Feed
+            commands::activity::get_activity_log,
             // Langfuse Observability
             commands::langfuse::save_langfuse_config,
             commands::langfuse::get_langfuse_config,
             commands::langfuse::delete_langfuse_config,
+            commands::logs::get_log_path,
+            commands::settings::set_offline_mode,
+            commands::settings::get_offline_mode,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,456 @@
+//! Memory consolidation.
+//!
+//! Over months, long-term memory collects overlapping facts ("User lives in
+//! Berlin", "The user moved to Berlin"). Consolidation clusters similar
+//! entries by embedding, asks the fact extractor to merge each cluster into
+//! one canonical fact, and replaces the cluster with the merged entry.
+//! Pinned entries and knowledge collection chunks are never merged.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::LazyLock;
+
+use super::fact_extraction::{parse_memory_type, FactExtractor};
+use super::long_term::{insert_entry, normalize_tags, recall_candidates};
+use super::{LongTermMemory, MemoryEntry, SharedLongTermMemory};
+
+/// Minimum cosine similarity to the first entry of a cluster. Lower than the
+/// deduplication threshold: clustered entries overlap but need not be equal.
+pub const CONSOLIDATION_SIMILARITY_THRESHOLD: f32 = 0.75;
+
+/// Maximum number of entries merged into one
+const MAX_CLUSTER_SIZE: usize = 8;
+
+/// Instances with a consolidation run in progress. Global because runs start
+/// from the agent build, the consolidate command and cache evictions alike.
+static RUNNING: LazyLock<std::sync::Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Marks the consolidation run of an instance as in progress until dropped.
+struct RunGuard {
+    instance_id: String,
+}
+
+impl RunGuard {
+    /// `None` if a run of the instance is already in progress.
+    fn try_start(instance_id: &str) -> Option<Self> {
+        let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        running.insert(instance_id.to_string()).then(|| RunGuard {
+            instance_id: instance_id.to_string(),
+        })
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        running.remove(&self.instance_id);
+    }
+}
+
+/// Outcome of a consolidation run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConsolidationResult {
+    /// Clusters replaced by a merged entry
+    pub clusters_merged: usize,
+    /// Entries removed (the merged entries are not counted as added)
+    pub entries_removed: usize,
+}
+
+/// Whether an entry may be merged into another one.
+fn is_mergeable(entry: &MemoryEntry) -> bool {
+    !entry.pinned && entry.collection_id.is_none()
+}
+
+/// Group mergeable entries into clusters of at least two. Each cluster starts
+/// with the most important unassigned entry and takes up to
+/// `MAX_CLUSTER_SIZE` entries with similarity >= `threshold` to it.
+pub(crate) fn cluster_entries(
+    candidates: Vec<(Vec<f32>, MemoryEntry)>,
+    threshold: f32,
+) -> Vec<Vec<MemoryEntry>> {
+    let mut candidates: Vec<(Vec<f32>, MemoryEntry)> = candidates
+        .into_iter()
+        .filter(|(_, entry)| is_mergeable(entry))
+        .collect();
+    candidates.sort_by(|(_, a), (_, b)| {
+        b.importance
+            .partial_cmp(&a.importance)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+
+    let mut assigned = vec![false; candidates.len()];
+    let mut clusters = Vec::new();
+    for seed in 0..candidates.len() {
+        if assigned[seed] {
+            continue;
+        }
+        let mut members = vec![seed];
+        for other in seed + 1..candidates.len() {
+            if members.len() >= MAX_CLUSTER_SIZE {
+                break;
+            }
+            if !assigned[other]
+                && LongTermMemory::cosine_similarity(&candidates[seed].0, &candidates[other].0)
+                    >= threshold
+            {
+                members.push(other);
+            }
+        }
+        if members.len() < 2 {
+            continue;
+        }
+        for &i in &members {
+            assigned[i] = true;
+        }
+        clusters.push(members.iter().map(|&i| candidates[i].1.clone()).collect());
+    }
+    clusters
+}
+
+/// Extractor input asking for one fact that merges `cluster`.
+fn merge_prompt(cluster: &[MemoryEntry]) -> String {
+    let entries = cluster
+        .iter()
+        .map(|entry| {
+            format!(
+                "- ({}) {}",
+                entry.created_at.format("%Y-%m-%d"),
+                entry.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "The following long-term memories about the user overlap. Merge them into \
+         exactly one canonical fact that keeps every detail that does not conflict. \
+         If they conflict, prefer the most recent one.\n\n{}",
+        entries
+    )
+}
+
+/// Ask `extractor` to merge `cluster` into one entry. Returns `None` if the
+/// extractor produced no fact. Importance, tags, sources, and access counts
+/// of the cluster are carried over.
+pub(crate) async fn merge_cluster(
+    extractor: &dyn FactExtractor,
+    cluster: &[MemoryEntry],
+) -> Result<Option<MemoryEntry>> {
+    let response = extractor.extract_facts(&merge_prompt(cluster)).await?;
+    let Some(fact) = response
+        .facts
+        .into_iter()
+        .find(|fact| !fact.content.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let tags: Vec<String> = cluster.iter().flat_map(|e| e.tags.clone()).collect();
+    let mut source_message_ids: Vec<String> = Vec::new();
+    for id in cluster.iter().flat_map(|e| &e.source_message_ids) {
+        if !source_message_ids.contains(id) {
+            source_message_ids.push(id.clone());
+        }
+    }
+    // The merged entry expires only if every merged entry would have
+    let expires_at: Option<DateTime<Utc>> = cluster
+        .iter()
+        .map(|e| e.expires_at)
+        .collect::<Option<Vec<_>>>()
+        .and_then(|times| times.into_iter().max());
+
+    Ok(Some(MemoryEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        content: fact.content.trim().to_string(),
+        entry_type: parse_memory_type(&fact.fact_type),
+        importance: cluster
+            .iter()
+            .map(|e| e.importance)
+            .fold(fact.importance.clamp(0.0, 1.0), f32::max),
+        created_at: cluster
+            .iter()
+            .map(|e| e.created_at)
+            .max()
+            .unwrap_or_else(Utc::now),
+        last_accessed: cluster
+            .iter()
+            .map(|e| e.last_accessed)
+            .max()
+            .unwrap_or_else(Utc::now),
+        access_count: cluster.iter().map(|e| e.access_count).sum(),
+        tags: normalize_tags(&tags),
+        source_message_ids,
+        collection_id: None,
+        expires_at,
+        pinned: false,
+    }))
+}
+
+/// Replace the entries of `cluster` with `merged` in one transaction.
+pub(crate) async fn replace_cluster(
+    db: &Pool<Sqlite>,
+    cluster: &[MemoryEntry],
+    merged: &MemoryEntry,
+    embedding: &[f32],
+) -> Result<()> {
+    let mut tx = db.begin().await?;
+    for entry in cluster {
+        sqlx::query("DELETE FROM memory_entries WHERE id = ?")
+            .bind(&entry.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    insert_entry(&mut *tx, merged, embedding).await?;
+    tx.commit()
+        .await
+        .context("Failed to replace consolidated memory entries")
+}
+
+/// Consolidate the unexpired long-term memories of an instance (see module
+/// docs) and record the run. Fails if a run of the instance is already in
+/// progress.
+pub async fn consolidate(
+    instance_id: &str,
+    db: &Pool<Sqlite>,
+    extractor: &dyn FactExtractor,
+    memory: &SharedLongTermMemory,
+) -> Result<ConsolidationResult> {
+    let Some(_guard) = RunGuard::try_start(instance_id) else {
+        anyhow::bail!("Memory consolidation is already running for this instance");
+    };
+    consolidate_with(db, extractor, |text| async move {
+        memory.lock().await.embed_text(&text)
+    })
+    .await
+}
+
+/// `consolidate` with the embedding of merged entries computed by `embed`.
+async fn consolidate_with<F, Fut>(
+    db: &Pool<Sqlite>,
+    extractor: &dyn FactExtractor,
+    embed: F,
+) -> Result<ConsolidationResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>>>,
+{
+    let candidates = recall_candidates(db, 0.0, None, &[], Utc::now()).await?;
+    let clusters = cluster_entries(candidates, CONSOLIDATION_SIMILARITY_THRESHOLD);
+
+    let mut result = ConsolidationResult::default();
+    for cluster in &clusters {
+        let Some(merged) = merge_cluster(extractor, cluster).await? else {
+            tracing::warn!(
+                "Fact extractor returned no merged fact for a cluster of {} memories",
+                cluster.len()
+            );
+            continue;
+        };
+        let embedding = embed(merged.content.clone()).await?;
+        replace_cluster(db, cluster, &merged, &embedding).await?;
+
+        result.clusters_merged += 1;
+        result.entries_removed += cluster.len() - 1;
+        tracing::debug!(
+            "Merged {} memories into '{}'",
+            cluster.len(),
+            merged.content
+        );
+    }
+
+    sqlx::query(
+        "INSERT INTO memory_consolidations (timestamp, clusters_merged, entries_removed) \
+         VALUES (?, ?, ?)",
+    )
+    .bind(Utc::now())
+    .bind(result.clusters_merged as i64)
+    .bind(result.entries_removed as i64)
+    .execute(db)
+    .await
+    .context("Failed to record memory consolidation")?;
+
+    tracing::info!(
+        "Memory consolidation merged {} clusters ({} entries removed)",
+        result.clusters_merged,
+        result.entries_removed
+    );
+    Ok(result)
+}
+
+/// Time of the last recorded consolidation run, if any.
+pub async fn last_consolidation(db: &Pool<Sqlite>) -> Result<Option<DateTime<Utc>>> {
+    sqlx::query_scalar("SELECT MAX(timestamp) FROM memory_consolidations")
+        .fetch_one(db)
+        .await
+        .context("Failed to load last memory consolidation")
+}
+
+/// Run `consolidate` if the last run is at least `interval_days` old (or
+/// there was none). Returns `None` if no run was due or a run of the
+/// instance is already in progress.
+pub async fn consolidate_if_due(
+    instance_id: &str,
+    db: &Pool<Sqlite>,
+    extractor: &dyn FactExtractor,
+    memory: &SharedLongTermMemory,
+    interval_days: u32,
+) -> Result<Option<ConsolidationResult>> {
+    let Some(_guard) = RunGuard::try_start(instance_id) else {
+        tracing::debug!("Memory consolidation of {} is already running", instance_id);
+        return Ok(None);
+    };
+    let due = match last_consolidation(db).await? {
+        Some(last) => Utc::now() - last >= chrono::Duration::days(interval_days as i64),
+        None => true,
+    };
+    if !due {
+        return Ok(None);
+    }
+    consolidate_with(db, extractor, |text| async move {
+        memory.lock().await.embed_text(&text)
+    })
+    .await
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{ExtractedFactItem, FactExtractionResponse, MemoryType};
+    use std::pin::Pin;
+
+    /// Mock extractor answering every merge request with one fixed fact
+    struct MockExtractor;
+
+    impl FactExtractor for MockExtractor {
+        fn extract_facts<'a>(
+            &'a self,
+            text: &'a str,
+        ) -> Pin<Box<dyn Future<Output = Result<FactExtractionResponse>> + Send + 'a>> {
+            Box::pin(async move {
+                assert!(text.contains("User lives in Berlin"));
+                Ok(FactExtractionResponse {
+                    facts: vec![ExtractedFactItem {
+                        content: "User lives in Berlin, Kreuzberg".to_string(),
+                        fact_type: "fact".to_string(),
+                        importance: 0.6,
+                    }],
+                })
+            })
+        }
+    }
+
+    fn entry(id: &str, content: &str, pinned: bool) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            content: content.to_string(),
+            entry_type: MemoryType::Fact,
+            importance: 0.7,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 2,
+            tags: vec!["home".to_string()],
+            source_message_ids: vec![format!("msg-{}", id)],
+            collection_id: None,
+            expires_at: None,
+            pinned,
+        }
+    }
+
+    async fn memory_ids(db: &Pool<Sqlite>) -> Vec<String> {
+        let mut ids: Vec<String> = sqlx::query_scalar("SELECT id FROM memory_entries")
+            .fetch_all(db)
+            .await
+            .unwrap();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_cluster_of_two_similar_entries_becomes_one() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+
+        let berlin = entry("berlin", "User lives in Berlin", false);
+        let kreuzberg = entry("kreuzberg", "User's apartment is in Kreuzberg", false);
+        let pinned = entry("pinned", "User's home is Berlin", true);
+        let python = entry("python", "User knows Python", false);
+        insert_entry(&db, &berlin, &[1.0, 0.0]).await.unwrap();
+        insert_entry(&db, &kreuzberg, &[0.9, 0.2]).await.unwrap();
+        insert_entry(&db, &pinned, &[1.0, 0.05]).await.unwrap();
+        insert_entry(&db, &python, &[0.0, 1.0]).await.unwrap();
+
+        let result = consolidate_with(&db, &MockExtractor, |_| async { Ok(vec![1.0, 0.1]) })
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            ConsolidationResult {
+                clusters_merged: 1,
+                entries_removed: 1,
+            }
+        );
+
+        let ids = memory_ids(&db).await;
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&"pinned".to_string()));
+        assert!(ids.contains(&"python".to_string()));
+        assert!(!ids.contains(&"berlin".to_string()));
+        assert!(!ids.contains(&"kreuzberg".to_string()));
+
+        let merged = recall_candidates(&db, 0.0, None, &[], Utc::now())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, e)| e)
+            .find(|e| e.content == "User lives in Berlin, Kreuzberg")
+            .expect("merged entry stored");
+        assert_eq!(merged.importance, 0.7);
+        assert_eq!(merged.access_count, 4);
+        assert_eq!(merged.tags, vec!["home"]);
+        assert_eq!(
+            merged.source_message_ids,
+            vec!["msg-berlin", "msg-kreuzberg"]
+        );
+
+        // The run is recorded, so a scheduled run is not due again right away
+        assert!(last_consolidation(&db).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_cluster_entries_skips_dissimilar_and_pinned() {
+        let candidates = vec![
+            (vec![1.0, 0.0], entry("a", "a", false)),
+            (vec![0.0, 1.0], entry("b", "b", false)),
+            (vec![1.0, 0.0], entry("c", "c", true)),
+        ];
+        assert!(cluster_entries(candidates, CONSOLIDATION_SIMILARITY_THRESHOLD).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_runs_of_one_instance_are_refused() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+        let memory: SharedLongTermMemory = std::sync::Arc::new(tokio::sync::Mutex::new(
+            LongTermMemory::without_model(db.clone()),
+        ));
+
+        let running = RunGuard::try_start("busy").unwrap();
+        assert!(RunGuard::try_start("busy").is_none());
+
+        let scheduled = consolidate_if_due("busy", &db, &MockExtractor, &memory, 1).await;
+        assert_eq!(scheduled.unwrap(), None);
+        let manual = consolidate("busy", &db, &MockExtractor, &memory).await;
+        assert!(manual.unwrap_err().to_string().contains("already running"));
+        assert_eq!(last_consolidation(&db).await.unwrap(), None);
+
+        drop(running);
+        let result = consolidate("busy", &db, &MockExtractor, &memory).await;
+        assert_eq!(result.unwrap(), ConsolidationResult::default());
+        assert!(RunGuard::try_start("busy").is_some());
+    }
+}
@@ -1,9 +1,26 @@
 use anyhow::Result;
 use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 use crate::tools::planning::SharedTodoList;
 
-use super::{SessionSummary, SharedLongTermMemory, SummarizationAgent, WorkingMemory};
+use super::{MemoryEntry, SessionSummary, SharedLongTermMemory, SummarizationAgent, WorkingMemory};
+
+/// Aggregated statistics across all memory layers (for the memory dashboard)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MemoryStats {
+    pub working_memory_count: usize,
+    pub working_memory_tokens: usize,
+    pub working_memory_max_tokens: usize,
+    pub working_memory_utilization: f32,
+    pub long_term_memory_count: i64,
+    /// Long-term entry counts keyed by snake_case memory type (e.g. "fact")
+    pub long_term_by_type: BTreeMap<String, i64>,
+    pub summaries_count: i64,
+    /// Cumulative tokens saved by summarizing evicted messages
+    pub total_token_savings: i64,
+}
 
 /// Context builder combines all memory layers into a coherent context
 pub struct ContextBuilder {
@@ -33,8 +50,10 @@ impl ContextBuilder {
         self.todo_list = Some(todo_list);
     }
 
-    /// Build complete context for a user query
-    pub async fn build_context(&self, user_query: &str) -> Result<String> {
+    /// Build complete context for a user query. `window` is the conversation
+    /// text sent along with the context; recalled memories it already quotes
+    /// are left out.
+    pub async fn build_context(&self, user_query: &str, window: &[String]) -> Result<String> {
         let mut context_parts = Vec::new();
 
         // 1. Temporal context: current date/time in user's local timezone
@@ -67,11 +86,13 @@ impl ContextBuilder {
             }
         }
 
-        // 3. Long-term memories (semantically relevant)
+        // 3. Long-term memories (semantically relevant), minus those already
+        //    quoted in the conversation window
         let memories = {
             let mut ltm = self.long_term_memory.lock().await;
             ltm.recall(user_query, 10, 0.5).await?
         };
+        let memories = Self::without_window_duplicates(memories, window);
 
         if !memories.is_empty() {
             context_parts.push("## Relevant Context:\n".to_string());
@@ -150,6 +171,25 @@ impl ContextBuilder {
         Ok(context_parts.join(""))
     }
 
+    /// Collect statistics across working memory, long-term memory and summaries
+    pub async fn memory_stats(&self) -> Result<MemoryStats> {
+        let long_term_by_type = {
+            let ltm = self.long_term_memory.lock().await;
+            ltm.count_by_type().await?
+        };
+
+        Ok(MemoryStats {
+            working_memory_count: self.working_memory.message_count(),
+            working_memory_tokens: self.working_memory.current_tokens(),
+            working_memory_max_tokens: self.working_memory.max_tokens(),
+            working_memory_utilization: self.working_memory.utilization(),
+            long_term_memory_count: long_term_by_type.values().sum(),
+            long_term_by_type,
+            summaries_count: self.summarization_agent.count_summaries().await?,
+            total_token_savings: self.summarization_agent.total_token_savings().await?,
+        })
+    }
+
     /// Get recent summaries from database
     async fn get_recent_summaries(&self, limit: usize) -> Result<Vec<SessionSummary>> {
         self.summarization_agent.get_recent_summaries(limit).await
@@ -232,6 +272,30 @@ impl ContextBuilder {
         ))
     }
 
+    /// Drop recalled memories whose text already appears in a window message,
+    /// so the model does not see the same content twice. Compared
+    /// case-insensitively with whitespace collapsed.
+    pub fn without_window_duplicates(
+        memories: Vec<(f32, MemoryEntry)>,
+        window: &[String],
+    ) -> Vec<(f32, MemoryEntry)> {
+        fn normalize(text: &str) -> String {
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        }
+
+        let window_texts: Vec<String> = window.iter().map(|text| normalize(text)).collect();
+        memories
+            .into_iter()
+            .filter(|(_, memory)| {
+                let content = normalize(&memory.content);
+                content.is_empty() || !window_texts.iter().any(|text| text.contains(&content))
+            })
+            .collect()
+    }
+
     /// Format a time gap between two consecutive messages as a short marker.
     /// Returns `None` if the gap is less than 4 hours (not significant for history).
     /// These markers are inserted into the chat history so the LLM can see
@@ -277,6 +341,42 @@ mod tests {
     use super::*;
     use chrono::TimeDelta;
 
+    fn memory(content: &str) -> (f32, MemoryEntry) {
+        let item = crate::memory::ExtractedFactItem {
+            content: content.to_string(),
+            fact_type: "fact".to_string(),
+            importance: 0.5,
+        };
+        (
+            0.8,
+            crate::memory::fact_extraction::to_memory_entry(item, "msg-0"),
+        )
+    }
+
+    #[test]
+    fn test_memories_in_window_are_excluded() {
+        let memories = vec![
+            memory("User's cat is called Miso"),
+            memory("User works as a nurse"),
+        ];
+        let window =
+            vec!["Remember: the user's  cat is   called Miso. What should I feed her?".to_string()];
+
+        let kept = ContextBuilder::without_window_duplicates(memories, &window);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].1.content, "User works as a nurse");
+    }
+
+    #[test]
+    fn test_memories_without_overlap_are_kept() {
+        let memories = vec![memory("User likes tea"), memory("User lives in Berlin")];
+        let window = vec!["What's the weather today?".to_string()];
+
+        let kept = ContextBuilder::without_window_duplicates(memories.clone(), &window);
+        assert_eq!(kept.len(), memories.len());
+        assert_eq!(kept[0].1.id, memories[0].1.id);
+    }
+
     #[test]
     fn test_format_time_gap_under_1_hour_returns_none() {
         let now = Utc::now();
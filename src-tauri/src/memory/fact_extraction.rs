@@ -1,8 +1,20 @@
+use anyhow::Result;
 use chrono::Utc;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-
-use super::{MemoryEntry, MemoryType};
+use std::future::Future;
+use std::pin::Pin;
+
+use super::{MemoryEntry, MemoryType, SharedLongTermMemory};
+
+/// Trait for LLM-based fact extraction, abstracting over providers.
+/// Implementations wrap provider-specific rig Extractors.
+pub trait FactExtractor: Send + Sync {
+    fn extract_facts<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<FactExtractionResponse>> + Send + 'a>>;
+}
 
 /// Structured response extracted from LLM when extracting facts from a conversation turn.
 /// Used with rig Extractors for type-safe structured output.
@@ -49,12 +61,97 @@ pub fn to_memory_entry(item: ExtractedFactItem, source_message_id: &str) -> Memo
         tags: Vec::new(),
         source_message_ids: vec![source_message_id.to_string()],
         collection_id: None,
+        expires_at: None,
+        pinned: false,
     }
 }
 
+/// Run `extractor` over `text` and store the extracted facts in long-term
+/// memory, attributed to `source_id`. Returns the stored entries (empty if
+/// no facts were found). Facts without content are skipped.
+pub async fn extract_and_store(
+    extractor: &dyn FactExtractor,
+    long_term_memory: &SharedLongTermMemory,
+    text: &str,
+    source_id: &str,
+) -> Result<Vec<MemoryEntry>> {
+    let extraction = extractor.extract_facts(text).await?;
+
+    let entries: Vec<MemoryEntry> = extraction
+        .facts
+        .into_iter()
+        .filter(|fact| !fact.content.trim().is_empty())
+        .map(|fact| to_memory_entry(fact, source_id))
+        .collect();
+    if entries.is_empty() {
+        return Ok(entries);
+    }
+
+    let mut mem = long_term_memory.lock().await;
+    for entry in &entries {
+        mem.store(entry.clone()).await?;
+    }
+    Ok(entries)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::LongTermMemory;
+
+    /// Mock extractor returning fixed facts without a real LLM
+    struct MockExtractor(Vec<ExtractedFactItem>);
+
+    impl FactExtractor for MockExtractor {
+        fn extract_facts<'a>(
+            &'a self,
+            _text: &'a str,
+        ) -> Pin<Box<dyn Future<Output = Result<FactExtractionResponse>> + Send + 'a>> {
+            Box::pin(async move {
+                Ok(FactExtractionResponse {
+                    facts: self.0.clone(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires fastembed model download (slow, ~1GB)
+    async fn test_extract_and_store_makes_facts_searchable() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+        let memory: SharedLongTermMemory = std::sync::Arc::new(tokio::sync::Mutex::new(
+            LongTermMemory::new(db)
+                .await
+                .expect("Failed to create LongTermMemory"),
+        ));
+
+        let extractor = MockExtractor(vec![ExtractedFactItem {
+            content: "The user's sister lives in Hamburg".to_string(),
+            fact_type: "fact".to_string(),
+            importance: 0.7,
+        }]);
+        let stored = extract_and_store(&extractor, &memory, "pasted document", "manual")
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].source_message_ids, vec!["manual"]);
+
+        let results = memory
+            .lock()
+            .await
+            .recall("Where does the sister live?", 5, 0.0)
+            .await
+            .unwrap();
+        assert!(results.iter().any(|(_, e)| e.id == stored[0].id));
+
+        // No facts: nothing is stored
+        let empty = extract_and_store(&MockExtractor(Vec::new()), &memory, "hello", "manual")
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(memory.lock().await.count().await.unwrap(), 1);
+    }
 
     #[test]
     fn test_parse_memory_type() {
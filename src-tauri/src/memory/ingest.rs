@@ -126,6 +126,8 @@ pub async fn ingest_document(
             tags: vec![],
             source_message_ids: vec![],
             collection_id: Some(collection.id.clone()),
+            expires_at: None,
+            pinned: false,
         };
 
         match memory.store(entry).await {
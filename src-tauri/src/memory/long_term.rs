@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use fastembed::Qwen3TextEmbedding;
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Row, Sqlite};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -35,11 +36,26 @@ pub struct MemoryEntry {
     pub source_message_ids: Vec<String>,
     /// Optional knowledge collection this entry belongs to.
     pub collection_id: Option<String>,
+    /// Optional expiry time for ephemeral entries; `None` never expires.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Pinned entries keep full weight: no recency decay, no deduplication,
+    /// no expiry.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl MemoryEntry {
+    /// Whether the entry has an expiry time at or before `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
 }
 
 /// Long-term memory with vector search using fastembed
 pub struct LongTermMemory {
-    embedder: Qwen3TextEmbedding,
+    /// `None` only for test instances built with `without_model`
+    embedder: Option<Qwen3TextEmbedding>,
     db: Pool<Sqlite>,
 }
 
@@ -60,7 +76,24 @@ impl LongTermMemory {
 
         tracing::info!("Fastembed model loaded successfully");
 
-        Ok(Self { embedder, db })
+        Ok(Self {
+            embedder: Some(embedder),
+            db,
+        })
+    }
+
+    /// Long-term memory without an embedding model, for tests that only
+    /// need the memory handle (e.g. to build tools). Storing and recalling
+    /// fail.
+    #[cfg(test)]
+    pub(crate) fn without_model(db: Pool<Sqlite>) -> Self {
+        Self { embedder: None, db }
+    }
+
+    fn embedder(&self) -> Result<&Qwen3TextEmbedding> {
+        self.embedder
+            .as_ref()
+            .context("Embedding model is not loaded")
     }
 
     /// Similarity threshold for deduplication.
@@ -70,20 +103,24 @@ impl LongTermMemory {
     /// Store a memory entry with its embedding.
     /// Performs semantic deduplication: if a very similar entry already exists
     /// (cosine similarity >= DEDUP_SIMILARITY_THRESHOLD), the new entry is skipped.
+    /// Pinned entries are always stored.
     pub async fn store(&mut self, entry: MemoryEntry) -> Result<()> {
         // Generate embedding
         let embeddings = self
-            .embedder
+            .embedder()?
             .embed(std::slice::from_ref(&entry.content))
             .context("Failed to generate embedding")?;
 
         let embedding_vec = &embeddings[0];
 
         // Check for semantic duplicates before inserting
-        if let Some(existing_id) = self
-            .find_similar(embedding_vec, Self::DEDUP_SIMILARITY_THRESHOLD)
-            .await?
-        {
+        let duplicate_of = if entry.pinned {
+            None
+        } else {
+            self.find_similar(embedding_vec, Self::DEDUP_SIMILARITY_THRESHOLD)
+                .await?
+        };
+        if let Some(existing_id) = duplicate_of {
             tracing::info!(
                 "Skipping duplicate memory entry '{}' (similar to existing entry {})",
                 entry.content,
@@ -92,30 +129,8 @@ impl LongTermMemory {
             return Ok(());
         }
 
-        let embedding_bytes = Self::vec_to_bytes(embedding_vec);
-
         // Store in database
-        sqlx::query(
-            r#"
-            INSERT INTO memory_entries 
-            (id, content, embedding, entry_type, importance, created_at, last_accessed, 
-             access_count, tags, source_message_ids, collection_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&entry.id)
-        .bind(&entry.content)
-        .bind(&embedding_bytes)
-        .bind(serde_json::to_string(&entry.entry_type)?)
-        .bind(entry.importance)
-        .bind(entry.created_at)
-        .bind(entry.last_accessed)
-        .bind(entry.access_count as i32)
-        .bind(serde_json::to_string(&entry.tags)?)
-        .bind(serde_json::to_string(&entry.source_message_ids)?)
-        .bind(&entry.collection_id)
-        .execute(&self.db)
-        .await?;
+        insert_entry(&self.db, &entry, embedding_vec).await?;
 
         tracing::info!(
             "Stored memory: {} (type: {:?}, importance: {})",
@@ -130,9 +145,12 @@ impl LongTermMemory {
     /// Find the most similar existing memory entry above a similarity threshold.
     /// Returns the ID of the most similar entry, or None if no entry is similar enough.
     async fn find_similar(&self, embedding: &[f32], threshold: f32) -> Result<Option<String>> {
-        let rows = sqlx::query("SELECT id, embedding FROM memory_entries")
-            .fetch_all(&self.db)
-            .await?;
+        let rows = sqlx::query(
+            "SELECT id, embedding FROM memory_entries WHERE expires_at IS NULL OR expires_at > ?",
+        )
+        .bind(Utc::now())
+        .fetch_all(&self.db)
+        .await?;
 
         let mut best_match: Option<(f32, String)> = None;
 
@@ -165,83 +183,55 @@ impl LongTermMemory {
         limit: usize,
         min_importance: f32,
     ) -> Result<Vec<(f32, MemoryEntry)>> {
-        self.recall_with_collection(query, limit, min_importance, None)
+        self.recall_with_collection(query, limit, min_importance, None, &[], None)
             .await
     }
 
     /// Recall memories using semantic search, optionally filtered by collection.
-    /// Returns entries paired with their similarity score (highest first).
+    /// Returns entries paired with their score (highest first).
+    ///
+    /// A non-empty `tag_filter` restricts results to entries carrying at least
+    /// one of the given tags (compared case-insensitively).
+    ///
+    /// When `half_life_days` is set, the similarity is weighted by an exponential
+    /// recency decay based on the entry's creation time (see `decayed_score`).
+    /// When `None`, entries are ranked by pure similarity.
     pub async fn recall_with_collection(
         &mut self,
         query: &str,
         limit: usize,
         min_importance: f32,
         collection_id: Option<&str>,
+        tag_filter: &[String],
+        half_life_days: Option<f32>,
     ) -> Result<Vec<(f32, MemoryEntry)>> {
         // Generate query embedding
         let query_embeddings = self
-            .embedder
+            .embedder()?
             .embed(&[query.to_string()])
             .context("Failed to generate query embedding")?;
 
         let query_vec = &query_embeddings[0];
 
-        // Fetch memories above importance threshold, optionally filtered by collection
-        let rows = if let Some(cid) = collection_id {
-            sqlx::query(
-                r#"
-                SELECT id, content, embedding, entry_type, importance, created_at, 
-                       last_accessed, access_count, tags, source_message_ids, collection_id
-                FROM memory_entries
-                WHERE importance >= ? AND collection_id = ?
-                "#,
-            )
-            .bind(min_importance)
-            .bind(cid)
-            .fetch_all(&self.db)
-            .await?
-        } else {
-            sqlx::query(
-                r#"
-                SELECT id, content, embedding, entry_type, importance, created_at, 
-                       last_accessed, access_count, tags, source_message_ids, collection_id
-                FROM memory_entries
-                WHERE importance >= ?
-                "#,
-            )
-            .bind(min_importance)
-            .fetch_all(&self.db)
-            .await?
-        };
+        // Fetch unexpired memories above importance threshold,
+        // optionally filtered by collection and tags
+        let candidates = recall_candidates(
+            &self.db,
+            min_importance,
+            collection_id,
+            tag_filter,
+            Utc::now(),
+        )
+        .await?;
 
         // Calculate cosine similarity and sort
-        let mut scored_memories: Vec<(f32, MemoryEntry)> = rows
+        let mut scored_memories: Vec<(f32, MemoryEntry)> = candidates
             .into_iter()
-            .filter_map(|row| {
-                let embedding_bytes: Vec<u8> = row.get("embedding");
-                let embedding = Self::bytes_to_vec(&embedding_bytes);
-                let similarity = Self::cosine_similarity(query_vec, &embedding);
-
-                // Parse memory entry
-                let entry = MemoryEntry {
-                    id: row.get("id"),
-                    content: row.get("content"),
-                    entry_type: serde_json::from_str(row.get("entry_type")).ok()?,
-                    importance: row.get("importance"),
-                    created_at: row.get("created_at"),
-                    last_accessed: row.get("last_accessed"),
-                    access_count: row.get::<i32, _>("access_count") as u32,
-                    tags: serde_json::from_str(row.get("tags")).ok()?,
-                    source_message_ids: serde_json::from_str(row.get("source_message_ids")).ok()?,
-                    collection_id: row.get("collection_id"),
-                };
-
-                Some((similarity, entry))
-            })
+            .map(|(embedding, entry)| (Self::cosine_similarity(query_vec, &embedding), entry))
             .collect();
 
-        // Sort by similarity (descending)
-        scored_memories.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        // Apply optional recency decay, then sort by score (descending)
+        Self::rank_memories(&mut scored_memories, half_life_days, Utc::now());
 
         // Take top N and update access tracking
         let mut results = Vec::new();
@@ -270,16 +260,64 @@ impl LongTermMemory {
         Ok(results)
     }
 
+    /// Combine a similarity score with an exponential recency decay.
+    /// The score halves every `half_life_days` since `created_at`.
+    /// Returns the plain similarity when `half_life_days` is `None` or not positive.
+    pub(crate) fn decayed_score(
+        similarity: f32,
+        created_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+        half_life_days: Option<f32>,
+    ) -> f32 {
+        match half_life_days {
+            Some(half_life) if half_life > 0.0 => {
+                let age_days =
+                    (now.signed_duration_since(created_at).num_seconds().max(0) as f32) / 86_400.0;
+                similarity * 0.5f32.powf(age_days / half_life)
+            }
+            _ => similarity,
+        }
+    }
+
+    /// Re-score memories with optional recency decay and sort them (highest first).
+    /// Pinned entries keep their plain similarity.
+    pub(crate) fn rank_memories(
+        scored: &mut [(f32, MemoryEntry)],
+        half_life_days: Option<f32>,
+        now: DateTime<Utc>,
+    ) {
+        for (score, entry) in scored.iter_mut().filter(|(_, entry)| !entry.pinned) {
+            *score = Self::decayed_score(*score, entry.created_at, now, half_life_days);
+        }
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
     /// Compute embedding vector for a text string.
     /// Exposed for use by other memory components (e.g. SummarizationAgent).
     pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
         let embeddings = self
-            .embedder
+            .embedder()?
             .embed(&[text.to_string()])
             .context("Failed to generate embedding")?;
         Ok(embeddings.into_iter().next().unwrap())
     }
 
+    /// Wrap shared long-term memory as an `Embedder` for the Rhai sandbox.
+    ///
+    /// The returned function blocks on the async memory lock, so it must be
+    /// called from a blocking context inside the runtime (scripts run within
+    /// `block_in_place`).
+    pub fn sandbox_embedder(memory: SharedLongTermMemory) -> crate::tools::rhai_engine::Embedder {
+        Arc::new(move |text: &str| {
+            let handle = tokio::runtime::Handle::try_current()
+                .map_err(|_| "No async runtime available".to_string())?;
+            handle.block_on(async {
+                let mem = memory.lock().await;
+                mem.embed_text(text).map_err(|e| e.to_string())
+            })
+        })
+    }
+
     /// Calculate cosine similarity between two vectors
     pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         if a.len() != b.len() {
@@ -313,6 +351,66 @@ impl LongTermMemory {
             .collect()
     }
 
+    /// Update the content (and optionally the type) of an existing memory entry.
+    /// Re-computes the stored embedding; the ID and `created_at` are preserved.
+    pub async fn update_entry(
+        &mut self,
+        id: &str,
+        new_content: &str,
+        new_type: Option<MemoryType>,
+    ) -> Result<()> {
+        let embedding = self.embed_text(new_content)?;
+        let embedding_bytes = Self::vec_to_bytes(&embedding);
+
+        let result = match new_type {
+            Some(ref entry_type) => sqlx::query(
+                "UPDATE memory_entries SET content = ?, embedding = ?, entry_type = ? WHERE id = ?",
+            )
+            .bind(new_content)
+            .bind(&embedding_bytes)
+            .bind(serde_json::to_string(entry_type)?)
+            .bind(id)
+            .execute(&self.db)
+            .await?,
+            None => {
+                sqlx::query("UPDATE memory_entries SET content = ?, embedding = ? WHERE id = ?")
+                    .bind(new_content)
+                    .bind(&embedding_bytes)
+                    .bind(id)
+                    .execute(&self.db)
+                    .await?
+            }
+        };
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Memory entry not found: {}", id);
+        }
+
+        tracing::info!("Updated memory entry: {}", id);
+        Ok(())
+    }
+
+    /// Pin or unpin a memory entry. Pinning also clears any expiry time.
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE memory_entries \
+             SET pinned = ?, expires_at = CASE WHEN ? THEN NULL ELSE expires_at END \
+             WHERE id = ?",
+        )
+        .bind(pinned)
+        .bind(pinned)
+        .bind(id)
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Memory entry not found: {}", id);
+        }
+
+        tracing::info!("Set pinned={} for memory entry: {}", pinned, id);
+        Ok(())
+    }
+
     /// Delete a memory entry by ID
     pub async fn delete(&self, id: &str) -> Result<()> {
         sqlx::query("DELETE FROM memory_entries WHERE id = ?")
@@ -332,36 +430,21 @@ impl LongTermMemory {
     ) -> Result<Vec<MemoryEntry>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, content, entry_type, importance, created_at,
-                   last_accessed, access_count, tags, source_message_ids, collection_id
+            SELECT id, content, entry_type, importance, created_at, last_accessed,
+                   access_count, tags, source_message_ids, collection_id, expires_at, pinned
             FROM memory_entries
-            WHERE entry_type = ?
+            WHERE entry_type = ? AND (expires_at IS NULL OR expires_at > ?)
             ORDER BY importance DESC, created_at DESC
             LIMIT ?
             "#,
         )
         .bind(serde_json::to_string(entry_type)?)
+        .bind(Utc::now())
         .bind(limit as i32)
         .fetch_all(&self.db)
         .await?;
 
-        let entries: Vec<MemoryEntry> = rows
-            .into_iter()
-            .filter_map(|row| {
-                Some(MemoryEntry {
-                    id: row.get("id"),
-                    content: row.get("content"),
-                    entry_type: serde_json::from_str(row.get("entry_type")).ok()?,
-                    importance: row.get("importance"),
-                    created_at: row.get("created_at"),
-                    last_accessed: row.get("last_accessed"),
-                    access_count: row.get::<i32, _>("access_count") as u32,
-                    tags: serde_json::from_str(row.get("tags")).ok()?,
-                    source_message_ids: serde_json::from_str(row.get("source_message_ids")).ok()?,
-                    collection_id: row.get("collection_id"),
-                })
-            })
-            .collect();
+        let entries: Vec<MemoryEntry> = rows.iter().filter_map(entry_from_row).collect();
 
         tracing::debug!("Found {} memories of type {:?}", entries.len(), entry_type);
 
@@ -375,6 +458,227 @@ impl LongTermMemory {
             .await?;
         Ok(count)
     }
+
+    /// Count memory entries grouped by type
+    pub async fn count_by_type(&self) -> Result<BTreeMap<String, i64>> {
+        count_by_type(&self.db).await
+    }
+}
+
+/// Insert `entry` with its `embedding` (no deduplication).
+pub(crate) async fn insert_entry<'c, E>(
+    executor: E,
+    entry: &MemoryEntry,
+    embedding: &[f32],
+) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO memory_entries
+        (id, content, embedding, entry_type, importance, created_at, last_accessed,
+         access_count, tags, source_message_ids, collection_id, expires_at, pinned)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&entry.id)
+    .bind(&entry.content)
+    .bind(LongTermMemory::vec_to_bytes(embedding))
+    .bind(serde_json::to_string(&entry.entry_type)?)
+    .bind(entry.importance)
+    .bind(entry.created_at)
+    .bind(entry.last_accessed)
+    .bind(entry.access_count as i32)
+    .bind(serde_json::to_string(&normalize_tags(&entry.tags))?)
+    .bind(serde_json::to_string(&entry.source_message_ids)?)
+    .bind(&entry.collection_id)
+    .bind(entry.expires_at)
+    .bind(entry.pinned)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Parse a `memory_entries` row (without the embedding column).
+/// Returns `None` if a JSON column is malformed.
+fn entry_from_row(row: &sqlx::sqlite::SqliteRow) -> Option<MemoryEntry> {
+    Some(MemoryEntry {
+        id: row.get("id"),
+        content: row.get("content"),
+        entry_type: serde_json::from_str(row.get("entry_type")).ok()?,
+        importance: row.get("importance"),
+        created_at: row.get("created_at"),
+        last_accessed: row.get("last_accessed"),
+        access_count: row.get::<i32, _>("access_count") as u32,
+        tags: serde_json::from_str(row.get("tags")).ok()?,
+        source_message_ids: serde_json::from_str(row.get("source_message_ids")).ok()?,
+        collection_id: row.get("collection_id"),
+        expires_at: row.get("expires_at"),
+        pinned: row.get("pinned"),
+    })
+}
+
+/// Normalize tags for storage and filtering: trimmed, lowercase, without
+/// empty or duplicate entries (first occurrence wins).
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Whether `entry` passes the tag filter: an empty filter matches everything,
+/// otherwise the entry needs at least one of the filter tags.
+fn matches_tag_filter(entry: &MemoryEntry, tag_filter: &[String]) -> bool {
+    let filter = normalize_tags(tag_filter);
+    filter.is_empty()
+        || normalize_tags(&entry.tags)
+            .iter()
+            .any(|tag| filter.contains(tag))
+}
+
+/// Load recall candidates (embedding + entry) that are not expired at `now`
+/// and meet the importance threshold, optionally within one collection and
+/// restricted by `tag_filter` (see `matches_tag_filter`).
+pub(crate) async fn recall_candidates(
+    db: &Pool<Sqlite>,
+    min_importance: f32,
+    collection_id: Option<&str>,
+    tag_filter: &[String],
+    now: DateTime<Utc>,
+) -> Result<Vec<(Vec<f32>, MemoryEntry)>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, content, embedding, entry_type, importance, created_at, last_accessed,
+               access_count, tags, source_message_ids, collection_id, expires_at, pinned
+        FROM memory_entries
+        WHERE importance >= ?
+          AND (? IS NULL OR collection_id = ?)
+          AND (expires_at IS NULL OR expires_at > ?)
+        "#,
+    )
+    .bind(min_importance)
+    .bind(collection_id)
+    .bind(collection_id)
+    .bind(now)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let entry = entry_from_row(row)?;
+            if !matches_tag_filter(&entry, tag_filter) {
+                return None;
+            }
+            let embedding_bytes: Vec<u8> = row.get("embedding");
+            Some((LongTermMemory::bytes_to_vec(&embedding_bytes), entry))
+        })
+        .collect())
+}
+
+/// Delete all memory entries that expired at or before `now`. Pinned entries
+/// are kept. Returns the number of deleted entries.
+pub async fn purge_expired(db: &Pool<Sqlite>, now: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM memory_entries
+        WHERE expires_at IS NOT NULL
+          AND expires_at <= ?
+          AND pinned = 0
+        "#,
+    )
+    .bind(now)
+    .execute(db)
+    .await
+    .context("Failed to purge expired memory entries")?;
+
+    if result.rows_affected() > 0 {
+        tracing::info!("Purged {} expired memory entries", result.rows_affected());
+    }
+
+    Ok(result.rows_affected())
+}
+
+/// IDs of the entries a deduplication pass removes from `candidates`: every
+/// unpinned entry with cosine similarity >= `threshold` to an entry that is
+/// kept. Pinned entries are considered first and always kept; among the rest,
+/// more important and then older entries win.
+pub(crate) fn duplicate_ids(candidates: &[(Vec<f32>, MemoryEntry)], threshold: f32) -> Vec<String> {
+    let mut order: Vec<&(Vec<f32>, MemoryEntry)> = candidates.iter().collect();
+    order.sort_by(|(_, a), (_, b)| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(
+                b.importance
+                    .partial_cmp(&a.importance)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+            .then(a.created_at.cmp(&b.created_at))
+    });
+
+    let mut kept: Vec<&[f32]> = Vec::new();
+    let mut duplicates = Vec::new();
+    for (embedding, entry) in order {
+        let is_duplicate = kept
+            .iter()
+            .any(|k| LongTermMemory::cosine_similarity(k, embedding) >= threshold);
+        if is_duplicate && !entry.pinned {
+            duplicates.push(entry.id.clone());
+        } else {
+            kept.push(embedding);
+        }
+    }
+    duplicates
+}
+
+/// Remove near-duplicate entries (see `duplicate_ids`) among the unexpired
+/// memories. Returns the number of deleted entries.
+pub async fn deduplicate(db: &Pool<Sqlite>) -> Result<u64> {
+    let candidates = recall_candidates(db, 0.0, None, &[], Utc::now()).await?;
+    let duplicates = duplicate_ids(&candidates, LongTermMemory::DEDUP_SIMILARITY_THRESHOLD);
+
+    let mut tx = db.begin().await?;
+    for id in &duplicates {
+        sqlx::query("DELETE FROM memory_entries WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit()
+        .await
+        .context("Failed to delete duplicate memory entries")?;
+
+    if !duplicates.is_empty() {
+        tracing::info!("Removed {} duplicate memory entries", duplicates.len());
+    }
+    Ok(duplicates.len() as u64)
+}
+
+/// Count memory entries grouped by type.
+/// Keys are the snake_case type names (e.g. "fact", "tool_usage").
+/// Types without any entries are omitted.
+pub async fn count_by_type(db: &Pool<Sqlite>) -> Result<BTreeMap<String, i64>> {
+    let rows =
+        sqlx::query("SELECT entry_type, COUNT(*) AS count FROM memory_entries GROUP BY entry_type")
+            .fetch_all(db)
+            .await
+            .context("Failed to count memory entries by type")?;
+
+    let mut counts = BTreeMap::new();
+    for row in rows {
+        let raw: String = row.get("entry_type");
+        // entry_type is stored as a JSON string (e.g. "\"fact\"")
+        let name = serde_json::from_str::<String>(&raw).unwrap_or(raw);
+        *counts.entry(name).or_insert(0) += row.get::<i64, _>("count");
+    }
+
+    Ok(counts)
 }
 
 #[cfg(test)]
@@ -408,9 +712,194 @@ mod tests {
             tags: vec![],
             source_message_ids: vec![],
             collection_id: None,
+            expires_at: None,
+            pinned: false,
         }
     }
 
+    /// Helper: insert an entry directly (bypassing embedding/dedup)
+    async fn insert_raw(db: &Pool<Sqlite>, entry: &MemoryEntry) {
+        insert_with_embedding(db, entry, &[1.0, 0.0]).await;
+    }
+
+    /// Helper: insert an entry with a given embedding
+    async fn insert_with_embedding(db: &Pool<Sqlite>, entry: &MemoryEntry, embedding: &[f32]) {
+        sqlx::query(
+            r#"
+            INSERT INTO memory_entries
+            (id, content, embedding, entry_type, importance, created_at, last_accessed,
+             access_count, tags, source_message_ids, collection_id, expires_at, pinned)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&entry.id)
+        .bind(&entry.content)
+        .bind(LongTermMemory::vec_to_bytes(embedding))
+        .bind(serde_json::to_string(&entry.entry_type).unwrap())
+        .bind(entry.importance)
+        .bind(entry.created_at)
+        .bind(entry.last_accessed)
+        .bind(entry.access_count as i32)
+        .bind(serde_json::to_string(&entry.tags).unwrap())
+        .bind(serde_json::to_string(&entry.source_message_ids).unwrap())
+        .bind(&entry.collection_id)
+        .bind(entry.expires_at)
+        .bind(entry.pinned)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pinned_entry_survives_dedup() {
+        let db = setup_test_db().await;
+
+        // The pinned entry is less important, so without the pin it would be
+        // the duplicate that gets removed
+        let mut pinned = create_test_entry("pinned", "User's name is Alex", MemoryType::Fact);
+        pinned.importance = 0.5;
+        pinned.pinned = true;
+        let important = create_test_entry("important", "The user is named Alex", MemoryType::Fact);
+        let duplicate = create_test_entry("duplicate", "User is called Alex", MemoryType::Fact);
+        let other = create_test_entry("other", "User lives in Berlin", MemoryType::Fact);
+        insert_with_embedding(&db, &pinned, &[1.0, 0.0]).await;
+        insert_with_embedding(&db, &important, &[0.99, 0.1]).await;
+        insert_with_embedding(&db, &duplicate, &[0.98, 0.12]).await;
+        insert_with_embedding(&db, &other, &[0.0, 1.0]).await;
+
+        assert_eq!(deduplicate(&db).await.unwrap(), 2);
+        let mut remaining: Vec<String> = sqlx::query_scalar("SELECT id FROM memory_entries")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        remaining.sort();
+        assert_eq!(remaining, vec!["other", "pinned"]);
+
+        // Pinned entries are never duplicates of each other either
+        let mut second = create_test_entry("second", "Name: Alex", MemoryType::Fact);
+        second.pinned = true;
+        insert_with_embedding(&db, &second, &[1.0, 0.0]).await;
+        assert_eq!(deduplicate(&db).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_entry_is_not_purged_or_decayed() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+
+        let mut entry = create_test_entry("pinned", "User's name is Alex", MemoryType::Fact);
+        entry.created_at = now - chrono::Duration::days(30);
+        entry.expires_at = Some(now - chrono::Duration::minutes(1));
+        entry.pinned = true;
+        insert_raw(&db, &entry).await;
+        assert_eq!(purge_expired(&db, now).await.unwrap(), 0);
+
+        let mut old = entry.clone();
+        old.id = "old".to_string();
+        old.pinned = false;
+        let mut scored = vec![(0.8, old), (0.8, entry)];
+        LongTermMemory::rank_memories(&mut scored, Some(1.0), now);
+        assert_eq!(scored[0].1.id, "pinned");
+        assert_eq!(scored[0].0, 0.8);
+        assert!(scored[1].0 < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entry_excluded_and_purged() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+
+        let permanent = create_test_entry("permanent", "User lives in Berlin", MemoryType::Fact);
+        let mut expired = create_test_entry("expired", "OTP is 123456", MemoryType::Context);
+        expired.expires_at = Some(now - chrono::Duration::minutes(1));
+        let mut pending = create_test_entry("pending", "OTP is 654321", MemoryType::Context);
+        pending.expires_at = Some(now + chrono::Duration::minutes(10));
+        for entry in [&permanent, &expired, &pending] {
+            insert_raw(&db, entry).await;
+        }
+
+        let ids: Vec<String> = recall_candidates(&db, 0.0, None, &[], now)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, entry)| entry.id)
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(!ids.contains(&"expired".to_string()));
+        assert!(expired.is_expired(now));
+        assert!(!pending.is_expired(now));
+
+        assert_eq!(purge_expired(&db, now).await.unwrap(), 1);
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM memory_entries")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 2);
+        // Nothing left to purge
+        assert_eq!(purge_expired(&db, now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tag_filter_scopes_recall_candidates() {
+        let db = setup_test_db().await;
+
+        let mut project = create_test_entry("project", "Uses PostgreSQL 16", MemoryType::Context);
+        project.tags = vec!["project-x".to_string(), "database".to_string()];
+        let mut personal = create_test_entry("personal", "Lives in Berlin", MemoryType::Fact);
+        personal.tags = vec!["personal".to_string()];
+        let untagged = create_test_entry("untagged", "Likes tea", MemoryType::Preference);
+        for entry in [&project, &personal, &untagged] {
+            insert_raw(&db, entry).await;
+        }
+
+        let ids = |candidates: Vec<(Vec<f32>, MemoryEntry)>| -> Vec<String> {
+            candidates.into_iter().map(|(_, entry)| entry.id).collect()
+        };
+
+        // Case-insensitive match on any filter tag
+        let filtered = recall_candidates(&db, 0.0, None, &["Project-X".to_string()], Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(ids(filtered), vec!["project".to_string()]);
+
+        let filtered = recall_candidates(
+            &db,
+            0.0,
+            None,
+            &["personal".to_string(), "database".to_string()],
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(filtered).len(), 2);
+
+        // No match
+        let filtered = recall_candidates(&db, 0.0, None, &["work".to_string()], Utc::now())
+            .await
+            .unwrap();
+        assert!(filtered.is_empty());
+
+        // Empty filter returns everything
+        let all = recall_candidates(&db, 0.0, None, &[], Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " Work ".to_string(),
+            "work".to_string(),
+            "".to_string(),
+            "Project-X".to_string(),
+        ];
+        assert_eq!(
+            normalize_tags(&tags),
+            vec!["work".to_string(), "project-x".to_string()]
+        );
+    }
+
     #[tokio::test]
     #[ignore] // Requires fastembed model download (slow, ~1GB)
     async fn test_delete_memory_entry() {
@@ -624,6 +1113,48 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[ignore] // Requires fastembed model download (slow, ~1GB)
+    async fn test_update_entry() {
+        let db = setup_test_db().await;
+        let mut memory = LongTermMemory::new(db)
+            .await
+            .expect("Failed to create LongTermMemory");
+
+        let mut entry = create_test_entry("e1", "User lives in Berlni", MemoryType::Fact);
+        entry.created_at = Utc::now() - chrono::Duration::days(3);
+        let created_at = entry.created_at;
+        memory.store(entry).await.expect("Failed to store");
+
+        memory
+            .update_entry("e1", "User lives in Berlin", Some(MemoryType::Context))
+            .await
+            .expect("Failed to update");
+
+        let results = memory
+            .recall("Where does the user live?", 5, 0.0)
+            .await
+            .expect("Failed to recall");
+        assert_eq!(results.len(), 1);
+        let (_, updated) = &results[0];
+        assert_eq!(updated.id, "e1");
+        assert_eq!(updated.content, "User lives in Berlin");
+        assert_eq!(updated.entry_type, MemoryType::Context);
+        assert_eq!(updated.created_at, created_at);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires fastembed model download (slow, ~1GB)
+    async fn test_update_nonexistent_entry_fails() {
+        let db = setup_test_db().await;
+        let mut memory = LongTermMemory::new(db)
+            .await
+            .expect("Failed to create LongTermMemory");
+
+        let result = memory.update_entry("missing", "New content", None).await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Requires fastembed model download (slow, ~1GB)
     async fn test_dedup_empty_store_no_error() {
@@ -639,4 +1170,91 @@ mod tests {
         let count = memory.count().await.expect("Failed to count");
         assert_eq!(count, 1);
     }
+
+    #[test]
+    fn test_decayed_score_none_keeps_similarity() {
+        let now = Utc::now();
+        let old = now - chrono::Duration::days(365);
+        assert_eq!(LongTermMemory::decayed_score(0.8, old, now, None), 0.8);
+    }
+
+    #[test]
+    fn test_decayed_score_halves_after_half_life() {
+        let now = Utc::now();
+        let created = now - chrono::Duration::days(10);
+        let score = LongTermMemory::decayed_score(0.8, created, now, Some(10.0));
+        assert!((score - 0.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_rank_memories_pure_similarity() {
+        let now = Utc::now();
+        let mut old_entry = create_test_entry("old", "Old entry", MemoryType::Fact);
+        old_entry.created_at = now - chrono::Duration::days(60);
+        let recent_entry = create_test_entry("recent", "Recent entry", MemoryType::Fact);
+
+        let mut scored = vec![(0.6, recent_entry), (0.9, old_entry)];
+        LongTermMemory::rank_memories(&mut scored, None, now);
+
+        assert_eq!(scored[0].1.id, "old");
+        assert_eq!(scored[0].0, 0.9);
+    }
+
+    #[test]
+    fn test_rank_memories_decay_prefers_recent() {
+        let now = Utc::now();
+        let mut old_entry = create_test_entry("old", "Old entry", MemoryType::Fact);
+        old_entry.created_at = now - chrono::Duration::days(60);
+        let mut recent_entry = create_test_entry("recent", "Recent entry", MemoryType::Fact);
+        recent_entry.created_at = now - chrono::Duration::hours(1);
+
+        // Aggressive decay: half-life of one day
+        let mut scored = vec![(0.9, old_entry), (0.6, recent_entry)];
+        LongTermMemory::rank_memories(&mut scored, Some(1.0), now);
+
+        assert_eq!(scored[0].1.id, "recent");
+        assert!(scored[0].0 > scored[1].0);
+    }
+
+    #[tokio::test]
+    async fn test_count_by_type() {
+        let db = setup_test_db().await;
+
+        // Insert rows directly (no embedder needed for counting)
+        let seeds = [
+            ("e1", "\"fact\""),
+            ("e2", "\"fact\""),
+            ("e3", "\"preference\""),
+            ("e4", "\"tool_usage\""),
+        ];
+        for (id, entry_type) in seeds {
+            sqlx::query(
+                "INSERT INTO memory_entries (id, content, embedding, entry_type, importance, created_at, last_accessed) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(format!("Content {}", id))
+            .bind(vec![0u8; 4])
+            .bind(entry_type)
+            .bind(0.5)
+            .bind(Utc::now())
+            .bind(Utc::now())
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        let counts = count_by_type(&db).await.unwrap();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts.get("fact"), Some(&2));
+        assert_eq!(counts.get("preference"), Some(&1));
+        assert_eq!(counts.get("tool_usage"), Some(&1));
+        assert_eq!(counts.get("skill"), None);
+    }
+
+    #[tokio::test]
+    async fn test_count_by_type_empty() {
+        let db = setup_test_db().await;
+        let counts = count_by_type(&db).await.unwrap();
+        assert!(counts.is_empty());
+    }
 }
@@ -1,16 +1,18 @@
 pub mod chunking;
 pub mod collections;
+pub mod consolidation;
 pub mod context_builder;
 pub mod document_parser;
 pub mod fact_extraction;
 pub mod ingest;
 pub mod long_term;
 pub mod summarization;
+pub mod transcript_import;
 pub mod working_memory;
 
 pub use collections::KnowledgeCollection;
-pub use context_builder::ContextBuilder;
-pub use fact_extraction::{ExtractedFactItem, FactExtractionResponse};
+pub use context_builder::{ContextBuilder, MemoryStats};
+pub use fact_extraction::{ExtractedFactItem, FactExtractionResponse, FactExtractor};
 pub use long_term::{LongTermMemory, MemoryEntry, MemoryType, SharedLongTermMemory};
 pub use summarization::{SessionSummary, SummarizationAgent, SummaryExtractor, SummaryResponse};
 pub use working_memory::WorkingMemory;
@@ -176,6 +176,8 @@ impl SummarizationAgent {
                         summary.end_message_id.clone(),
                     ],
                     collection_id: None,
+                    expires_at: None,
+                    pinned: false,
                 };
                 if let Err(e) = mem.store(entry).await {
                     tracing::warn!("Failed to store key fact as memory entry: {}", e);
@@ -293,6 +295,15 @@ impl SummarizationAgent {
         Ok(count)
     }
 
+    /// Get cumulative token savings across all summaries
+    pub async fn total_token_savings(&self) -> Result<i64> {
+        let total: i64 =
+            sqlx::query_scalar("SELECT COALESCE(SUM(token_savings), 0) FROM summaries")
+                .fetch_one(&self.db)
+                .await?;
+        Ok(total)
+    }
+
     /// Search for summaries semantically similar to a query embedding.
     /// Returns summaries sorted by cosine similarity (descending),
     /// filtered by minimum similarity threshold.
@@ -486,6 +497,31 @@ mod tests {
         assert_eq!(agent.count_summaries().await.unwrap(), 2);
     }
 
+    #[tokio::test]
+    async fn test_total_token_savings() {
+        let db = setup_test_db().await;
+        let agent = SummarizationAgent::new(db);
+
+        for id in ["msg_start", "msg_end", "msg_start2", "msg_end2"] {
+            insert_test_message_row(&agent.db, id).await;
+        }
+
+        assert_eq!(agent.total_token_savings().await.unwrap(), 0);
+
+        agent
+            .save_summary(&create_test_summary("s1"))
+            .await
+            .unwrap();
+
+        let mut s2 = create_test_summary("s2");
+        s2.start_message_id = "msg_start2".to_string();
+        s2.end_message_id = "msg_end2".to_string();
+        s2.token_savings = 250;
+        agent.save_summary(&s2).await.unwrap();
+
+        assert_eq!(agent.total_token_savings().await.unwrap(), 750);
+    }
+
     #[tokio::test]
     async fn test_link_messages_to_summary() {
         let db = setup_test_db().await;
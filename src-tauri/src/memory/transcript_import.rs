@@ -0,0 +1,351 @@
+//! Import of conversation transcripts from other tools.
+//!
+//! Two formats are accepted:
+//! - `json`: an array of `{ "role", "content", "timestamp"? }` objects, or an
+//!   object with such an array under `messages` (the `export_conversation`
+//!   JSON output).
+//! - `markdown`: one `## Speaker` heading per message followed by its text,
+//!   as written by `export_conversation`. A heading may end with a
+//!   `(YYYY-MM-DD HH:MM UTC)` timestamp; `# ` headings are titles and a
+//!   `# Summaries` heading ends the transcript.
+//!
+//! Tool and system messages are skipped: they cannot be replayed without the
+//! tool calls that produced them.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use sqlx::{Pool, Sqlite};
+
+use super::working_memory::Message;
+
+/// Transcript formats understood by `parse_transcript`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Json,
+}
+
+impl TranscriptFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => anyhow::bail!(
+                "Unsupported transcript format '{}' (expected 'markdown' or 'json')",
+                other
+            ),
+        }
+    }
+}
+
+/// Messages parsed from a transcript, ready to be stored.
+#[derive(Debug)]
+pub struct ParsedTranscript {
+    /// User and agent messages in chronological order
+    pub messages: Vec<Message>,
+    /// Tool and system messages that were left out
+    pub skipped: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonMessage {
+    role: String,
+    content: String,
+    #[serde(default)]
+    timestamp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonTranscript {
+    List(Vec<JsonMessage>),
+    Export { messages: Vec<JsonMessage> },
+}
+
+/// Role of an imported message: `Some("user" | "agent")`, `None` for
+/// skipped roles, or an error for roles that are not understood.
+fn normalize_role(role: &str) -> Result<Option<&'static str>> {
+    match role.trim().to_lowercase().as_str() {
+        "user" | "human" => Ok(Some("user")),
+        "agent" | "assistant" | "ai" | "bot" | "model" => Ok(Some("agent")),
+        "system" | "tool" | "tool_result" | "tool result" | "function" => Ok(None),
+        other => anyhow::bail!("Unknown message role '{}'", other),
+    }
+}
+
+/// Parse a stored or exported timestamp (RFC 3339, SQLite datetime or the
+/// `YYYY-MM-DD HH:MM UTC` form of Markdown exports).
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M UTC")
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
+
+/// Parse `text` in the given format. Fails on malformed input, unknown roles
+/// or a transcript without any user or agent message.
+pub fn parse_transcript(text: &str, format: TranscriptFormat) -> Result<ParsedTranscript> {
+    let entries = match format {
+        TranscriptFormat::Json => parse_json(text)?,
+        TranscriptFormat::Markdown => parse_markdown(text)?,
+    };
+
+    let mut skipped = 0;
+    let mut kept: Vec<(&'static str, String, Option<DateTime<Utc>>)> = Vec::new();
+    for (i, (role, content, timestamp)) in entries.into_iter().enumerate() {
+        let role = normalize_role(&role).with_context(|| format!("Message {}", i + 1))?;
+        match role {
+            Some(role) if !content.trim().is_empty() => kept.push((
+                role,
+                content,
+                timestamp.as_deref().and_then(parse_timestamp),
+            )),
+            _ => skipped += 1,
+        }
+    }
+    if kept.is_empty() {
+        anyhow::bail!("Transcript contains no user or agent messages");
+    }
+
+    // Keep the original times only if every message has one; otherwise the
+    // messages are spaced one second apart, ending now. Exported times are
+    // per minute, so a message not later than the previous one is moved a
+    // millisecond past it to keep the order in history.
+    let all_timed = kept.iter().all(|(_, _, t)| t.is_some());
+    let now = Utc::now();
+    let count = kept.len() as i64;
+    let mut previous: Option<DateTime<Utc>> = None;
+    let messages = kept
+        .into_iter()
+        .enumerate()
+        .map(|(i, (role, content, timestamp))| {
+            let mut timestamp = match timestamp {
+                Some(t) if all_timed => t,
+                _ => now - chrono::Duration::seconds(count - i as i64),
+            };
+            if let Some(prev) = previous.filter(|prev| timestamp <= *prev) {
+                timestamp = prev + chrono::Duration::milliseconds(1);
+            }
+            previous = Some(timestamp);
+            Message {
+                id: uuid::Uuid::new_v4().to_string(),
+                role: role.to_string(),
+                content,
+                timestamp,
+                importance_score: None,
+                metadata: None,
+            }
+        })
+        .collect();
+
+    Ok(ParsedTranscript { messages, skipped })
+}
+
+type RawEntry = (String, String, Option<String>);
+
+fn parse_json(text: &str) -> Result<Vec<RawEntry>> {
+    let transcript: JsonTranscript = serde_json::from_str(text).context(
+        "Invalid JSON transcript: expected an array of {role, content} objects \
+         or an object with a \"messages\" array",
+    )?;
+    let messages = match transcript {
+        JsonTranscript::List(messages) | JsonTranscript::Export { messages } => messages,
+    };
+    Ok(messages
+        .into_iter()
+        .map(|m| (m.role, m.content, m.timestamp))
+        .collect())
+}
+
+fn parse_markdown(text: &str) -> Result<Vec<RawEntry>> {
+    let mut entries: Vec<RawEntry> = Vec::new();
+    let mut current: Option<(String, Option<String>, Vec<&str>)> = None;
+
+    let finish = |current: Option<(String, Option<String>, Vec<&str>)>,
+                  entries: &mut Vec<RawEntry>| {
+        if let Some((role, timestamp, lines)) = current {
+            entries.push((role, strip_code_fence(&lines.join("\n")), timestamp));
+        }
+    };
+
+    for line in text.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            finish(current.take(), &mut entries);
+            let (speaker, timestamp) = split_heading(heading);
+            current = Some((speaker_role(&speaker), timestamp, Vec::new()));
+        } else if let Some(title) = line.strip_prefix("# ") {
+            if title.trim().eq_ignore_ascii_case("summaries") {
+                break;
+            }
+        } else if let Some((_, _, lines)) = current.as_mut() {
+            lines.push(line);
+        } else if !line.trim().is_empty() {
+            anyhow::bail!(
+                "Invalid Markdown transcript: text before the first '## Speaker' heading"
+            );
+        }
+    }
+    finish(current, &mut entries);
+
+    if entries.is_empty() {
+        anyhow::bail!("Invalid Markdown transcript: no '## Speaker' headings found");
+    }
+    // A trailing `---` separates the messages from the summaries section
+    if let Some(last) = entries.last_mut() {
+        if let Some(body) = last.1.strip_suffix("---") {
+            last.1 = body.trim_end().to_string();
+        }
+    }
+    Ok(entries)
+}
+
+/// Split `Speaker (timestamp)` into the speaker and the timestamp, if any.
+fn split_heading(heading: &str) -> (String, Option<String>) {
+    let heading = heading.trim();
+    if let Some(open) = heading.rfind(" (") {
+        if let Some(inner) = heading[open + 2..].strip_suffix(')') {
+            if parse_timestamp(inner).is_some() {
+                return (heading[..open].to_string(), Some(inner.to_string()));
+            }
+        }
+    }
+    (heading.to_string(), None)
+}
+
+/// Speakers other than the user and tools are taken to be the agent (exports
+/// use the instance name as the agent's heading).
+fn speaker_role(speaker: &str) -> String {
+    match normalize_role(speaker) {
+        Ok(Some(role)) => role.to_string(),
+        Ok(None) => "tool_result".to_string(),
+        Err(_) => "agent".to_string(),
+    }
+}
+
+fn strip_code_fence(body: &str) -> String {
+    let body = body.trim();
+    body.strip_prefix("```")
+        .and_then(|b| b.strip_suffix("```"))
+        .map(|b| b.trim().to_string())
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Insert parsed messages into the `messages` table in one transaction.
+pub async fn store_messages(db: &Pool<Sqlite>, messages: &[Message]) -> Result<()> {
+    let mut tx = db.begin().await.context("Failed to start transaction")?;
+    for msg in messages {
+        sqlx::query("INSERT INTO messages (id, role, content, timestamp) VALUES (?, ?, ?, ?)")
+            .bind(&msg.id)
+            .bind(&msg.role)
+            .bind(&msg.content)
+            .bind(msg.timestamp)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store imported message")?;
+    }
+    tx.commit()
+        .await
+        .context("Failed to commit imported messages")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_import_json_transcript_stores_messages() {
+        let json = r#"[
+            {"role": "user", "content": "Hi, I'm Sam.", "timestamp": "2025-03-01T10:00:00Z"},
+            {"role": "assistant", "content": "Hello Sam!", "timestamp": "2025-03-01T10:00:05Z"},
+            {"role": "tool", "content": "{}", "timestamp": "2025-03-01T10:00:06Z"},
+            {"role": "user", "content": "I live in Berlin.", "timestamp": "2025-03-01T10:01:00Z"}
+        ]"#;
+        let parsed = parse_transcript(json, TranscriptFormat::Json).unwrap();
+        assert_eq!(parsed.messages.len(), 3);
+        assert_eq!(parsed.skipped, 1);
+
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+        store_messages(&db, &parsed.messages).await.unwrap();
+
+        let rows: Vec<(String, String, DateTime<Utc>)> =
+            sqlx::query_as("SELECT role, content, timestamp FROM messages ORDER BY timestamp")
+                .fetch_all(&db)
+                .await
+                .unwrap();
+        let stored: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|(role, content, _)| (role.as_str(), content.as_str()))
+            .collect();
+        assert_eq!(
+            stored,
+            vec![
+                ("user", "Hi, I'm Sam."),
+                ("agent", "Hello Sam!"),
+                ("user", "I live in Berlin."),
+            ]
+        );
+        assert_eq!(rows[0].2, parse_timestamp("2025-03-01T10:00:00Z").unwrap());
+    }
+
+    #[test]
+    fn test_parse_markdown_export() {
+        let markdown = "# Conversation with Ava\n\n\
+                        ## User (2025-03-01 10:00 UTC)\n\nWhat's in notes.txt?\n\n\
+                        ## Tool result (2025-03-01 10:00 UTC)\n\n```\nbuy milk\n```\n\n\
+                        ## Ava (2025-03-01 10:01 UTC)\n\nIt says:\n\nbuy milk.\n\n\
+                        ## User (2025-03-01 10:01 UTC)\n\nThanks!\n\n\
+                        ---\n\n# Summaries\n\n## 2025-03-01 12:00 UTC\n\nNotes.\n";
+        let parsed = parse_transcript(markdown, TranscriptFormat::Markdown).unwrap();
+        assert_eq!(parsed.skipped, 1);
+        assert_eq!(parsed.messages.len(), 3);
+        assert_eq!(parsed.messages[0].role, "user");
+        assert_eq!(parsed.messages[1].role, "agent");
+        assert_eq!(parsed.messages[1].content, "It says:\n\nbuy milk.");
+        assert_eq!(
+            parsed.messages[1].timestamp,
+            parse_timestamp("2025-03-01T10:01:00Z").unwrap()
+        );
+        assert_eq!(
+            parsed.messages[2].timestamp,
+            parse_timestamp("2025-03-01T10:01:00.001Z").unwrap()
+        );
+        assert!(parsed
+            .messages
+            .windows(2)
+            .all(|pair| pair[0].timestamp < pair[1].timestamp));
+    }
+
+    #[test]
+    fn test_malformed_transcripts_are_rejected() {
+        let err = parse_transcript("{\"role\": \"user\"}", TranscriptFormat::Json).unwrap_err();
+        assert!(err.to_string().contains("Invalid JSON transcript"));
+
+        let err = parse_transcript(
+            r#"[{"role": "narrator", "content": "Once upon a time"}]"#,
+            TranscriptFormat::Json,
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("Unknown message role 'narrator'"));
+
+        let err = parse_transcript("just some text", TranscriptFormat::Markdown).unwrap_err();
+        assert!(err.to_string().contains("Invalid Markdown transcript"));
+
+        let err = parse_transcript(
+            r#"[{"role": "tool", "content": "x"}]"#,
+            TranscriptFormat::Json,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no user or agent messages"));
+
+        assert!(TranscriptFormat::parse("csv").is_err());
+    }
+}
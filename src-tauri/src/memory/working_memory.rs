@@ -141,25 +141,27 @@ impl WorkingMemory {
     }
 
     /// Load messages from database into working memory (e.g., on agent initialization)
-    /// Respects token budget - only loads as many messages as fit within the budget
+    /// Respects token budget - keeps the newest messages that fit within the budget
+    /// (messages are expected in chronological order)
     pub fn load_from_messages(&mut self, messages: Vec<Message>) {
         self.clear();
+        let total = messages.len();
 
-        // Load messages in order, respecting token budget
-        for msg in messages {
+        // Walk from the newest message backwards, respecting token budget
+        for msg in messages.into_iter().rev() {
             let msg_tokens = Self::estimate_tokens(&msg);
 
             // Stop loading if adding this message would exceed budget
             if self.current_tokens + msg_tokens > self.max_tokens {
                 tracing::warn!(
-                    "Working memory budget reached during load. Loaded {}/{} messages",
+                    "Working memory budget reached during load. Loaded the newest {}/{} messages",
                     self.messages.len(),
-                    self.messages.len() + 1
+                    total
                 );
                 break;
             }
 
-            self.messages.push_back(msg);
+            self.messages.push_front(msg);
             self.current_tokens += msg_tokens;
         }
 
@@ -430,6 +432,26 @@ mod tests {
         assert!(wm.current_tokens() <= wm.max_tokens());
     }
 
+    #[test]
+    fn test_load_from_messages_over_budget_keeps_newest() {
+        let mut wm = WorkingMemory::new(30);
+        let messages: Vec<Message> = (0..10)
+            .map(|i| create_test_message(&format!("Message number {} with some padding", i)))
+            .collect();
+
+        wm.load_from_messages(messages);
+
+        let context = wm.get_context();
+        assert!(!context.is_empty() && context.len() < 10);
+        assert!(wm.current_tokens() <= wm.max_tokens());
+        // Newest message kept, chronological order preserved
+        assert_eq!(
+            context.last().unwrap().content,
+            "Message number 9 with some padding"
+        );
+        assert!(context.windows(2).all(|w| w[0].content < w[1].content));
+    }
+
     #[test]
     fn test_load_from_messages_clears_existing() {
         let mut wm = WorkingMemory::new(10000);
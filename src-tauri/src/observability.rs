@@ -4,19 +4,40 @@
 //! When Langfuse credentials are configured in the OS keychain, an OpenTelemetry
 //! pipeline is set up that exports traces to Langfuse alongside the standard
 //! fmt (console) layer. Without credentials, only console logging is active.
+//!
+//! In both cases, logs are also written as JSON lines to daily-rotated files in
+//! ~/.ownai/logs, so they survive in packaged builds without a console.
 
 use crate::ai_instances::LangfuseKeyStorage;
 use opentelemetry::trace::TracerProvider;
 use opentelemetry::KeyValue;
 use opentelemetry_sdk::trace::span_processor_with_async_runtime::BatchSpanProcessor;
 use opentelemetry_sdk::{resource::Resource, runtime::Tokio, trace::SdkTracerProvider};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 /// Dedicated Tokio runtime for OpenTelemetry's BatchSpanProcessor.
 /// Lives for the entire app lifetime so the background export task keeps running.
 static OTEL_RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
 
+/// Guard of the non-blocking log file writer. Kept for the app lifetime so
+/// buffered lines are flushed by the background writer thread.
+static LOG_FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Subdirectory of the app data dir holding the log files
+const LOG_DIR_NAME: &str = "logs";
+
+/// Log files are named `ownai.<date>.log`
+const LOG_FILE_PREFIX: &str = "ownai";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Number of daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
 /// Initialize the tracing subscriber.
 ///
 /// If Langfuse credentials are found in the keychain, sets up an OpenTelemetry
@@ -25,6 +46,7 @@ static OTEL_RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
 ///
 /// Log level defaults to INFO (showing INFO, WARN, ERROR). Override with the
 /// `RUST_LOG` environment variable, e.g. `RUST_LOG=debug` for more detail.
+/// The same filter applies to the log file (see `log_dir`).
 pub fn init_tracing() {
     // Check if Langfuse is configured
     let public_key = LangfuseKeyStorage::load_public_key().ok().flatten();
@@ -54,11 +76,64 @@ fn init_fmt_only() {
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
     tracing_subscriber::registry()
-        .with(filter)
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_filter(filter))
+        .with(file_layer())
         .init();
 }
 
+/// Create (if needed) and return the log directory under `app_dir`.
+pub fn ensure_log_dir(app_dir: &Path) -> std::io::Result<PathBuf> {
+    let path = app_dir.join(LOG_DIR_NAME);
+    std::fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// The directory containing the log files (~/.ownai/logs)
+pub fn log_dir() -> anyhow::Result<PathBuf> {
+    Ok(ensure_log_dir(&crate::utils::paths::get_app_dir()?)?)
+}
+
+/// Build a daily-rotating appender writing to `dir`.
+fn rolling_appender(dir: &Path) -> Result<RollingFileAppender, Box<dyn std::error::Error>> {
+    Ok(RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)?)
+}
+
+/// JSON file logging layer with EnvFilter (default: info).
+/// Returns `None` (console logging only) if the log directory is unusable.
+fn file_layer<S>() -> Option<impl Layer<S>>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    let appender = log_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| rolling_appender(&dir).map_err(|e| e.to_string()));
+    let appender = match appender {
+        Ok(appender) => appender,
+        Err(e) => {
+            eprintln!("Failed to initialize file logging, using console only: {e}");
+            return None;
+        }
+    };
+
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    // init_tracing runs once; if it did not, the first writer keeps its guard
+    let _ = LOG_FILE_GUARD.set(guard);
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    Some(
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_ansi(false)
+            .with_writer(writer)
+            .with_filter(filter),
+    )
+}
+
 /// Set up the full OpenTelemetry + Langfuse tracing pipeline.
 fn setup_langfuse_tracing(
     public_key: &str,
@@ -102,11 +177,13 @@ fn setup_langfuse_tracing(
     // Per-layer filtering:
     // - OTel layer: No filter (all spans go to Langfuse)
     // - fmt layer: INFO+ only on console (overridable via RUST_LOG)
+    // - file layer: same level, written to ~/.ownai/logs
     let fmt_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
     tracing_subscriber::registry()
         .with(otel_layer)
         .with(tracing_subscriber::fmt::layer().with_filter(fmt_filter))
+        .with(file_layer())
         .init();
 
     Ok(())
@@ -151,3 +228,41 @@ pub fn langfuse_context(
 
     Some(ctx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_log_dir_created_under_app_dir() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let dir = ensure_log_dir(temp_dir.path()).unwrap();
+
+        assert_eq!(dir, temp_dir.path().join("logs"));
+        assert!(dir.is_dir());
+        // Idempotent
+        assert_eq!(ensure_log_dir(temp_dir.path()).unwrap(), dir);
+    }
+
+    #[test]
+    fn test_rolling_appender_writes_into_log_dir() {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = ensure_log_dir(temp_dir.path()).unwrap();
+
+        let mut appender = rolling_appender(&dir).unwrap();
+        appender.write_all(b"{\"message\":\"hello\"}\n").unwrap();
+        appender.flush().unwrap();
+
+        let files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].starts_with("ownai."));
+        assert!(files[0].ends_with(".log"));
+    }
+}
@@ -7,7 +7,7 @@
 //! ## Module Structure
 //!
 //! - `mod.rs` - Core types (`ScheduledTask`, `Scheduler`, `SharedScheduler`)
-//! - `storage.rs` - Database CRUD operations for scheduled tasks
+//! - `storage.rs` - Database CRUD operations for scheduled tasks and their run history
 //! - `runner.rs` - Task execution logic (temporary agent creation)
 //! - `tools.rs` - rig Tools for the agent to manage scheduled tasks
 
@@ -39,13 +39,33 @@ pub struct ScheduledTask {
     pub enabled: bool,
     /// Whether to send OS notifications and show results in the chat on completion.
     /// When false, results are still saved in last_result and as messages in the DB,
-    /// but no notification or frontend event is emitted.
+    /// but no notification or frontend event is emitted (failed runs still
+    /// notify when `notify_on_failure` is set).
     pub notify: bool,
+    /// Whether a failed run sends an OS notification even when `notify` is false.
+    #[serde(default = "default_notify_on_failure")]
+    pub notify_on_failure: bool,
     pub last_run: Option<DateTime<Utc>>,
     pub last_result: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+fn default_notify_on_failure() -> bool {
+    true
+}
+
+/// One recorded execution of a scheduled task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTaskRun {
+    pub id: String,
+    pub task_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub success: bool,
+    /// Task result (truncated) or error message
+    pub result: String,
+    pub duration_ms: i64,
+}
+
 /// The scheduler manages cron jobs for all AI instances.
 ///
 /// It wraps `tokio-cron-scheduler`'s `JobScheduler` and maintains a mapping
@@ -80,10 +100,10 @@ impl Scheduler {
         Ok(())
     }
 
-    /// Register a task as a cron job.
+    /// Register a task as a cron job, replacing any job already registered
+    /// for the same task (e.g. after its schedule or prompt was edited).
     ///
     /// The `on_fire` callback is called each time the cron expression triggers.
-    /// Returns the internal job UUID.
     pub async fn add_job(
         &mut self,
         task_id: &str,
@@ -99,6 +119,9 @@ impl Scheduler {
         let job = tokio_cron_scheduler::Job::new_async(cron_expression, on_fire)
             .map_err(|e| anyhow::anyhow!("Invalid cron expression '{}': {}", cron_expression, e))?;
 
+        // Only drop the old job once the new cron expression has parsed
+        self.remove_job(task_id).await?;
+
         let job_uuid = job.guid();
         self.job_scheduler
             .add(job)
@@ -142,6 +165,11 @@ impl Scheduler {
         self.job_ids.contains_key(task_id)
     }
 
+    /// Job UUID registered for a task, if any.
+    pub fn job_uuid(&self, task_id: &str) -> Option<uuid::Uuid> {
+        self.job_ids.get(task_id).copied()
+    }
+
     /// Return the number of registered jobs.
     pub fn job_count(&self) -> usize {
         self.job_ids.len()
@@ -196,6 +224,7 @@ mod tests {
             task_prompt: "Remind me to check emails".to_string(),
             enabled: true,
             notify: true,
+            notify_on_failure: true,
             last_run: None,
             last_result: None,
             created_at: Utc::now(),
@@ -214,6 +243,42 @@ mod tests {
         assert_eq!(scheduler.job_count(), 0);
     }
 
+    fn noop_job() -> impl FnMut(
+        uuid::Uuid,
+        JobScheduler,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>
+           + Send
+           + Sync
+           + 'static {
+        |_uuid, _scheduler| Box::pin(async {})
+    }
+
+    #[tokio::test]
+    async fn test_add_job_replaces_existing_job() {
+        let mut scheduler = Scheduler::new().await.unwrap();
+        scheduler
+            .add_job("task-1", "0 0 8 * * *", noop_job())
+            .await
+            .unwrap();
+        let old_uuid = scheduler.job_uuid("task-1").unwrap();
+
+        // Changing the cron removes the old job and adds a new one
+        scheduler
+            .add_job("task-1", "0 0 9 * * 1", noop_job())
+            .await
+            .unwrap();
+        let new_uuid = scheduler.job_uuid("task-1").unwrap();
+        assert_ne!(old_uuid, new_uuid);
+        assert_eq!(scheduler.job_count(), 1);
+
+        // An invalid cron leaves the current job in place
+        assert!(scheduler
+            .add_job("task-1", "not a cron", noop_job())
+            .await
+            .is_err());
+        assert_eq!(scheduler.job_uuid("task-1"), Some(new_uuid));
+    }
+
     #[tokio::test]
     async fn test_scheduler_has_job() {
         // Test the HashMap logic for job tracking
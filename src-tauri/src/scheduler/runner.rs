@@ -1,23 +1,29 @@
 //! Task execution logic for scheduled tasks.
 //!
 //! When a cron job fires, this module creates a temporary agent (similar to
-//! sub-agents) that executes the task prompt and returns the result.
+//! sub-agents) that streams the task prompt and returns the result, reporting
+//! progress along the way.
 
 use anyhow::Result;
+use futures::{Stream, StreamExt};
+use rig::agent::MultiTurnStreamItem;
 use rig::client::{CompletionClient, Nothing};
-use rig::completion::Prompt;
-use rig::providers::{anthropic, ollama, openai};
+use rig::providers::{anthropic, ollama};
+use rig::streaming::{StreamedAssistantContent, StreamingPrompt};
+use serde::Serialize;
 use sqlx::{Pool, Sqlite};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock, RwLock};
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+use crate::agent::{openai_client, OutputLimit};
 use crate::ai_instances::{AIInstance, AIInstanceManager, APIKeyStorage, LLMProvider};
 use crate::database::{get_or_init_db, DbCache};
 use crate::memory::{LongTermMemory, SharedLongTermMemory};
+use crate::tools::read_only::READ_ONLY_PROMPT_NOTE;
 use crate::tools::registry::RhaiToolRegistry;
 use crate::tools::rhai_bridge_tool::SharedRegistry;
 use crate::tools::subagents::{base_tools_prompt, build_sub_agent_tools};
@@ -29,6 +35,81 @@ use super::SharedScheduler;
 /// Maximum number of multi-turn iterations for scheduled task agents.
 const TASK_AGENT_MAX_TURNS: usize = 25;
 
+/// Default cap on scheduled tasks executing at the same time. Tasks that fire
+/// while the cap is reached wait for a running task to finish.
+pub const DEFAULT_MAX_CONCURRENT_TASKS: usize = 2;
+
+/// Global limiter shared by all scheduled task jobs.
+static TASK_LIMITER: LazyLock<TaskLimiter> =
+    LazyLock::new(|| TaskLimiter::new(DEFAULT_MAX_CONCURRENT_TASKS));
+
+/// Caps concurrent task executions with a semaphore.
+struct TaskLimiter {
+    semaphore: RwLock<Arc<Semaphore>>,
+}
+
+impl TaskLimiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            semaphore: RwLock::new(Arc::new(Semaphore::new(limit.max(1)))),
+        }
+    }
+
+    /// Swap in a semaphore with the new limit. Runs holding a permit from the
+    /// old semaphore finish normally; later runs queue on the new one.
+    fn set_limit(&self, limit: usize) {
+        let mut semaphore = self.semaphore.write().unwrap_or_else(|e| e.into_inner());
+        *semaphore = Arc::new(Semaphore::new(limit.max(1)));
+    }
+
+    /// Wait for a free execution slot.
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .semaphore
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("task semaphore is never closed")
+    }
+}
+
+/// Set the maximum number of scheduled tasks that may run at once (min 1).
+pub fn set_max_concurrent_tasks(limit: usize) {
+    TASK_LIMITER.set_limit(limit);
+    tracing::info!("Scheduled task concurrency limit set to {}", limit.max(1));
+}
+
+/// Incremental progress of a running task, sent as `scheduler:task_progress`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskProgress {
+    /// A chunk of streamed response text
+    Text { text: String },
+    /// The agent started a tool call
+    ToolCall { name: String },
+}
+
+/// Wrap a progress sink so it only receives events when the task notifies.
+fn gated_progress(
+    notify: bool,
+    mut sink: impl FnMut(TaskProgress) + Send,
+) -> impl FnMut(TaskProgress) + Send {
+    move |progress| {
+        if notify {
+            sink(progress)
+        }
+    }
+}
+
+/// Whether a finished run should notify the user (OS notification and frontend
+/// event). Failures notify unless the task opted out via `notify_on_failure`.
+fn should_notify(success: bool, notify: bool, notify_on_failure: bool) -> bool {
+    notify || (!success && notify_on_failure)
+}
+
 /// Register a scheduled task as a cron job in the scheduler.
 ///
 /// The job closure captures all necessary context to create a temporary agent
@@ -42,6 +123,7 @@ pub async fn register_task_job(
     task_prompt: String,
     instance_id: String,
     notify: bool,
+    notify_on_failure: bool,
     manager: Arc<Mutex<AIInstanceManager>>,
     app_handle: AppHandle,
 ) -> Result<()> {
@@ -67,7 +149,33 @@ pub async fn register_task_job(
                     instance_id
                 );
 
-                match execute_task(&instance_id, &task_prompt, &manager, &app_handle).await {
+                // Queue behind other running tasks when the concurrency cap is reached
+                let _permit = TASK_LIMITER.acquire().await;
+
+                let mut on_progress = gated_progress(notify, |progress| {
+                    let payload = serde_json::json!({
+                        "task_id": task_id,
+                        "task_name": task_name,
+                        "instance_id": instance_id,
+                        "progress": progress,
+                    });
+                    if let Err(e) = app_handle.emit("scheduler:task_progress", payload) {
+                        tracing::warn!("Failed to emit task_progress event: {}", e);
+                    }
+                });
+                let started = std::time::Instant::now();
+                let outcome = execute_task(
+                    &instance_id,
+                    &task_prompt,
+                    &manager,
+                    &app_handle,
+                    &mut on_progress,
+                )
+                .await;
+                drop(on_progress);
+                let duration_ms = started.elapsed().as_millis() as i64;
+
+                match outcome {
                     Ok(result) => {
                         tracing::info!(
                             "Scheduled task '{}' completed (result length: {} chars)",
@@ -88,13 +196,24 @@ pub async fn register_task_job(
                             {
                                 tracing::warn!("Failed to update task last_run: {}", e);
                             }
+                            if let Err(e) = storage::record_task_run(
+                                &db,
+                                &task_id,
+                                true,
+                                &truncated,
+                                duration_ms,
+                            )
+                            .await
+                            {
+                                tracing::warn!("Failed to record task run: {}", e);
+                            }
 
                             // Save result as agent message in chat history
                             // (the LLM generated this response, so it appears as the AI speaking)
                             save_task_result_as_message(&db, "agent", &result).await;
                         }
 
-                        if notify {
+                        if should_notify(true, notify, notify_on_failure) {
                             // Send OS notification
                             send_task_notification(&app_handle, &task_name, &result, true);
 
@@ -118,6 +237,17 @@ pub async fn register_task_job(
                             // Update last_run with error
                             let error_msg = format!("Error: {}", e);
                             let _ = storage::update_task_last_run(&db, &task_id, &error_msg).await;
+                            if let Err(e) = storage::record_task_run(
+                                &db,
+                                &task_id,
+                                false,
+                                &error_msg,
+                                duration_ms,
+                            )
+                            .await
+                            {
+                                tracing::warn!("Failed to record task run: {}", e);
+                            }
 
                             // Save error as system message in chat history
                             let message_content =
@@ -125,7 +255,7 @@ pub async fn register_task_job(
                             save_task_result_as_message(&db, "system", &message_content).await;
                         }
 
-                        if notify {
+                        if should_notify(false, notify, notify_on_failure) {
                             // Send OS notification for failure
                             send_task_notification(&app_handle, &task_name, &e.to_string(), false);
 
@@ -216,6 +346,7 @@ async fn execute_task(
     task_prompt: &str,
     manager: &Arc<Mutex<AIInstanceManager>>,
     app_handle: &AppHandle,
+    on_progress: &mut (dyn FnMut(TaskProgress) + Send),
 ) -> Result<String> {
     // 1. Get instance configuration
     let instance = {
@@ -227,9 +358,9 @@ async fn execute_task(
 
     // 2. Load API key
     let api_key = if instance.provider.needs_api_key() {
-        APIKeyStorage::load(&instance.provider)?.ok_or_else(|| {
-            anyhow::anyhow!("API key not found for provider: {}", instance.provider)
-        })?
+        APIKeyStorage::load(&instance.provider, instance.api_base_url.as_deref())?.ok_or_else(
+            || anyhow::anyhow!("API key not found for provider: {}", instance.provider),
+        )?
     } else {
         String::new()
     };
@@ -240,34 +371,44 @@ async fn execute_task(
 
     // 4. Build tools (same as sub-agents, without delegate_task)
     let workspace =
-        paths::get_instance_workspace_path(instance_id).unwrap_or_else(|_| PathBuf::from("."));
+        paths::get_instance_workspace_path(instance_id, instance.workspace_override.as_deref())
+            .unwrap_or_else(|_| PathBuf::from("."));
     let programs_root = paths::get_instance_programs_path(instance_id)
         .unwrap_or_else(|_| PathBuf::from("./programs"));
 
+    let long_term_memory = LongTermMemory::new(db.clone()).await?;
+    let shared_ltm: SharedLongTermMemory = Arc::new(tokio::sync::Mutex::new(long_term_memory));
+
     let rhai_registry = RhaiToolRegistry::new(
         db.clone(),
-        workspace,
+        workspace.clone(),
         Some(app_handle.clone()),
         Some(instance.name.clone()),
-    );
+    )
+    .with_instance_id(instance_id)
+    .with_embedder(LongTermMemory::sandbox_embedder(shared_ltm.clone()));
     let available_dynamic_tools = rhai_registry.tool_summary().await.unwrap_or_default();
     let registry: SharedRegistry = Arc::new(tokio::sync::RwLock::new(rhai_registry));
 
-    let long_term_memory = LongTermMemory::new(db.clone()).await?;
-    let shared_ltm: SharedLongTermMemory = Arc::new(tokio::sync::Mutex::new(long_term_memory));
-
+    // Scheduled task agents run without delegation
+    let output_limit = OutputLimit::for_instance(&instance, &workspace);
     let tools = build_sub_agent_tools(
+        0,
+        None,
         instance_id,
         registry,
         available_dynamic_tools,
         db,
+        workspace,
         programs_root,
         shared_ltm,
         Some(app_handle.clone()),
+        instance.read_only,
+        output_limit,
     );
 
     // 5. Build system prompt for scheduled task agent
-    let system_prompt = format!(
+    let mut system_prompt = format!(
         "You are a scheduled task agent for '{}'. \
          You are running autonomously as part of a recurring scheduled task. \
          Complete the task described below using the tools available to you. \
@@ -275,6 +416,9 @@ async fn execute_task(
         instance.name,
         base_tools_prompt()
     );
+    if instance.read_only {
+        system_prompt = format!("{}\n\n{}", system_prompt, READ_ONLY_PROMPT_NOTE);
+    }
 
     // 6. Create and run provider-specific agent with Langfuse tracing
     let task_span = tracing::info_span!(
@@ -291,9 +435,16 @@ async fn execute_task(
     task_span.set_attribute("gen_ai.prompt.0.role", "user");
     task_span.set_attribute("gen_ai.prompt.0.content", task_prompt.to_string());
 
-    let result = run_task_agent(&instance, &api_key, &system_prompt, task_prompt, tools)
-        .instrument(task_span.clone())
-        .await?;
+    let result = run_task_agent(
+        &instance,
+        &api_key,
+        &system_prompt,
+        task_prompt,
+        tools,
+        on_progress,
+    )
+    .instrument(task_span.clone())
+    .await?;
 
     task_span.set_attribute("gen_ai.completion.0.role", "assistant");
     task_span.set_attribute("gen_ai.completion.0.content", result.clone());
@@ -301,13 +452,14 @@ async fn execute_task(
     Ok(result)
 }
 
-/// Create a temporary rig agent and run the task prompt.
+/// Create a temporary rig agent and stream the task prompt.
 async fn run_task_agent(
     instance: &AIInstance,
     api_key: &str,
     system_prompt: &str,
     task_prompt: &str,
     tools: Vec<Box<dyn rig::tool::ToolDyn>>,
+    on_progress: &mut (dyn FnMut(TaskProgress) + Send),
 ) -> Result<String> {
     match instance.provider {
         LLMProvider::Anthropic => {
@@ -320,14 +472,18 @@ async fn run_task_agent(
                 .temperature(0.7)
                 .tools(tools)
                 .build();
-            let result = agent
-                .prompt(task_prompt)
-                .max_turns(TASK_AGENT_MAX_TURNS)
-                .await?;
-            Ok(result)
+            let stream = agent
+                .stream_prompt(task_prompt)
+                .multi_turn(TASK_AGENT_MAX_TURNS)
+                .await;
+            consume_task_stream(stream, on_progress).await
         }
-        LLMProvider::OpenAI => {
-            let client: openai::Client = openai::Client::builder().api_key(api_key).build()?;
+        LLMProvider::OpenAI | LLMProvider::OpenAICompatible => {
+            let client = openai_client(
+                &instance.provider,
+                api_key,
+                instance.api_base_url.as_deref(),
+            )?;
             let agent = client
                 .completions_api()
                 .agent(&instance.model)
@@ -335,11 +491,11 @@ async fn run_task_agent(
                 .temperature(0.7)
                 .tools(tools)
                 .build();
-            let result = agent
-                .prompt(task_prompt)
-                .max_turns(TASK_AGENT_MAX_TURNS)
-                .await?;
-            Ok(result)
+            let stream = agent
+                .stream_prompt(task_prompt)
+                .multi_turn(TASK_AGENT_MAX_TURNS)
+                .await;
+            consume_task_stream(stream, on_progress).await
         }
         LLMProvider::Ollama => {
             let ollama_client: ollama::Client = if let Some(url) = &instance.api_base_url {
@@ -355,13 +511,57 @@ async fn run_task_agent(
                 .preamble(system_prompt)
                 .tools(tools)
                 .build();
-            let result = agent
-                .prompt(task_prompt)
-                .max_turns(TASK_AGENT_MAX_TURNS)
-                .await?;
-            Ok(result)
+            let stream = agent
+                .stream_prompt(task_prompt)
+                .multi_turn(TASK_AGENT_MAX_TURNS)
+                .await;
+            consume_task_stream(stream, on_progress).await
+        }
+    }
+}
+
+/// Drive a task agent's stream to completion, forwarding progress events.
+/// Returns the text of the final turn (earlier turns only led up to tool calls).
+async fn consume_task_stream<R, E>(
+    mut stream: impl Stream<Item = Result<MultiTurnStreamItem<R>, E>> + Unpin,
+    on_progress: &mut (dyn FnMut(TaskProgress) + Send),
+) -> Result<String>
+where
+    E: std::fmt::Display,
+{
+    let mut turn_text = String::new();
+    let mut turn_has_tool_calls = false;
+    let mut final_text: Option<String> = None;
+
+    while let Some(item) = stream.next().await {
+        match item.map_err(|e| anyhow::anyhow!("Streaming error: {}", e))? {
+            MultiTurnStreamItem::StreamAssistantItem(content) => match content {
+                StreamedAssistantContent::Text(text) => {
+                    on_progress(TaskProgress::Text {
+                        text: text.text.clone(),
+                    });
+                    turn_text.push_str(&text.text);
+                }
+                StreamedAssistantContent::ToolCall { tool_call, .. } => {
+                    turn_has_tool_calls = true;
+                    on_progress(TaskProgress::ToolCall {
+                        name: tool_call.function.name.clone(),
+                    });
+                }
+                StreamedAssistantContent::Final(_) if turn_has_tool_calls => {
+                    turn_text.clear();
+                    turn_has_tool_calls = false;
+                }
+                _ => {}
+            },
+            MultiTurnStreamItem::FinalResponse(res) if !res.response().is_empty() => {
+                final_text = Some(res.response().to_string());
+            }
+            _ => {}
         }
     }
+
+    Ok(final_text.unwrap_or(turn_text))
 }
 
 /// Load all enabled tasks for an instance and register them with the scheduler.
@@ -388,6 +588,7 @@ pub async fn load_and_register_instance_tasks(
             task.task_prompt.clone(),
             task.instance_id.clone(),
             task.notify,
+            task.notify_on_failure,
             manager.clone(),
             app_handle.clone(),
         )
@@ -414,3 +615,140 @@ pub async fn load_and_register_instance_tasks(
 
     Ok(registered)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::message::{ToolCall, ToolFunction};
+
+    type FakeItem = Result<MultiTurnStreamItem<()>, String>;
+
+    fn fake_task_stream() -> impl Stream<Item = FakeItem> + Unpin {
+        futures::stream::iter(vec![
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("Checking. "),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::ToolCall {
+                    tool_call: ToolCall::new(
+                        "call-1".to_string(),
+                        ToolFunction {
+                            name: "web_fetch".to_string(),
+                            arguments: serde_json::json!({}),
+                        },
+                    ),
+                    internal_call_id: "internal-1".to_string(),
+                },
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::Final(()),
+            )),
+            Ok(MultiTurnStreamItem::StreamAssistantItem(
+                StreamedAssistantContent::text("All done."),
+            )),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_progress_emitted_when_notify_enabled() {
+        let mut events = Vec::new();
+        let mut on_progress = gated_progress(true, |p| events.push(p));
+        let result = consume_task_stream(fake_task_stream(), &mut on_progress)
+            .await
+            .unwrap();
+        drop(on_progress);
+
+        assert_eq!(result, "All done.");
+        assert_eq!(
+            events,
+            vec![
+                TaskProgress::Text {
+                    text: "Checking. ".to_string()
+                },
+                TaskProgress::ToolCall {
+                    name: "web_fetch".to_string()
+                },
+                TaskProgress::Text {
+                    text: "All done.".to_string()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progress_suppressed_when_notify_disabled() {
+        let mut events = Vec::new();
+        let mut on_progress = gated_progress(false, |p| events.push(p));
+        let result = consume_task_stream(fake_task_stream(), &mut on_progress)
+            .await
+            .unwrap();
+        drop(on_progress);
+
+        assert_eq!(result, "All done.");
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_should_notify() {
+        // Successful runs follow `notify`
+        assert!(!should_notify(true, false, true));
+        assert!(should_notify(true, true, false));
+        // Failures notify even when `notify` is off, unless opted out
+        assert!(should_notify(false, false, true));
+        assert!(!should_notify(false, false, false));
+        assert!(should_notify(false, true, false));
+    }
+
+    #[tokio::test]
+    async fn test_task_limiter_runs_tasks_sequentially() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let limiter = Arc::new(TaskLimiter::new(1));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let (limiter, running, max_running, finished) = (
+                    limiter.clone(),
+                    running.clone(),
+                    max_running.clone(),
+                    finished.clone(),
+                );
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await;
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    finished.lock().unwrap().push(i);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+        assert_eq!(finished.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_task_limiter_set_limit() {
+        let limiter = TaskLimiter::new(1);
+        let _first = limiter.acquire().await;
+        limiter.set_limit(2);
+        // A new slot is available despite the first permit still being held
+        let second =
+            tokio::time::timeout(std::time::Duration::from_secs(1), limiter.acquire()).await;
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_fails_task() {
+        let stream = futures::stream::iter(vec![FakeItem::Err("overloaded".to_string())]);
+        let err = consume_task_stream(stream, &mut |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+    }
+}
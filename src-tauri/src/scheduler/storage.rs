@@ -4,14 +4,14 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use sqlx::{Pool, Row, Sqlite};
 
-use super::ScheduledTask;
+use super::{ScheduledTask, ScheduledTaskRun};
 
 /// Load all scheduled tasks for an instance from the database.
 pub async fn load_tasks(db: &Pool<Sqlite>, instance_id: &str) -> Result<Vec<ScheduledTask>> {
     let rows = sqlx::query(
         r#"
         SELECT id, instance_id, name, cron_expression, task_prompt,
-               enabled, notify, last_run, last_result, created_at
+               enabled, notify, notify_on_failure, last_run, last_result, created_at
         FROM scheduled_tasks
         WHERE instance_id = ?
         ORDER BY created_at ASC
@@ -32,6 +32,7 @@ pub async fn load_tasks(db: &Pool<Sqlite>, instance_id: &str) -> Result<Vec<Sche
             task_prompt: row.get("task_prompt"),
             enabled: row.get::<i32, _>("enabled") != 0,
             notify: row.get::<i32, _>("notify") != 0,
+            notify_on_failure: row.get::<i32, _>("notify_on_failure") != 0,
             last_run: row.get("last_run"),
             last_result: row.get("last_result"),
             created_at: row.get("created_at"),
@@ -46,8 +47,9 @@ pub async fn save_task(db: &Pool<Sqlite>, task: &ScheduledTask) -> Result<()> {
     sqlx::query(
         r#"
         INSERT INTO scheduled_tasks
-            (id, instance_id, name, cron_expression, task_prompt, enabled, notify, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            (id, instance_id, name, cron_expression, task_prompt, enabled, notify,
+             notify_on_failure, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&task.id)
@@ -57,6 +59,7 @@ pub async fn save_task(db: &Pool<Sqlite>, task: &ScheduledTask) -> Result<()> {
     .bind(&task.task_prompt)
     .bind(task.enabled as i32)
     .bind(task.notify as i32)
+    .bind(task.notify_on_failure as i32)
     .bind(task.created_at)
     .execute(db)
     .await
@@ -82,6 +85,34 @@ pub async fn delete_task(db: &Pool<Sqlite>, task_id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Update the editable fields of a task (name, schedule, prompt, notification
+/// settings). Run state and creation time are left untouched.
+pub async fn update_task(db: &Pool<Sqlite>, task: &ScheduledTask) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE scheduled_tasks
+        SET name = ?, cron_expression = ?, task_prompt = ?, notify = ?, notify_on_failure = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&task.name)
+    .bind(&task.cron_expression)
+    .bind(&task.task_prompt)
+    .bind(task.notify as i32)
+    .bind(task.notify_on_failure as i32)
+    .bind(&task.id)
+    .execute(db)
+    .await
+    .context("Failed to update scheduled task")?;
+
+    if result.rows_affected() == 0 {
+        anyhow::bail!("Scheduled task not found: {}", task.id);
+    }
+
+    tracing::info!("Updated scheduled task '{}' ({})", task.name, task.id);
+    Ok(())
+}
+
 /// Update the last_run timestamp and last_result for a task.
 pub async fn update_task_last_run(db: &Pool<Sqlite>, task_id: &str, result: &str) -> Result<()> {
     sqlx::query(
@@ -101,6 +132,69 @@ pub async fn update_task_last_run(db: &Pool<Sqlite>, task_id: &str, result: &str
     Ok(())
 }
 
+/// Record one execution of a task in its run history.
+pub async fn record_task_run(
+    db: &Pool<Sqlite>,
+    task_id: &str,
+    success: bool,
+    result: &str,
+    duration_ms: i64,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO scheduled_task_runs (id, task_id, timestamp, success, result, duration_ms)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(task_id)
+    .bind(Utc::now())
+    .bind(success as i32)
+    .bind(result)
+    .bind(duration_ms)
+    .execute(db)
+    .await
+    .context("Failed to record task run")?;
+
+    Ok(())
+}
+
+/// Load the most recent runs of a task, newest first.
+pub async fn load_task_runs(
+    db: &Pool<Sqlite>,
+    task_id: &str,
+    limit: u32,
+) -> Result<Vec<ScheduledTaskRun>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, task_id, timestamp, success, result, duration_ms
+        FROM scheduled_task_runs
+        WHERE task_id = ?
+        ORDER BY timestamp DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(task_id)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await
+    .context("Failed to load task runs")?;
+
+    let runs = rows
+        .into_iter()
+        .map(|row| ScheduledTaskRun {
+            id: row.get("id"),
+            task_id: row.get("task_id"),
+            timestamp: row.get("timestamp"),
+            success: row.get::<i32, _>("success") != 0,
+            result: row.get("result"),
+            duration_ms: row.get("duration_ms"),
+        })
+        .collect();
+
+    Ok(runs)
+}
+
 /// Enable or disable a scheduled task.
 pub async fn set_task_enabled(db: &Pool<Sqlite>, task_id: &str, enabled: bool) -> Result<()> {
     let result = sqlx::query("UPDATE scheduled_tasks SET enabled = ? WHERE id = ?")
@@ -127,7 +221,7 @@ pub async fn get_task(db: &Pool<Sqlite>, task_id: &str) -> Result<Option<Schedul
     let row = sqlx::query(
         r#"
         SELECT id, instance_id, name, cron_expression, task_prompt,
-               enabled, notify, last_run, last_result, created_at
+               enabled, notify, notify_on_failure, last_run, last_result, created_at
         FROM scheduled_tasks
         WHERE id = ?
         "#,
@@ -145,6 +239,7 @@ pub async fn get_task(db: &Pool<Sqlite>, task_id: &str) -> Result<Option<Schedul
         task_prompt: row.get("task_prompt"),
         enabled: row.get::<i32, _>("enabled") != 0,
         notify: row.get::<i32, _>("notify") != 0,
+        notify_on_failure: row.get::<i32, _>("notify_on_failure") != 0,
         last_run: row.get("last_run"),
         last_result: row.get("last_result"),
         created_at: row.get("created_at"),
@@ -177,6 +272,7 @@ mod tests {
             task_prompt: "Do something".to_string(),
             enabled: true,
             notify: true,
+            notify_on_failure: true,
             last_run: None,
             last_result: None,
             created_at: Utc::now(),
@@ -245,6 +341,30 @@ mod tests {
         assert_eq!(task.last_result.unwrap(), "Success: done");
     }
 
+    #[tokio::test]
+    async fn test_update_task() {
+        let db = setup_test_db().await;
+        save_task(&db, &make_task("t1", "task-one")).await.unwrap();
+        record_task_run(&db, "t1", true, "done", 10).await.unwrap();
+
+        let mut task = get_task(&db, "t1").await.unwrap().unwrap();
+        task.cron_expression = "0 9 * * 1".to_string();
+        task.task_prompt = "Do something else".to_string();
+        task.notify = false;
+        update_task(&db, &task).await.unwrap();
+
+        let updated = get_task(&db, "t1").await.unwrap().unwrap();
+        assert_eq!(updated.name, "task-one");
+        assert_eq!(updated.cron_expression, "0 9 * * 1");
+        assert_eq!(updated.task_prompt, "Do something else");
+        assert!(!updated.notify);
+        // Id and history are kept
+        assert_eq!(load_task_runs(&db, "t1", 10).await.unwrap().len(), 1);
+
+        task.id = "missing".to_string();
+        assert!(update_task(&db, &task).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_set_task_enabled() {
         let db = setup_test_db().await;
@@ -261,6 +381,37 @@ mod tests {
         assert!(task.enabled);
     }
 
+    #[tokio::test]
+    async fn test_record_and_load_task_runs() {
+        let db = setup_test_db().await;
+        save_task(&db, &make_task("t1", "task-one")).await.unwrap();
+
+        record_task_run(&db, "t1", true, "first", 1200)
+            .await
+            .unwrap();
+        record_task_run(&db, "t1", false, "Error: timeout", 30_000)
+            .await
+            .unwrap();
+        record_task_run(&db, "t1", true, "third", 900)
+            .await
+            .unwrap();
+
+        let runs = load_task_runs(&db, "t1", 10).await.unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].result, "third");
+        assert!(!runs[1].success);
+        assert_eq!(runs[1].duration_ms, 30_000);
+        assert_eq!(runs[2].result, "first");
+
+        let latest = load_task_runs(&db, "t1", 1).await.unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].result, "third");
+
+        // History goes away with the task
+        delete_task(&db, "t1").await.unwrap();
+        assert!(load_task_runs(&db, "t1", 10).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_get_task_not_found() {
         let db = setup_test_db().await;
@@ -16,6 +16,7 @@ use tauri::AppHandle;
 use tokio::sync::Mutex;
 
 use crate::ai_instances::AIInstanceManager;
+use crate::tools::confirmation::ConfirmationGate;
 
 use super::runner::register_task_job;
 use super::storage;
@@ -45,6 +46,10 @@ pub struct CreateScheduledTaskArgs {
     /// Defaults to true. Set to false for silent background tasks.
     #[serde(default = "default_notify")]
     notify: bool,
+    /// Whether a failed run sends an OS notification even when `notify` is false.
+    /// Defaults to true.
+    #[serde(default = "default_notify")]
+    notify_on_failure: bool,
 }
 
 fn default_notify() -> bool {
@@ -121,6 +126,10 @@ impl Tool for CreateScheduledTaskTool {
                     "notify": {
                         "type": "boolean",
                         "description": "Whether to send OS notifications and show results in the chat when the task completes. Defaults to true. Set to false for silent background tasks."
+                    },
+                    "notify_on_failure": {
+                        "type": "boolean",
+                        "description": "Whether a failed run sends an OS notification even when notify is false. Defaults to true."
                     }
                 },
                 "required": ["name", "cron_expression", "task_prompt"]
@@ -154,6 +163,7 @@ impl Tool for CreateScheduledTaskTool {
             task_prompt: args.task_prompt.clone(),
             enabled: true,
             notify: args.notify,
+            notify_on_failure: args.notify_on_failure,
             last_run: None,
             last_result: None,
             created_at: Utc::now(),
@@ -174,6 +184,7 @@ impl Tool for CreateScheduledTaskTool {
                 task.task_prompt.clone(),
                 task.instance_id.clone(),
                 task.notify,
+                task.notify_on_failure,
                 manager.clone(),
                 app_handle.clone(),
             )
@@ -298,6 +309,8 @@ pub struct DeleteScheduledTaskTool {
     db: Option<Pool<Sqlite>>,
     #[serde(skip)]
     scheduler: Option<SharedScheduler>,
+    #[serde(skip)]
+    confirmation: ConfirmationGate,
 }
 
 impl DeleteScheduledTaskTool {
@@ -305,8 +318,15 @@ impl DeleteScheduledTaskTool {
         Self {
             db: Some(db),
             scheduler: Some(scheduler),
+            confirmation: ConfirmationGate::default(),
         }
     }
+
+    /// Ask the user before deleting when the instance requires it
+    pub fn with_confirmation(mut self, confirmation: ConfirmationGate) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
 }
 
 impl Tool for DeleteScheduledTaskTool {
@@ -345,6 +365,14 @@ impl Tool for DeleteScheduledTaskTool {
             .as_ref()
             .ok_or_else(|| SchedulerToolError("Scheduler not initialized".to_string()))?;
 
+        self.confirmation
+            .confirm(
+                Self::NAME,
+                &format!("Delete scheduled task '{}'", args.task_id),
+            )
+            .await
+            .map_err(SchedulerToolError)?;
+
         // Remove from scheduler
         {
             let mut sched = scheduler.lock().await;
@@ -420,6 +448,7 @@ mod tests {
         let tool = DeleteScheduledTaskTool {
             db: None,
             scheduler: None,
+            confirmation: ConfirmationGate::default(),
         };
         let def = Tool::definition(&tool, "test".to_string()).await;
         assert_eq!(def.name, "delete_scheduled_task");
@@ -442,6 +471,7 @@ mod tests {
                 cron_expression: "0 8 * * *".to_string(),
                 task_prompt: "test prompt".to_string(),
                 notify: true,
+                notify_on_failure: true,
             },
         )
         .await;
@@ -464,6 +494,7 @@ mod tests {
         let tool = DeleteScheduledTaskTool {
             db: None,
             scheduler: None,
+            confirmation: ConfirmationGate::default(),
         };
         let result = Tool::call(
             &tool,
@@ -70,6 +70,12 @@ pub struct CreateToolArgs {
     /// Optional parameter definitions for the tool.
     #[serde(default)]
     parameters: Vec<ParameterDefArg>,
+    /// Optional HTTP hosts the tool may reach; any host when omitted.
+    #[serde(default)]
+    allowed_domains: Option<Vec<String>>,
+    /// Optional category for grouping (e.g. "web", "data", "files").
+    #[serde(default)]
+    category: Option<String>,
 }
 
 /// Parameter definition as provided by the LLM.
@@ -153,6 +159,15 @@ impl Tool for CreateToolTool {
                             },
                             "required": ["name"]
                         }
+                    },
+                    "allowed_domains": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional list of hosts the tool may call via HTTP (e.g. ['api.github.com']); subdomains are included. Omit to allow any HTTPS host."
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Optional category for grouping tools, e.g. 'web', 'data', 'files'"
                     }
                 },
                 "required": ["name", "description", "script_content"]
@@ -190,7 +205,14 @@ impl Tool for CreateToolTool {
         // Register in the registry
         let mut registry_guard = registry.write().await;
         let tool = registry_guard
-            .register_tool(&args.name, &args.description, &args.script_content, params)
+            .register_tool(
+                &args.name,
+                &args.description,
+                &args.script_content,
+                params,
+                args.allowed_domains,
+                args.category.as_deref(),
+            )
             .await
             .map_err(|e| CodeGenError(format!("Failed to register tool: {}", e)))?;
 
@@ -341,6 +363,9 @@ pub struct UpdateToolArgs {
     /// Optional updated parameter definitions.
     #[serde(default)]
     parameters: Option<Vec<ParameterDefArg>>,
+    /// Optional new category; an empty string clears it.
+    #[serde(default)]
+    category: Option<String>,
 }
 
 /// rig Tool that updates an existing dynamic tool's code and metadata.
@@ -403,6 +428,10 @@ impl Tool for UpdateToolTool {
                             },
                             "required": ["name"]
                         }
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "New category (optional, keeps existing if omitted; empty string clears it)"
                     }
                 },
                 "required": ["tool_name", "script_content"]
@@ -449,6 +478,12 @@ impl Tool for UpdateToolTool {
             )
             .await
             .map_err(|e| CodeGenError(format!("Failed to update tool: {}", e)))?;
+        if args.category.is_some() {
+            registry_guard
+                .set_category(&tool.name, args.category.as_deref())
+                .await
+                .map_err(|e| CodeGenError(format!("Failed to set category: {}", e)))?;
+        }
 
         let mut result = format!(
             "Tool '{}' updated successfully to version {}.\n\
@@ -574,6 +609,8 @@ mod tests {
                         required: true,
                     },
                 ],
+                allowed_domains: None,
+                category: None,
             })
             .await
             .unwrap();
@@ -598,12 +635,39 @@ mod tests {
                 description: "Will fail".to_string(),
                 script_content: "let x = ;; broken".to_string(),
                 parameters: vec![],
+                allowed_domains: None,
+                category: None,
             })
             .await;
 
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_create_tool_with_allowed_domains() {
+        let registry = test_registry().await;
+        let tool = CreateToolTool::new(registry.clone(), test_workspace());
+
+        tool.call(CreateToolArgs {
+            name: "gh_repos".to_string(),
+            description: "Lists repos".to_string(),
+            script_content: r#"http_get("https://api.github.com/users/octocat/repos")"#.to_string(),
+            parameters: vec![],
+            allowed_domains: Some(vec!["API.github.com".to_string()]),
+            category: Some("web".to_string()),
+        })
+        .await
+        .unwrap();
+
+        let guard = registry.read().await;
+        let stored = guard.get_tool("gh_repos").await.unwrap().unwrap();
+        assert_eq!(
+            stored.allowed_domains,
+            Some(vec!["api.github.com".to_string()])
+        );
+        assert_eq!(stored.category.as_deref(), Some("web"));
+    }
+
     // -- ReadToolTool tests --
 
     #[tokio::test]
@@ -614,7 +678,7 @@ mod tests {
         {
             let mut guard = registry.write().await;
             guard
-                .register_tool("readable", "A readable tool", "40 + 2", vec![])
+                .register_tool("readable", "A readable tool", "40 + 2", vec![], None, None)
                 .await
                 .unwrap();
         }
@@ -658,7 +722,14 @@ mod tests {
         {
             let mut guard = registry.write().await;
             guard
-                .register_tool("updatable", "Original description", "1 + 1", vec![])
+                .register_tool(
+                    "updatable",
+                    "Original description",
+                    "1 + 1",
+                    vec![],
+                    None,
+                    None,
+                )
                 .await
                 .unwrap();
         }
@@ -670,6 +741,7 @@ mod tests {
                 script_content: "2 + 2".to_string(),
                 description: Some("Updated description".to_string()),
                 parameters: None,
+                category: None,
             })
             .await
             .unwrap();
@@ -692,7 +764,7 @@ mod tests {
         {
             let mut guard = registry.write().await;
             guard
-                .register_tool("will_fail_update", "A tool", "42", vec![])
+                .register_tool("will_fail_update", "A tool", "42", vec![], None, None)
                 .await
                 .unwrap();
         }
@@ -704,6 +776,7 @@ mod tests {
                 script_content: "let x = ;; broken".to_string(),
                 description: None,
                 parameters: None,
+                category: None,
             })
             .await;
 
@@ -721,6 +794,7 @@ mod tests {
                 script_content: "42".to_string(),
                 description: None,
                 parameters: None,
+                category: None,
             })
             .await;
 
@@ -0,0 +1,192 @@
+//! User confirmation for destructive tool actions.
+//!
+//! When an instance has `require_confirmation_for_destructive` enabled, tools
+//! such as `delete_file` ask the user before acting: a `tool:confirm_request`
+//! event is emitted and the tool waits until the frontend answers via
+//! `commands::chat::confirm_tool_action`. No answer within
+//! `CONFIRMATION_TIMEOUT` counts as a denial.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+use crate::ai_instances::AIInstanceManager;
+
+/// Event asking the frontend to approve or deny a destructive action
+pub const CONFIRM_REQUEST_EVENT: &str = "tool:confirm_request";
+
+/// How long a tool waits for the user's answer before treating it as a denial
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Confirmation requests awaiting an answer, keyed by request ID.
+#[derive(Default)]
+pub struct PendingConfirmations {
+    pending: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+/// Shared pending confirmations, managed as Tauri state.
+pub type SharedConfirmations = Arc<PendingConfirmations>;
+
+impl PendingConfirmations {
+    /// Open a new request. The receiver yields the user's answer.
+    pub fn register(&self) -> (String, oneshot::Receiver<bool>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        self.lock().insert(id.clone(), tx);
+        (id, rx)
+    }
+
+    /// Answer a request. Returns false if it is unknown or already timed out.
+    pub fn resolve(&self, id: &str, approved: bool) -> bool {
+        match self.lock().remove(id) {
+            Some(tx) => tx.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Wait for the answer to request `id`; a timeout counts as a denial.
+    pub async fn wait(&self, id: &str, rx: oneshot::Receiver<bool>, timeout: Duration) -> bool {
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(approved)) => approved,
+            // Timed out or sender dropped: forget the request and deny
+            _ => {
+                self.lock().remove(id);
+                false
+            }
+        }
+    }
+
+    /// Number of requests still awaiting an answer.
+    pub fn pending_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, oneshot::Sender<bool>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Payload of `tool:confirm_request`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmRequest {
+    pub id: String,
+    pub instance_id: String,
+    pub tool: String,
+    /// Human-readable description of the action (e.g. "Delete 'notes.txt'")
+    pub action: String,
+    pub timeout_secs: u64,
+}
+
+/// Per-tool handle that asks the user before a destructive action.
+///
+/// The instance policy is looked up on every call, so toggling it applies
+/// immediately. Without an `AppHandle` (tests, no UI) actions proceed.
+#[derive(Clone, Default)]
+pub struct ConfirmationGate {
+    app_handle: Option<AppHandle>,
+    instance_id: String,
+}
+
+impl ConfirmationGate {
+    pub fn new(app_handle: Option<AppHandle>, instance_id: &str) -> Self {
+        Self {
+            app_handle,
+            instance_id: instance_id.to_string(),
+        }
+    }
+
+    /// Whether the instance requires confirmation for destructive actions.
+    async fn required(&self, app_handle: &AppHandle) -> bool {
+        let Some(manager) = app_handle.try_state::<Arc<tokio::sync::Mutex<AIInstanceManager>>>()
+        else {
+            return false;
+        };
+        let manager = manager.lock().await;
+        manager
+            .get_instance(&self.instance_id)
+            .is_some_and(|i| i.require_confirmation_for_destructive)
+    }
+
+    /// Ask the user to approve `action`. Returns an error message if the
+    /// action was denied or not answered in time.
+    pub async fn confirm(&self, tool: &str, action: &str) -> Result<(), String> {
+        let Some(app_handle) = &self.app_handle else {
+            return Ok(());
+        };
+        if !self.required(app_handle).await {
+            return Ok(());
+        }
+        let Some(confirmations) = app_handle.try_state::<SharedConfirmations>() else {
+            return Err(format!("Cannot ask for confirmation: {}", action));
+        };
+        let confirmations = confirmations.inner().clone();
+
+        let (id, rx) = confirmations.register();
+        let request = ConfirmRequest {
+            id: id.clone(),
+            instance_id: self.instance_id.clone(),
+            tool: tool.to_string(),
+            action: action.to_string(),
+            timeout_secs: CONFIRMATION_TIMEOUT.as_secs(),
+        };
+        if let Err(e) = app_handle.emit(CONFIRM_REQUEST_EVENT, &request) {
+            confirmations.resolve(&id, false);
+            return Err(format!("Failed to request confirmation: {}", e));
+        }
+
+        tracing::info!("Waiting for user confirmation of '{}' ({})", action, id);
+        if confirmations.wait(&id, rx, CONFIRMATION_TIMEOUT).await {
+            Ok(())
+        } else {
+            Err(format!(
+                "The user did not approve this action: {}. Do not retry it unless asked.",
+                action
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_approves_pending_request() {
+        let confirmations = Arc::new(PendingConfirmations::default());
+        let (id, rx) = confirmations.register();
+        assert_eq!(confirmations.pending_count(), 1);
+
+        let waiter = {
+            let confirmations = confirmations.clone();
+            let id = id.clone();
+            tokio::spawn(async move { confirmations.wait(&id, rx, Duration::from_secs(5)).await })
+        };
+        assert!(confirmations.resolve(&id, true));
+        assert!(waiter.await.unwrap());
+        assert_eq!(confirmations.pending_count(), 0);
+
+        // Answering twice is rejected
+        assert!(!confirmations.resolve(&id, true));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_counts_as_denial() {
+        let confirmations = PendingConfirmations::default();
+        let (id, rx) = confirmations.register();
+
+        let approved = confirmations.wait(&id, rx, Duration::from_millis(20)).await;
+        assert!(!approved);
+        assert_eq!(confirmations.pending_count(), 0);
+        // A late answer finds nothing to resolve
+        assert!(!confirmations.resolve(&id, true));
+    }
+
+    #[tokio::test]
+    async fn test_gate_without_app_handle_allows_action() {
+        let gate = ConfirmationGate::default();
+        assert!(gate.confirm("delete_file", "Delete 'a.txt'").await.is_ok());
+    }
+}
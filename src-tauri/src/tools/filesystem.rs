@@ -1,3 +1,5 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use regex::{Regex, RegexBuilder};
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
@@ -5,12 +7,15 @@ use serde_json::json;
 use std::path::{Component, Path, PathBuf};
 use tokio::fs;
 
+use super::confirmation::ConfirmationGate;
+use crate::utils::workspace_ignore::WorkspaceIgnore;
+
 // ---------------------------------------------------------------------------
 // Shared helpers
 // ---------------------------------------------------------------------------
 
 /// Resolves a user-provided relative path within the workspace root.
-/// Prevents directory traversal attacks.
+/// Prevents directory traversal attacks, including via symlinks.
 fn resolve_path(root: &Path, user_path: &str) -> Result<PathBuf, String> {
     let path = Path::new(user_path);
 
@@ -22,7 +27,49 @@ fn resolve_path(root: &Path, user_path: &str) -> Result<PathBuf, String> {
         return Err("Parent directory traversal (..) is not allowed".to_string());
     }
 
-    Ok(root.join(path))
+    let resolved = root.join(path);
+    ensure_inside_root(root, &resolved)?;
+    Ok(resolved)
+}
+
+/// Check that the real location of `path` (after following symlinks) is
+/// still inside `root`. For paths that do not exist yet, the closest existing
+/// ancestor is checked instead, since that is where the file would be created.
+fn ensure_inside_root(root: &Path, path: &Path) -> Result<(), String> {
+    // Without an existing root there is nothing a symlink could point from
+    let Ok(real_root) = root.canonicalize() else {
+        return Ok(());
+    };
+
+    let existing = path
+        .ancestors()
+        .take_while(|p| p.starts_with(root))
+        .find(|p| p.symlink_metadata().is_ok());
+    let Some(existing) = existing else {
+        return Ok(());
+    };
+
+    // Dangling symlinks cannot be resolved; writing through them could
+    // create a file anywhere, so they are refused as well
+    let real = existing
+        .canonicalize()
+        .map_err(|_| "Path could not be resolved inside the workspace".to_string())?;
+    if !real.starts_with(&real_root) {
+        return Err("Path resolves outside the workspace (symlink)".to_string());
+    }
+    Ok(())
+}
+
+/// Like `resolve_path`, but rejects paths that point at the workspace root
+/// itself (e.g. `.` or an empty path). Used by destructive operations.
+fn resolve_entry_path(root: &Path, user_path: &str) -> Result<PathBuf, String> {
+    if Path::new(user_path)
+        .components()
+        .all(|c| matches!(c, Component::CurDir))
+    {
+        return Err("Path must refer to a file or directory inside the workspace".to_string());
+    }
+    resolve_path(root, user_path)
 }
 
 // ---------------------------------------------------------------------------
@@ -33,6 +80,9 @@ fn resolve_path(root: &Path, user_path: &str) -> Result<PathBuf, String> {
 pub struct LsArgs {
     #[serde(default = "default_current_dir")]
     path: String,
+    pattern: Option<String>,
+    #[serde(default)]
+    recursive: bool,
 }
 
 fn default_current_dir() -> String {
@@ -63,13 +113,21 @@ impl Tool for LsTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: "ls".to_string(),
-            description: "List files and directories in the workspace. Returns names with type indicators (DIR/FILE) and file sizes.".to_string(),
+            description: "List files and directories in the workspace. Returns names with type indicators (DIR/FILE) and file sizes. With a glob pattern (e.g. '*.md', '**/*.json') and/or recursive=true, returns matching paths relative to the workspace root, skipping paths listed in the workspace's .ownaiignore.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
                     "path": {
                         "type": "string",
                         "description": "Relative directory path to list (default: current directory)"
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "Optional glob pattern relative to path (e.g. '*.md', 'docs/**/*.txt')"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Match the pattern in all subdirectories (default: false)"
                     }
                 }
             }),
@@ -83,6 +141,11 @@ impl Tool for LsTool {
             return Err(ToolError(format!("Directory not found: {}", args.path)));
         }
 
+        if args.pattern.is_some() || args.recursive {
+            let pattern = args.pattern.as_deref().unwrap_or("*");
+            return glob_list(&self.root, &path, pattern, args.recursive);
+        }
+
         let mut entries = fs::read_dir(&path)
             .await
             .map_err(|e| ToolError(format!("Failed to read directory: {}", e)))?;
@@ -111,6 +174,73 @@ impl Tool for LsTool {
     }
 }
 
+/// List workspace entries under `dir` matching a glob `pattern`.
+/// With `recursive`, the pattern is matched in all subdirectories
+/// (unless it already contains `**`). Paths are shown relative to `root`;
+/// matches that resolve outside the workspace (via symlinks) or are excluded
+/// by `.ownaiignore` are skipped.
+fn glob_list(root: &Path, dir: &Path, pattern: &str, recursive: bool) -> Result<String, ToolError> {
+    let pattern_path = Path::new(pattern);
+    if pattern_path.is_absolute()
+        || pattern_path
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+    {
+        return Err(ToolError(
+            "Glob pattern must be relative and must not contain '..'".to_string(),
+        ));
+    }
+
+    let pattern = if recursive && !pattern.contains("**") {
+        format!("**/{}", pattern)
+    } else {
+        pattern.to_string()
+    };
+    let base = glob::Pattern::escape(&dir.to_string_lossy());
+    let full_pattern = format!("{}/{}", base.trim_end_matches('/'), pattern);
+
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let ignore = WorkspaceIgnore::load(root);
+    let paths =
+        glob::glob(&full_pattern).map_err(|e| ToolError(format!("Invalid glob pattern: {}", e)))?;
+
+    let mut output = Vec::new();
+    for entry in paths.flatten() {
+        let inside_root = entry
+            .canonicalize()
+            .map(|p| p.starts_with(&canonical_root))
+            .unwrap_or(false);
+        if !inside_root {
+            continue;
+        }
+        let Ok(metadata) = std::fs::metadata(&entry) else {
+            continue;
+        };
+        if ignore.is_ignored(&entry, metadata.is_dir()) {
+            continue;
+        }
+
+        let rel = entry.strip_prefix(root).unwrap_or(&entry);
+        let rel = rel
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if metadata.is_dir() {
+            output.push(format!("DIR  {}", rel));
+        } else {
+            output.push(format!("FILE {}  ({} bytes)", rel, metadata.len()));
+        }
+    }
+
+    if output.is_empty() {
+        Ok("No files match the pattern".to_string())
+    } else {
+        Ok(output.join("\n"))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // read_file
 // ---------------------------------------------------------------------------
@@ -120,6 +250,44 @@ pub struct ReadFileArgs {
     path: String,
     start_line: Option<usize>,
     end_line: Option<usize>,
+    #[serde(default)]
+    binary: bool,
+}
+
+/// Maximum number of bytes returned (base64-encoded) for a binary file read.
+const MAX_BINARY_READ_BYTES: usize = 64 * 1024;
+
+/// Maximum number of bytes loaded from a file; larger files are truncated.
+const MAX_READ_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Format raw bytes as base64 for the agent, truncated to `MAX_BINARY_READ_BYTES`.
+/// `total_len` is the full file size (`bytes` may already be a prefix).
+fn format_binary_content(path: &str, bytes: &[u8], total_len: u64) -> String {
+    let shown = bytes.len().min(MAX_BINARY_READ_BYTES);
+    let truncated = if (shown as u64) < total_len {
+        format!(", truncated to first {} bytes", shown)
+    } else {
+        String::new()
+    };
+    format!(
+        "Binary file '{}' ({} bytes{}), base64-encoded:\n{}",
+        path,
+        total_len,
+        truncated,
+        BASE64.encode(&bytes[..shown])
+    )
+}
+
+/// Read at most `MAX_READ_BYTES` of a file. Returns the bytes and the full
+/// file size.
+async fn read_capped(path: &Path) -> std::io::Result<(Vec<u8>, u64)> {
+    use tokio::io::AsyncReadExt;
+
+    let file = fs::File::open(path).await?;
+    let total_len = file.metadata().await?.len();
+    let mut bytes = Vec::with_capacity(total_len.min(MAX_READ_BYTES) as usize);
+    file.take(MAX_READ_BYTES).read_to_end(&mut bytes).await?;
+    Ok((bytes, total_len))
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -142,7 +310,7 @@ impl Tool for ReadFileTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: "read_file".to_string(),
-            description: "Read the contents of a file in the workspace. Optionally specify start_line and end_line to read only a portion.".to_string(),
+            description: "Read the contents of a file in the workspace. Optionally specify start_line and end_line to read only a portion. Files that are not valid UTF-8 text (images, archives, ...) are returned base64-encoded (capped at 64 KB); set binary=true to force this. Text beyond the first 2 MB is truncated.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -157,6 +325,10 @@ impl Tool for ReadFileTool {
                     "end_line": {
                         "type": "number",
                         "description": "Last line to read (inclusive, optional)"
+                    },
+                    "binary": {
+                        "type": "boolean",
+                        "description": "Return the raw bytes base64-encoded instead of text (default: false)"
                     }
                 },
                 "required": ["path"]
@@ -167,12 +339,39 @@ impl Tool for ReadFileTool {
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
         let path = resolve_path(&self.root, &args.path).map_err(ToolError)?;
 
-        let content = fs::read_to_string(&path)
+        let (mut bytes, total_len) = read_capped(&path)
             .await
             .map_err(|e| ToolError(format!("Failed to read file '{}': {}", args.path, e)))?;
 
+        if args.binary {
+            return Ok(format_binary_content(&args.path, &bytes, total_len));
+        }
+
+        let truncated = (bytes.len() as u64) < total_len;
+        if truncated {
+            // The cut may split a multi-byte character; drop the partial tail
+            if let Err(e) = std::str::from_utf8(&bytes) {
+                if e.error_len().is_none() {
+                    bytes.truncate(e.valid_up_to());
+                }
+            }
+        }
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => return Ok(format_binary_content(&args.path, e.as_bytes(), total_len)),
+        };
+        let notice = if truncated {
+            format!(
+                "\n\n[File truncated: showing the first {} of {} bytes]",
+                content.len(),
+                total_len
+            )
+        } else {
+            String::new()
+        };
+
         if args.start_line.is_none() && args.end_line.is_none() {
-            return Ok(content);
+            return Ok(format!("{}{}", content, notice));
         }
 
         let lines: Vec<&str> = content.lines().collect();
@@ -180,10 +379,10 @@ impl Tool for ReadFileTool {
         let end = args.end_line.unwrap_or(lines.len()).min(lines.len());
 
         if start >= lines.len() {
-            return Ok("(no lines in range)".to_string());
+            return Ok(format!("(no lines in range){}", notice));
         }
 
-        Ok(lines[start..end].join("\n"))
+        Ok(format!("{}{}", lines[start..end].join("\n"), notice))
     }
 }
 
@@ -195,6 +394,8 @@ impl Tool for ReadFileTool {
 pub struct WriteFileArgs {
     path: String,
     content: String,
+    #[serde(default)]
+    append: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -217,7 +418,7 @@ impl Tool for WriteFileTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: "write_file".to_string(),
-            description: "Write content to a file in the workspace. Creates the file and parent directories if they don't exist, overwrites if the file exists.".to_string(),
+            description: "Write content to a file in the workspace. Creates the file and parent directories if they don't exist, overwrites if the file exists (or appends when append is true).".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -228,6 +429,10 @@ impl Tool for WriteFileTool {
                     "content": {
                         "type": "string",
                         "description": "Content to write to the file"
+                    },
+                    "append": {
+                        "type": "boolean",
+                        "description": "Append to the end of the file instead of overwriting it (default: false)"
                     }
                 },
                 "required": ["path", "content"]
@@ -245,6 +450,30 @@ impl Tool for WriteFileTool {
                 .map_err(|e| ToolError(format!("Failed to create directories: {}", e)))?;
         }
 
+        if args.append {
+            use tokio::io::AsyncWriteExt;
+
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+                .map_err(|e| ToolError(format!("Failed to open file: {}", e)))?;
+            file.write_all(args.content.as_bytes())
+                .await
+                .map_err(|e| ToolError(format!("Failed to append to file: {}", e)))?;
+            // tokio writes in the background; flush before reporting success
+            file.flush()
+                .await
+                .map_err(|e| ToolError(format!("Failed to append to file: {}", e)))?;
+
+            return Ok(format!(
+                "Appended to file: {} ({} bytes)",
+                args.path,
+                args.content.len()
+            ));
+        }
+
         fs::write(&path, &args.content)
             .await
             .map_err(|e| ToolError(format!("Failed to write file: {}", e)))?;
@@ -337,6 +566,192 @@ impl Tool for EditFileTool {
     }
 }
 
+// ---------------------------------------------------------------------------
+// move_file
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct MoveFileArgs {
+    source: String,
+    destination: String,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MoveFileTool {
+    root: PathBuf,
+    #[serde(skip)]
+    confirmation: ConfirmationGate,
+}
+
+impl MoveFileTool {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            confirmation: ConfirmationGate::default(),
+        }
+    }
+
+    /// Ask the user before acting when the instance requires it
+    pub fn with_confirmation(mut self, confirmation: ConfirmationGate) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+}
+
+impl Tool for MoveFileTool {
+    const NAME: &'static str = "move_file";
+    type Error = ToolError;
+    type Args = MoveFileArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "move_file".to_string(),
+            description: "Move or rename a file or directory in the workspace. Creates destination parent directories if needed. Refuses to overwrite an existing destination unless overwrite is true.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "source": {
+                        "type": "string",
+                        "description": "Relative path of the file or directory to move"
+                    },
+                    "destination": {
+                        "type": "string",
+                        "description": "Relative destination path (including the new name)"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace an existing destination file (default: false)"
+                    }
+                },
+                "required": ["source", "destination"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let source = resolve_entry_path(&self.root, &args.source).map_err(ToolError)?;
+        let destination = resolve_entry_path(&self.root, &args.destination).map_err(ToolError)?;
+
+        if !source.exists() {
+            return Err(ToolError(format!("Path not found: {}", args.source)));
+        }
+        if destination.exists() {
+            if !args.overwrite {
+                return Err(ToolError(format!(
+                    "Destination already exists: {} (set overwrite to true to replace it)",
+                    args.destination
+                )));
+            }
+            if destination.is_dir() {
+                return Err(ToolError(format!(
+                    "Cannot overwrite directory: {}",
+                    args.destination
+                )));
+            }
+        }
+
+        self.confirmation
+            .confirm(
+                Self::NAME,
+                &format!("Move '{}' to '{}'", args.source, args.destination),
+            )
+            .await
+            .map_err(ToolError)?;
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| ToolError(format!("Failed to create directories: {}", e)))?;
+        }
+
+        fs::rename(&source, &destination)
+            .await
+            .map_err(|e| ToolError(format!("Failed to move '{}': {}", args.source, e)))?;
+
+        Ok(format!("Moved: {} -> {}", args.source, args.destination))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// delete_file
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteFileArgs {
+    path: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeleteFileTool {
+    root: PathBuf,
+    #[serde(skip)]
+    confirmation: ConfirmationGate,
+}
+
+impl DeleteFileTool {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            confirmation: ConfirmationGate::default(),
+        }
+    }
+
+    /// Ask the user before acting when the instance requires it
+    pub fn with_confirmation(mut self, confirmation: ConfirmationGate) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+}
+
+impl Tool for DeleteFileTool {
+    const NAME: &'static str = "delete_file";
+    type Error = ToolError;
+    type Args = DeleteFileArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "delete_file".to_string(),
+            description: "Delete a file or an empty directory in the workspace.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Relative path of the file or empty directory to delete"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = resolve_entry_path(&self.root, &args.path).map_err(ToolError)?;
+
+        let metadata = fs::symlink_metadata(&path)
+            .await
+            .map_err(|_| ToolError(format!("Path not found: {}", args.path)))?;
+
+        self.confirmation
+            .confirm(Self::NAME, &format!("Delete '{}'", args.path))
+            .await
+            .map_err(ToolError)?;
+
+        let result = if metadata.is_dir() {
+            fs::remove_dir(&path).await
+        } else {
+            fs::remove_file(&path).await
+        };
+        result.map_err(|e| ToolError(format!("Failed to delete '{}': {}", args.path, e)))?;
+
+        Ok(format!("Deleted: {}", args.path))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // grep
 // ---------------------------------------------------------------------------
@@ -347,6 +762,29 @@ pub struct GrepArgs {
     path: String,
     #[serde(default)]
     recursive: bool,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    regex: bool,
+}
+
+/// Maximum number of matching lines returned by `grep`.
+const MAX_GREP_RESULTS: usize = 200;
+
+/// Collected grep matches, capped at `MAX_GREP_RESULTS` lines.
+#[derive(Default)]
+struct GrepResults {
+    lines: Vec<String>,
+    total: usize,
+}
+
+impl GrepResults {
+    fn push(&mut self, line: String) {
+        self.total += 1;
+        if self.lines.len() < MAX_GREP_RESULTS {
+            self.lines.push(line);
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -369,13 +807,13 @@ impl Tool for GrepTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: "grep".to_string(),
-            description: "Search for a text pattern in files within the workspace. Returns matching lines with file paths and line numbers.".to_string(),
+            description: "Search for a text pattern in files within the workspace. Returns matching lines with file paths and line numbers. Literal matching by default; set regex=true for regular expressions (capture groups are reported) and case_insensitive=true to ignore case. Paths listed in the workspace's .ownaiignore are skipped when searching directories.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
                     "pattern": {
                         "type": "string",
-                        "description": "Text pattern to search for"
+                        "description": "Text (or regex, if regex=true) to search for"
                     },
                     "path": {
                         "type": "string",
@@ -384,6 +822,14 @@ impl Tool for GrepTool {
                     "recursive": {
                         "type": "boolean",
                         "description": "Search recursively in directories (default: false)"
+                    },
+                    "case_insensitive": {
+                        "type": "boolean",
+                        "description": "Ignore case when matching (default: false)"
+                    },
+                    "regex": {
+                        "type": "boolean",
+                        "description": "Treat pattern as a regular expression (default: false)"
                     }
                 },
                 "required": ["pattern", "path"]
@@ -393,39 +839,90 @@ impl Tool for GrepTool {
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
         let path = resolve_path(&self.root, &args.path).map_err(ToolError)?;
-        let mut results = Vec::new();
+        let matcher = build_grep_matcher(&args.pattern, args.regex, args.case_insensitive)?;
+        let report_captures = args.regex && matcher.captures_len() > 1;
+        let mut results = GrepResults::default();
 
         if path.is_file() {
-            search_file(&path, &args.pattern, &mut results).await?;
+            search_file(&path, &matcher, report_captures, &mut results).await?;
         } else if path.is_dir() {
-            search_directory(&path, &args.pattern, args.recursive, &mut results).await?;
+            let ignore = WorkspaceIgnore::load(&self.root);
+            search_directory(
+                &path,
+                &ignore,
+                &matcher,
+                report_captures,
+                args.recursive,
+                &mut results,
+            )
+            .await?;
         } else {
             return Err(ToolError(format!("Path not found: {}", args.path)));
         }
 
-        if results.is_empty() {
-            Ok("No matches found".to_string())
-        } else {
-            Ok(results.join("\n"))
+        if results.lines.is_empty() {
+            return Ok("No matches found".to_string());
+        }
+
+        let mut output = results.lines.join("\n");
+        if results.total > results.lines.len() {
+            output.push_str(&format!(
+                "\n... ({} more matches truncated)",
+                results.total - results.lines.len()
+            ));
         }
+        Ok(output)
     }
 }
 
+/// Build the line matcher for `grep`. Literal patterns are escaped so both
+/// modes share the same matching code.
+fn build_grep_matcher(
+    pattern: &str,
+    regex: bool,
+    case_insensitive: bool,
+) -> Result<Regex, ToolError> {
+    let source = if regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| ToolError(format!("Invalid regex: {}", e)))
+}
+
 async fn search_file(
     path: &Path,
-    pattern: &str,
-    results: &mut Vec<String>,
+    matcher: &Regex,
+    report_captures: bool,
+    results: &mut GrepResults,
 ) -> Result<(), ToolError> {
-    if let Ok(content) = fs::read_to_string(path).await {
+    let Ok((bytes, _)) = read_capped(path).await else {
+        return Ok(());
+    };
+    // Binary files are skipped; a character cut off by the size cap is dropped
+    let content = match std::str::from_utf8(&bytes) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+    if let Some(content) = content {
         for (line_num, line) in content.lines().enumerate() {
-            if line.contains(pattern) {
-                results.push(format!(
-                    "{}:{}: {}",
-                    path.display(),
-                    line_num + 1,
-                    line.trim()
-                ));
+            let Some(captures) = matcher.captures(line) else {
+                continue;
+            };
+            let mut entry = format!("{}:{}: {}", path.display(), line_num + 1, line.trim());
+            if report_captures {
+                let groups: Vec<&str> = captures
+                    .iter()
+                    .skip(1)
+                    .map(|g| g.map(|m| m.as_str()).unwrap_or(""))
+                    .collect();
+                entry.push_str(&format!("  [groups: {}]", groups.join(", ")));
             }
+            results.push(entry);
         }
     }
     Ok(())
@@ -433,9 +930,11 @@ async fn search_file(
 
 async fn search_directory(
     dir: &Path,
-    pattern: &str,
+    ignore: &WorkspaceIgnore,
+    matcher: &Regex,
+    report_captures: bool,
     recursive: bool,
-    results: &mut Vec<String>,
+    results: &mut GrepResults,
 ) -> Result<(), ToolError> {
     let mut entries = fs::read_dir(dir)
         .await
@@ -443,11 +942,27 @@ async fn search_directory(
 
     while let Ok(Some(entry)) = entries.next_entry().await {
         let entry_path = entry.path();
-        if entry_path.is_file() {
-            search_file(&entry_path, pattern, results).await?;
-        } else if entry_path.is_dir() && recursive {
+        // Symlinks are not followed: they could lead outside the workspace
+        // or into a cycle
+        let Ok(file_type) = entry.file_type().await else {
+            continue;
+        };
+        if file_type.is_symlink() || ignore.is_ignored(&entry_path, file_type.is_dir()) {
+            continue;
+        }
+        if file_type.is_file() {
+            search_file(&entry_path, matcher, report_captures, results).await?;
+        } else if file_type.is_dir() && recursive {
             // Use Box::pin() for recursive async
-            Box::pin(search_directory(&entry_path, pattern, recursive, results)).await?;
+            Box::pin(search_directory(
+                &entry_path,
+                ignore,
+                matcher,
+                report_captures,
+                recursive,
+                results,
+            ))
+            .await?;
         }
     }
     Ok(())
@@ -490,4 +1005,430 @@ mod tests {
         let root = PathBuf::from("/workspace");
         assert!(resolve_path(&root, "/etc/passwd").is_err());
     }
+
+    #[test]
+    fn test_resolve_entry_path_blocks_root() {
+        let root = PathBuf::from("/workspace");
+        assert!(resolve_entry_path(&root, ".").is_err());
+        assert!(resolve_entry_path(&root, "").is_err());
+        assert!(resolve_entry_path(&root, "../x").is_err());
+        assert!(resolve_entry_path(&root, "a.txt").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_move_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("draft.txt"), "content").unwrap();
+
+        let tool = MoveFileTool::new(root.to_path_buf());
+        let result = tool
+            .call(MoveFileArgs {
+                source: "draft.txt".to_string(),
+                destination: "archive/2026/final.txt".to_string(),
+                overwrite: false,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.contains("Moved"));
+        assert!(!root.join("draft.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(root.join("archive/2026/final.txt")).unwrap(),
+            "content"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_file_refuses_overwrite() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("a.txt"), "new").unwrap();
+        std::fs::write(root.join("b.txt"), "old").unwrap();
+
+        let tool = MoveFileTool::new(root.to_path_buf());
+        let args = |overwrite| MoveFileArgs {
+            source: "a.txt".to_string(),
+            destination: "b.txt".to_string(),
+            overwrite,
+        };
+
+        let err = tool.call(args(false)).await.unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(std::fs::read_to_string(root.join("b.txt")).unwrap(), "old");
+        assert!(root.join("a.txt").exists());
+
+        tool.call(args(true)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("b.txt")).unwrap(), "new");
+        assert!(!root.join("a.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_file_append() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = WriteFileTool::new(temp_dir.path().to_path_buf());
+        let append = |content: &str| {
+            tool.call(WriteFileArgs {
+                path: "log.txt".to_string(),
+                content: content.to_string(),
+                append: true,
+            })
+        };
+
+        append("first\n").await.unwrap();
+        append("second\n").await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("log.txt")).unwrap(),
+            "first\nsecond\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_file_append_blocks_traversal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = WriteFileTool::new(temp_dir.path().to_path_buf());
+        let result = tool
+            .call(WriteFileArgs {
+                path: "../escaped.txt".to_string(),
+                content: "x".to_string(),
+                append: true,
+            })
+            .await;
+        assert!(result.unwrap_err().to_string().contains("traversal"));
+    }
+
+    #[tokio::test]
+    async fn test_move_file_blocks_traversal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "x").unwrap();
+
+        let tool = MoveFileTool::new(temp_dir.path().to_path_buf());
+        let result = tool
+            .call(MoveFileArgs {
+                source: "a.txt".to_string(),
+                destination: "../escaped.txt".to_string(),
+                overwrite: false,
+            })
+            .await;
+        assert!(result.unwrap_err().to_string().contains("traversal"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("old.txt"), "x").unwrap();
+        std::fs::create_dir_all(root.join("full")).unwrap();
+        std::fs::write(root.join("full").join("keep.txt"), "x").unwrap();
+        std::fs::create_dir_all(root.join("empty")).unwrap();
+
+        let tool = DeleteFileTool::new(root.to_path_buf());
+        let delete = |path: &str| {
+            tool.call(DeleteFileArgs {
+                path: path.to_string(),
+            })
+        };
+
+        delete("old.txt").await.unwrap();
+        assert!(!root.join("old.txt").exists());
+
+        delete("empty").await.unwrap();
+        assert!(!root.join("empty").exists());
+
+        // Non-empty directories and the workspace root are refused
+        assert!(delete("full").await.is_err());
+        assert!(root.join("full").join("keep.txt").exists());
+        assert!(delete(".").await.is_err());
+        assert!(delete("missing.txt").await.is_err());
+    }
+
+    fn grep_args(pattern: &str, case_insensitive: bool, regex: bool) -> GrepArgs {
+        GrepArgs {
+            pattern: pattern.to_string(),
+            path: ".".to_string(),
+            recursive: true,
+            case_insensitive,
+            regex,
+        }
+    }
+
+    fn grep_workspace() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("log.txt"),
+            "Error: disk full\nerror: retry 3\nok\nTotal: a.b\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("sub").join("more.txt"), "ERROR 42\n").unwrap();
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_grep_literal() {
+        let temp_dir = grep_workspace();
+        let tool = GrepTool::new(temp_dir.path().to_path_buf());
+
+        let result = tool.call(grep_args("error", false, false)).await.unwrap();
+        assert_eq!(result.lines().count(), 1);
+        assert!(result.contains("log.txt:2: error: retry 3"));
+
+        // Regex metacharacters are matched literally
+        let result = tool.call(grep_args("a.b", false, false)).await.unwrap();
+        assert!(result.contains("Total: a.b"));
+        let result = tool.call(grep_args("a.*", false, false)).await.unwrap();
+        assert_eq!(result, "No matches found");
+    }
+
+    #[tokio::test]
+    async fn test_grep_case_insensitive() {
+        let temp_dir = grep_workspace();
+        let tool = GrepTool::new(temp_dir.path().to_path_buf());
+
+        let result = tool.call(grep_args("error", true, false)).await.unwrap();
+        assert_eq!(result.lines().count(), 3);
+        assert!(result.contains("more.txt:1: ERROR 42"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_regex_with_captures() {
+        let temp_dir = grep_workspace();
+        let tool = GrepTool::new(temp_dir.path().to_path_buf());
+
+        let result = tool
+            .call(grep_args(r"(?i)^error\W+(\w+)", false, true))
+            .await
+            .unwrap();
+        assert_eq!(result.lines().count(), 3);
+        assert!(result.contains("retry 3  [groups: retry]"));
+        assert!(result.contains("ERROR 42  [groups: 42]"));
+
+        let err = tool.call(grep_args("(", false, true)).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid regex"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_truncates_results() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let content = "match\n".repeat(MAX_GREP_RESULTS + 5);
+        std::fs::write(temp_dir.path().join("many.txt"), content).unwrap();
+
+        let tool = GrepTool::new(temp_dir.path().to_path_buf());
+        let result = tool.call(grep_args("match", false, false)).await.unwrap();
+        assert_eq!(result.lines().count(), MAX_GREP_RESULTS + 1);
+        assert!(result.ends_with("(5 more matches truncated)"));
+    }
+
+    #[tokio::test]
+    async fn test_grep_skips_ownaiignore_patterns() {
+        let temp_dir = grep_workspace();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("node_modules").join("pkg")).unwrap();
+        std::fs::write(
+            root.join("node_modules").join("pkg").join("index.js"),
+            "throw new Error('x')\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("debug.log"), "error in log\n").unwrap();
+
+        let tool = GrepTool::new(root.to_path_buf());
+        let result = tool.call(grep_args("error", true, false)).await.unwrap();
+        assert!(result.contains("node_modules"));
+        assert!(result.contains("debug.log"));
+
+        std::fs::write(root.join(".ownaiignore"), "node_modules/\n*.log\n").unwrap();
+        let result = tool.call(grep_args("error", true, false)).await.unwrap();
+        assert!(!result.contains("node_modules"));
+        assert!(!result.contains("debug.log"));
+        assert!(result.contains("more.txt:1: ERROR 42"));
+    }
+
+    fn ls_args(path: &str, pattern: Option<&str>, recursive: bool) -> LsArgs {
+        LsArgs {
+            path: path.to_string(),
+            pattern: pattern.map(|p| p.to_string()),
+            recursive,
+        }
+    }
+
+    fn ls_workspace() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("README.md"), "readme").unwrap();
+        std::fs::write(root.join("notes.txt"), "notes").unwrap();
+        std::fs::create_dir_all(root.join("docs").join("guides")).unwrap();
+        std::fs::write(root.join("docs").join("intro.md"), "intro").unwrap();
+        std::fs::write(root.join("docs").join("guides").join("setup.md"), "setup!").unwrap();
+        temp_dir
+    }
+
+    #[tokio::test]
+    async fn test_ls_recursive_glob() {
+        let temp_dir = ls_workspace();
+        let tool = LsTool::new(temp_dir.path().to_path_buf());
+
+        let result = tool.call(ls_args(".", Some("*.md"), true)).await.unwrap();
+        let mut lines: Vec<&str> = result.lines().collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "FILE README.md  (6 bytes)",
+                "FILE docs/guides/setup.md  (6 bytes)",
+                "FILE docs/intro.md  (5 bytes)",
+            ]
+        );
+
+        // Explicit ** pattern scoped to a subdirectory
+        let result = tool
+            .call(ls_args("docs", Some("**/*.md"), false))
+            .await
+            .unwrap();
+        assert_eq!(result.lines().count(), 2);
+        assert!(result.contains("docs/guides/setup.md"));
+
+        // Non-recursive pattern only matches the top level
+        let result = tool.call(ls_args(".", Some("*.md"), false)).await.unwrap();
+        assert_eq!(result, "FILE README.md  (6 bytes)");
+    }
+
+    #[tokio::test]
+    async fn test_ls_glob_no_match_and_invalid() {
+        let temp_dir = ls_workspace();
+        let tool = LsTool::new(temp_dir.path().to_path_buf());
+
+        let result = tool.call(ls_args(".", Some("*.pdf"), true)).await.unwrap();
+        assert_eq!(result, "No files match the pattern");
+
+        let err = tool
+            .call(ls_args(".", Some("../*"), false))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must not contain"));
+    }
+
+    fn read_args(path: &str, binary: bool) -> ReadFileArgs {
+        ReadFileArgs {
+            path: path.to_string(),
+            start_line: None,
+            end_line: None,
+            binary,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_file_non_utf8_returns_base64() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let bytes = [0x89u8, 0x50, 0x4e, 0x47, 0xff, 0xfe, 0x00];
+        std::fs::write(temp_dir.path().join("image.png"), bytes).unwrap();
+
+        let tool = ReadFileTool::new(temp_dir.path().to_path_buf());
+        let result = tool.call(read_args("image.png", false)).await.unwrap();
+
+        assert!(result.starts_with("Binary file 'image.png' (7 bytes)"));
+        assert!(result.ends_with(&BASE64.encode(bytes)));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_binary_flag_and_cap() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "hello").unwrap();
+        std::fs::write(
+            temp_dir.path().join("big.bin"),
+            vec![0xffu8; MAX_BINARY_READ_BYTES + 10],
+        )
+        .unwrap();
+
+        let tool = ReadFileTool::new(temp_dir.path().to_path_buf());
+
+        // Text files are returned as text unless binary is requested
+        assert_eq!(
+            tool.call(read_args("notes.txt", false)).await.unwrap(),
+            "hello"
+        );
+        let result = tool.call(read_args("notes.txt", true)).await.unwrap();
+        assert!(result.ends_with(&BASE64.encode("hello")));
+
+        let result = tool.call(read_args("big.bin", false)).await.unwrap();
+        assert!(result.contains(&format!(
+            "truncated to first {} bytes",
+            MAX_BINARY_READ_BYTES
+        )));
+        let encoded = result.lines().last().unwrap();
+        assert_eq!(BASE64.decode(encoded).unwrap().len(), MAX_BINARY_READ_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_truncates_oversized_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let size = MAX_READ_BYTES as usize + 100;
+        std::fs::write(temp_dir.path().join("huge.log"), "a".repeat(size)).unwrap();
+
+        let tool = ReadFileTool::new(temp_dir.path().to_path_buf());
+        let result = tool.call(read_args("huge.log", false)).await.unwrap();
+        assert!(result.ends_with(&format!(
+            "[File truncated: showing the first {} of {} bytes]",
+            MAX_READ_BYTES, size
+        )));
+        assert!(result.len() < size);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_escaping_workspace_is_rejected() {
+        let outside = tempfile::TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let root = workspace.path();
+        std::os::unix::fs::symlink(outside.path(), root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("gone.txt"), root.join("dangling")).unwrap();
+        std::fs::create_dir(root.join("inner")).unwrap();
+        std::os::unix::fs::symlink(root.join("inner"), root.join("alias")).unwrap();
+
+        let read = ReadFileTool::new(root.to_path_buf());
+        let err = read
+            .call(read_args("escape/secret.txt", false))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the workspace"));
+
+        let write = WriteFileTool::new(root.to_path_buf());
+        let write_to = |path: &str| {
+            write.call(WriteFileArgs {
+                path: path.to_string(),
+                content: "x".to_string(),
+                append: false,
+            })
+        };
+        assert!(write_to("escape/new.txt").await.is_err());
+        assert!(write_to("dangling").await.is_err());
+        assert!(!outside.path().join("new.txt").exists());
+        assert!(!outside.path().join("gone.txt").exists());
+
+        // Symlinks that stay inside the workspace keep working
+        write_to("alias/ok.txt").await.unwrap();
+        assert!(root.join("inner").join("ok.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_grep_does_not_follow_symlinks_while_recursing() {
+        let outside = tempfile::TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "needle outside").unwrap();
+        let workspace = tempfile::TempDir::new().unwrap();
+        let root = workspace.path();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src").join("main.rs"), "needle inside").unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.txt"), root.join("secret.txt"))
+            .unwrap();
+        // A cycle back to the workspace root
+        std::os::unix::fs::symlink(root, root.join("src").join("loop")).unwrap();
+
+        let grep = GrepTool::new(root.to_path_buf());
+        let result = grep.call(grep_args("needle", false, false)).await.unwrap();
+        assert!(result.contains("needle inside"), "{}", result);
+        assert!(!result.contains("needle outside"), "{}", result);
+        assert_eq!(result.lines().count(), 1, "{}", result);
+    }
 }
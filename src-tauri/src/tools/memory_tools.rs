@@ -1,10 +1,12 @@
 //! Memory tools for agent access to the long-term vector store.
 //!
-//! Provides three rig Tools that allow agents (main and sub-agents) to
+//! Provides five rig Tools that allow agents (main and sub-agents) to
 //! interact with the long-term memory system:
 //! - `SearchMemoryTool`: Semantic search over stored memories
 //! - `AddMemoryTool`: Store new facts/preferences/skills in long-term memory
 //! - `DeleteMemoryTool`: Remove memory entries by ID
+//! - `PinMemoryTool`: Protect critical entries from decay and deduplication
+//! - `ForgetMemoryTool`: Remove memories matching a natural-language description
 
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
@@ -15,6 +17,7 @@ use sqlx::{Pool, Sqlite};
 
 use crate::memory::collections;
 use crate::memory::fact_extraction::parse_memory_type;
+use crate::memory::long_term::normalize_tags;
 use crate::memory::{MemoryEntry, SharedLongTermMemory};
 
 // ---------------------------------------------------------------------------
@@ -43,6 +46,9 @@ pub struct SearchMemoryArgs {
     /// Optional collection name to search within (filters to a specific knowledge collection).
     #[serde(default)]
     collection: Option<String>,
+    /// Optional tags; only entries with at least one of them are returned.
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 fn default_limit() -> usize {
@@ -99,6 +105,11 @@ impl Tool for SearchMemoryTool {
                     "collection": {
                         "type": "string",
                         "description": "Optional: search only within a specific knowledge collection (by name)"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional: only return entries with at least one of these tags (e.g. [\"project-x\"])"
                     }
                 },
                 "required": ["query"]
@@ -134,6 +145,8 @@ impl Tool for SearchMemoryTool {
                 args.limit,
                 args.min_importance,
                 collection_id.as_deref(),
+                &args.tags,
+                None,
             )
             .await
             .map_err(|e| MemoryToolError(format!("Memory search failed: {}", e)))?;
@@ -144,13 +157,22 @@ impl Tool for SearchMemoryTool {
 
         let mut output = format!("Found {} matching memories:\n\n", results.len());
         for (i, (similarity, entry)) in results.iter().enumerate() {
+            let mut tag_info = if entry.tags.is_empty() {
+                String::new()
+            } else {
+                format!(", tags: {}", entry.tags.join(", "))
+            };
+            if entry.pinned {
+                tag_info.push_str(", pinned");
+            }
             output.push_str(&format!(
-                "{}. [{}] (type: {:?}, importance: {:.2}, similarity: {:.3})\n   {}\n\n",
+                "{}. [{}] (type: {:?}, importance: {:.2}, similarity: {:.3}{})\n   {}\n\n",
                 i + 1,
                 entry.id,
                 entry.entry_type,
                 entry.importance,
                 similarity,
+                tag_info,
                 entry.content,
             ));
         }
@@ -183,6 +205,12 @@ pub struct AddMemoryArgs {
     /// Optional collection name to add this entry to.
     #[serde(default)]
     collection: Option<String>,
+    /// Optional lifetime in minutes; the entry is forgotten afterwards.
+    #[serde(default)]
+    ttl_minutes: Option<u32>,
+    /// Optional tags for scoping later searches (e.g. a project name).
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 fn default_entry_type() -> String {
@@ -244,6 +272,15 @@ impl Tool for AddMemoryTool {
                     "collection": {
                         "type": "string",
                         "description": "Optional: add this entry to a specific knowledge collection (by name)"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional: tags to scope this entry (e.g. [\"project-x\"] or [\"personal\"]) so searches can filter by them"
+                    },
+                    "ttl_minutes": {
+                        "type": "integer",
+                        "description": "Optional: forget this entry after the given number of minutes (for short-lived information such as one-time codes)"
                     }
                 },
                 "required": ["content"]
@@ -276,18 +313,24 @@ impl Tool for AddMemoryTool {
 
         let memory_type = parse_memory_type(&args.entry_type);
         let importance = args.importance.clamp(0.0, 1.0);
+        let now = chrono::Utc::now();
+        let expires_at = args
+            .ttl_minutes
+            .map(|minutes| now + chrono::Duration::minutes(i64::from(minutes)));
 
         let entry = MemoryEntry {
             id: uuid::Uuid::new_v4().to_string(),
             content: args.content.clone(),
             entry_type: memory_type.clone(),
             importance,
-            created_at: chrono::Utc::now(),
-            last_accessed: chrono::Utc::now(),
+            created_at: now,
+            last_accessed: now,
             access_count: 0,
-            tags: Vec::new(),
+            tags: normalize_tags(&args.tags),
             source_message_ids: Vec::new(),
             collection_id: collection_id.clone(),
+            expires_at,
+            pinned: false,
         };
 
         let entry_id = entry.id.clone();
@@ -320,10 +363,15 @@ impl Tool for AddMemoryTool {
             collection_info
         );
 
+        let expiry_info = match expires_at {
+            Some(at) => format!(", expires: {}", at.format("%Y-%m-%d %H:%M UTC")),
+            None => String::new(),
+        };
+
         Ok(format!(
-            "Memory stored successfully (id: {}, type: {:?}, importance: {:.2}{}).\n\
+            "Memory stored successfully (id: {}, type: {:?}, importance: {:.2}{}{}).\n\
              Content: {}",
-            entry_id, memory_type, importance, collection_info, args.content
+            entry_id, memory_type, importance, collection_info, expiry_info, args.content
         ))
     }
 }
@@ -400,6 +448,250 @@ impl Tool for DeleteMemoryTool {
     }
 }
 
+// ---------------------------------------------------------------------------
+// PinMemoryTool
+// ---------------------------------------------------------------------------
+
+/// Arguments for pinning a memory entry.
+#[derive(Debug, Deserialize)]
+pub struct PinMemoryArgs {
+    /// The ID of the memory entry to pin or unpin.
+    entry_id: String,
+    /// `true` to pin (default), `false` to unpin.
+    #[serde(default = "default_pinned")]
+    pinned: bool,
+}
+
+fn default_pinned() -> bool {
+    true
+}
+
+/// rig Tool for pinning critical memories (e.g. the user's name), which are
+/// never down-ranked, deduplicated, or expired.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PinMemoryTool {
+    #[serde(skip)]
+    memory: Option<SharedLongTermMemory>,
+}
+
+impl PinMemoryTool {
+    pub fn new(memory: SharedLongTermMemory) -> Self {
+        Self {
+            memory: Some(memory),
+        }
+    }
+}
+
+impl Tool for PinMemoryTool {
+    const NAME: &'static str = "pin_memory";
+    type Error = MemoryToolError;
+    type Args = PinMemoryArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "pin_memory".to_string(),
+            description: "Pin (or unpin) a memory entry by its ID. Pinned memories \
+                always keep full weight in search results and are never merged \
+                away as duplicates or expired. Pin only critical facts, such as \
+                the user's name or key preferences. Use search_memory first to \
+                find the entry ID."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "entry_id": {
+                        "type": "string",
+                        "description": "The ID of the memory entry"
+                    },
+                    "pinned": {
+                        "type": "boolean",
+                        "description": "true to pin (default), false to unpin"
+                    }
+                },
+                "required": ["entry_id"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let memory = self
+            .memory
+            .as_ref()
+            .ok_or_else(|| MemoryToolError("Long-term memory not initialized".to_string()))?;
+
+        let mem = memory.lock().await;
+        mem.set_pinned(&args.entry_id, args.pinned)
+            .await
+            .map_err(|e| MemoryToolError(format!("Failed to pin memory: {}", e)))?;
+
+        tracing::info!(
+            "Agent set pinned={} for memory entry '{}'",
+            args.pinned,
+            args.entry_id
+        );
+
+        Ok(format!(
+            "Memory entry '{}' {}.",
+            args.entry_id,
+            if args.pinned { "pinned" } else { "unpinned" }
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ForgetMemoryTool
+// ---------------------------------------------------------------------------
+
+/// Default minimum similarity for a memory to be forgotten.
+const DEFAULT_FORGET_THRESHOLD: f32 = 0.6;
+
+/// Upper bound on how many memories a single forget call may delete.
+const MAX_FORGET_MATCHES: usize = 5;
+
+/// Number of candidates considered (and shown when nothing matches).
+const FORGET_CANDIDATE_LIMIT: usize = 5;
+
+/// Arguments for forgetting memories by description.
+#[derive(Debug, Deserialize)]
+pub struct ForgetMemoryArgs {
+    /// Natural-language description of what to forget.
+    description: String,
+    /// Maximum number of matching entries to delete (default: 1).
+    #[serde(default = "default_forget_matches")]
+    max_matches: usize,
+    /// Minimum similarity (0.0-1.0) an entry needs to be deleted.
+    #[serde(default)]
+    threshold: Option<f32>,
+}
+
+fn default_forget_matches() -> usize {
+    1
+}
+
+/// rig Tool that deletes the memories best matching a description
+/// (e.g. "forget that I live in Berlin").
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForgetMemoryTool {
+    #[serde(skip)]
+    memory: Option<SharedLongTermMemory>,
+}
+
+impl ForgetMemoryTool {
+    pub fn new(memory: SharedLongTermMemory) -> Self {
+        Self {
+            memory: Some(memory),
+        }
+    }
+}
+
+impl Tool for ForgetMemoryTool {
+    const NAME: &'static str = "forget_memory";
+    type Error = MemoryToolError;
+    type Args = ForgetMemoryArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: "forget_memory".to_string(),
+            description: "Forget memories matching a natural-language description. \
+                Use this when the user asks you to forget something (e.g. \"forget \
+                that I live in Berlin\"). Deletes the closest matches above a \
+                similarity threshold and reports what was deleted; if nothing is \
+                close enough, lists the nearest candidates instead."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "What to forget, described in natural language"
+                    },
+                    "max_matches": {
+                        "type": "integer",
+                        "description": format!("Maximum number of entries to delete (default: 1, max: {})", MAX_FORGET_MATCHES)
+                    },
+                    "threshold": {
+                        "type": "number",
+                        "description": format!("Minimum similarity 0.0-1.0 an entry needs to be deleted (default: {})", DEFAULT_FORGET_THRESHOLD)
+                    }
+                },
+                "required": ["description"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let memory = self
+            .memory
+            .as_ref()
+            .ok_or_else(|| MemoryToolError("Long-term memory not initialized".to_string()))?;
+
+        if args.description.trim().is_empty() {
+            return Err(MemoryToolError("Description must not be empty".to_string()));
+        }
+        let threshold = args
+            .threshold
+            .unwrap_or(DEFAULT_FORGET_THRESHOLD)
+            .clamp(0.0, 1.0);
+        let max_matches = args.max_matches.clamp(1, MAX_FORGET_MATCHES);
+
+        let mut mem = memory.lock().await;
+        let candidates = mem
+            .recall(&args.description, FORGET_CANDIDATE_LIMIT, 0.0)
+            .await
+            .map_err(|e| MemoryToolError(format!("Failed to search memory: {}", e)))?;
+
+        if candidates.is_empty() {
+            return Ok("No memories found matching that description.".to_string());
+        }
+
+        let (matches, rest): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .enumerate()
+            .partition(|(i, (similarity, _))| *i < max_matches && *similarity >= threshold);
+
+        if matches.is_empty() {
+            let listing: Vec<String> = rest
+                .iter()
+                .map(|(_, (similarity, entry))| {
+                    format!(
+                        "- [{}] {} (similarity: {:.2})",
+                        entry.id, entry.content, similarity
+                    )
+                })
+                .collect();
+            return Ok(format!(
+                "No memory matched closely enough (threshold {:.2}); nothing was deleted. \
+                 Closest candidates:\n{}\n\
+                 Use delete_memory with an ID if one of these should be removed.",
+                threshold,
+                listing.join("\n")
+            ));
+        }
+
+        let mut deleted = Vec::with_capacity(matches.len());
+        for (_, (similarity, entry)) in matches {
+            mem.delete(&entry.id)
+                .await
+                .map_err(|e| MemoryToolError(format!("Failed to delete memory: {}", e)))?;
+            tracing::info!(
+                "Agent forgot memory entry '{}' (similarity: {:.3})",
+                entry.id,
+                similarity
+            );
+            deleted.push(format!("- [{}] {}", entry.id, entry.content));
+        }
+
+        Ok(format!(
+            "Forgot {} memory entr{}:\n{}",
+            deleted.len(),
+            if deleted.len() == 1 { "y" } else { "ies" },
+            deleted.join("\n")
+        ))
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -423,6 +715,16 @@ mod tests {
         assert_eq!(DeleteMemoryTool::NAME, "delete_memory");
     }
 
+    #[test]
+    fn test_pin_memory_tool_name() {
+        assert_eq!(PinMemoryTool::NAME, "pin_memory");
+    }
+
+    #[test]
+    fn test_forget_memory_tool_name() {
+        assert_eq!(ForgetMemoryTool::NAME, "forget_memory");
+    }
+
     #[tokio::test]
     async fn test_search_memory_no_init() {
         let tool = SearchMemoryTool {
@@ -436,6 +738,7 @@ mod tests {
                 limit: 5,
                 min_importance: 0.0,
                 collection: None,
+                tags: Vec::new(),
             })
             .await;
 
@@ -456,6 +759,8 @@ mod tests {
                 entry_type: "fact".to_string(),
                 importance: 0.5,
                 collection: None,
+                ttl_minutes: None,
+                tags: Vec::new(),
             })
             .await;
 
@@ -509,4 +814,50 @@ mod tests {
         assert_eq!(def.name, "delete_memory");
         assert!(def.description.contains("Delete"));
     }
+
+    #[tokio::test]
+    #[ignore] // Requires fastembed model download (slow, ~1GB)
+    async fn test_forget_memory_removes_described_fact() {
+        let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&db).await.unwrap();
+        let memory: SharedLongTermMemory = std::sync::Arc::new(tokio::sync::Mutex::new(
+            crate::memory::LongTermMemory::new(db.clone())
+                .await
+                .unwrap(),
+        ));
+
+        let add = AddMemoryTool::new(memory.clone(), db);
+        for (content, entry_type) in [
+            ("User lives in Berlin", "fact"),
+            ("User prefers dark mode in all editors", "preference"),
+        ] {
+            add.call(AddMemoryArgs {
+                content: content.to_string(),
+                entry_type: entry_type.to_string(),
+                importance: 0.7,
+                collection: None,
+                ttl_minutes: None,
+                tags: Vec::new(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let output = ForgetMemoryTool::new(memory.clone())
+            .call(ForgetMemoryArgs {
+                description: "where the user lives".to_string(),
+                max_matches: 1,
+                threshold: Some(0.3),
+            })
+            .await
+            .unwrap();
+        assert!(output.contains("User lives in Berlin"));
+
+        let mut mem = memory.lock().await;
+        assert_eq!(mem.count().await.unwrap(), 1);
+        let remaining = mem.recall("dark mode", 5, 0.0).await.unwrap();
+        assert!(remaining
+            .iter()
+            .all(|(_, e)| e.content != "User lives in Berlin"));
+    }
 }
@@ -1,8 +1,10 @@
 pub mod code_generation;
 pub mod collection_tools;
+pub mod confirmation;
 pub mod filesystem;
 pub mod memory_tools;
 pub mod planning;
+pub mod read_only;
 pub mod registry;
 pub mod rhai_bridge_tool;
 pub mod rhai_engine;
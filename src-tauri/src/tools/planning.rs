@@ -1,10 +1,16 @@
+use anyhow::Context;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sqlx::{Pool, Row, Sqlite};
 use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
 use tokio::sync::RwLock;
 
+/// Event emitted with the full `TodoList` whenever `write_todos` changes it
+pub const PLANNING_UPDATED_EVENT: &str = "planning:updated";
+
 // ---------------------------------------------------------------------------
 // Data structures
 // ---------------------------------------------------------------------------
@@ -78,6 +84,68 @@ pub fn create_shared_todo_list() -> SharedTodoList {
     Arc::new(RwLock::new(None))
 }
 
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+/// Save the instance's active TODO list, replacing any previous one.
+pub async fn save_todo_list(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    list: &TodoList,
+) -> anyhow::Result<()> {
+    let items = serde_json::to_string(&list.items).context("Failed to serialize TODO items")?;
+    sqlx::query(
+        r#"
+        INSERT INTO todos (instance_id, context, items, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(instance_id) DO UPDATE SET
+            context = excluded.context,
+            items = excluded.items,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(instance_id)
+    .bind(&list.context)
+    .bind(items)
+    .bind(chrono::Utc::now())
+    .execute(db)
+    .await
+    .context("Failed to save TODO list")?;
+    Ok(())
+}
+
+/// Load the instance's persisted TODO list, if any.
+pub async fn load_todo_list(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+) -> anyhow::Result<Option<TodoList>> {
+    let row = sqlx::query("SELECT context, items FROM todos WHERE instance_id = ?")
+        .bind(instance_id)
+        .fetch_optional(db)
+        .await
+        .context("Failed to load TODO list")?;
+
+    row.map(|row| {
+        let items: String = row.get("items");
+        Ok(TodoList {
+            context: row.get("context"),
+            items: serde_json::from_str(&items).context("Invalid TODO items in database")?,
+        })
+    })
+    .transpose()
+}
+
+/// Create the shared TODO list for an agent, restoring the persisted list.
+/// Load failures are logged and start with an empty list.
+pub async fn load_shared_todo_list(db: &Pool<Sqlite>, instance_id: &str) -> SharedTodoList {
+    let list = load_todo_list(db, instance_id).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to restore TODO list: {}", e);
+        None
+    });
+    Arc::new(RwLock::new(list))
+}
+
 // ---------------------------------------------------------------------------
 // Tool error
 // ---------------------------------------------------------------------------
@@ -173,14 +241,33 @@ pub struct TodoUpdate {
 pub struct WriteTodosTool {
     #[serde(skip)]
     current_list: Option<SharedTodoList>,
+    /// Used to emit `planning:updated` so the UI can render the plan live
+    #[serde(skip)]
+    app_handle: Option<AppHandle>,
+    /// Database and instance the list is saved to after every change
+    #[serde(skip)]
+    persistence: Option<(Pool<Sqlite>, String)>,
 }
 
 impl WriteTodosTool {
-    pub fn new(shared_list: SharedTodoList) -> Self {
+    pub fn new(shared_list: SharedTodoList, app_handle: Option<AppHandle>) -> Self {
         Self {
             current_list: Some(shared_list),
+            app_handle,
+            persistence: None,
         }
     }
+
+    /// Persist the list to the instance database on every change.
+    pub fn with_persistence(mut self, db: Pool<Sqlite>, instance_id: &str) -> Self {
+        self.persistence = Some((db, instance_id.to_string()));
+        self
+    }
+}
+
+/// Payload of the `planning:updated` event: the full list with statuses.
+fn planning_updated_payload(list: &TodoList) -> serde_json::Value {
+    serde_json::to_value(list).unwrap_or_default()
 }
 
 impl Tool for WriteTodosTool {
@@ -284,6 +371,19 @@ impl Tool for WriteTodosTool {
             }
         }
 
+        if let Some((db, instance_id)) = &self.persistence {
+            if let Err(e) = save_todo_list(db, instance_id, list).await {
+                tracing::warn!("Failed to persist TODO list: {}", e);
+            }
+        }
+
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(PLANNING_UPDATED_EVENT, planning_updated_payload(list))
+            {
+                tracing::warn!("Failed to emit planning:updated event: {}", e);
+            }
+        }
+
         Ok(format!("TODO list updated:\n\n{}", list.to_markdown()))
     }
 }
@@ -341,7 +441,7 @@ mod tests {
         let shared = create_shared_todo_list();
 
         // Populate via WriteTodosTool
-        let write_tool = WriteTodosTool::new(shared.clone());
+        let write_tool = WriteTodosTool::new(shared.clone(), None);
         let args = WriteTodosArgs {
             context: "Test project".to_string(),
             todos: vec![NewTodoItem {
@@ -360,6 +460,108 @@ mod tests {
         assert!(result.contains("[P5]"));
     }
 
+    #[tokio::test]
+    async fn test_planning_updated_payload() {
+        let shared = create_shared_todo_list();
+        let write_tool = WriteTodosTool::new(shared.clone(), None);
+        let args = WriteTodosArgs {
+            context: "Release".to_string(),
+            todos: vec![
+                NewTodoItem {
+                    description: "Write changelog".to_string(),
+                    priority: 4,
+                },
+                NewTodoItem {
+                    description: "Tag version".to_string(),
+                    priority: 3,
+                },
+            ],
+            updates: vec![],
+        };
+        Tool::call(&write_tool, args).await.unwrap();
+        let args = WriteTodosArgs {
+            context: "Release".to_string(),
+            todos: vec![],
+            updates: vec![TodoUpdate {
+                id: "todo-1".to_string(),
+                status: TodoStatus::InProgress,
+            }],
+        };
+        Tool::call(&write_tool, args).await.unwrap();
+
+        let guard = shared.read().await;
+        let payload = planning_updated_payload(guard.as_ref().unwrap());
+        assert_eq!(payload["context"], "Release");
+        assert_eq!(payload["items"][0]["id"], "todo-1");
+        assert_eq!(payload["items"][0]["status"], "in_progress");
+        assert_eq!(payload["items"][1]["status"], "pending");
+        assert_eq!(payload["items"][1]["priority"], 3);
+    }
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_todo_list_save_load_roundtrip() {
+        let db = setup_test_db().await;
+        assert!(load_todo_list(&db, "inst-1").await.unwrap().is_none());
+
+        let mut list = TodoList::new("Migrate database".to_string());
+        for (i, status) in [
+            TodoStatus::Completed,
+            TodoStatus::InProgress,
+            TodoStatus::Blocked,
+            TodoStatus::Pending,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            list.items.push(TodoItem {
+                id: format!("todo-{}", i + 1),
+                description: format!("Step {}", i + 1),
+                status,
+                priority: (i + 1) as u8,
+            });
+        }
+        save_todo_list(&db, "inst-1", &list).await.unwrap();
+
+        let loaded = load_todo_list(&db, "inst-1").await.unwrap().unwrap();
+        assert_eq!(loaded.context, "Migrate database");
+        assert_eq!(loaded.items.len(), 4);
+        assert_eq!(loaded.items[1].status, TodoStatus::InProgress);
+        assert_eq!(loaded.items[2].status, TodoStatus::Blocked);
+        assert_eq!(loaded.items[3].priority, 4);
+        assert!(load_todo_list(&db, "inst-2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_write_todos_persists_list() {
+        let db = setup_test_db().await;
+        let write_tool = WriteTodosTool::new(create_shared_todo_list(), None)
+            .with_persistence(db.clone(), "inst-1");
+        let args = WriteTodosArgs {
+            context: "Plan".to_string(),
+            todos: vec![NewTodoItem {
+                description: "Survive restart".to_string(),
+                priority: 2,
+            }],
+            updates: vec![],
+        };
+        Tool::call(&write_tool, args).await.unwrap();
+
+        // A rebuilt agent restores the list
+        let restored = load_shared_todo_list(&db, "inst-1").await;
+        let guard = restored.read().await;
+        let list = guard.as_ref().unwrap();
+        assert_eq!(list.items[0].description, "Survive restart");
+        assert_eq!(list.items[0].status, TodoStatus::Pending);
+    }
+
     #[tokio::test]
     async fn test_read_todos_definition() {
         let shared = create_shared_todo_list();
@@ -0,0 +1,132 @@
+//! Read-only safe mode.
+//!
+//! Instances with `read_only` set get no tools that change the workspace,
+//! programs, dynamic tools, memory, knowledge collections, or scheduled
+//! tasks. Reading, searching, and listing stay available. Dynamic tools
+//! cannot run at all (neither as a tool nor via the Canvas `executeTool`
+//! bridge), since their Rhai sandbox can write to the workspace. Canvas
+//! programs likewise cannot use the bridge methods that write files or
+//! program data.
+
+use rig::tool::ToolDyn;
+
+/// Tools omitted from the tool set of read-only agents
+pub const WRITE_TOOLS: &[&str] = &[
+    // Filesystem
+    "write_file",
+    "edit_file",
+    "move_file",
+    "delete_file",
+    // Self-programming (the Rhai sandbox has write_file / append_file)
+    "execute_dynamic_tool",
+    "create_tool",
+    "update_tool",
+    // Canvas programs
+    "create_program",
+    "rename_program",
+    "clone_program",
+    "program_write_file",
+    "program_write_file_base64",
+    "program_edit_file",
+    // Memory and knowledge collections
+    "add_memory",
+    "delete_memory",
+    "forget_memory",
+    "pin_memory",
+    "create_knowledge_collection",
+    "delete_knowledge_collection",
+    "ingest_document",
+    // Scheduled tasks
+    "create_scheduled_task",
+    "delete_scheduled_task",
+];
+
+/// Canvas bridge methods refused for read-only instances
+pub const WRITE_BRIDGE_METHODS: &[&str] = &[
+    "writeFile",
+    "writeFileBase64",
+    "storeData",
+    "deleteData",
+    "executeTool",
+];
+
+/// Error message for a bridge `method` that a read-only instance refuses,
+/// or `None` if the call may go ahead.
+pub fn refuse_bridge_method(method: &str, read_only: bool) -> Option<String> {
+    (read_only && WRITE_BRIDGE_METHODS.contains(&method)).then(|| {
+        format!(
+            "'{}' is not available while the instance is in read-only mode",
+            method
+        )
+    })
+}
+
+/// Note appended to the system prompt of read-only agents
+pub const READ_ONLY_PROMPT_NOTE: &str = "## Read-Only Mode\n\n\
+    This instance runs in read-only mode. You can read, search, and list files, \
+    programs, and memories, but you cannot create, change, or delete anything. \
+    If the user asks for a change, explain that read-only mode is enabled.";
+
+/// Remove every tool listed in `WRITE_TOOLS` from `tools`.
+pub fn without_write_tools(mut tools: Vec<Box<dyn ToolDyn>>) -> Vec<Box<dyn ToolDyn>> {
+    tools.retain(|tool| !WRITE_TOOLS.contains(&tool.name().as_str()));
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::filesystem::{GrepTool, LsTool, ReadFileTool, WriteFileTool};
+    use crate::tools::registry::RhaiToolRegistry;
+    use crate::tools::rhai_bridge_tool::RhaiExecuteTool;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_write_tools_are_removed() {
+        let workspace = PathBuf::from("/tmp");
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let registry = RhaiToolRegistry::new(pool, workspace.clone(), None, None);
+        let tools: Vec<Box<dyn ToolDyn>> = vec![
+            Box::new(LsTool::new(workspace.clone())),
+            Box::new(ReadFileTool::new(workspace.clone())),
+            Box::new(WriteFileTool::new(workspace.clone())),
+            Box::new(RhaiExecuteTool::new(
+                Arc::new(tokio::sync::RwLock::new(registry)),
+                vec![],
+            )),
+            Box::new(GrepTool::new(workspace)),
+        ];
+
+        let names: Vec<String> = without_write_tools(tools)
+            .iter()
+            .map(|tool| tool.name())
+            .collect();
+        assert_eq!(names, vec!["ls", "read_file", "grep"]);
+    }
+
+    #[test]
+    fn test_write_bridge_methods_are_refused() {
+        for method in [
+            "writeFile",
+            "writeFileBase64",
+            "storeData",
+            "deleteData",
+            "executeTool",
+        ] {
+            let error = refuse_bridge_method(method, true).expect(method);
+            assert!(error.contains("read-only mode"), "{}", error);
+            assert!(refuse_bridge_method(method, false).is_none());
+        }
+        for method in [
+            "readFile",
+            "loadData",
+            "listKeys",
+            "getMetadata",
+            "chat",
+            "notify",
+        ] {
+            assert!(refuse_bridge_method(method, true).is_none(), "{}", method);
+        }
+    }
+}
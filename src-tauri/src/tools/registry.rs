@@ -10,10 +10,14 @@ use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Row, Sqlite};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
 use tauri::AppHandle;
 
-use super::rhai_engine::create_sandboxed_engine;
+use super::rhai_engine::{
+    create_sandboxed_engine, format_script_result, normalize_domains, register_embedder,
+    with_allowed_domains, with_cancel_flag, Embedder,
+};
 
 // ---------------------------------------------------------------------------
 // Types
@@ -35,6 +39,12 @@ pub struct ToolRecord {
     pub success_count: i32,
     pub failure_count: i32,
     pub parent_tool_id: Option<String>,
+    /// HTTP hosts the tool may reach (subdomains included); `None` = any host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    /// Grouping label for the UI (e.g. "web", "data"); `None` = uncategorized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 /// Lifecycle status of a tool.
@@ -75,6 +85,37 @@ pub struct ParameterDef {
     pub required: bool,
 }
 
+/// Usage statistics of a single tool, as returned by `usage_report`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolUsageSummary {
+    pub name: String,
+    pub version: String,
+    pub status: ToolStatus,
+    pub usage_count: i32,
+    pub success_count: i32,
+    pub failure_count: i32,
+    /// Share of successful executions (0.0 - 1.0), `None` if never used
+    pub success_rate: Option<f64>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl From<&ToolRecord> for ToolUsageSummary {
+    fn from(tool: &ToolRecord) -> Self {
+        let success_rate = (tool.usage_count > 0)
+            .then(|| f64::from(tool.success_count) / f64::from(tool.usage_count));
+        Self {
+            name: tool.name.clone(),
+            version: tool.version.clone(),
+            status: tool.status.clone(),
+            usage_count: tool.usage_count,
+            success_count: tool.success_count,
+            failure_count: tool.failure_count,
+            success_rate,
+            last_used: tool.last_used,
+        }
+    }
+}
+
 /// Record of a single tool execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolExecutionRecord {
@@ -93,6 +134,12 @@ pub struct ToolExecutionRecord {
 // ---------------------------------------------------------------------------
 
 /// Increment a semver-like version string (e.g. "1.0.0" -> "1.1.0").
+/// Trim and lowercase a category name; empty names mean "no category".
+pub fn normalize_category(category: &str) -> Option<String> {
+    let category = category.trim().to_lowercase();
+    (!category.is_empty()).then_some(category)
+}
+
 /// Increments the minor version. Falls back to appending ".1" on parse failure.
 fn increment_version(version: &str) -> String {
     let parts: Vec<&str> = version.split('.').collect();
@@ -106,6 +153,159 @@ fn increment_version(version: &str) -> String {
     }
 }
 
+/// Loose check of a JSON value against a parameter's `type_hint`.
+/// Scalars may be passed as strings (e.g. "42" for a number) since models
+/// often quote them; unknown hints accept any value.
+fn matches_type_hint(value: &serde_json::Value, type_hint: &str) -> bool {
+    use serde_json::Value;
+    match type_hint.trim().to_lowercase().as_str() {
+        "string" | "str" | "text" => !matches!(value, Value::Array(_) | Value::Object(_)),
+        "number" | "integer" | "int" | "float" => match value {
+            Value::Number(_) => true,
+            Value::String(s) => s.trim().parse::<f64>().is_ok(),
+            _ => false,
+        },
+        "boolean" | "bool" => match value {
+            Value::Bool(_) => true,
+            Value::String(s) => matches!(s.trim(), "true" | "false"),
+            _ => false,
+        },
+        "array" | "list" => value.is_array(),
+        "object" | "map" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// JSON type name for error messages.
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+    use serde_json::Value;
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Validate call parameters against a tool's parameter definitions:
+/// required parameters must be present (and not null), and present
+/// parameters must loosely match their type hint. Unknown extra parameters
+/// are allowed.
+pub fn validate_params(definitions: &[ParameterDef], params: &serde_json::Value) -> Result<()> {
+    let empty = serde_json::Map::new();
+    let values = match params {
+        serde_json::Value::Object(map) => map,
+        serde_json::Value::Null => &empty,
+        other => {
+            return Err(anyhow::anyhow!(
+                "Parameters must be a JSON object, got {}",
+                json_type_name(other)
+            ))
+        }
+    };
+
+    for def in definitions {
+        match values.get(&def.name) {
+            None | Some(serde_json::Value::Null) => {
+                if def.required {
+                    return Err(anyhow::anyhow!("Missing required parameter '{}'", def.name));
+                }
+            }
+            Some(value) => {
+                if !matches_type_hint(value, &def.type_hint) {
+                    return Err(anyhow::anyhow!(
+                        "Parameter '{}' should be of type {}, got {}",
+                        def.name,
+                        def.type_hint,
+                        json_type_name(value)
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Running executions
+// ---------------------------------------------------------------------------
+
+/// A tool script currently executing, cancellable via `cancel_tool_executions`.
+struct RunningExecution {
+    instance_id: String,
+    tool_name: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Running executions across all instances, keyed by execution ID. Global
+/// because the registry itself stays locked while a script runs.
+static RUNNING_EXECUTIONS: LazyLock<std::sync::Mutex<HashMap<u64, RunningExecution>>> =
+    LazyLock::new(Default::default);
+static NEXT_EXECUTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Removes its execution from `RUNNING_EXECUTIONS` when dropped.
+struct ExecutionGuard(u64);
+
+impl Drop for ExecutionGuard {
+    fn drop(&mut self) {
+        running_executions().remove(&self.0);
+    }
+}
+
+fn running_executions() -> std::sync::MutexGuard<'static, HashMap<u64, RunningExecution>> {
+    RUNNING_EXECUTIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Track a new execution; the returned flag stops the script when set.
+fn start_execution(instance_id: &str, tool_name: &str) -> (ExecutionGuard, Arc<AtomicBool>) {
+    let id = NEXT_EXECUTION_ID.fetch_add(1, Ordering::Relaxed);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    running_executions().insert(
+        id,
+        RunningExecution {
+            instance_id: instance_id.to_string(),
+            tool_name: tool_name.to_string(),
+            cancelled: cancelled.clone(),
+        },
+    );
+    (ExecutionGuard(id), cancelled)
+}
+
+/// Cancel the running executions of an instance, optionally only those of
+/// one tool. Returns how many were cancelled.
+pub fn cancel_tool_executions(instance_id: &str, tool_name: Option<&str>) -> usize {
+    let executions = running_executions();
+    let mut count = 0;
+    for execution in executions.values() {
+        if execution.instance_id == instance_id
+            && tool_name.is_none_or(|name| execution.tool_name == name)
+        {
+            execution.cancelled.store(true, Ordering::Relaxed);
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Normalized allowed domains (an empty list lifts the restriction) and
+/// their JSON form for the `allowed_domains` column.
+fn domains_for_storage(
+    allowed_domains: Option<Vec<String>>,
+) -> Result<(Option<Vec<String>>, Option<String>)> {
+    let domains = allowed_domains
+        .map(|d| normalize_domains(&d))
+        .filter(|d| !d.is_empty());
+    let domains_json = domains
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("Failed to serialize allowed domains")?;
+    Ok((domains, domains_json))
+}
+
 // ---------------------------------------------------------------------------
 // Registry
 // ---------------------------------------------------------------------------
@@ -113,8 +313,12 @@ fn increment_version(version: &str) -> String {
 /// Manages dynamic Rhai tool lifecycle: register, compile, cache, execute.
 pub struct RhaiToolRegistry {
     engine: Engine,
-    compiled_cache: HashMap<String, Arc<AST>>,
+    /// Behind a mutex so tools can be executed through a shared reference
+    /// (and a read lock on `SharedRegistry`) while they run
+    compiled_cache: std::sync::Mutex<HashMap<String, Arc<AST>>>,
     db: Pool<Sqlite>,
+    /// Owning instance, used to scope `cancel_tool_executions`
+    instance_id: String,
 }
 
 impl RhaiToolRegistry {
@@ -131,18 +335,43 @@ impl RhaiToolRegistry {
         let engine = create_sandboxed_engine(workspace, app_handle, instance_name);
         Self {
             engine,
-            compiled_cache: HashMap::new(),
+            compiled_cache: std::sync::Mutex::new(HashMap::new()),
             db,
+            instance_id: String::new(),
         }
     }
 
+    /// Set the owning instance so its running tools can be cancelled.
+    pub fn with_instance_id(mut self, instance_id: &str) -> Self {
+        self.instance_id = instance_id.to_string();
+        self
+    }
+
+    /// Compiled scripts by tool name.
+    fn cache(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<AST>>> {
+        self.compiled_cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make `embed_text(text)` available to tool scripts.
+    pub fn with_embedder(mut self, embedder: Embedder) -> Self {
+        register_embedder(&mut self.engine, embedder);
+        self
+    }
+
     /// Register a new tool: validate the script, store in DB, and cache the compiled AST.
+    /// `allowed_domains` restricts the HTTP hosts the tool may reach (`None`
+    /// for any host) and is stored with the tool, as is `category` (see
+    /// `normalize_category`).
     pub async fn register_tool(
         &mut self,
         name: &str,
         description: &str,
         script_content: &str,
         parameters: Vec<ParameterDef>,
+        allowed_domains: Option<Vec<String>>,
+        category: Option<&str>,
     ) -> Result<ToolRecord> {
         // Validate: compile the script to check for syntax errors
         let ast = self
@@ -153,12 +382,15 @@ impl RhaiToolRegistry {
         let id = uuid::Uuid::new_v4().to_string();
         let params_json =
             serde_json::to_string(&parameters).context("Failed to serialize parameters")?;
+        let (allowed_domains, domains_json) = domains_for_storage(allowed_domains)?;
+        let category = category.and_then(normalize_category);
         let now = Utc::now();
 
         sqlx::query(
             r#"
-            INSERT INTO tools (id, name, description, version, script_content, parameters, status, created_at)
-            VALUES (?, ?, ?, '1.0.0', ?, ?, 'active', ?)
+            INSERT INTO tools (id, name, description, version, script_content, parameters, status,
+                               created_at, allowed_domains, category)
+            VALUES (?, ?, ?, '1.0.0', ?, ?, 'active', ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -167,12 +399,14 @@ impl RhaiToolRegistry {
         .bind(script_content)
         .bind(&params_json)
         .bind(now)
+        .bind(&domains_json)
+        .bind(&category)
         .execute(&self.db)
         .await
         .context("Failed to insert tool into database")?;
 
         // Cache compiled AST
-        self.compiled_cache.insert(name.to_string(), Arc::new(ast));
+        self.cache().insert(name.to_string(), Arc::new(ast));
 
         tracing::info!("Registered dynamic tool '{}' (id: {})", name, id);
 
@@ -190,6 +424,8 @@ impl RhaiToolRegistry {
             success_count: 0,
             failure_count: 0,
             parent_tool_id: None,
+            allowed_domains,
+            category,
         })
     }
 
@@ -197,7 +433,7 @@ impl RhaiToolRegistry {
     ///
     /// The parameters are injected as a Rhai scope variable named `params`.
     /// The script's last expression value is returned as a string.
-    pub async fn execute_tool(&mut self, name: &str, params: serde_json::Value) -> Result<String> {
+    pub async fn execute_tool(&self, name: &str, params: serde_json::Value) -> Result<String> {
         let start = std::time::Instant::now();
 
         // Look up the tool in DB
@@ -210,16 +446,21 @@ impl RhaiToolRegistry {
             return Err(anyhow::anyhow!("Tool '{}' is deprecated", name));
         }
 
+        // Reject bad input before the script runs, with a clear message
+        validate_params(&tool.parameters, &params)
+            .map_err(|e| anyhow::anyhow!("Invalid parameters for tool '{}': {}", name, e))?;
+
         // Get or compile the AST
-        let ast = if let Some(cached) = self.compiled_cache.get(name) {
-            cached.clone()
+        let cached = self.cache().get(name).cloned();
+        let ast = if let Some(cached) = cached {
+            cached
         } else {
             let compiled = self
                 .engine
                 .compile(&tool.script_content)
                 .map_err(|e| anyhow::anyhow!("Script compilation failed: {}", e))?;
             let arc = Arc::new(compiled);
-            self.compiled_cache.insert(name.to_string(), arc.clone());
+            self.cache().insert(name.to_string(), arc.clone());
             arc
         };
 
@@ -231,19 +472,25 @@ impl RhaiToolRegistry {
         // Execute the script inside block_in_place so that synchronous
         // blocking operations (e.g. reqwest::blocking in Rhai HTTP helpers)
         // do not panic when they create/drop their own tokio runtime.
+        // HTTP helpers only reach the tool's allowed hosts during this run,
+        // and the script stops early once the execution is cancelled.
+        let (_guard, cancelled) = start_execution(&self.instance_id, name);
         let result = tokio::task::block_in_place(|| {
-            self.engine
-                .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &ast)
+            with_cancel_flag(cancelled, || {
+                with_allowed_domains(tool.allowed_domains.as_deref(), || {
+                    self.engine
+                        .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &ast)
+                })
+            })
         });
 
         let elapsed_ms = start.elapsed().as_millis() as i64;
 
         // Build execution record
+        // Maps and arrays are returned as JSON so the agent can read them directly
+        let result = result.map(format_script_result);
         let (success, output, error_message) = match &result {
-            Ok(val) => {
-                let output_str = format!("{}", val);
-                (true, Some(output_str), None)
-            }
+            Ok(output_str) => (true, Some(output_str.clone()), None),
             Err(e) => (false, None, Some(format!("{}", e))),
         };
 
@@ -261,26 +508,32 @@ impl RhaiToolRegistry {
         // Update usage stats
         self.update_usage_stats(&tool.id, success).await?;
 
-        match result {
-            Ok(val) => Ok(format!("{}", val)),
-            Err(e) => Err(anyhow::anyhow!("Script execution failed: {}", e)),
-        }
+        result.map_err(|e| anyhow::anyhow!("Script execution failed: {}", e))
     }
 
     /// List all tools with the given status filter. If `None`, lists all active tools.
-    pub async fn list_tools(&self, status: Option<ToolStatus>) -> Result<Vec<ToolRecord>> {
+    /// `category` optionally restricts the list to one category.
+    pub async fn list_tools(
+        &self,
+        status: Option<ToolStatus>,
+        category: Option<&str>,
+    ) -> Result<Vec<ToolRecord>> {
         let status_filter = status.unwrap_or(ToolStatus::Active).to_string();
+        let category = category.and_then(normalize_category);
 
         let rows = sqlx::query(
             r#"
             SELECT id, name, description, version, script_content, parameters, status,
-                   created_at, last_used, usage_count, success_count, failure_count, parent_tool_id
+                   created_at, last_used, usage_count, success_count, failure_count, parent_tool_id,
+                   allowed_domains, category
             FROM tools
-            WHERE status = ?
+            WHERE status = ? AND (? IS NULL OR category = ?)
             ORDER BY name
             "#,
         )
         .bind(&status_filter)
+        .bind(&category)
+        .bind(&category)
         .fetch_all(&self.db)
         .await
         .context("Failed to list tools")?;
@@ -293,12 +546,33 @@ impl RhaiToolRegistry {
         Ok(tools)
     }
 
+    /// Usage statistics of all tools (any status), most used first.
+    pub async fn usage_report(&self) -> Result<Vec<ToolUsageSummary>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, description, version, script_content, parameters, status,
+                   created_at, last_used, usage_count, success_count, failure_count, parent_tool_id,
+                   allowed_domains, category
+            FROM tools
+            ORDER BY usage_count DESC, name
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to load tool usage")?;
+
+        rows.into_iter()
+            .map(|row| Ok(ToolUsageSummary::from(&self.row_to_tool_record(row)?)))
+            .collect()
+    }
+
     /// Get a single tool by name.
     pub async fn get_tool(&self, name: &str) -> Result<Option<ToolRecord>> {
         let row = sqlx::query(
             r#"
             SELECT id, name, description, version, script_content, parameters, status,
-                   created_at, last_used, usage_count, success_count, failure_count, parent_tool_id
+                   created_at, last_used, usage_count, success_count, failure_count, parent_tool_id,
+                   allowed_domains, category
             FROM tools
             WHERE name = ?
             "#,
@@ -323,14 +597,160 @@ impl RhaiToolRegistry {
             .await
             .context("Failed to deprecate tool")?;
 
-        self.compiled_cache.remove(name);
+        self.cache().remove(name);
         tracing::info!("Deprecated dynamic tool '{}'", name);
         Ok(())
     }
 
+    /// Bring a deprecated tool back: sets its status to active and
+    /// re-compiles and caches its script.
+    /// Fails if the tool is not deprecated, if its script no longer compiles,
+    /// or if another non-deprecated tool already uses the same name.
+    pub async fn reactivate_tool(&mut self, name: &str) -> Result<ToolRecord> {
+        let tool = self
+            .get_tool(name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
+
+        if tool.status != ToolStatus::Deprecated {
+            return Err(anyhow::anyhow!("Tool '{}' is not deprecated", name));
+        }
+
+        let conflicts: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM tools WHERE name = ? AND id != ? AND status != 'deprecated'",
+        )
+        .bind(name)
+        .bind(&tool.id)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to check for conflicting tools")?;
+        if conflicts > 0 {
+            return Err(anyhow::anyhow!(
+                "Cannot reactivate '{}': another active tool with the same name exists",
+                name
+            ));
+        }
+
+        let ast = self
+            .engine
+            .compile(&tool.script_content)
+            .map_err(|e| anyhow::anyhow!("Script compilation failed: {}", e))?;
+
+        sqlx::query("UPDATE tools SET status = 'active' WHERE id = ?")
+            .bind(&tool.id)
+            .execute(&self.db)
+            .await
+            .context("Failed to reactivate tool")?;
+
+        self.cache().insert(name.to_string(), Arc::new(ast));
+        tracing::info!("Reactivated dynamic tool '{}'", name);
+
+        Ok(ToolRecord {
+            status: ToolStatus::Active,
+            ..tool
+        })
+    }
+
+    /// Permanently delete a deprecated tool and its execution log.
+    /// Returns the number of deleted execution records. Active tools must be
+    /// deprecated with `delete_tool` first.
+    pub async fn purge_tool(&mut self, name: &str) -> Result<u64> {
+        let tool = self
+            .get_tool(name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", name))?;
+
+        if tool.status != ToolStatus::Deprecated {
+            return Err(anyhow::anyhow!(
+                "Tool '{}' must be deleted (deprecated) before it can be purged",
+                name
+            ));
+        }
+
+        // Execution logs reference the tool row, so they go first
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+        let executions = sqlx::query("DELETE FROM tool_executions WHERE tool_id = ?")
+            .bind(&tool.id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete tool executions")?
+            .rows_affected();
+        sqlx::query("DELETE FROM tools WHERE id = ?")
+            .bind(&tool.id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete tool")?;
+        tx.commit().await.context("Failed to commit tool purge")?;
+
+        self.cache().remove(name);
+        tracing::info!(
+            "Purged dynamic tool '{}' ({} execution records)",
+            name,
+            executions
+        );
+        Ok(executions)
+    }
+
+    /// Restrict the HTTP hosts a tool may reach (`None` lifts the restriction).
+    pub async fn set_allowed_domains(
+        &mut self,
+        name: &str,
+        allowed_domains: Option<Vec<String>>,
+    ) -> Result<()> {
+        let (_, domains_json) = domains_for_storage(allowed_domains)?;
+
+        let result = sqlx::query(
+            "UPDATE tools SET allowed_domains = ? WHERE name = ? AND status != 'deprecated'",
+        )
+        .bind(&domains_json)
+        .bind(name)
+        .execute(&self.db)
+        .await
+        .context("Failed to update allowed domains")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Tool not found: {}", name);
+        }
+
+        tracing::info!(
+            "Set allowed domains for tool '{}': {}",
+            name,
+            domains_json.as_deref().unwrap_or("any")
+        );
+        Ok(())
+    }
+
+    /// Set or clear (with `None` or an empty string) the category of a tool.
+    pub async fn set_category(&mut self, name: &str, category: Option<&str>) -> Result<()> {
+        let category = category.and_then(normalize_category);
+
+        let result =
+            sqlx::query("UPDATE tools SET category = ? WHERE name = ? AND status != 'deprecated'")
+                .bind(&category)
+                .bind(name)
+                .execute(&self.db)
+                .await
+                .context("Failed to update tool category")?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Tool not found: {}", name);
+        }
+
+        tracing::info!(
+            "Set category for tool '{}': {}",
+            name,
+            category.as_deref().unwrap_or("none")
+        );
+        Ok(())
+    }
+
     /// Clear the compilation cache and force re-compilation on next use.
     pub fn clear_cache(&mut self) {
-        self.compiled_cache.clear();
+        self.cache().clear();
         tracing::debug!("Cleared Rhai AST compilation cache");
     }
 
@@ -391,7 +811,7 @@ impl RhaiToolRegistry {
         .context("Failed to update tool in database")?;
 
         // Invalidate cache and store new AST
-        self.compiled_cache.insert(name.to_string(), Arc::new(ast));
+        self.cache().insert(name.to_string(), Arc::new(ast));
 
         tracing::info!("Updated dynamic tool '{}' to version {}", name, new_version);
 
@@ -427,6 +847,9 @@ impl RhaiToolRegistry {
     fn row_to_tool_record(&self, row: sqlx::sqlite::SqliteRow) -> Result<ToolRecord> {
         let params_str: String = row.get("parameters");
         let parameters: Vec<ParameterDef> = serde_json::from_str(&params_str).unwrap_or_default();
+        let allowed_domains = row
+            .get::<Option<String>, _>("allowed_domains")
+            .and_then(|json| serde_json::from_str(&json).ok());
 
         Ok(ToolRecord {
             id: row.get("id"),
@@ -442,6 +865,8 @@ impl RhaiToolRegistry {
             success_count: row.get("success_count"),
             failure_count: row.get("failure_count"),
             parent_tool_id: row.get("parent_tool_id"),
+            allowed_domains,
+            category: row.get("category"),
         })
     }
 
@@ -537,6 +962,8 @@ mod tests {
                 "Returns a greeting",
                 r#"let name = "World"; "Hello, " + name + "!""#,
                 vec![],
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -552,7 +979,14 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         let result = registry
-            .register_tool("bad", "A broken tool", "let x = ;; invalid", vec![])
+            .register_tool(
+                "bad",
+                "A broken tool",
+                "let x = ;; invalid",
+                vec![],
+                None,
+                None,
+            )
             .await;
 
         assert!(result.is_err());
@@ -564,7 +998,7 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("add", "Adds two numbers", "40 + 2", vec![])
+            .register_tool("add", "Adds two numbers", "40 + 2", vec![], None, None)
             .await
             .unwrap();
 
@@ -576,6 +1010,70 @@ mod tests {
         assert_eq!(result, "42");
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_execute_tool_structured_results() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool(
+                "report",
+                "Returns a map",
+                r#"#{ name: "Berlin", temps: [12, 14.5], sunny: true }"#,
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        registry
+            .register_tool(
+                "hello",
+                "Returns a string",
+                r#""Hello, World!""#,
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let output = registry
+            .execute_tool("report", serde_json::json!({}))
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"name": "Berlin", "temps": [12, 14.5], "sunny": true})
+        );
+
+        let output = registry
+            .execute_tool("hello", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(output, "Hello, World!");
+    }
+
+    #[test]
+    fn test_cancel_tool_executions_scoped_to_instance() {
+        let (_a, flag_a) = start_execution("cancel-test-a", "slow");
+        let (_b, flag_b) = start_execution("cancel-test-a", "other");
+        let (guard_c, flag_c) = start_execution("cancel-test-b", "slow");
+
+        assert_eq!(cancel_tool_executions("cancel-test-a", Some("slow")), 1);
+        assert!(flag_a.load(Ordering::Relaxed));
+        assert!(!flag_b.load(Ordering::Relaxed));
+
+        assert_eq!(cancel_tool_executions("cancel-test-a", None), 2);
+        assert!(flag_b.load(Ordering::Relaxed));
+        assert!(!flag_c.load(Ordering::Relaxed));
+
+        // Finished executions are no longer tracked
+        drop(guard_c);
+        assert_eq!(cancel_tool_executions("cancel-test-b", None), 0);
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_execute_tool_with_params() {
         let db = test_db().await;
@@ -589,7 +1087,14 @@ mod tests {
         "#;
 
         registry
-            .register_tool("add_params", "Add two numbers from params", script, vec![])
+            .register_tool(
+                "add_params",
+                "Add two numbers from params",
+                script,
+                vec![],
+                None,
+                None,
+            )
             .await
             .unwrap();
 
@@ -604,7 +1109,7 @@ mod tests {
     #[tokio::test]
     async fn test_execute_nonexistent_tool() {
         let db = test_db().await;
-        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+        let registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         let result = registry
             .execute_tool("nonexistent", serde_json::json!({}))
@@ -619,25 +1124,60 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("tool_a", "First tool", "42", vec![])
+            .register_tool("tool_a", "First tool", "42", vec![], None, None)
             .await
             .unwrap();
         registry
-            .register_tool("tool_b", "Second tool", "43", vec![])
+            .register_tool("tool_b", "Second tool", "43", vec![], None, None)
             .await
             .unwrap();
 
-        let tools = registry.list_tools(None).await.unwrap();
+        let tools = registry.list_tools(None, None).await.unwrap();
         assert_eq!(tools.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_list_tools_by_category() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        // Categories can be given at registration or set afterwards
+        for (name, category) in [
+            ("fetch_page", Some("Web")),
+            ("fetch_feed", None),
+            ("sum_csv", Some("data")),
+        ] {
+            let tool = registry
+                .register_tool(name, "Tool", "42", vec![], None, category)
+                .await
+                .unwrap();
+            assert_eq!(tool.category, category.and_then(normalize_category));
+        }
+        registry
+            .set_category("fetch_feed", Some(" web "))
+            .await
+            .unwrap();
+
+        let web = registry.list_tools(None, Some("WEB")).await.unwrap();
+        let names: Vec<&str> = web.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["fetch_feed", "fetch_page"]);
+        assert!(web.iter().all(|t| t.category.as_deref() == Some("web")));
+        assert_eq!(registry.list_tools(None, None).await.unwrap().len(), 3);
+
+        // An empty category clears it
+        registry.set_category("sum_csv", Some("")).await.unwrap();
+        let tool = registry.get_tool("sum_csv").await.unwrap().unwrap();
+        assert!(tool.category.is_none());
+        assert!(registry.set_category("missing", Some("web")).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_get_tool() {
         let db = test_db().await;
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("my_tool", "A tool", "1 + 1", vec![])
+            .register_tool("my_tool", "A tool", "1 + 1", vec![], None, None)
             .await
             .unwrap();
 
@@ -655,19 +1195,19 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("to_delete", "Will be deleted", "0", vec![])
+            .register_tool("to_delete", "Will be deleted", "0", vec![], None, None)
             .await
             .unwrap();
 
         registry.delete_tool("to_delete").await.unwrap();
 
         // Should no longer appear in active tools
-        let active = registry.list_tools(None).await.unwrap();
+        let active = registry.list_tools(None, None).await.unwrap();
         assert!(active.is_empty());
 
         // Should appear in deprecated list
         let deprecated = registry
-            .list_tools(Some(ToolStatus::Deprecated))
+            .list_tools(Some(ToolStatus::Deprecated), None)
             .await
             .unwrap();
         assert_eq!(deprecated.len(), 1);
@@ -679,7 +1219,7 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("old_tool", "Deprecated", "0", vec![])
+            .register_tool("old_tool", "Deprecated", "0", vec![], None, None)
             .await
             .unwrap();
 
@@ -693,13 +1233,277 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("deprecated"));
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_reactivate_deprecated_tool() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool("revived", "Comes back", "40 + 2", vec![], None, None)
+            .await
+            .unwrap();
+        registry.delete_tool("revived").await.unwrap();
+        assert!(registry
+            .execute_tool("revived", serde_json::json!({}))
+            .await
+            .is_err());
+
+        let tool = registry.reactivate_tool("revived").await.unwrap();
+        assert_eq!(tool.status, ToolStatus::Active);
+        assert_eq!(registry.list_tools(None, None).await.unwrap().len(), 1);
+
+        let result = registry
+            .execute_tool("revived", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result, "42");
+
+        // Active tools cannot be reactivated; missing tools are reported
+        let err = registry.reactivate_tool("revived").await.unwrap_err();
+        assert!(err.to_string().contains("not deprecated"));
+        assert!(registry.reactivate_tool("missing").await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_purge_tool_removes_tool_and_executions() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db.clone(), PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool("short_lived", "Purged later", "1", vec![], None, None)
+            .await
+            .unwrap();
+        registry
+            .register_tool("keeper", "Stays", "2", vec![], None, None)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            registry
+                .execute_tool("short_lived", serde_json::json!({}))
+                .await
+                .unwrap();
+        }
+        registry
+            .execute_tool("keeper", serde_json::json!({}))
+            .await
+            .unwrap();
+
+        // Active tools cannot be purged
+        let err = registry.purge_tool("short_lived").await.unwrap_err();
+        assert!(err.to_string().contains("deprecated"));
+
+        registry.delete_tool("short_lived").await.unwrap();
+        assert_eq!(registry.purge_tool("short_lived").await.unwrap(), 2);
+
+        assert!(registry.get_tool("short_lived").await.unwrap().is_none());
+        let tools: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tools")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let executions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tool_executions")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(tools, 1);
+        assert_eq!(executions, 1);
+
+        assert!(registry.purge_tool("short_lived").await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_execute_blocks_host_outside_allowed_domains() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+        registry
+            .register_tool(
+                "fetch_other",
+                "Fetches a non-GitHub host",
+                r#"http_get("https://example.com/data")"#,
+                vec![],
+                Some(vec!["*.API.GitHub.com ".to_string()]),
+                None,
+            )
+            .await
+            .unwrap();
+        // Stored (normalized) by the insert itself
+        let tool = registry.get_tool("fetch_other").await.unwrap().unwrap();
+        assert_eq!(
+            tool.allowed_domains,
+            Some(vec!["api.github.com".to_string()])
+        );
+
+        let err = registry
+            .execute_tool("fetch_other", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Host 'example.com' is not allowed for this tool"));
+
+        // Lifting the restriction clears the stored list
+        registry
+            .set_allowed_domains("fetch_other", None)
+            .await
+            .unwrap();
+        let tool = registry.get_tool("fetch_other").await.unwrap().unwrap();
+        assert!(tool.allowed_domains.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_usage_report_counts() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool(
+                "flaky",
+                "Fails on request",
+                r#"if params_json.contains("fail") { throw "boom"; } 1"#,
+                vec![],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        registry
+            .register_tool("unused", "Never called", "0", vec![], None, None)
+            .await
+            .unwrap();
+        registry
+            .register_tool("retired", "Deprecated", "0", vec![], None, None)
+            .await
+            .unwrap();
+        registry.delete_tool("retired").await.unwrap();
+
+        for _ in 0..3 {
+            registry
+                .execute_tool("flaky", serde_json::json!({}))
+                .await
+                .unwrap();
+        }
+        assert!(registry
+            .execute_tool("flaky", serde_json::json!({"fail": true}))
+            .await
+            .is_err());
+
+        let report = registry.usage_report().await.unwrap();
+        assert_eq!(report.len(), 3);
+
+        let flaky = &report[0];
+        assert_eq!(flaky.name, "flaky");
+        assert_eq!(flaky.usage_count, 4);
+        assert_eq!(flaky.success_count, 3);
+        assert_eq!(flaky.failure_count, 1);
+        assert_eq!(flaky.success_rate, Some(0.75));
+        assert!(flaky.last_used.is_some());
+
+        let retired = report.iter().find(|t| t.name == "retired").unwrap();
+        assert_eq!(retired.status, ToolStatus::Deprecated);
+        assert_eq!(retired.success_rate, None);
+        assert!(retired.last_used.is_none());
+    }
+
+    fn param(name: &str, type_hint: &str, required: bool) -> ParameterDef {
+        ParameterDef {
+            name: name.to_string(),
+            type_hint: type_hint.to_string(),
+            description: String::new(),
+            required,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_missing_required_param() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool(
+                "fetch",
+                "Needs a URL",
+                "params_json",
+                vec![param("url", "string", true)],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let err = registry
+            .execute_tool("fetch", serde_json::json!({"timeout": 5}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Missing required parameter 'url'"));
+
+        // Rejected before execution: no usage recorded
+        let tool = registry.get_tool("fetch").await.unwrap().unwrap();
+        assert_eq!(tool.usage_count, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_execute_with_present_optional_param() {
+        let db = test_db().await;
+        let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
+
+        registry
+            .register_tool(
+                "greet",
+                "Optional name",
+                "params_json",
+                vec![
+                    param("name", "string", false),
+                    param("times", "number", false),
+                ],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let result = registry
+            .execute_tool("greet", serde_json::json!({"name": "Ada"}))
+            .await
+            .unwrap();
+        assert!(result.contains("Ada"));
+
+        // Optional params may be omitted entirely
+        assert!(registry
+            .execute_tool("greet", serde_json::json!({}))
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_params_type_hints() {
+        let defs = vec![
+            param("count", "number", true),
+            param("enabled", "boolean", false),
+            param("tags", "array", false),
+        ];
+
+        assert!(validate_params(&defs, &serde_json::json!({"count": 3})).is_ok());
+        // Loose: quoted scalars are accepted
+        assert!(
+            validate_params(&defs, &serde_json::json!({"count": "3", "enabled": "true"})).is_ok()
+        );
+
+        let err = validate_params(&defs, &serde_json::json!({"count": "three"})).unwrap_err();
+        assert!(err.to_string().contains("'count' should be of type number"));
+        let err =
+            validate_params(&defs, &serde_json::json!({"count": 1, "tags": "a,b"})).unwrap_err();
+        assert!(err.to_string().contains("got string"));
+
+        assert!(validate_params(&defs, &serde_json::json!([1, 2])).is_err());
+        assert!(validate_params(&[], &serde_json::Value::Null).is_ok());
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_usage_stats_updated() {
         let db = test_db().await;
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("counter", "Counting tool", "42", vec![])
+            .register_tool("counter", "Counting tool", "42", vec![], None, None)
             .await
             .unwrap();
 
@@ -725,11 +1529,11 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("alpha", "First tool", "1", vec![])
+            .register_tool("alpha", "First tool", "1", vec![], None, None)
             .await
             .unwrap();
         registry
-            .register_tool("beta", "Second tool", "2", vec![])
+            .register_tool("beta", "Second tool", "2", vec![], None, None)
             .await
             .unwrap();
 
@@ -745,14 +1549,14 @@ mod tests {
         let mut registry = RhaiToolRegistry::new(db, PathBuf::from("/tmp"), None, None);
 
         registry
-            .register_tool("cached", "Cached tool", "42", vec![])
+            .register_tool("cached", "Cached tool", "42", vec![], None, None)
             .await
             .unwrap();
 
-        assert!(registry.compiled_cache.contains_key("cached"));
+        assert!(registry.cache().contains_key("cached"));
 
         registry.clear_cache();
-        assert!(registry.compiled_cache.is_empty());
+        assert!(registry.cache().is_empty());
 
         // Should still work after cache clear (re-compiles from DB)
         let result = registry
@@ -121,7 +121,7 @@ impl Tool for RhaiExecuteTool {
             .as_ref()
             .ok_or_else(|| RhaiToolError("Tool registry not initialized".to_string()))?;
 
-        let mut registry_guard = registry.write().await;
+        let registry_guard = registry.read().await;
 
         tracing::info!(
             "Executing dynamic tool '{}' with params: {}",
@@ -164,7 +164,7 @@ mod tests {
 
         // Register a test tool
         registry
-            .register_tool("test_add", "Adds 1 + 1", "1 + 1", vec![])
+            .register_tool("test_add", "Adds 1 + 1", "1 + 1", vec![], None, None)
             .await
             .unwrap();
 
@@ -5,11 +5,21 @@
 //! the instance workspace, and all HTTP requests enforce HTTPS with timeouts.
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use regex::Regex;
 use rhai::{Dynamic, Engine, Map};
+use scraper::{ElementRef, Html, Node, Selector};
+use std::cell::RefCell;
+use std::io::{Read, Write};
 use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tauri::AppHandle;
 
+use crate::utils::offline;
+
 // ---------------------------------------------------------------------------
 // Engine creation
 // ---------------------------------------------------------------------------
@@ -24,6 +34,18 @@ const MAX_ARRAY_SIZE: usize = 10_000;
 const MAX_MAP_SIZE: usize = 5_000;
 /// HTTP request timeout in seconds.
 const HTTP_TIMEOUT_SECS: u64 = 30;
+/// Maximum number of redirects an HTTP request follows.
+const MAX_HTTP_REDIRECTS: usize = 10;
+/// Error prefix for requests that got no HTTP response (DNS, TLS, timeout, ...).
+const NETWORK_ERROR_PREFIX: &str = "NETWORK_ERROR";
+/// Maximum number of response body characters included in an HTTP status error.
+const MAX_ERROR_BODY_CHARS: usize = 2_000;
+/// Maximum number of characters `embed_text` accepts.
+const MAX_EMBED_TEXT_CHARS: usize = 8_000;
+
+/// Computes the embedding vector of a text for `embed_text`.
+/// Injected by the caller so the engine stays independent of the memory system.
+pub type Embedder = Arc<dyn Fn(&str) -> Result<Vec<f32>, String> + Send + Sync>;
 
 /// Create a sandboxed Rhai engine with security limits and safe built-in functions.
 ///
@@ -47,6 +69,15 @@ pub fn create_sandboxed_engine(
     engine.set_max_array_size(MAX_ARRAY_SIZE);
     engine.set_max_map_size(MAX_MAP_SIZE);
 
+    // -- Cooperative cancellation (see `with_cancel_flag`) --
+    engine.on_progress(|_ops| {
+        if is_cancelled() {
+            Some(Dynamic::from(CANCELLED_MESSAGE))
+        } else {
+            None
+        }
+    });
+
     // -- HTTP functions --
     engine.register_fn("http_get", safe_http_get);
     engine.register_fn("http_post", safe_http_post);
@@ -61,7 +92,7 @@ pub fn create_sandboxed_engine(
         },
     );
 
-    let ws_write = workspace;
+    let ws_write = workspace.clone();
     engine.register_fn(
         "write_file",
         move |path: String, content: String| -> Result<(), Box<rhai::EvalAltResult>> {
@@ -69,21 +100,62 @@ pub fn create_sandboxed_engine(
         },
     );
 
+    let ws_append = workspace;
+    engine.register_fn(
+        "append_file",
+        move |path: String, content: String| -> Result<(), Box<rhai::EvalAltResult>> {
+            safe_append_file(&ws_append, &path, &content)
+        },
+    );
+
     // -- JSON functions --
     engine.register_fn("json_parse", safe_json_parse);
     engine.register_fn("json_stringify", safe_json_stringify);
 
+    // -- YAML / TOML functions --
+    engine.register_fn("yaml_parse", safe_yaml_parse);
+    engine.register_fn("yaml_stringify", safe_yaml_stringify);
+    engine.register_fn("toml_parse", safe_toml_parse);
+    engine.register_fn("toml_stringify", safe_toml_stringify);
+
+    // -- CSV functions --
+    engine.register_fn("csv_parse", safe_csv_parse);
+    engine.register_fn("csv_stringify", safe_csv_stringify);
+    engine.register_fn("csv_stringify", safe_csv_stringify_with_headers);
+
+    // -- Math / statistics functions --
+    engine.register_fn("sum", safe_sum);
+    engine.register_fn("mean", safe_mean);
+    engine.register_fn("median", safe_median);
+    engine.register_fn("min", safe_array_min);
+    engine.register_fn("max", safe_array_max);
+    engine.register_fn("round", safe_round);
+    engine.register_fn("pow", safe_pow);
+
     // -- Regex functions --
     engine.register_fn("regex_match", safe_regex_match);
     engine.register_fn("regex_replace", safe_regex_replace);
 
+    // -- HTML functions (offline parsing only) --
+    engine.register_fn("html_to_text", safe_html_to_text);
+    engine.register_fn("html_select", safe_html_select);
+    engine.register_fn("html_select_attr", safe_html_select_attr);
+
     // -- Encoding functions --
     engine.register_fn("base64_encode", safe_base64_encode);
     engine.register_fn("base64_decode", safe_base64_decode);
     engine.register_fn("url_encode", safe_url_encode);
+    engine.register_fn("url_decode", safe_url_decode);
+
+    // -- Compression functions (binary data as Base64) --
+    engine.register_fn("gzip_compress", safe_gzip_compress);
+    engine.register_fn("gzip_decompress", safe_gzip_decompress);
 
     // -- System functions --
     engine.register_fn("get_current_datetime", safe_get_current_datetime);
+    engine.register_fn("parse_datetime", safe_parse_datetime);
+    engine.register_fn("format_datetime", safe_format_datetime);
+    engine.register_fn("datetime_add", safe_datetime_add);
 
     // -- Notification function --
     let default_title = instance_name.unwrap_or_else(|| "ownAI".to_string());
@@ -126,6 +198,96 @@ fn resolve_workspace_path(root: &Path, user_path: &str) -> Result<PathBuf, Strin
 // HTTP helpers
 // ---------------------------------------------------------------------------
 
+thread_local! {
+    /// Hosts the currently executing tool may reach (`None` = any host).
+    /// Set by `with_allowed_domains` around a script run; the HTTP helpers
+    /// run synchronously on the same thread.
+    static ALLOWED_DOMAINS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+
+    /// Cancellation flag of the currently executing tool.
+    /// Set by `with_cancel_flag` around a script run.
+    static CANCEL_FLAG: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Error raised when a running script is cancelled.
+const CANCELLED_MESSAGE: &str = "Tool execution cancelled";
+
+/// Run `f` (a script evaluation) so that setting `flag` stops it at the next
+/// operation. A blocking HTTP request in progress is not interrupted (it ends
+/// after at most `HTTP_TIMEOUT_SECS`), but no further requests are sent.
+pub fn with_cancel_flag<T>(flag: Arc<AtomicBool>, f: impl FnOnce() -> T) -> T {
+    let previous = CANCEL_FLAG.with(|cell| cell.replace(Some(flag)));
+    let result = f();
+    CANCEL_FLAG.with(|cell| cell.replace(previous));
+    result
+}
+
+/// Whether the currently executing tool has been cancelled.
+fn is_cancelled() -> bool {
+    CANCEL_FLAG.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    })
+}
+
+/// Lowercase domains and strip wildcard/dot prefixes (`*.example.com` -> `example.com`).
+pub fn normalize_domains(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|d| {
+            d.trim()
+                .trim_start_matches("*.")
+                .trim_start_matches('.')
+                .to_lowercase()
+        })
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// Run `f` with HTTP requests restricted to `domains` (and their subdomains).
+/// The previous restriction is restored afterwards.
+pub fn with_allowed_domains<T>(domains: Option<&[String]>, f: impl FnOnce() -> T) -> T {
+    let previous = ALLOWED_DOMAINS.with(|cell| cell.replace(domains.map(normalize_domains)));
+    let result = f();
+    ALLOWED_DOMAINS.with(|cell| cell.replace(previous));
+    result
+}
+
+/// Whether `host` is one of `domains` or a subdomain of one.
+fn host_allowed(host: &str, domains: &[String]) -> bool {
+    let host = host.to_lowercase();
+    domains
+        .iter()
+        .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+}
+
+/// Validate that the URL's host is permitted for the running tool.
+fn require_allowed_host(url: &str) -> Result<(), Box<rhai::EvalAltResult>> {
+    ALLOWED_DOMAINS.with(|cell| {
+        let allowed = cell.borrow();
+        let Some(domains) = allowed.as_ref() else {
+            return Ok(());
+        };
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .ok_or_else(|| -> Box<rhai::EvalAltResult> {
+                format!("Invalid URL: {}", url).into()
+            })?;
+        if host_allowed(&host, domains) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Host '{}' is not allowed for this tool (allowed: {})",
+                host,
+                domains.join(", ")
+            )
+            .into())
+        }
+    })
+}
+
 /// Validate that a URL uses HTTPS.
 fn require_https(url: &str) -> Result<(), Box<rhai::EvalAltResult>> {
     if !url.starts_with("https://") {
@@ -134,14 +296,86 @@ fn require_https(url: &str) -> Result<(), Box<rhai::EvalAltResult>> {
     Ok(())
 }
 
-/// Build a blocking reqwest client with timeout.
+/// Refuse network access while offline mode is enabled.
+fn require_online() -> Result<(), Box<rhai::EvalAltResult>> {
+    offline::require_online().map_err(|e| e.into())
+}
+
+/// Build a blocking reqwest client with timeout. Redirects are only followed
+/// to hosts the running tool may reach.
 fn blocking_client() -> Result<reqwest::blocking::Client, Box<rhai::EvalAltResult>> {
+    // The policy runs on reqwest's own thread, so capture the thread-local
+    // allowlist now
+    let allowed = ALLOWED_DOMAINS.with(|cell| cell.borrow().clone());
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_HTTP_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let Some(domains) = allowed.as_ref() else {
+            return attempt.follow();
+        };
+        match attempt.url().host_str() {
+            Some(host) if host_allowed(host, domains) => attempt.follow(),
+            host => {
+                let message = format!(
+                    "redirect to host '{}' is not allowed for this tool (allowed: {})",
+                    host.unwrap_or_default(),
+                    domains.join(", ")
+                );
+                attempt.error(message)
+            }
+        }
+    });
+
     reqwest::blocking::Client::builder()
         .timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECS))
+        .redirect(redirect_policy)
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e).into())
 }
 
+/// Send a request and return the response body.
+///
+/// Failures are raised with a machine-readable prefix so scripts can branch
+/// on them (e.g. `catch (err) { if err.starts_with("HTTP_STATUS_404") ... }`):
+/// - `NETWORK_ERROR: ...` when no response was received
+/// - `HTTP_STATUS_<code>: <body>` for non-2xx responses (body truncated)
+fn send_request(
+    method: &str,
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    if is_cancelled() {
+        return Err(CANCELLED_MESSAGE.into());
+    }
+    let response = request.send().map_err(|e| -> Box<rhai::EvalAltResult> {
+        // A refused redirect only names the URL; add the reason
+        let reason = match std::error::Error::source(&e) {
+            Some(source) if e.is_redirect() => format!("{}: {}", e, source),
+            _ => e.to_string(),
+        };
+        format!(
+            "{}: HTTP {} failed: {}",
+            NETWORK_ERROR_PREFIX, method, reason
+        )
+        .into()
+    })?;
+
+    let status = response.status();
+    let body = response.text().map_err(|e| -> Box<rhai::EvalAltResult> {
+        format!("{}: Failed to read response: {}", NETWORK_ERROR_PREFIX, e).into()
+    })?;
+
+    if !status.is_success() {
+        let mut snippet: String = body.chars().take(MAX_ERROR_BODY_CHARS).collect();
+        if snippet.len() < body.len() {
+            snippet.push_str("...");
+        }
+        return Err(format!("HTTP_STATUS_{}: {}", status.as_u16(), snippet).into());
+    }
+
+    Ok(body)
+}
+
 // ---------------------------------------------------------------------------
 // Safe functions: HTTP
 // ---------------------------------------------------------------------------
@@ -149,31 +383,25 @@ fn blocking_client() -> Result<reqwest::blocking::Client, Box<rhai::EvalAltResul
 /// Simple HTTPS GET request. Returns response body as string.
 fn safe_http_get(url: String) -> Result<String, Box<rhai::EvalAltResult>> {
     require_https(&url)?;
+    require_allowed_host(&url)?;
+    require_online()?;
     let client = blocking_client()?;
-    let response = client
-        .get(&url)
-        .send()
-        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("HTTP GET failed: {}", e).into() })?;
-
-    response.text().map_err(|e| -> Box<rhai::EvalAltResult> {
-        format!("Failed to read response: {}", e).into()
-    })
+    send_request("GET", client.get(&url))
 }
 
 /// Simple HTTPS POST request with a string body. Returns response body as string.
 fn safe_http_post(url: String, body: String) -> Result<String, Box<rhai::EvalAltResult>> {
     require_https(&url)?;
+    require_allowed_host(&url)?;
+    require_online()?;
     let client = blocking_client()?;
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .body(body)
-        .send()
-        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("HTTP POST failed: {}", e).into() })?;
-
-    response.text().map_err(|e| -> Box<rhai::EvalAltResult> {
-        format!("Failed to read response: {}", e).into()
-    })
+    send_request(
+        "POST",
+        client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(body),
+    )
 }
 
 /// Flexible HTTPS request with custom method, headers, and body.
@@ -186,6 +414,8 @@ fn safe_http_request(
     body: String,
 ) -> Result<String, Box<rhai::EvalAltResult>> {
     require_https(&url)?;
+    require_allowed_host(&url)?;
+    require_online()?;
     let client = blocking_client()?;
 
     let method_parsed = method.to_uppercase();
@@ -218,13 +448,7 @@ fn safe_http_request(
         request = request.body(body);
     }
 
-    let response = request.send().map_err(|e| -> Box<rhai::EvalAltResult> {
-        format!("HTTP {} failed: {}", method_parsed, e).into()
-    })?;
-
-    response.text().map_err(|e| -> Box<rhai::EvalAltResult> {
-        format!("Failed to read response: {}", e).into()
-    })
+    send_request(&method_parsed, request)
 }
 
 // ---------------------------------------------------------------------------
@@ -262,6 +486,34 @@ fn safe_write_file(
     })
 }
 
+/// Append content to a file within the workspace directory.
+/// Creates the file and its parent directories if they do not exist.
+fn safe_append_file(
+    workspace: &Path,
+    path: &str,
+    content: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    use std::io::Write;
+
+    let resolved = resolve_workspace_path(workspace, path)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { e.into() })?;
+
+    if let Some(parent) = resolved.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| -> Box<rhai::EvalAltResult> {
+            format!("Failed to create directories: {}", e).into()
+        })?;
+    }
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&resolved)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| -> Box<rhai::EvalAltResult> {
+            format!("Failed to append to file '{}': {}", path, e).into()
+        })
+}
+
 // ---------------------------------------------------------------------------
 // Safe functions: JSON
 // ---------------------------------------------------------------------------
@@ -281,6 +533,19 @@ fn safe_json_stringify(value: Dynamic) -> Result<String, Box<rhai::EvalAltResult
         .map_err(|e| -> Box<rhai::EvalAltResult> { format!("JSON stringify error: {}", e).into() })
 }
 
+/// Render a script's return value for the agent: maps and arrays become
+/// JSON, everything else its plain string form.
+pub fn format_script_result(value: Dynamic) -> String {
+    if value.is_map() || value.is_array() {
+        let fallback = format!("{}", value);
+        return dynamic_to_json_value(value)
+            .ok()
+            .and_then(|json| serde_json::to_string(&json).ok())
+            .unwrap_or(fallback);
+    }
+    format!("{}", value)
+}
+
 /// Convert serde_json::Value to Rhai Dynamic
 fn json_value_to_dynamic(value: serde_json::Value) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
     match value {
@@ -355,6 +620,364 @@ fn dynamic_to_json_value(value: Dynamic) -> Result<serde_json::Value, Box<rhai::
     }
 }
 
+// ---------------------------------------------------------------------------
+// Safe functions: YAML / TOML
+// ---------------------------------------------------------------------------
+
+/// Parse a YAML document into a Rhai Dynamic value.
+fn safe_yaml_parse(text: String) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    let value: serde_json::Value = serde_yaml::from_str(&text)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("YAML parse error: {}", e).into() })?;
+
+    check_collection_sizes(&value)?;
+    json_value_to_dynamic(value)
+}
+
+/// Convert a Rhai Dynamic value to a YAML document.
+fn safe_yaml_stringify(value: Dynamic) -> Result<String, Box<rhai::EvalAltResult>> {
+    let json_value = dynamic_to_json_value(value)?;
+    serde_yaml::to_string(&json_value)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("YAML stringify error: {}", e).into() })
+}
+
+/// Parse a TOML document into a Rhai Map. Datetimes become strings.
+fn safe_toml_parse(text: String) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    let table: toml::Table = toml::from_str(&text)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("TOML parse error: {}", e).into() })?;
+
+    let value = toml_value_to_json(toml::Value::Table(table));
+    check_collection_sizes(&value)?;
+    json_value_to_dynamic(value)
+}
+
+/// Convert a Rhai Map to a TOML document. TOML has no null, so `()` values
+/// are rejected.
+fn safe_toml_stringify(value: Dynamic) -> Result<String, Box<rhai::EvalAltResult>> {
+    let json_value = dynamic_to_json_value(value)?;
+    if !json_value.is_object() {
+        return Err("TOML stringify error: top-level value must be a map".into());
+    }
+    toml::to_string_pretty(&json_value)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("TOML stringify error: {}", e).into() })
+}
+
+/// Convert toml::Value to serde_json::Value (datetimes as RFC 3339 strings)
+fn toml_value_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(dt) => serde_json::Value::String(dt.to_string()),
+        toml::Value::Array(arr) => {
+            serde_json::Value::Array(arr.into_iter().map(toml_value_to_json).collect())
+        }
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_value_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Reject parsed documents with arrays or maps above the sandbox limits.
+fn check_collection_sizes(value: &serde_json::Value) -> Result<(), Box<rhai::EvalAltResult>> {
+    match value {
+        serde_json::Value::Array(arr) => {
+            if arr.len() > MAX_ARRAY_SIZE {
+                return Err(format!("Array too large (max {} items)", MAX_ARRAY_SIZE).into());
+            }
+            arr.iter().try_for_each(check_collection_sizes)
+        }
+        serde_json::Value::Object(obj) => {
+            if obj.len() > MAX_MAP_SIZE {
+                return Err(format!("Map too large (max {} keys)", MAX_MAP_SIZE).into());
+            }
+            obj.values().try_for_each(check_collection_sizes)
+        }
+        _ => Ok(()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Safe functions: CSV
+// ---------------------------------------------------------------------------
+
+/// Parse CSV text into an array of maps, using the first row as headers.
+/// Quoted fields (including embedded commas and newlines) are supported.
+/// Missing trailing fields become empty strings.
+fn safe_csv_parse(text: String) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("CSV parse error: {}", e).into() })?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    if headers.len() > MAX_MAP_SIZE {
+        return Err(format!("CSV has too many columns (max {})", MAX_MAP_SIZE).into());
+    }
+
+    let mut rows = rhai::Array::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| -> Box<rhai::EvalAltResult> {
+            format!("CSV parse error: {}", e).into()
+        })?;
+
+        if rows.len() >= MAX_ARRAY_SIZE {
+            return Err(format!("CSV has too many rows (max {})", MAX_ARRAY_SIZE).into());
+        }
+
+        let mut row = Map::new();
+        for (i, header) in headers.iter().enumerate() {
+            let value = record.get(i).unwrap_or("").to_string();
+            row.insert(header.as_str().into(), Dynamic::from(value));
+        }
+        rows.push(Dynamic::from_map(row));
+    }
+
+    Ok(rows)
+}
+
+/// Convert an array of maps into CSV text with a header row.
+/// Columns are the keys of all rows in sorted order, since Rhai maps keep
+/// their keys sorted. Missing values are written as empty fields.
+fn safe_csv_stringify(rows: rhai::Array) -> Result<String, Box<rhai::EvalAltResult>> {
+    let maps = csv_rows(rows)?;
+    let headers: std::collections::BTreeSet<String> = maps
+        .iter()
+        .flat_map(|map| map.keys().map(|key| key.to_string()))
+        .collect();
+    write_csv(&maps, &headers.into_iter().collect::<Vec<_>>())
+}
+
+/// `csv_stringify` with the columns (and their order) given by `headers`.
+/// Keys not in `headers` are left out.
+fn safe_csv_stringify_with_headers(
+    rows: rhai::Array,
+    headers: rhai::Array,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let headers: Vec<String> = headers
+        .into_iter()
+        .map(|header| {
+            header
+                .into_string()
+                .map_err(|_| -> Box<rhai::EvalAltResult> {
+                    "csv_stringify headers must be strings".into()
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    write_csv(&csv_rows(rows)?, &headers)
+}
+
+/// Cast the rows passed to `csv_stringify` to maps.
+fn csv_rows(rows: rhai::Array) -> Result<Vec<Map>, Box<rhai::EvalAltResult>> {
+    rows.into_iter()
+        .map(|row| {
+            row.try_cast::<Map>()
+                .ok_or_else(|| -> Box<rhai::EvalAltResult> {
+                    "csv_stringify expects an array of maps".into()
+                })
+        })
+        .collect()
+}
+
+/// Write `maps` as CSV with the given header row.
+fn write_csv(maps: &[Map], headers: &[String]) -> Result<String, Box<rhai::EvalAltResult>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let write_err =
+        |e: csv::Error| -> Box<rhai::EvalAltResult> { format!("CSV write error: {}", e).into() };
+
+    if !headers.is_empty() {
+        writer.write_record(headers).map_err(write_err)?;
+    }
+    for map in maps {
+        let record: Vec<String> = headers
+            .iter()
+            .map(|h| match map.get(h.as_str()) {
+                Some(value) if value.is_unit() => String::new(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            })
+            .collect();
+        writer.write_record(&record).map_err(write_err)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("CSV write error: {}", e).into() })?;
+    String::from_utf8(bytes)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("UTF-8 decode error: {}", e).into() })
+}
+
+// ---------------------------------------------------------------------------
+// Safe functions: Embeddings
+// ---------------------------------------------------------------------------
+
+/// Register `embed_text(text)` on `engine`, backed by `embedder`.
+/// Without it, scripts calling `embed_text` fail with "function not found".
+pub fn register_embedder(engine: &mut Engine, embedder: Embedder) {
+    engine.register_fn(
+        "embed_text",
+        move |text: String| -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+            safe_embed_text(&embedder, &text)
+        },
+    );
+}
+
+/// Embed `text` and return the vector as an array of floats.
+fn safe_embed_text(
+    embedder: &Embedder,
+    text: &str,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    if text.trim().is_empty() {
+        return Err("embed_text: text must not be empty".into());
+    }
+    if text.chars().count() > MAX_EMBED_TEXT_CHARS {
+        return Err(format!(
+            "embed_text: text too long (max {} characters)",
+            MAX_EMBED_TEXT_CHARS
+        )
+        .into());
+    }
+
+    let vector = embedder(text)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("embed_text failed: {}", e).into() })?;
+    Ok(vector
+        .into_iter()
+        .map(|v| Dynamic::from(v as rhai::FLOAT))
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Safe functions: Math / statistics
+// ---------------------------------------------------------------------------
+
+/// A number from a script value (integers are widened to floats).
+fn to_number(value: &Dynamic) -> Option<f64> {
+    if let Ok(i) = value.as_int() {
+        Some(i as f64)
+    } else {
+        value.as_float().ok()
+    }
+}
+
+/// All elements of `values` as numbers; errors on the first non-numeric one.
+fn numeric_values(name: &str, values: &rhai::Array) -> Result<Vec<f64>, Box<rhai::EvalAltResult>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            to_number(v).ok_or_else(|| -> Box<rhai::EvalAltResult> {
+                format!(
+                    "{}: element {} is not a number ({})",
+                    name,
+                    i,
+                    v.type_name()
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Like `numeric_values`, but rejects empty arrays.
+fn non_empty_values(
+    name: &str,
+    values: &rhai::Array,
+) -> Result<Vec<f64>, Box<rhai::EvalAltResult>> {
+    if values.is_empty() {
+        return Err(format!("{}: array is empty", name).into());
+    }
+    numeric_values(name, values)
+}
+
+/// Keep integer results as integers when every input was an integer.
+fn number_result(values: &rhai::Array, result: f64) -> Dynamic {
+    if values.iter().all(|v| v.is_int()) {
+        Dynamic::from(result as i64)
+    } else {
+        Dynamic::from(result)
+    }
+}
+
+/// Sum of an array of numbers (0 for an empty array).
+fn safe_sum(values: rhai::Array) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    if values.iter().all(|v| v.is_int()) {
+        let mut ints = values.iter().map(|v| v.as_int().unwrap_or_default());
+        return ints
+            .try_fold(0i64, |acc, i| acc.checked_add(i))
+            .map(Dynamic::from)
+            .ok_or_else(|| "sum: integer overflow".into());
+    }
+    Ok(Dynamic::from(
+        numeric_values("sum", &values)?.iter().sum::<f64>(),
+    ))
+}
+
+/// Arithmetic mean of an array of numbers.
+fn safe_mean(values: rhai::Array) -> Result<f64, Box<rhai::EvalAltResult>> {
+    let numbers = non_empty_values("mean", &values)?;
+    Ok(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+/// Median of an array of numbers (mean of the two middle values for an
+/// even count).
+fn safe_median(values: rhai::Array) -> Result<f64, Box<rhai::EvalAltResult>> {
+    let mut numbers = non_empty_values("median", &values)?;
+    numbers.sort_by(|a, b| a.total_cmp(b));
+    let mid = numbers.len() / 2;
+    if numbers.len() % 2 == 0 {
+        Ok((numbers[mid - 1] + numbers[mid]) / 2.0)
+    } else {
+        Ok(numbers[mid])
+    }
+}
+
+/// Smallest element of an array of numbers.
+fn safe_array_min(values: rhai::Array) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    let numbers = non_empty_values("min", &values)?;
+    let min = numbers.into_iter().fold(f64::INFINITY, f64::min);
+    Ok(number_result(&values, min))
+}
+
+/// Largest element of an array of numbers.
+fn safe_array_max(values: rhai::Array) -> Result<Dynamic, Box<rhai::EvalAltResult>> {
+    let numbers = non_empty_values("max", &values)?;
+    let max = numbers.into_iter().fold(f64::NEG_INFINITY, f64::max);
+    Ok(number_result(&values, max))
+}
+
+/// Round `x` to `digits` decimal places (negative digits round to tens,
+/// hundreds, ...).
+fn safe_round(x: Dynamic, digits: i64) -> Result<f64, Box<rhai::EvalAltResult>> {
+    let x = to_number(&x).ok_or_else(|| -> Box<rhai::EvalAltResult> {
+        format!("round: value is not a number ({})", x.type_name()).into()
+    })?;
+    let factor = 10f64.powi(digits.clamp(-15, 15) as i32);
+    Ok((x * factor).round() / factor)
+}
+
+/// `base` raised to the power `exp`.
+fn safe_pow(base: Dynamic, exp: Dynamic) -> Result<f64, Box<rhai::EvalAltResult>> {
+    match (to_number(&base), to_number(&exp)) {
+        (Some(base), Some(exp)) => Ok(base.powf(exp)),
+        _ => Err(format!(
+            "pow: arguments must be numbers ({}, {})",
+            base.type_name(),
+            exp.type_name()
+        )
+        .into()),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Safe functions: Regex
 // ---------------------------------------------------------------------------
@@ -388,6 +1011,137 @@ fn safe_regex_replace(
     Ok(re.replace_all(&text, replacement.as_str()).to_string())
 }
 
+// ---------------------------------------------------------------------------
+// Safe functions: HTML
+// ---------------------------------------------------------------------------
+
+/// Elements whose text content is never shown to a reader.
+const HTML_HIDDEN_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "head"];
+
+/// Elements that start a new line when converted to text.
+const HTML_BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Convert HTML to readable plain text.
+/// Scripts, styles and the document head are dropped, block elements become
+/// line breaks, and whitespace within each line is collapsed.
+fn safe_html_to_text(html: String) -> String {
+    fn collect_text(element: ElementRef, out: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(text),
+                Node::Element(el) => {
+                    let name = el.name();
+                    if HTML_HIDDEN_ELEMENTS.contains(&name) {
+                        continue;
+                    }
+                    let is_block = HTML_BLOCK_ELEMENTS.contains(&name);
+                    if is_block {
+                        out.push('\n');
+                    }
+                    if let Some(child_ref) = ElementRef::wrap(child) {
+                        collect_text(child_ref, out);
+                    }
+                    if is_block {
+                        out.push('\n');
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let document = Html::parse_document(&html);
+    let mut raw = String::new();
+    collect_text(document.root_element(), &mut raw);
+
+    raw.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Run a CSS selector against an HTML document and collect a value per match.
+fn select_html<F>(
+    html: &str,
+    css_selector: &str,
+    mut extract: F,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>>
+where
+    F: FnMut(ElementRef) -> Option<String>,
+{
+    let selector = Selector::parse(css_selector).map_err(|e| -> Box<rhai::EvalAltResult> {
+        format!("Invalid CSS selector: {}", e).into()
+    })?;
+    let document = Html::parse_document(html);
+
+    let mut results = rhai::Array::new();
+    for element in document.select(&selector) {
+        if let Some(value) = extract(element) {
+            if results.len() >= MAX_ARRAY_SIZE {
+                return Err(format!("Too many matching elements (max {})", MAX_ARRAY_SIZE).into());
+            }
+            results.push(Dynamic::from(value));
+        }
+    }
+    Ok(results)
+}
+
+/// Return the trimmed inner text of every element matching a CSS selector.
+fn safe_html_select(
+    html: String,
+    css_selector: String,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    select_html(&html, &css_selector, |element| {
+        Some(element.text().collect::<String>().trim().to_string())
+    })
+}
+
+/// Return the value of an attribute for every element matching a CSS selector.
+/// Elements without the attribute are skipped.
+fn safe_html_select_attr(
+    html: String,
+    css_selector: String,
+    attribute: String,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    select_html(&html, &css_selector, |element| {
+        element.value().attr(&attribute).map(|v| v.to_string())
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Safe functions: Encoding
 // ---------------------------------------------------------------------------
@@ -426,6 +1180,90 @@ fn safe_url_encode(text: String) -> String {
     encoded
 }
 
+/// Decode a percent-encoded string (the inverse of `url_encode`).
+/// `+` is decoded to a space as in form-encoded query strings; a literal plus
+/// must be encoded as `%2B`. Returns an error on malformed escapes or if the
+/// decoded bytes are not valid UTF-8.
+fn safe_url_decode(text: String) -> Result<String, Box<rhai::EvalAltResult>> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                // from_str_radix alone would accept a sign ("%+1")
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| -> Box<rhai::EvalAltResult> {
+                        format!("Invalid percent-encoding at position {}", i).into()
+                    })?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("UTF-8 decode error: {}", e).into() })
+}
+
+// ---------------------------------------------------------------------------
+// Safe functions: Compression
+// ---------------------------------------------------------------------------
+
+/// Gzip-compress a string. Returns the compressed bytes as Base64.
+fn safe_gzip_compress(text: String) -> Result<String, Box<rhai::EvalAltResult>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map(|bytes| BASE64.encode(bytes))
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("Gzip compress error: {}", e).into() })
+}
+
+/// Decompress Base64-encoded gzip data into a string.
+/// Errors if the decompressed output would exceed `MAX_STRING_SIZE`.
+fn safe_gzip_decompress(encoded: String) -> Result<String, Box<rhai::EvalAltResult>> {
+    let compressed =
+        BASE64
+            .decode(encoded.as_bytes())
+            .map_err(|e| -> Box<rhai::EvalAltResult> {
+                format!("Base64 decode error: {}", e).into()
+            })?;
+
+    // Read at most one byte past the limit to detect oversized output
+    // without inflating the whole payload into memory.
+    let mut bytes = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .take(MAX_STRING_SIZE as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| -> Box<rhai::EvalAltResult> {
+            format!("Gzip decompress error: {}", e).into()
+        })?;
+
+    if bytes.len() > MAX_STRING_SIZE {
+        return Err(format!(
+            "Decompressed data exceeds maximum string size ({} bytes)",
+            MAX_STRING_SIZE
+        )
+        .into());
+    }
+
+    String::from_utf8(bytes)
+        .map_err(|e| -> Box<rhai::EvalAltResult> { format!("UTF-8 decode error: {}", e).into() })
+}
+
 // ---------------------------------------------------------------------------
 // Safe functions: System
 // ---------------------------------------------------------------------------
@@ -435,6 +1273,64 @@ fn safe_get_current_datetime() -> String {
     chrono::Utc::now().to_rfc3339()
 }
 
+/// Check a strftime-style `format` before use (chrono panics on invalid
+/// specifiers when formatting).
+fn validate_datetime_format(format: &str) -> Result<(), Box<rhai::EvalAltResult>> {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid datetime format: '{}'", format).into());
+    }
+    Ok(())
+}
+
+/// Parse an ISO 8601 / RFC 3339 timestamp as produced by the other
+/// datetime functions.
+fn parse_iso(iso: &str) -> Result<chrono::DateTime<chrono::FixedOffset>, Box<rhai::EvalAltResult>> {
+    chrono::DateTime::parse_from_rfc3339(iso.trim()).map_err(|e| -> Box<rhai::EvalAltResult> {
+        format!("Invalid ISO datetime '{}': {}", iso, e).into()
+    })
+}
+
+/// Parse `text` with a strftime-style `format` and return it as an ISO 8601
+/// string. Formats without a time zone are read as UTC; date-only formats
+/// give midnight.
+fn safe_parse_datetime(text: String, format: String) -> Result<String, Box<rhai::EvalAltResult>> {
+    use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+    validate_datetime_format(&format)?;
+    let text = text.trim();
+
+    if let Ok(dt) = chrono::DateTime::parse_from_str(text, &format) {
+        return Ok(dt.to_rfc3339());
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(text, &format) {
+        return Ok(Utc.from_utc_datetime(&naive).to_rfc3339());
+    }
+    match NaiveDate::parse_from_str(text, &format) {
+        Ok(date) => Ok(Utc
+            .from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .to_rfc3339()),
+        Err(e) => Err(format!("Failed to parse '{}' with format '{}': {}", text, format, e).into()),
+    }
+}
+
+/// Format an ISO 8601 timestamp with a strftime-style `format`
+/// (e.g. `"%d.%m.%Y %H:%M"`). The timestamp's own offset is kept.
+fn safe_format_datetime(iso: String, format: String) -> Result<String, Box<rhai::EvalAltResult>> {
+    validate_datetime_format(&format)?;
+    Ok(parse_iso(&iso)?.format(&format).to_string())
+}
+
+/// Shift an ISO 8601 timestamp by `seconds` (negative moves back in time).
+fn safe_datetime_add(iso: String, seconds: i64) -> Result<String, Box<rhai::EvalAltResult>> {
+    let dt = parse_iso(&iso)?;
+    chrono::TimeDelta::try_seconds(seconds)
+        .and_then(|delta| dt.checked_add_signed(delta))
+        .map(|shifted| shifted.to_rfc3339())
+        .ok_or_else(|| format!("datetime_add: result out of range ({} seconds)", seconds).into())
+}
+
 /// Send a system notification via `tauri-plugin-notification` if an `AppHandle`
 /// is available, otherwise fall back to logging only (e.g. in tests).
 ///
@@ -526,6 +1422,151 @@ mod tests {
         assert!(resolve_workspace_path(&root, "/etc/passwd").is_err());
     }
 
+    /// Run `send_request` (blocking reqwest) against a canned local response.
+    async fn send_to_mock(status_line: &'static str, body: &'static str) -> Result<String, String> {
+        let base =
+            crate::ai_instances::provider_api::test_server::serve_once(status_line, body).await;
+        tokio::task::spawn_blocking(move || {
+            let client = blocking_client().unwrap();
+            send_request("GET", client.get(&base)).map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_send_request_success_returns_body() {
+        let body = send_to_mock("200 OK", r#"{"ok": true}"#).await.unwrap();
+        assert_eq!(body, r#"{"ok": true}"#);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_send_request_status_error_prefix() {
+        let err = send_to_mock("503 Service Unavailable", "try later")
+            .await
+            .unwrap_err();
+        assert!(err.contains("HTTP_STATUS_503: try later"), "{}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_send_request_network_error_prefix() {
+        // Bind and immediately drop a listener so the port refuses connections
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let err = tokio::task::spawn_blocking(move || {
+            let client = blocking_client().unwrap();
+            send_request("GET", client.get(format!("http://{}", addr))).map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap()
+        .unwrap_err();
+        assert!(err.contains("NETWORK_ERROR: HTTP GET failed"), "{}", err);
+    }
+
+    /// Serve `requests` HTTP requests on a local port: `/start` redirects to
+    /// another host (`localhost`), `/hop` to `/end` on the same host, and
+    /// `/end` answers "done".
+    fn spawn_redirect_server(requests: usize) -> std::net::SocketAddr {
+        use std::io::BufRead;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+                let response = match path.as_str() {
+                    "/start" => format!(
+                        "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/end\r\n\
+                         Content-Length: 0\r\nConnection: close\r\n\r\n",
+                        addr.port()
+                    ),
+                    "/hop" => "HTTP/1.1 302 Found\r\nLocation: /end\r\n\
+                               Content-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string(),
+                    _ => "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\
+                          Connection: close\r\n\r\ndone"
+                        .to_string(),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_redirects_stay_within_allowed_domains() {
+        let addr = spawn_redirect_server(3);
+        let domains = vec!["127.0.0.1".to_string()];
+        let (blocked, followed) = tokio::task::spawn_blocking(move || {
+            with_allowed_domains(Some(&domains), || {
+                let client = blocking_client().unwrap();
+                let blocked = send_request("GET", client.get(format!("http://{}/start", addr)))
+                    .map_err(|e| e.to_string());
+                let followed = send_request("GET", client.get(format!("http://{}/hop", addr)))
+                    .map_err(|e| e.to_string());
+                (blocked, followed)
+            })
+        })
+        .await
+        .unwrap();
+
+        let err = blocked.unwrap_err();
+        assert!(
+            err.contains("redirect to host 'localhost' is not allowed"),
+            "{}",
+            err
+        );
+        assert_eq!(followed.unwrap(), "done");
+    }
+
+    #[test]
+    fn test_allowed_domains_scope() {
+        let domains = vec!["*.GitHub.com".to_string(), "api.example.org".to_string()];
+        assert_eq!(
+            normalize_domains(&domains),
+            vec!["github.com", "api.example.org"]
+        );
+
+        with_allowed_domains(Some(&domains), || {
+            assert!(require_allowed_host("https://api.github.com/repos").is_ok());
+            assert!(require_allowed_host("https://github.com/").is_ok());
+            assert!(require_allowed_host("https://api.example.org/v1").is_ok());
+            assert!(require_allowed_host("https://example.org/").is_err());
+            assert!(require_allowed_host("https://evilgithub.com/").is_err());
+        });
+        // Unrestricted outside the scope
+        assert!(require_allowed_host("https://example.org/").is_ok());
+    }
+
+    #[test]
+    fn test_http_functions_fail_in_offline_mode() {
+        offline::set_offline(true);
+        let get = safe_http_get("https://example.com/".to_string());
+        let post = safe_http_post("https://example.com/".to_string(), "{}".to_string());
+        let request = safe_http_request(
+            "PUT".to_string(),
+            "https://example.com/".to_string(),
+            Map::new(),
+            String::new(),
+        );
+        offline::set_offline(false);
+
+        for result in [get, post, request] {
+            let err = result.unwrap_err().to_string();
+            assert!(err.contains(offline::OFFLINE_ERROR), "{}", err);
+        }
+    }
+
     #[test]
     fn test_require_https() {
         assert!(require_https("https://example.com").is_ok());
@@ -551,12 +1592,260 @@ mod tests {
         assert!(parsed.is_array());
     }
 
+    #[test]
+    fn test_yaml_roundtrip_nested() {
+        let yaml = "server:\n  host: localhost\n  ports: [80, 443]\n  tls:\n    enabled: true\n    ratio: 0.5\nname: demo\n";
+        let parsed = safe_yaml_parse(yaml.to_string()).unwrap();
+        let map = parsed.clone().cast::<Map>();
+        let server = map["server"].clone().cast::<Map>();
+        assert_eq!(server["host"].clone().into_string().unwrap(), "localhost");
+        assert_eq!(server["ports"].clone().into_array().unwrap().len(), 2);
+
+        let stringified = safe_yaml_stringify(parsed).unwrap();
+        let v1: serde_json::Value = serde_yaml::from_str(yaml).unwrap();
+        let v2: serde_json::Value = serde_yaml::from_str(&stringified).unwrap();
+        assert_eq!(v1, v2);
+    }
+
+    #[test]
+    fn test_yaml_parse_invalid() {
+        assert!(safe_yaml_parse("key: [unclosed".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_toml_roundtrip_nested() {
+        let toml_text = "title = \"demo\"\n\n[database]\nport = 5432\nhosts = [\"a\", \"b\"]\n\n[database.pool]\nmax = 10\nenabled = true\n";
+        let parsed = safe_toml_parse(toml_text.to_string()).unwrap();
+        let map = parsed.clone().cast::<Map>();
+        let database = map["database"].clone().cast::<Map>();
+        assert_eq!(database["port"].as_int().unwrap(), 5432);
+        let pool = database["pool"].clone().cast::<Map>();
+        assert_eq!(pool["max"].as_int().unwrap(), 10);
+
+        let stringified = safe_toml_stringify(parsed).unwrap();
+        let v1: toml::Table = toml::from_str(toml_text).unwrap();
+        let v2: toml::Table = toml::from_str(&stringified).unwrap();
+        assert_eq!(v1, v2);
+    }
+
+    #[test]
+    fn test_toml_datetime_and_errors() {
+        let parsed = safe_toml_parse("created = 2026-01-02T03:04:05Z".to_string()).unwrap();
+        let map = parsed.cast::<Map>();
+        assert_eq!(
+            map["created"].clone().into_string().unwrap(),
+            "2026-01-02T03:04:05Z"
+        );
+
+        assert!(safe_toml_parse("not = = toml".to_string()).is_err());
+        assert!(safe_toml_stringify(Dynamic::from(rhai::Array::new())).is_err());
+    }
+
+    #[test]
+    fn test_yaml_parse_respects_array_limit() {
+        let items: Vec<String> = (0..=MAX_ARRAY_SIZE).map(|i| i.to_string()).collect();
+        let yaml = format!("[{}]", items.join(", "));
+        assert!(safe_yaml_parse(yaml).is_err());
+    }
+
+    fn mock_embedder() -> Embedder {
+        std::sync::Arc::new(|text: &str| Ok(vec![text.len() as f32, 0.5, -0.25]))
+    }
+
+    #[test]
+    fn test_embed_text_returns_float_array() {
+        let mut engine = create_sandboxed_engine(PathBuf::from("/tmp/test_workspace"), None, None);
+        register_embedder(&mut engine, mock_embedder());
+
+        let vector: rhai::Array = engine.eval(r#"embed_text("hello")"#).unwrap();
+        assert_eq!(vector.len(), 3);
+        assert!(vector.iter().all(|v| v.is_float()));
+        assert_eq!(vector[0].as_float().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_embed_text_limits() {
+        let embedder = mock_embedder();
+        assert!(safe_embed_text(&embedder, "  ").is_err());
+        let long = "x".repeat(MAX_EMBED_TEXT_CHARS + 1);
+        let err = safe_embed_text(&embedder, &long).unwrap_err().to_string();
+        assert!(err.contains("too long"));
+
+        let failing: Embedder = std::sync::Arc::new(|_: &str| Err("model not loaded".to_string()));
+        let err = safe_embed_text(&failing, "hi").unwrap_err().to_string();
+        assert!(err.contains("model not loaded"));
+    }
+
+    fn numbers(values: &[f64]) -> rhai::Array {
+        values.iter().map(|v| Dynamic::from(*v)).collect()
+    }
+
+    #[test]
+    fn test_mean_and_median() {
+        let sample = numbers(&[3.0, 1.0, 4.0, 1.0, 5.0, 9.0]);
+        assert!((safe_mean(sample.clone()).unwrap() - 23.0 / 6.0).abs() < 1e-9);
+        assert_eq!(safe_median(sample).unwrap(), 3.5);
+        assert_eq!(safe_median(numbers(&[7.0, 2.0, 5.0])).unwrap(), 5.0);
+        assert!(safe_mean(rhai::Array::new()).is_err());
+    }
+
+    #[test]
+    fn test_math_rejects_non_numeric_element() {
+        let values: rhai::Array = vec![Dynamic::from(1_i64), Dynamic::from("two".to_string())];
+        let err = safe_mean(values).unwrap_err().to_string();
+        assert!(err.contains("element 1 is not a number"));
+    }
+
+    #[test]
+    fn test_engine_math_in_script() {
+        let engine = create_sandboxed_engine(PathBuf::from("/tmp/test_workspace"), None, None);
+
+        assert_eq!(engine.eval::<i64>("sum([1, 2, 3])").unwrap(), 6);
+        assert_eq!(engine.eval::<f64>("sum([1, 2.5])").unwrap(), 3.5);
+        assert_eq!(engine.eval::<i64>("min([4, -2, 7])").unwrap(), -2);
+        assert_eq!(engine.eval::<f64>("max([1.5, 0.5])").unwrap(), 1.5);
+        assert_eq!(engine.eval::<f64>("mean([1, 2, 3, 4])").unwrap(), 2.5);
+        assert_eq!(engine.eval::<f64>("round(2.71828, 2)").unwrap(), 2.72);
+        assert_eq!(engine.eval::<f64>("pow(2, 10)").unwrap(), 1024.0);
+        // Built-in two-argument min/max still work
+        assert_eq!(engine.eval::<i64>("min(3, 5)").unwrap(), 3);
+        assert!(engine.eval::<f64>(r#"median([1, "x"])"#).is_err());
+    }
+
     #[test]
     fn test_json_parse_invalid() {
         let result = safe_json_parse("not valid json".to_string());
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_csv_parse_headers_and_rows() {
+        let rows = safe_csv_parse("name,age\nAlice,30\nBob,25\n".to_string()).unwrap();
+        assert_eq!(rows.len(), 2);
+        let first = rows[0].clone().cast::<Map>();
+        assert_eq!(first["name"].clone().into_string().unwrap(), "Alice");
+        assert_eq!(first["age"].clone().into_string().unwrap(), "30");
+    }
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let original = "age,name\n30,Alice\n25,Bob\n";
+        let rows = safe_csv_parse(original.to_string()).unwrap();
+        let text = safe_csv_stringify(rows).unwrap();
+        assert_eq!(text, original);
+    }
+
+    #[test]
+    fn test_csv_quoted_fields() {
+        let input = "city,note\n\"Berlin, DE\",\"line one\nline two\"\n";
+        let rows = safe_csv_parse(input.to_string()).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].clone().cast::<Map>();
+        assert_eq!(row["city"].clone().into_string().unwrap(), "Berlin, DE");
+        assert_eq!(
+            row["note"].clone().into_string().unwrap(),
+            "line one\nline two"
+        );
+
+        // Stringify quotes fields that need it
+        let text = safe_csv_stringify(rows).unwrap();
+        assert!(text.contains("\"Berlin, DE\""));
+        let reparsed = safe_csv_parse(text).unwrap();
+        let row = reparsed[0].clone().cast::<Map>();
+        assert_eq!(row["city"].clone().into_string().unwrap(), "Berlin, DE");
+    }
+
+    #[test]
+    fn test_csv_parse_too_many_rows() {
+        let mut input = String::from("n\n");
+        for i in 0..=MAX_ARRAY_SIZE {
+            input.push_str(&format!("{}\n", i));
+        }
+        assert!(safe_csv_parse(input).is_err());
+    }
+
+    #[test]
+    fn test_csv_stringify_rejects_non_maps() {
+        let rows: rhai::Array = vec![Dynamic::from("not a map".to_string())];
+        assert!(safe_csv_stringify(rows).is_err());
+    }
+
+    #[test]
+    fn test_csv_stringify_column_order() {
+        let rows = safe_csv_parse("name,age\nAlice,30\nBob,25\n".to_string()).unwrap();
+
+        // Without headers, columns are sorted by name
+        let text = safe_csv_stringify(rows.clone()).unwrap();
+        assert_eq!(text, "age,name\n30,Alice\n25,Bob\n");
+
+        let headers: rhai::Array = vec!["name".into(), "age".into()];
+        let text = safe_csv_stringify_with_headers(rows.clone(), headers).unwrap();
+        assert_eq!(text, "name,age\nAlice,30\nBob,25\n");
+
+        let headers: rhai::Array = vec!["name".into()];
+        let text = safe_csv_stringify_with_headers(rows.clone(), headers).unwrap();
+        assert_eq!(text, "name\nAlice\nBob\n");
+
+        let headers: rhai::Array = vec![Dynamic::from(1_i64)];
+        assert!(safe_csv_stringify_with_headers(rows, headers).is_err());
+    }
+
+    const SAMPLE_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Example Page</title>
+  <style>body { color: red; }</style>
+</head>
+<body>
+  <h1>Welcome</h1>
+  <p>Hello <b>world</b>, see the links below.</p>
+  <script>console.log("hidden");</script>
+  <ul>
+    <li><a href="https://example.com/one">One</a></li>
+    <li><a href="https://example.com/two">Two</a></li>
+    <li><a name="anchor">No link</a></li>
+  </ul>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_html_select_title() {
+        let titles = safe_html_select(SAMPLE_HTML.to_string(), "title".to_string()).unwrap();
+        assert_eq!(titles.len(), 1);
+        assert_eq!(titles[0].clone().into_string().unwrap(), "Example Page");
+    }
+
+    #[test]
+    fn test_html_select_attr_hrefs() {
+        let hrefs =
+            safe_html_select_attr(SAMPLE_HTML.to_string(), "a".to_string(), "href".to_string())
+                .unwrap();
+        let hrefs: Vec<String> = hrefs
+            .into_iter()
+            .map(|h| h.into_string().unwrap())
+            .collect();
+        assert_eq!(
+            hrefs,
+            vec!["https://example.com/one", "https://example.com/two"]
+        );
+    }
+
+    #[test]
+    fn test_html_select_invalid_selector() {
+        assert!(safe_html_select(SAMPLE_HTML.to_string(), "a[".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let text = safe_html_to_text(SAMPLE_HTML.to_string());
+        assert_eq!(
+            text,
+            "Welcome\nHello world, see the links below.\nOne\nTwo\nNo link"
+        );
+        assert!(!text.contains("console.log"));
+        assert!(!text.contains("color: red"));
+    }
+
     #[test]
     fn test_regex_match_finds_matches() {
         let text = "The price is $42.50 and $100.00".to_string();
@@ -602,6 +1891,36 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_gzip_roundtrip() {
+        let original = "Hello, gzip! ".repeat(100);
+        let compressed = safe_gzip_compress(original.clone()).unwrap();
+        assert!(compressed.len() < original.len());
+        let decompressed = safe_gzip_decompress(compressed).unwrap();
+        assert_eq!(original, decompressed);
+    }
+
+    #[test]
+    fn test_gzip_decompress_rejects_oversized_output() {
+        // Highly compressible payload that inflates beyond MAX_STRING_SIZE
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![b'a'; MAX_STRING_SIZE + 1]).unwrap();
+        let encoded = BASE64.encode(encoder.finish().unwrap());
+
+        let result = safe_gzip_decompress(encoded);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("maximum string size"));
+    }
+
+    #[test]
+    fn test_gzip_decompress_invalid_data() {
+        let result = safe_gzip_decompress(BASE64.encode("not gzip"));
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_url_encode() {
         assert_eq!(safe_url_encode("hello world".to_string()), "hello%20world");
@@ -615,6 +1934,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_url_decode_roundtrip() {
+        let encoded = safe_url_encode("foo=bar&baz".to_string());
+        assert_eq!(safe_url_decode(encoded).unwrap(), "foo=bar&baz");
+
+        let encoded = safe_url_encode("Grüße + mehr".to_string());
+        assert_eq!(safe_url_decode(encoded).unwrap(), "Grüße + mehr");
+    }
+
+    #[test]
+    fn test_url_decode_spaces() {
+        assert_eq!(
+            safe_url_decode("hello%20world".to_string()).unwrap(),
+            "hello world"
+        );
+        assert_eq!(
+            safe_url_decode("hello+world".to_string()).unwrap(),
+            "hello world"
+        );
+    }
+
+    #[test]
+    fn test_url_decode_invalid() {
+        assert!(safe_url_decode("100%".to_string()).is_err());
+        assert!(safe_url_decode("%zz".to_string()).is_err());
+        assert!(safe_url_decode("%+1".to_string()).is_err());
+        assert!(safe_url_decode("%-1".to_string()).is_err());
+        // Lone continuation byte is not valid UTF-8
+        assert!(safe_url_decode("%80".to_string()).is_err());
+    }
+
     #[test]
     fn test_get_current_datetime() {
         let dt = safe_get_current_datetime();
@@ -623,12 +1973,97 @@ mod tests {
         assert!(dt.len() > 20);
     }
 
+    #[test]
+    fn test_parse_and_format_date() {
+        let iso = safe_parse_datetime("2024-01-02".to_string(), "%Y-%m-%d".to_string()).unwrap();
+        assert_eq!(iso, "2024-01-02T00:00:00+00:00");
+        assert_eq!(
+            safe_format_datetime(iso, "%d.%m.%Y".to_string()).unwrap(),
+            "02.01.2024"
+        );
+
+        // Time zone offsets in the input are kept
+        let iso = safe_parse_datetime(
+            "2024-01-02 08:30 +0100".to_string(),
+            "%Y-%m-%d %H:%M %z".to_string(),
+        )
+        .unwrap();
+        assert_eq!(iso, "2024-01-02T08:30:00+01:00");
+    }
+
+    #[test]
+    fn test_datetime_add() {
+        let shifted = safe_datetime_add("2024-01-31T23:00:00+00:00".to_string(), 7200).unwrap();
+        assert_eq!(shifted, "2024-02-01T01:00:00+00:00");
+        let back = safe_datetime_add(shifted, -86_400).unwrap();
+        assert_eq!(back, "2024-01-31T01:00:00+00:00");
+        assert!(safe_datetime_add("2024-01-01T00:00:00Z".to_string(), i64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_datetime_errors() {
+        // Text does not match the format
+        let err = safe_parse_datetime("02/01/2024".to_string(), "%Y-%m-%d".to_string())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Failed to parse"));
+        // Invalid specifier is rejected instead of panicking
+        let err = safe_format_datetime("2024-01-02T00:00:00Z".to_string(), "%Q".to_string())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid datetime format"));
+        assert!(safe_format_datetime("yesterday".to_string(), "%Y".to_string()).is_err());
+    }
+
     #[test]
     fn test_send_notification_without_app_handle() {
         let result = send_notification_impl(None, "Test", "Body");
         assert!(result.contains("Notification logged"));
     }
 
+    #[test]
+    fn test_append_file_creates_then_appends() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let engine = create_sandboxed_engine(temp_dir.path().to_path_buf(), None, None);
+
+        engine
+            .run(r#"append_file("logs/run.log", "one\n"); append_file("logs/run.log", "two\n");"#)
+            .unwrap();
+        let content = std::fs::read_to_string(temp_dir.path().join("logs/run.log")).unwrap();
+        assert_eq!(content, "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_append_file_blocks_traversal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let result = safe_append_file(temp_dir.path(), "../escaped.log", "x");
+        assert!(result.is_err());
+        assert!(!temp_dir.path().join("../escaped.log").exists());
+    }
+
+    #[test]
+    fn test_cancel_flag_aborts_long_loop() {
+        let engine = create_sandboxed_engine(PathBuf::from("/tmp/test_workspace"), None, None);
+        let flag = Arc::new(AtomicBool::new(false));
+
+        // Not cancelled: the loop runs into the operation limit
+        let result = with_cancel_flag(flag.clone(), || engine.run("loop { }"));
+        assert!(matches!(
+            *result.unwrap_err(),
+            rhai::EvalAltResult::ErrorTooManyOperations(_)
+        ));
+
+        flag.store(true, Ordering::Relaxed);
+        let result = with_cancel_flag(flag, || engine.run("let i = 0; loop { i += 1; }"));
+        assert!(matches!(
+            *result.unwrap_err(),
+            rhai::EvalAltResult::ErrorTerminated(_, _)
+        ));
+        // The flag only applies inside `with_cancel_flag`
+        assert!(!is_cancelled());
+        assert_eq!(engine.eval::<i64>("40 + 2").unwrap(), 42);
+    }
+
     #[test]
     fn test_engine_basic_script() {
         let workspace = PathBuf::from("/tmp/test_workspace");
@@ -672,6 +2107,39 @@ mod tests {
         assert_eq!(result, "hello");
     }
 
+    #[test]
+    fn test_engine_gzip_in_script() {
+        let workspace = PathBuf::from("/tmp/test_workspace");
+        let engine = create_sandboxed_engine(workspace, None, None);
+
+        let result: String = engine
+            .eval(r#"let gz = gzip_compress("hello"); gzip_decompress(gz)"#)
+            .unwrap();
+        assert_eq!(result, "hello");
+    }
+
+    #[test]
+    fn test_engine_csv_in_script() {
+        let workspace = PathBuf::from("/tmp/test_workspace");
+        let engine = create_sandboxed_engine(workspace, None, None);
+
+        let result: String = engine
+            .eval(r#"let rows = csv_parse("a,b\n1,2\n"); rows[0]["b"]"#)
+            .unwrap();
+        assert_eq!(result, "2");
+    }
+
+    #[test]
+    fn test_engine_html_in_script() {
+        let workspace = PathBuf::from("/tmp/test_workspace");
+        let engine = create_sandboxed_engine(workspace, None, None);
+
+        let result: String = engine
+            .eval(r#"let t = html_select("<html><head><title>Hi</title></head></html>", "title"); t[0]"#)
+            .unwrap();
+        assert_eq!(result, "Hi");
+    }
+
     #[test]
     fn test_engine_datetime_in_script() {
         let workspace = PathBuf::from("/tmp/test_workspace");
@@ -3,23 +3,33 @@
 //! Provides `DelegateTaskTool`, a rig Tool that the main agent can call to
 //! create temporary sub-agents for complex tasks. Sub-agents get their own
 //! system prompt (written by the main agent) and access to all available tools,
-//! keeping the main conversation context clean.
+//! keeping the main conversation context clean. While a sub-agent works, its
+//! streamed text is forwarded to the frontend as `subagent:progress` events.
 
+use anyhow::Context;
+use futures::{Stream, StreamExt};
+use rig::agent::MultiTurnStreamItem;
 use rig::client::CompletionClient;
-use rig::completion::{Prompt, ToolDefinition};
+use rig::completion::ToolDefinition;
 use rig::providers::{anthropic, ollama, openai};
+use rig::streaming::{StreamedAssistantContent, StreamingPrompt};
 use rig::tool::{Tool, ToolDyn};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Pool, Sqlite};
+use std::future::Future;
 use std::path::PathBuf;
-use tauri::AppHandle;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+use crate::agent::{apply_output_limit, OutputLimit};
 use crate::canvas::tools::{
-    CreateProgramTool, ListProgramsTool, OpenProgramTool, ProgramEditFileTool, ProgramLsTool,
-    ProgramReadFileTool, ProgramWriteFileTool,
+    CloneProgramTool, CreateProgramTool, ListProgramsTool, OpenProgramTool, ProgramEditFileTool,
+    ProgramLsTool, ProgramReadFileTool, ProgramWriteFileBase64Tool, ProgramWriteFileTool,
+    RenameProgramTool,
 };
 use crate::memory::SharedLongTermMemory;
 use crate::tools::code_generation::{CreateToolTool, ReadToolTool, UpdateToolTool};
@@ -27,11 +37,16 @@ use crate::tools::collection_tools::{
     CreateKnowledgeCollectionTool, DeleteKnowledgeCollectionTool, IngestDocumentTool,
     ListKnowledgeCollectionsTool,
 };
-use crate::tools::filesystem::{EditFileTool, GrepTool, LsTool, ReadFileTool, WriteFileTool};
-use crate::tools::memory_tools::{AddMemoryTool, DeleteMemoryTool, SearchMemoryTool};
+use crate::tools::confirmation::ConfirmationGate;
+use crate::tools::filesystem::{
+    DeleteFileTool, EditFileTool, GrepTool, LsTool, MoveFileTool, ReadFileTool, WriteFileTool,
+};
+use crate::tools::memory_tools::{
+    AddMemoryTool, DeleteMemoryTool, ForgetMemoryTool, PinMemoryTool, SearchMemoryTool,
+};
 use crate::tools::planning::{self, ReadTodosTool, WriteTodosTool};
+use crate::tools::read_only::{without_write_tools, READ_ONLY_PROMPT_NOTE};
 use crate::tools::rhai_bridge_tool::{RhaiExecuteTool, SharedRegistry};
-use crate::utils::paths;
 
 // ---------------------------------------------------------------------------
 // Error type
@@ -54,39 +69,221 @@ pub enum ClientProvider {
     Ollama(ollama::Client),
 }
 
+// ---------------------------------------------------------------------------
+// Progress streaming
+// ---------------------------------------------------------------------------
+
+/// Payload of the `subagent:progress` event, emitted for each text chunk a
+/// sub-agent streams while it works. `instance_id` tells listeners which
+/// instance the sub-agent belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubAgentProgress {
+    pub instance_id: String,
+    pub task_name: String,
+    pub text: String,
+}
+
+/// Consume a sub-agent's multi-turn stream, calling `on_text` for every text
+/// chunk and awaiting `on_turn` with the turn's text whenever a turn ends in
+/// tool calls. Returns the final response (the same text `prompt()` would
+/// return).
+async fn collect_sub_agent_stream<S, R, E, F>(
+    mut stream: S,
+    mut on_text: impl FnMut(&str),
+    mut on_turn: impl FnMut(&str) -> F,
+) -> Result<String, SubAgentError>
+where
+    S: Stream<Item = Result<MultiTurnStreamItem<R>, E>> + Unpin,
+    E: std::fmt::Display,
+    F: Future<Output = ()>,
+{
+    // Text of the current turn, used if the stream ends without a FinalResponse
+    let mut turn_text = String::new();
+    // Several tool calls in one response count as a single turn
+    let mut turn_open = true;
+
+    while let Some(item) = stream.next().await {
+        match item {
+            Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::Text(text))) => {
+                on_text(&text.text);
+                turn_text.push_str(&text.text);
+                turn_open = true;
+            }
+            Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::ToolCall {
+                ..
+            })) => {
+                if turn_open {
+                    on_turn(&turn_text).await;
+                    turn_open = false;
+                }
+                turn_text.clear();
+            }
+            Ok(MultiTurnStreamItem::StreamUserItem(_)) => turn_open = true,
+            Ok(MultiTurnStreamItem::FinalResponse(res)) => return Ok(res.response().to_string()),
+            Ok(_) => {}
+            Err(e) => return Err(SubAgentError(format!("Sub-agent execution failed: {}", e))),
+        }
+    }
+
+    Ok(turn_text)
+}
+
+// ---------------------------------------------------------------------------
+// Checkpoints
+// ---------------------------------------------------------------------------
+
+/// Progress of a sub-agent task, saved after every tool-calling turn so an
+/// interrupted run can be resumed with `resume: true`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubAgentCheckpoint {
+    pub task_name: String,
+    /// Tool-calling turns completed so far (across resumed runs)
+    pub turn_count: usize,
+    /// Most recent non-empty assistant message
+    pub last_message: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Save the checkpoint of a sub-agent task, replacing any previous one.
+pub async fn save_checkpoint(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    checkpoint: &SubAgentCheckpoint,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO subagent_checkpoints (instance_id, task_name, turn_count, last_message, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(instance_id, task_name) DO UPDATE SET
+            turn_count = excluded.turn_count,
+            last_message = excluded.last_message,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(instance_id)
+    .bind(&checkpoint.task_name)
+    .bind(checkpoint.turn_count as i64)
+    .bind(&checkpoint.last_message)
+    .bind(checkpoint.updated_at)
+    .execute(db)
+    .await
+    .context("Failed to save sub-agent checkpoint")?;
+    Ok(())
+}
+
+/// Load the checkpoint of a sub-agent task, if any.
+pub async fn load_checkpoint(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    task_name: &str,
+) -> anyhow::Result<Option<SubAgentCheckpoint>> {
+    let row: Option<(i64, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT turn_count, last_message, updated_at FROM subagent_checkpoints \
+         WHERE instance_id = ? AND task_name = ?",
+    )
+    .bind(instance_id)
+    .bind(task_name)
+    .fetch_optional(db)
+    .await
+    .context("Failed to load sub-agent checkpoint")?;
+
+    Ok(row.map(
+        |(turn_count, last_message, updated_at)| SubAgentCheckpoint {
+            task_name: task_name.to_string(),
+            turn_count: turn_count.max(0) as usize,
+            last_message,
+            updated_at,
+        },
+    ))
+}
+
+/// Remove the checkpoint of a sub-agent task.
+pub async fn delete_checkpoint(
+    db: &Pool<Sqlite>,
+    instance_id: &str,
+    task_name: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM subagent_checkpoints WHERE instance_id = ? AND task_name = ?")
+        .bind(instance_id)
+        .bind(task_name)
+        .execute(db)
+        .await
+        .context("Failed to delete sub-agent checkpoint")?;
+    Ok(())
+}
+
+/// Task prompt for a resumed run: the original task, primed with the progress
+/// of the interrupted run.
+fn resume_task_prompt(task: &str, checkpoint: &SubAgentCheckpoint) -> String {
+    let progress = if checkpoint.last_message.trim().is_empty() {
+        "(no progress summary was recorded)"
+    } else {
+        checkpoint.last_message.trim()
+    };
+    format!(
+        "{task}\n\n\
+         [Resuming an interrupted run of this task after {turns} completed turns. \
+         Your last progress message was:]\n{progress}\n\n\
+         Check the current state (files, TODOs) before continuing, and do not redo \
+         steps that are already finished.",
+        task = task,
+        turns = checkpoint.turn_count,
+        progress = progress,
+    )
+}
+
 // ---------------------------------------------------------------------------
 // Sub-agent tool builder
 // ---------------------------------------------------------------------------
 
-/// Maximum number of multi-turn iterations for sub-agent tool calling.
+/// Default number of multi-turn iterations for sub-agent tool calling.
 const SUB_AGENT_MAX_TURNS: usize = 25;
 
-/// Build the full set of tools for a sub-agent.
-/// Sub-agents get all tools except `delegate_task` (to prevent recursion).
+/// Upper bound for a caller-provided `max_turns`.
+const SUB_AGENT_MAX_TURNS_LIMIT: usize = 100;
+
+/// Maximum nesting depth of delegated agents. The main agent runs at depth 0;
+/// an agent at this depth cannot delegate further. With a value of 1, only the
+/// main agent can spawn sub-agents.
+pub const MAX_DELEGATION_DEPTH: usize = 1;
+
+/// Build the full set of tools for a sub-agent running at `depth`.
+/// `delegate_task` is only included when a `delegate` tool is given and
+/// `depth` is below `MAX_DELEGATION_DEPTH` (to bound recursion).
 /// Also used by the scheduler runner for task execution agents.
+/// With `read_only`, every tool that writes is left out. Tool results are
+/// limited by `output_limit`.
+#[allow(clippy::too_many_arguments)]
 pub fn build_sub_agent_tools(
+    depth: usize,
+    delegate: Option<DelegateTaskTool>,
     instance_id: &str,
     registry: SharedRegistry,
     available_dynamic_tools: Vec<(String, String)>,
     db: Pool<Sqlite>,
+    workspace: PathBuf,
     programs_root: PathBuf,
     long_term_memory: SharedLongTermMemory,
     app_handle: Option<AppHandle>,
+    read_only: bool,
+    output_limit: OutputLimit,
 ) -> Vec<Box<dyn ToolDyn>> {
-    let workspace =
-        paths::get_instance_workspace_path(instance_id).unwrap_or_else(|_| PathBuf::from("."));
+    let confirmation = ConfirmationGate::new(app_handle.clone(), instance_id);
     let todo_list = planning::create_shared_todo_list();
 
-    vec![
+    let mut tools: Vec<Box<dyn ToolDyn>> = vec![
         // Filesystem tools
         Box::new(LsTool::new(workspace.clone())),
         Box::new(ReadFileTool::new(workspace.clone())),
         Box::new(WriteFileTool::new(workspace.clone())),
         Box::new(EditFileTool::new(workspace.clone())),
+        Box::new(MoveFileTool::new(workspace.clone()).with_confirmation(confirmation.clone())),
+        Box::new(DeleteFileTool::new(workspace.clone()).with_confirmation(confirmation.clone())),
         Box::new(GrepTool::new(workspace.clone())),
         // Planning tools
         Box::new(ReadTodosTool::new(todo_list.clone())),
-        Box::new(WriteTodosTool::new(todo_list)),
+        // Sub-agent plans are private; only the main agent's plan is shown in the UI
+        Box::new(WriteTodosTool::new(todo_list, None)),
         // Dynamic Rhai tool executor
         Box::new(RhaiExecuteTool::new(
             registry.clone(),
@@ -104,6 +301,17 @@ pub fn build_sub_agent_tools(
             app_handle.clone(),
         )),
         Box::new(ListProgramsTool::new(db.clone(), instance_id.to_string())),
+        Box::new(RenameProgramTool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+            app_handle.clone(),
+        )),
+        Box::new(CloneProgramTool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+        )),
         Box::new(OpenProgramTool::new(
             db.clone(),
             instance_id.to_string(),
@@ -117,6 +325,12 @@ pub fn build_sub_agent_tools(
             programs_root.clone(),
             app_handle.clone(),
         )),
+        Box::new(ProgramWriteFileBase64Tool::new(
+            db.clone(),
+            instance_id.to_string(),
+            programs_root.clone(),
+            app_handle.clone(),
+        )),
         Box::new(ProgramEditFileTool::new(
             db.clone(),
             instance_id.to_string(),
@@ -127,12 +341,35 @@ pub fn build_sub_agent_tools(
         Box::new(SearchMemoryTool::new(long_term_memory.clone(), db.clone())),
         Box::new(AddMemoryTool::new(long_term_memory.clone(), db.clone())),
         Box::new(DeleteMemoryTool::new(long_term_memory.clone())),
+        Box::new(ForgetMemoryTool::new(long_term_memory.clone())),
+        Box::new(PinMemoryTool::new(long_term_memory.clone())),
         // Knowledge Collection tools
         Box::new(CreateKnowledgeCollectionTool::new(db.clone())),
         Box::new(ListKnowledgeCollectionsTool::new(db.clone())),
         Box::new(DeleteKnowledgeCollectionTool::new(db.clone())),
         Box::new(IngestDocumentTool::new(db, long_term_memory, workspace)),
-    ]
+    ];
+
+    if let Some(delegate) = delegate_for_depth(delegate, depth) {
+        tools.push(Box::new(delegate));
+    }
+
+    if read_only {
+        tools = without_write_tools(tools);
+    }
+    apply_output_limit(tools, &output_limit)
+}
+
+/// Return the delegate tool for an agent at `depth`, or `None` if agents at
+/// that depth may not delegate.
+fn delegate_for_depth(
+    delegate: Option<DelegateTaskTool>,
+    depth: usize,
+) -> Option<DelegateTaskTool> {
+    if depth >= MAX_DELEGATION_DEPTH {
+        return None;
+    }
+    delegate.map(|d| d.with_depth(depth))
 }
 
 // ---------------------------------------------------------------------------
@@ -145,11 +382,13 @@ pub fn base_tools_prompt() -> String {
     r#"## Available Tools
 
 ### Filesystem (Workspace)
-- **ls**: List files and directories in your workspace
+- **ls**: List files and directories in your workspace (optional glob `pattern` and `recursive`)
 - **read_file**: Read file contents (supports line ranges)
 - **write_file**: Write content to a file (creates dirs if needed)
 - **edit_file**: Replace text in a file (old_text -> new_text)
-- **grep**: Search for text patterns in files
+- **move_file**: Move or rename a file or directory (refuses to overwrite unless overwrite=true)
+- **delete_file**: Delete a file or an empty directory
+- **grep**: Search for text in files (literal by default; supports regex=true and case_insensitive=true)
 
 IMPORTANT: You can ONLY access files within your workspace directory. If the user asks you to read, write, or access files at absolute paths or outside the workspace, you MUST decline and explain that for security reasons you can only access files within the workspace.
 Tell the user they can click the "Open Workspace" button (📂) in the header to open the workspace folder in their file manager, and then copy or move the needed files into the workspace.
@@ -179,9 +418,11 @@ Use write_todos when:
 - **update_tool**: Update/fix an existing tool's Rhai script code
 
 ### Long-Term Memory
-- **search_memory**: Search long-term memory using semantic similarity
-- **add_memory**: Store a new entry in long-term memory (facts, preferences, skills, context)
+- **search_memory**: Search long-term memory using semantic similarity (optional `tags` filter)
+- **add_memory**: Store a new entry in long-term memory (facts, preferences, skills, context); `tags` scope it, `ttl_minutes` makes it expire
 - **delete_memory**: Delete a memory entry by its ID
+- **forget_memory**: Forget the memories best matching a description (when the user asks you to forget something)
+- **pin_memory**: Pin a critical memory (e.g. the user's name) so it is never down-ranked, deduplicated, or expired
 
 Use memory tools to:
 - Remember important facts about the user or their projects
@@ -244,27 +485,46 @@ Available built-in functions:
 - **http_get(url)**: HTTPS GET request, returns response body
 - **http_post(url, body)**: HTTPS POST with JSON body
 - **http_request(method, url, headers, body)**: Flexible HTTP with custom method/headers
+  (all three fail while the user has enabled offline mode)
 - **read_file(path)**: Read file from workspace
 - **write_file(path, content)**: Write file to workspace
+- **append_file(path, content)**: Append to a file in the workspace (creates it if missing)
 - **json_parse(text)**: Parse JSON string to object/array
 - **json_stringify(value)**: Convert value to JSON string
+- **yaml_parse(text)** / **yaml_stringify(value)**: Parse / produce YAML
+- **toml_parse(text)** / **toml_stringify(map)**: Parse / produce TOML
+- **csv_parse(text)**: Parse CSV (first row as headers) to an array of maps
+- **csv_stringify(rows)** / **csv_stringify(rows, headers)**: Convert an array of maps to CSV text (columns sorted by name, or the keys in `headers` in that order)
+- **embed_text(text)**: Embedding vector (array of floats) for semantic comparisons
+- **sum(array)**, **mean(array)**, **median(array)**, **min(array)**, **max(array)**: Aggregate an array of numbers
+- **round(x, digits)**, **pow(base, exp)**: Round to decimal places / exponentiation
 - **regex_match(text, pattern)**: Find all regex matches
 - **regex_replace(text, pattern, replacement)**: Replace regex matches
+- **html_to_text(html)**: Strip tags and return readable text
+- **html_select(html, css_selector)**: Inner texts of elements matching a CSS selector
+- **html_select_attr(html, css_selector, attribute)**: Attribute values of matching elements
 - **base64_encode(text)**: Encode string to Base64
 - **base64_decode(text)**: Decode Base64 to string
 - **url_encode(text)**: URL-encode a string
+- **url_decode(text)**: Decode a percent-encoded string
+- **gzip_compress(text)**: Gzip-compress a string, returns Base64
+- **gzip_decompress(base64)**: Decompress Base64-encoded gzip data to a string
 - **get_current_datetime()**: Get current UTC datetime (ISO 8601)
+- **parse_datetime(text, format)**: Parse a date string with a strftime format (e.g. "%Y-%m-%d"), returns ISO 8601
+- **format_datetime(iso, format)**: Format an ISO 8601 datetime with a strftime format
+- **datetime_add(iso, seconds)**: Shift an ISO 8601 datetime by a number of seconds
 - **send_notification(title, body)**: Queue a system notification
 
 Security constraints:
 - All HTTP requests must use HTTPS
+- HTTP failures raise errors prefixed `NETWORK_ERROR:` (no response) or `HTTP_STATUS_<code>:` (non-2xx, with body); use `try { ... } catch (err) { ... }` to handle them
 - File operations are restricted to the workspace directory
 - Scripts are terminated after 100,000 operations (prevents infinite loops)
 
 ### Example: Creating a Simple Tool
 To create a tool that fetches a URL and extracts the title:
 1. Call create_tool with name="fetch_title", description="Fetches a URL and returns the page title"
-2. Script: `let params = json_parse(params_json); let body = http_get(params["url"]); let matches = regex_match(body, "<title>(.*?)</title>"); if matches.len() > 0 { matches[0] } else { "No title found" }`
+2. Script: `let params = json_parse(params_json); let body = http_get(params["url"]); let titles = html_select(body, "title"); if titles.len() > 0 { titles[0] } else { "No title found" }`
 3. Parameters: [{"name": "url", "type_hint": "string", "description": "URL to fetch", "required": true}]
 
 ## Canvas Programs (Visual Apps)
@@ -274,10 +534,13 @@ You can create interactive HTML/CSS/JS applications that the user can see and in
 ### Canvas Tools
 - **create_program**: Create a new program with an initial index.html
 - **list_programs**: List all programs you have created
+- **rename_program**: Rename an existing program (files and stored data are kept)
+- **clone_program**: Fork a program into a new one (starts at version 1.0.0) before risky changes
 - **open_program**: Open an existing program in the Canvas panel for the user to see
 - **program_ls**: List files within a program directory
 - **program_read_file**: Read the contents of a file in a program
 - **program_write_file**: Write/create a file in a program (bumps version, auto-reloads in frontend)
+- **program_write_file_base64**: Write a binary file (e.g. an image) to a program from base64 content
 - **program_edit_file**: Edit a file with search/replace (bumps version, auto-reloads in frontend)
 
 ### When to Use an Existing Program
@@ -303,12 +566,18 @@ program already exists. If the user asks for something that matches an existing
 
 Every Canvas program automatically has access to `window.ownai`, a JavaScript API for communicating with the backend. Programs can use these methods:
 
-- **window.ownai.chat(prompt)**: Send a message to you (the AI agent) and get a response. Useful for programs that need AI-generated content.
-- **window.ownai.storeData(key, value)**: Persist a key-value pair for this program. Data is stored in the database and survives page reloads.
+- **window.ownai.chat(prompt)**: Send a message to you (the AI agent) and get a response. Useful for programs that need AI-generated content. Calls are rate limited per program (rejected with "rate limited"), so never call it in a tight loop.
+- **window.ownai.chatJson(prompt, schema)**: Like `chat`, but returns parsed JSON conforming to the given JSON schema (e.g. `{type: "object", properties: {question: {type: "string"}}, required: ["question"]}`). Prefer it over parsing `chat` text.
+- **window.ownai.storeData(key, value)**: Persist a key-value pair for this program. Data is stored in the database and survives page reloads. Each program has a storage quota (5 MB by default); writes that exceed it are rejected.
 - **window.ownai.loadData(key)**: Load a previously stored value by key. Returns null if the key does not exist.
+- **window.ownai.listKeys()**: List all stored keys for this program.
+- **window.ownai.deleteData(key)**: Delete a stored key. Returns true if the key existed.
+- **window.ownai.executeTool(name, params)**: Run an active dynamic tool directly (without asking the AI) and get its result as a string.
+- **window.ownai.getMetadata()**: Get this program's metadata (name, description, version, created_at, updated_at), e.g. to show its version in the UI.
 - **window.ownai.notify(message, delay_ms?)**: Show a notification to the user. Optional delay in milliseconds.
 - **window.ownai.readFile(path)**: Read a file from the workspace directory. Path must be relative.
 - **window.ownai.writeFile(path, content)**: Write a file to the workspace directory. Creates parent directories if needed.
+- **window.ownai.writeFileBase64(path, base64)**: Write binary data (base64 or a data URL, e.g. from `canvas.toDataURL()`) to a file in the workspace directory.
 
 All methods return Promises. Example usage in a program:
 ```javascript
@@ -373,6 +642,16 @@ pub struct DelegateTaskArgs {
     system_prompt: String,
     /// The specific task to accomplish.
     task: String,
+    /// Maximum number of tool-calling turns (default: 25).
+    #[serde(default)]
+    max_turns: Option<usize>,
+    /// Wall-clock timeout in seconds (default: none).
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// Continue from the checkpoint of an interrupted run with the same
+    /// `task_name` instead of starting over.
+    #[serde(default)]
+    resume: bool,
 }
 
 /// rig Tool that creates temporary sub-agents for task delegation.
@@ -395,11 +674,22 @@ pub struct DelegateTaskTool {
     #[serde(skip)]
     db: Option<Pool<Sqlite>>,
     #[serde(skip)]
+    workspace: Option<PathBuf>,
+    #[serde(skip)]
     programs_root: Option<PathBuf>,
     #[serde(skip)]
     long_term_memory: Option<SharedLongTermMemory>,
     #[serde(skip)]
     app_handle: Option<AppHandle>,
+    /// Delegation depth of the agent that owns this tool (0 = main agent).
+    #[serde(skip, default)]
+    depth: usize,
+    /// Sub-agents get read-only tools (see `tools::read_only`)
+    #[serde(skip, default)]
+    read_only: bool,
+    /// Limit of sub-agent tool results (see `agent::OutputLimit`)
+    #[serde(skip, default)]
+    output_limit: OutputLimit,
 }
 
 fn default_model() -> String {
@@ -420,6 +710,7 @@ impl DelegateTaskTool {
         instance_name: String,
         registry: SharedRegistry,
         db: Pool<Sqlite>,
+        workspace: PathBuf,
         programs_root: PathBuf,
         long_term_memory: SharedLongTermMemory,
         app_handle: Option<AppHandle>,
@@ -431,9 +722,46 @@ impl DelegateTaskTool {
             instance_name,
             registry: Some(registry),
             db: Some(db),
+            workspace: Some(workspace),
             programs_root: Some(programs_root),
             long_term_memory: Some(long_term_memory),
             app_handle,
+            depth: 0,
+            read_only: false,
+            output_limit: OutputLimit::default(),
+        }
+    }
+
+    /// Set the delegation depth of the agent that owns this tool.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Restrict sub-agents to read-only tools.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Limit the tool results of sub-agents like those of the main agent.
+    pub fn with_output_limit(mut self, output_limit: OutputLimit) -> Self {
+        self.output_limit = output_limit;
+        self
+    }
+
+    /// Point the caller at the resume path if the failed run left a checkpoint.
+    async fn with_resume_hint(&self, error: SubAgentError, task_name: &str) -> SubAgentError {
+        let Some(db) = &self.db else {
+            return error;
+        };
+        match load_checkpoint(db, &self.instance_id, task_name).await {
+            Ok(Some(checkpoint)) => SubAgentError(format!(
+                "{} Progress was checkpointed after {} turns; call delegate_task again with \
+                 the same task_name and resume: true to continue.",
+                error, checkpoint.turn_count
+            )),
+            _ => error,
         }
     }
 
@@ -450,12 +778,18 @@ impl DelegateTaskTool {
     /// Run a sub-agent task with the given system prompt and task description.
     ///
     /// Creates an instrumented tracing span so Langfuse can display the
-    /// sub-agent execution as a named trace with Input/Output.
+    /// sub-agent execution as a named trace with Input/Output. All streamed
+    /// text is also appended to `partial`, so callers can report partial work
+    /// if the run is cut short. Progress is checkpointed after every
+    /// tool-calling turn, continuing from `checkpoint` when resuming.
     async fn run_sub_agent(
         &self,
         system_prompt: &str,
         task: &str,
         task_name: &str,
+        max_turns: usize,
+        partial: &Mutex<String>,
+        checkpoint: Option<SubAgentCheckpoint>,
     ) -> Result<String, SubAgentError> {
         let sub_agent_span = tracing::info_span!(
             "ownai.sub_agent",
@@ -474,7 +808,14 @@ impl DelegateTaskTool {
         sub_agent_span.set_attribute("gen_ai.prompt.0.content", task.to_string());
 
         let result = self
-            .run_sub_agent_inner(system_prompt, task, task_name)
+            .run_sub_agent_inner(
+                system_prompt,
+                task,
+                task_name,
+                max_turns,
+                partial,
+                checkpoint,
+            )
             .instrument(sub_agent_span.clone())
             .await;
 
@@ -492,6 +833,9 @@ impl DelegateTaskTool {
         system_prompt: &str,
         task: &str,
         task_name: &str,
+        max_turns: usize,
+        partial: &Mutex<String>,
+        checkpoint: Option<SubAgentCheckpoint>,
     ) -> Result<String, SubAgentError> {
         let client = self
             .client
@@ -505,6 +849,10 @@ impl DelegateTaskTool {
             .db
             .as_ref()
             .ok_or_else(|| SubAgentError("Database not initialized".to_string()))?;
+        let workspace = self
+            .workspace
+            .as_ref()
+            .ok_or_else(|| SubAgentError("Workspace not initialized".to_string()))?;
         let programs_root = self
             .programs_root
             .as_ref()
@@ -520,18 +868,26 @@ impl DelegateTaskTool {
             reg.tool_summary().await.unwrap_or_default()
         };
 
-        // Build tools for the sub-agent (all tools except delegate_task)
+        // Build tools for the sub-agent (delegate_task only if depth allows)
         let tools = build_sub_agent_tools(
+            self.depth + 1,
+            Some(self.clone()),
             &self.instance_id,
             registry.clone(),
             available_dynamic_tools,
             db.clone(),
+            workspace.clone(),
             programs_root.clone(),
             long_term_memory.clone(),
             self.app_handle.clone(),
+            self.read_only,
+            self.output_limit.clone(),
         );
 
-        let full_prompt = Self::build_sub_agent_prompt(system_prompt);
+        let mut full_prompt = Self::build_sub_agent_prompt(system_prompt);
+        if self.read_only {
+            full_prompt = format!("{}\n\n{}", full_prompt, READ_ONLY_PROMPT_NOTE);
+        }
 
         tracing::info!(
             "Starting sub-agent '{}' with {} tools",
@@ -539,6 +895,47 @@ impl DelegateTaskTool {
             tools.len()
         );
 
+        // Stream progress to the frontend while the sub-agent works
+        let app_handle = self.app_handle.clone();
+        let instance_id = self.instance_id.clone();
+        let on_text = |text: &str| {
+            if let Ok(mut partial) = partial.lock() {
+                partial.push_str(text);
+            }
+            if let Some(handle) = &app_handle {
+                let _ = handle.emit(
+                    "subagent:progress",
+                    SubAgentProgress {
+                        instance_id: instance_id.clone(),
+                        task_name: task_name.to_string(),
+                        text: text.to_string(),
+                    },
+                );
+            }
+        };
+
+        // Checkpoint progress after every tool-calling turn
+        let (mut turn_count, mut last_message) = checkpoint
+            .map(|c| (c.turn_count, c.last_message))
+            .unwrap_or_default();
+        let on_turn = |text: &str| {
+            turn_count += 1;
+            if !text.trim().is_empty() {
+                last_message = text.trim().to_string();
+            }
+            let checkpoint = SubAgentCheckpoint {
+                task_name: task_name.to_string(),
+                turn_count,
+                last_message: last_message.clone(),
+                updated_at: chrono::Utc::now(),
+            };
+            async move {
+                if let Err(e) = save_checkpoint(db, &self.instance_id, &checkpoint).await {
+                    tracing::warn!("Failed to checkpoint sub-agent '{}': {}", task_name, e);
+                }
+            }
+        };
+
         // Build and run provider-specific agent
         let result = match client {
             ClientProvider::Anthropic(c) => {
@@ -550,11 +947,8 @@ impl DelegateTaskTool {
                     .tools(tools)
                     .build();
 
-                agent
-                    .prompt(task)
-                    .max_turns(SUB_AGENT_MAX_TURNS)
-                    .await
-                    .map_err(|e| SubAgentError(format!("Sub-agent execution failed: {}", e)))?
+                let stream = agent.stream_prompt(task).multi_turn(max_turns).await;
+                collect_sub_agent_stream(stream, on_text, on_turn).await?
             }
             ClientProvider::OpenAI(c) => {
                 let agent = c
@@ -566,11 +960,8 @@ impl DelegateTaskTool {
                     .tools(tools)
                     .build();
 
-                agent
-                    .prompt(task)
-                    .max_turns(SUB_AGENT_MAX_TURNS)
-                    .await
-                    .map_err(|e| SubAgentError(format!("Sub-agent execution failed: {}", e)))?
+                let stream = agent.stream_prompt(task).multi_turn(max_turns).await;
+                collect_sub_agent_stream(stream, on_text, on_turn).await?
             }
             ClientProvider::Ollama(c) => {
                 let agent = c
@@ -580,11 +971,8 @@ impl DelegateTaskTool {
                     .tools(tools)
                     .build();
 
-                agent
-                    .prompt(task)
-                    .max_turns(SUB_AGENT_MAX_TURNS)
-                    .await
-                    .map_err(|e| SubAgentError(format!("Sub-agent execution failed: {}", e)))?
+                let stream = agent.stream_prompt(task).multi_turn(max_turns).await;
+                collect_sub_agent_stream(stream, on_text, on_turn).await?
             }
         };
 
@@ -637,6 +1025,18 @@ impl Tool for DelegateTaskTool {
                     "task": {
                         "type": "string",
                         "description": "The specific task for the sub-agent to accomplish"
+                    },
+                    "max_turns": {
+                        "type": "integer",
+                        "description": "Maximum number of tool-calling turns (default: 25, max: 100)"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Optional wall-clock timeout in seconds. On timeout, any partial output is returned with the error."
+                    },
+                    "resume": {
+                        "type": "boolean",
+                        "description": "Continue an interrupted run of the task with the same task_name from its last checkpoint instead of starting over (default: false)"
                     }
                 },
                 "required": ["task_name", "system_prompt", "task"]
@@ -645,11 +1045,67 @@ impl Tool for DelegateTaskTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        if self.depth >= MAX_DELEGATION_DEPTH {
+            return Err(SubAgentError(format!(
+                "Cannot delegate task '{}': maximum delegation depth ({}) reached",
+                args.task_name, MAX_DELEGATION_DEPTH
+            )));
+        }
+
         tracing::info!("Delegating task '{}' to sub-agent", args.task_name);
 
-        let result = self
-            .run_sub_agent(&args.system_prompt, &args.task, &args.task_name)
-            .await?;
+        let max_turns = args
+            .max_turns
+            .unwrap_or(SUB_AGENT_MAX_TURNS)
+            .clamp(1, SUB_AGENT_MAX_TURNS_LIMIT);
+        // Resume from the last checkpoint, or discard a stale one
+        let mut checkpoint = None;
+        if let Some(db) = &self.db {
+            if args.resume {
+                checkpoint = load_checkpoint(db, &self.instance_id, &args.task_name)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Failed to load sub-agent checkpoint: {}", e);
+                        None
+                    });
+            } else if let Err(e) = delete_checkpoint(db, &self.instance_id, &args.task_name).await {
+                tracing::warn!("Failed to clear sub-agent checkpoint: {}", e);
+            }
+        }
+        let task = match &checkpoint {
+            Some(checkpoint) => {
+                tracing::info!(
+                    "Resuming sub-agent '{}' after {} turns",
+                    args.task_name,
+                    checkpoint.turn_count
+                );
+                resume_task_prompt(&args.task, checkpoint)
+            }
+            None => args.task.clone(),
+        };
+
+        let partial = Mutex::new(String::new());
+        let run = self.run_sub_agent(
+            &args.system_prompt,
+            &task,
+            &args.task_name,
+            max_turns,
+            &partial,
+            checkpoint,
+        );
+        let result =
+            match run_with_timeout(run, &args.task_name, args.timeout_secs, max_turns, &partial)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => return Err(self.with_resume_hint(e, &args.task_name).await),
+            };
+
+        if let Some(db) = &self.db {
+            if let Err(e) = delete_checkpoint(db, &self.instance_id, &args.task_name).await {
+                tracing::warn!("Failed to clear sub-agent checkpoint: {}", e);
+            }
+        }
 
         Ok(format!(
             "[Sub-agent '{}' completed]\n\n{}",
@@ -658,6 +1114,38 @@ impl Tool for DelegateTaskTool {
     }
 }
 
+/// Await a sub-agent run, optionally bounded by `timeout_secs`. On timeout,
+/// the error reports the budget and any partial output streamed so far.
+async fn run_with_timeout(
+    run: impl Future<Output = Result<String, SubAgentError>>,
+    task_name: &str,
+    timeout_secs: Option<u64>,
+    max_turns: usize,
+    partial: &Mutex<String>,
+) -> Result<String, SubAgentError> {
+    let Some(secs) = timeout_secs else {
+        return run.await;
+    };
+
+    match tokio::time::timeout(Duration::from_secs(secs), run).await {
+        Ok(result) => result,
+        Err(_) => {
+            let partial = partial.lock().map(|p| p.clone()).unwrap_or_default();
+            tracing::warn!("Sub-agent '{}' timed out after {}s", task_name, secs);
+
+            let partial_report = if partial.trim().is_empty() {
+                "No output was produced before the timeout.".to_string()
+            } else {
+                format!("Partial output:\n{}", partial)
+            };
+            Err(SubAgentError(format!(
+                "Sub-agent '{}' timed out after {} seconds (turn budget: {} turns). {}",
+                task_name, secs, max_turns, partial_report
+            )))
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -680,9 +1168,13 @@ mod tests {
             instance_name: String::new(),
             registry: None,
             db: None,
+            workspace: None,
             programs_root: None,
             long_term_memory: None,
             app_handle: None,
+            depth: 0,
+            read_only: false,
+            output_limit: OutputLimit::default(),
         };
 
         let def = Tool::definition(&tool, "test".to_string()).await;
@@ -702,9 +1194,13 @@ mod tests {
             instance_name: String::new(),
             registry: None,
             db: None,
+            workspace: None,
             programs_root: None,
             long_term_memory: None,
             app_handle: None,
+            depth: 0,
+            read_only: false,
+            output_limit: OutputLimit::default(),
         };
 
         let result = Tool::call(
@@ -713,6 +1209,9 @@ mod tests {
                 task_name: "test-task".to_string(),
                 system_prompt: "You are a test agent.".to_string(),
                 task: "Do something.".to_string(),
+                max_turns: None,
+                timeout_secs: None,
+                resume: false,
             },
         )
         .await;
@@ -721,6 +1220,265 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("not initialized"));
     }
 
+    type FakeItem = Result<MultiTurnStreamItem<()>, String>;
+
+    fn text_item(text: &str) -> FakeItem {
+        Ok(MultiTurnStreamItem::StreamAssistantItem(
+            StreamedAssistantContent::text(text),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_collect_sub_agent_stream_reports_progress() {
+        let items = vec![
+            text_item("Reading files... "),
+            text_item("Done. "),
+            text_item("Summary: all good."),
+            Ok(MultiTurnStreamItem::final_response(
+                "Summary: all good.",
+                rig::completion::Usage::new(),
+            )),
+        ];
+
+        // Record progress events and the point at which the result arrives
+        let progress = std::sync::Mutex::new(Vec::new());
+        let result = collect_sub_agent_stream(
+            futures::stream::iter(items),
+            |text| {
+                progress.lock().unwrap().push(SubAgentProgress {
+                    instance_id: "test-instance".to_string(),
+                    task_name: "organize-notes".to_string(),
+                    text: text.to_string(),
+                })
+            },
+            |_| async {},
+        )
+        .await
+        .unwrap();
+
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress[0].text, "Reading files... ");
+        assert_eq!(progress[2].text, "Summary: all good.");
+        // The final value is the FinalResponse text, as with prompt()
+        assert_eq!(result, "Summary: all good.");
+    }
+
+    #[tokio::test]
+    async fn test_collect_sub_agent_stream_error() {
+        let items: Vec<FakeItem> = vec![text_item("Working"), Err("rate limited".to_string())];
+
+        let mut chunks = Vec::new();
+        let err = collect_sub_agent_stream(
+            futures::stream::iter(items),
+            |t| chunks.push(t.to_string()),
+            |_| async {},
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(chunks, vec!["Working"]);
+        assert!(err.to_string().contains("rate limited"));
+    }
+
+    fn tool_call_item(name: &str) -> FakeItem {
+        Ok(MultiTurnStreamItem::StreamAssistantItem(
+            StreamedAssistantContent::ToolCall {
+                tool_call: rig::message::ToolCall::new(
+                    format!("call-{}", name),
+                    rig::message::ToolFunction::new(name.to_string(), json!({})),
+                ),
+                internal_call_id: name.to_string(),
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_collect_sub_agent_stream_reports_turns() {
+        let items = vec![
+            text_item("Listing files."),
+            tool_call_item("ls"),
+            // Parallel tool calls in the same response are one turn
+            tool_call_item("read_file"),
+            text_item("Found 3 notes."),
+            tool_call_item("write_file"),
+            text_item("Done."),
+        ];
+
+        let turns = std::sync::Mutex::new(Vec::new());
+        let result = collect_sub_agent_stream(
+            futures::stream::iter(items),
+            |_| {},
+            |text| {
+                turns.lock().unwrap().push(text.to_string());
+                async {}
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            turns.into_inner().unwrap(),
+            vec!["Listing files.", "Found 3 notes."]
+        );
+        assert_eq!(result, "Done.");
+    }
+
+    async fn setup_test_db() -> Pool<Sqlite> {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::database::schema::run_migrations(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_save_load_delete() {
+        let db = setup_test_db().await;
+        assert!(load_checkpoint(&db, "inst", "organize-notes")
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut checkpoint = SubAgentCheckpoint {
+            task_name: "organize-notes".to_string(),
+            turn_count: 1,
+            last_message: "Read the index.".to_string(),
+            updated_at: chrono::Utc::now(),
+        };
+        save_checkpoint(&db, "inst", &checkpoint).await.unwrap();
+
+        // A later turn overwrites the previous checkpoint
+        checkpoint.turn_count = 4;
+        checkpoint.last_message = "Sorted 12 of 20 notes.".to_string();
+        save_checkpoint(&db, "inst", &checkpoint).await.unwrap();
+
+        let loaded = load_checkpoint(&db, "inst", "organize-notes")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.turn_count, 4);
+        assert_eq!(loaded.last_message, "Sorted 12 of 20 notes.");
+
+        // Checkpoints are scoped to task name and instance
+        assert!(load_checkpoint(&db, "inst", "other-task")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(load_checkpoint(&db, "other-inst", "organize-notes")
+            .await
+            .unwrap()
+            .is_none());
+
+        delete_checkpoint(&db, "inst", "organize-notes")
+            .await
+            .unwrap();
+        assert!(load_checkpoint(&db, "inst", "organize-notes")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_resume_task_prompt_includes_progress() {
+        let checkpoint = SubAgentCheckpoint {
+            task_name: "organize-notes".to_string(),
+            turn_count: 4,
+            last_message: "Sorted 12 of 20 notes.".to_string(),
+            updated_at: chrono::Utc::now(),
+        };
+        let prompt = resume_task_prompt("Sort my notes.", &checkpoint);
+        assert!(prompt.starts_with("Sort my notes."));
+        assert!(prompt.contains("after 4 completed turns"));
+        assert!(prompt.contains("Sorted 12 of 20 notes."));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_timeout_reports_partial_output() {
+        let partial = Mutex::new(String::new());
+        let run = async {
+            partial.lock().unwrap().push_str("Read 3 of 10 files");
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok("never".to_string())
+        };
+
+        let started = std::time::Instant::now();
+        let err = run_with_timeout(run, "slow-task", Some(1), 5, &partial)
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(started.elapsed() < Duration::from_secs(30));
+        assert!(err.contains("'slow-task' timed out after 1 seconds"));
+        assert!(err.contains("turn budget: 5 turns"));
+        assert!(err.contains("Read 3 of 10 files"));
+    }
+
+    #[tokio::test]
+    async fn test_run_with_timeout_unset_or_not_exceeded() {
+        let partial = Mutex::new(String::new());
+
+        let result = run_with_timeout(async { Ok("done".to_string()) }, "t", None, 25, &partial)
+            .await
+            .unwrap();
+        assert_eq!(result, "done");
+
+        let result = run_with_timeout(async { Ok("fast".to_string()) }, "t", Some(5), 25, &partial)
+            .await
+            .unwrap();
+        assert_eq!(result, "fast");
+    }
+
+    fn uninitialized_tool() -> DelegateTaskTool {
+        DelegateTaskTool {
+            client: None,
+            model: String::new(),
+            instance_id: String::new(),
+            instance_name: String::new(),
+            registry: None,
+            db: None,
+            workspace: None,
+            programs_root: None,
+            long_term_memory: None,
+            app_handle: None,
+            depth: 0,
+            read_only: false,
+            output_limit: OutputLimit::default(),
+        }
+    }
+
+    #[test]
+    fn test_delegate_for_depth() {
+        let delegate = delegate_for_depth(Some(uninitialized_tool()), 0).unwrap();
+        assert_eq!(delegate.depth, 0);
+
+        // Agents at max depth get no further delegate capability
+        assert!(delegate_for_depth(Some(uninitialized_tool()), MAX_DELEGATION_DEPTH).is_none());
+        assert!(delegate_for_depth(Some(uninitialized_tool()), MAX_DELEGATION_DEPTH + 1).is_none());
+        assert!(delegate_for_depth(None, 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delegate_task_refuses_at_max_depth() {
+        let tool = uninitialized_tool().with_depth(MAX_DELEGATION_DEPTH);
+
+        let err = Tool::call(
+            &tool,
+            DelegateTaskArgs {
+                task_name: "nested".to_string(),
+                system_prompt: "You are a test agent.".to_string(),
+                task: "Do something.".to_string(),
+                max_turns: None,
+                timeout_secs: None,
+                resume: false,
+            },
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().contains("maximum delegation depth"));
+    }
+
     #[test]
     fn test_base_tools_prompt_contains_all_sections() {
         let prompt = base_tools_prompt();
@@ -0,0 +1,36 @@
+//! Filesystem helpers shared by workspace and program directory commands.
+
+use std::path::Path;
+
+/// Walk `root` without following symlinks, calling `visit` for every regular
+/// file with its path relative to `root` and metadata.
+pub fn walk_files(
+    root: &Path,
+    mut visit: impl FnMut(&Path, &str, &std::fs::Metadata) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut stack = vec![(root.to_path_buf(), String::new())];
+
+    while let Some((dir, rel_prefix)) = stack.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel = if rel_prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel_prefix, name)
+            };
+
+            let metadata = entry.path().symlink_metadata()?;
+            if metadata.file_type().is_symlink() {
+                continue;
+            }
+            if metadata.is_dir() {
+                stack.push((entry.path(), rel));
+            } else if metadata.is_file() {
+                visit(&entry.path(), &rel, &metadata)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1 +1,4 @@
+pub mod files;
+pub mod offline;
 pub mod paths;
+pub mod workspace_ignore;
@@ -0,0 +1,32 @@
+//! Global offline ("airplane") mode.
+//!
+//! When enabled, tools that would make outbound HTTP requests (the sandbox
+//! `http_*` functions) fail with `OFFLINE_ERROR` instead of connecting.
+//! LLM requests are not blocked here, so local providers such as Ollama keep
+//! working. The flag is process-wide and not persisted.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Error returned by network functions while offline mode is enabled.
+pub const OFFLINE_ERROR: &str = "Offline mode is enabled: network access is disabled";
+
+static OFFLINE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Whether offline mode is enabled.
+pub fn is_offline() -> bool {
+    OFFLINE_MODE.load(Ordering::Relaxed)
+}
+
+/// Enable or disable offline mode.
+pub fn set_offline(enabled: bool) {
+    OFFLINE_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Fail with `OFFLINE_ERROR` if offline mode is enabled.
+pub fn require_online() -> Result<(), String> {
+    if is_offline() {
+        Err(OFFLINE_ERROR.to_string())
+    } else {
+        Ok(())
+    }
+}
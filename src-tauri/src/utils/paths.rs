@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get the main application directory (~/.ownai)
 pub fn get_app_dir() -> Result<PathBuf> {
@@ -25,13 +25,28 @@ pub fn get_config_path() -> Result<PathBuf> {
     Ok(get_app_dir()?.join("instances.json"))
 }
 
+/// Get the encrypted API key file path (~/.ownai/api_keys.enc), used when
+/// the OS keychain is unavailable
+pub fn get_encrypted_keys_path() -> Result<PathBuf> {
+    Ok(get_app_dir()?.join("api_keys.enc"))
+}
+
 /// Get the database path for a specific instance
 pub fn get_instance_db_path(instance_id: &str) -> Result<PathBuf> {
     Ok(get_instances_path()?.join(instance_id).join("ownai.db"))
 }
 
-/// Get the workspace directory for a specific instance
-pub fn get_instance_workspace_path(instance_id: &str) -> Result<PathBuf> {
+/// Get the workspace directory for a specific instance: its
+/// `workspace_override` (see `AIInstance::workspace_override`) if one is set,
+/// otherwise ~/.ownai/instances/<id>/workspace
+pub fn get_instance_workspace_path(
+    instance_id: &str,
+    workspace_override: Option<&Path>,
+) -> Result<PathBuf> {
+    if let Some(path) = workspace_override {
+        return Ok(path.to_path_buf());
+    }
+
     let path = get_instances_path()?.join(instance_id).join("workspace");
     std::fs::create_dir_all(&path).context("Failed to create workspace directory")?;
     Ok(path)
@@ -49,3 +64,17 @@ pub fn get_program_path(instance_id: &str, program_name: &str) -> Result<PathBuf
     let path = get_instance_programs_path(instance_id)?.join(program_name);
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_override_is_returned_when_present() {
+        let project = tempfile::TempDir::new().unwrap();
+
+        let workspace =
+            get_instance_workspace_path("workspace-override-test", Some(project.path())).unwrap();
+        assert_eq!(workspace, project.path());
+    }
+}
@@ -0,0 +1,95 @@
+//! Workspace exclude patterns (`.ownaiignore`).
+//!
+//! An optional `.ownaiignore` file in the workspace root lists gitignore-style
+//! glob patterns (e.g. `node_modules/`, `*.log`). Matching paths are skipped by
+//! `grep`, glob/recursive `ls` and the workspace tree, so build output and
+//! dependency folders do not flood results.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// Name of the exclude file in the workspace root.
+pub const IGNORE_FILE_NAME: &str = ".ownaiignore";
+
+/// Exclude patterns loaded from a workspace's `.ownaiignore`.
+/// Without the file, nothing is ignored.
+#[derive(Debug)]
+pub struct WorkspaceIgnore {
+    root: PathBuf,
+    matcher: Option<Gitignore>,
+}
+
+impl WorkspaceIgnore {
+    /// Load `.ownaiignore` from `root`. Invalid patterns are logged and
+    /// skipped; the remaining ones still apply.
+    pub fn load(root: &Path) -> Self {
+        let file = root.join(IGNORE_FILE_NAME);
+        let mut loaded = Self {
+            root: root.to_path_buf(),
+            matcher: None,
+        };
+        if !file.is_file() {
+            return loaded;
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+        if let Some(e) = builder.add(&file) {
+            tracing::warn!("Invalid pattern in {}: {}", file.display(), e);
+        }
+        match builder.build() {
+            Ok(matcher) => loaded.matcher = Some(matcher),
+            Err(e) => tracing::warn!("Failed to load {}: {}", file.display(), e),
+        }
+        loaded
+    }
+
+    /// Whether `path` (absolute under the workspace root, or relative to it)
+    /// or one of its parent directories is excluded.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(matcher) = &self.matcher else {
+            return false;
+        };
+        let rel = if path.is_absolute() {
+            match path.strip_prefix(&self.root) {
+                Ok(rel) => rel,
+                Err(_) => return false,
+            }
+        } else {
+            path
+        };
+        if rel.as_os_str().is_empty() {
+            return false;
+        }
+        matcher.matched_path_or_any_parents(rel, is_dir).is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_match_files_and_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "# dependencies\nnode_modules/\n*.log\n!keep.log\n",
+        )
+        .unwrap();
+
+        let ignore = WorkspaceIgnore::load(root);
+        assert!(ignore.is_ignored(Path::new("node_modules"), true));
+        assert!(ignore.is_ignored(&root.join("app/node_modules/pkg/index.js"), false));
+        assert!(ignore.is_ignored(Path::new("build/out.log"), false));
+        assert!(!ignore.is_ignored(Path::new("keep.log"), false));
+        assert!(!ignore.is_ignored(Path::new("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_missing_file_ignores_nothing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ignore = WorkspaceIgnore::load(temp_dir.path());
+        assert!(!ignore.is_ignored(Path::new("node_modules"), true));
+    }
+}
@@ -21,6 +21,11 @@ interface RawMessage {
   instance_id: string;
 }
 
+interface MessagePage {
+  messages: RawMessage[];
+  total_count: number;
+}
+
 function App() {
   const { t } = useTranslation();
   const {
@@ -58,14 +63,14 @@ function App() {
     async (instanceId: string) => {
       setIsLoadingMessages(true);
       try {
-        const loadedMessages = await invoke<RawMessage[]>("load_messages", {
+        const page = await invoke<MessagePage>("load_messages", {
           instanceId,
           limit: 1000,
           offset: 0,
         });
 
         // Convert timestamps to Date objects
-        const parsedMessages = loadedMessages.map((msg) => ({
+        const parsedMessages = page.messages.map((msg) => ({
           ...msg,
           timestamp: new Date(msg.timestamp),
         }));